@@ -0,0 +1,134 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use kanban_board_lib::{
+    file_system::{
+        binary_reader::BinaryReader,
+        binary_writer::BinaryWriter,
+        encoding::{Decode, Encode},
+    },
+    kanban::{board::Board, board_list::BoardList, project},
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tempdir::TempDir;
+
+const BOARDS: usize = 10;
+const LISTS: usize = 10;
+const ITEMS: usize = 1_000;
+
+/// Counts allocated bytes, so the peak memory of each variant can be reported.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Peak bytes allocated by `f` on top of what was allocated before it.
+fn peak_memory<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    drop(f());
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+/// A project with 100k items spread over boards and lists.
+fn large_project() -> project::Project {
+    let boards = (0..BOARDS)
+        .map(|b| Board {
+            name: format!("Board {}", b),
+            lists: (0..LISTS)
+                .map(|l| BoardList {
+                    title: format!("List {}", l),
+                    color: [0xFF, 0xFF, 0xFF],
+                    items: (0..ITEMS)
+                        .map(|i| format!("Item {} of board {} list {}", i, b, l))
+                        .collect(),
+                })
+                .collect(),
+            archived: false,
+        })
+        .collect();
+    project::Project {
+        id: [0x01; 16],
+        revision: 0,
+        locked: false,
+        archived: false,
+        name: "Large Project".to_string(),
+        description: "100k items".to_string(),
+        boards,
+    }
+}
+
+fn write_in_memory(path: &Path, project: &project::Project) {
+    let mut bw = BinaryWriter::new();
+    project.encode(&mut bw).unwrap();
+    bw.write_to_file(path).unwrap();
+}
+
+fn write_streaming(path: &Path, project: &project::Project) {
+    let mut bw = BinaryWriter::create_file(path).unwrap();
+    project.encode(&mut bw).unwrap();
+    bw.finish().unwrap();
+}
+
+fn read_in_memory(path: &Path) -> project::Project {
+    let bytes = fs::read(path).unwrap();
+    project::Project::decode(&mut BinaryReader::new(&bytes)).unwrap()
+}
+
+fn read_streaming(path: &Path) -> project::Project {
+    project::Project::decode(&mut BinaryReader::read_from_file(path).unwrap()).unwrap()
+}
+
+fn binary_io(c: &mut Criterion) {
+    let dir = TempDir::new("kanban-bench").expect("Failed to create directory");
+    let path = dir.path().join("project.bin");
+    let project = large_project();
+
+    println!(
+        "Peak memory writing: in memory {} bytes, streaming {} bytes",
+        peak_memory(|| write_in_memory(&path, &project)),
+        peak_memory(|| write_streaming(&path, &project)),
+    );
+    println!(
+        "Peak memory reading: in memory {} bytes, streaming {} bytes",
+        peak_memory(|| read_in_memory(&path)),
+        peak_memory(|| read_streaming(&path)),
+    );
+
+    let mut group = c.benchmark_group("write_100k_items");
+    group.sample_size(10);
+    group.bench_function("in_memory", |b| b.iter(|| write_in_memory(&path, &project)));
+    group.bench_function("streaming", |b| b.iter(|| write_streaming(&path, &project)));
+    group.finish();
+
+    let mut group = c.benchmark_group("read_100k_items");
+    group.sample_size(10);
+    group.bench_function("in_memory", |b| b.iter(|| read_in_memory(&path)));
+    group.bench_function("streaming", |b| b.iter(|| read_streaming(&path)));
+    group.finish();
+}
+
+criterion_group!(benches, binary_io);
+criterion_main!(benches);
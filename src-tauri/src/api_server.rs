@@ -0,0 +1,651 @@
+use crate::{
+    commands::{project_commands, CommandResult},
+    errors::kanban_error::{KanbanError, KanbanErrorKind},
+    kanban::{board::Board, config::Configs, merge, project::Project},
+};
+use serde_json::Value;
+use std::{
+    io::Read,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+use tauri::async_runtime::block_on;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// Large enough for any project export, small enough to refuse garbage
+const MAX_BODY_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(serde::Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(serde::Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, serde::Serialize, PartialEq)]
+struct RpcError {
+    code: i32,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new<M: Into<String>>(code: i32, message: M) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateProjectParams {
+    name: String,
+    description: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectIdParams {
+    project_id: Vec<u8>,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ListProjectsParams {
+    include_archived: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+struct SaveProjectParams {
+    project: Project,
+}
+
+#[derive(serde::Deserialize)]
+struct MergeProjectParams {
+    project: Project,
+    choices: Option<Vec<merge::Side>>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadBoardParams {
+    project_id: Vec<u8>,
+    board_name: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveBoardParams {
+    project_id: Vec<u8>,
+    board_name: String,
+    board: Board,
+    revision: u64,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnlockProjectParams {
+    project_id: Vec<u8>,
+    passphrase: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangePassphraseParams {
+    project_id: Vec<u8>,
+    passphrase: Option<String>,
+    new_passphrase: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct DiffProjectsParams {
+    old: Project,
+    new: Project,
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(-32602, e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(-32603, e.to_string()))
+}
+
+fn into_result<T: serde::Serialize>(result: CommandResult<T>) -> Result<Value, RpcError> {
+    if result.success {
+        return to_value(&result.data);
+    }
+    // Validation failures carry the invalid fields, save conflicts the stored
+    // copy so the caller can merge
+    let data = match result.errors {
+        Some(errors) => Some(to_value(&errors)?),
+        None => result.data.map(|data| to_value(&data)).transpose()?,
+    };
+    Err(RpcError {
+        code: -32000,
+        message: result.message.unwrap_or_default(),
+        data,
+    })
+}
+
+/// Runs one API method through the Tauri command of the same name in
+/// `project_commands.rs`, so the cache, the watcher and the window see the
+/// change as if the window made it. `set_project_dirty` is left out, only the
+/// window has unsaved edits.
+fn dispatch<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    let app = app.clone();
+    match method {
+        "create_project_command" => {
+            let params: CreateProjectParams = parse_params(params)?;
+            into_result(block_on(project_commands::create_project_command(
+                app,
+                params.name,
+                params.description,
+            )))
+        }
+        "get_all_projects" => {
+            let params: ListProjectsParams = match params {
+                Value::Null => ListProjectsParams::default(),
+                params => parse_params(params)?,
+            };
+            into_result(block_on(project_commands::get_all_projects(
+                app,
+                None,
+                params.include_archived,
+            )))
+        }
+        "read_project" => {
+            let params: ProjectIdParams = parse_params(params)?;
+            into_result(block_on(project_commands::read_project(
+                app,
+                params.project_id,
+            )))
+        }
+        "save_project" => {
+            let params: SaveProjectParams = parse_params(params)?;
+            into_result(block_on(project_commands::save_project(
+                app,
+                params.project,
+            )))
+        }
+        "merge_project" => {
+            let params: MergeProjectParams = parse_params(params)?;
+            into_result(block_on(project_commands::merge_project(
+                app,
+                params.project,
+                params.choices,
+            )))
+        }
+        "delete_project" => {
+            let params: ProjectIdParams = parse_params(params)?;
+            into_result(block_on(project_commands::delete_project(
+                app,
+                params.project_id,
+            )))
+        }
+        "archive_project" => {
+            let params: ProjectIdParams = parse_params(params)?;
+            into_result(block_on(project_commands::archive_project(
+                app,
+                params.project_id,
+            )))
+        }
+        "unarchive_project" => {
+            let params: ProjectIdParams = parse_params(params)?;
+            into_result(block_on(project_commands::unarchive_project(
+                app,
+                params.project_id,
+            )))
+        }
+        "read_board" => {
+            let params: ReadBoardParams = parse_params(params)?;
+            into_result(block_on(project_commands::read_board(
+                app,
+                params.project_id,
+                params.board_name,
+            )))
+        }
+        "save_board" => {
+            let params: SaveBoardParams = parse_params(params)?;
+            into_result(block_on(project_commands::save_board(
+                app,
+                params.project_id,
+                params.board_name,
+                params.board,
+                params.revision,
+            )))
+        }
+        "unlock_project" => {
+            let params: UnlockProjectParams = parse_params(params)?;
+            into_result(block_on(project_commands::unlock_project(
+                app,
+                params.project_id,
+                params.passphrase,
+            )))
+        }
+        "lock_project" => {
+            let params: ProjectIdParams = parse_params(params)?;
+            into_result(project_commands::lock_project(app, params.project_id))
+        }
+        "change_project_passphrase" => {
+            let params: ChangePassphraseParams = parse_params(params)?;
+            into_result(block_on(project_commands::change_project_passphrase(
+                app,
+                params.project_id,
+                params.passphrase,
+                params.new_passphrase,
+            )))
+        }
+        "recompress_projects" => {
+            into_result(block_on(project_commands::recompress_projects(app, None)))
+        }
+        "diff_projects" => {
+            let params: DiffProjectsParams = parse_params(params)?;
+            into_result(project_commands::diff_projects(params.old, params.new))
+        }
+        _ => Err(RpcError::new(
+            -32601,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Authorization"))
+        .any(|h| {
+            // Compare without short-circuiting to avoid leaking the token length prefix
+            let given = h.value.as_str().as_bytes();
+            given.len() == expected.len()
+                && given
+                    .iter()
+                    .zip(expected.as_bytes())
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        })
+}
+
+fn handle_request<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    token: &str,
+    request: &mut Request,
+) -> (u16, Option<RpcResponse>) {
+    if !is_authorized(request, token) {
+        return (401, None);
+    }
+    if request.url() != "/rpc" {
+        return (404, None);
+    }
+    if *request.method() != Method::Post {
+        return (405, None);
+    }
+    let mut body = String::new();
+    let read_result = request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body);
+    let rpc_request: Result<RpcRequest, RpcError> = read_result
+        .map_err(|e| RpcError::new(-32700, e.to_string()))
+        .and_then(|_| {
+            serde_json::from_str(&body).map_err(|e| RpcError::new(-32700, e.to_string()))
+        });
+    let (id, result) = match rpc_request {
+        Ok(rpc_request) => (
+            rpc_request.id,
+            dispatch(app, &rpc_request.method, rpc_request.params),
+        ),
+        Err(err) => (Value::Null, Err(err)),
+    };
+    let (result, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(err) => (None, Some(err)),
+    };
+    let response = RpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result,
+        error,
+    };
+    (200, Some(response))
+}
+
+pub struct ApiServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+    token: String,
+}
+
+impl ApiServer {
+    /// Starts serving on localhost only.
+    pub fn start<R: tauri::Runtime>(
+        app: tauri::AppHandle<R>,
+        port: u16,
+        token: &str,
+    ) -> Result<Self, KanbanError> {
+        if token.is_empty() {
+            return Err(KanbanError::new(
+                KanbanErrorKind::ProjectError,
+                "The local API requires a token",
+            ));
+        }
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::IoError, e))?;
+        let server = Arc::new(server);
+        let bound_port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .unwrap_or(port);
+        let thread_server = server.clone();
+        let thread_token = token.to_string();
+        let thread = thread::spawn(move || {
+            for mut request in thread_server.incoming_requests() {
+                let (status, response) = handle_request(&app, &thread_token, &mut request);
+                let body = response
+                    .and_then(|r| serde_json::to_string(&r).ok())
+                    .unwrap_or_default();
+                let content_type = Header::from_bytes("Content-Type", "application/json")
+                    .expect("Static header is valid");
+                let _ = request.respond(
+                    Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(content_type),
+                );
+            }
+        });
+        Ok(ApiServer {
+            server,
+            thread: Some(thread),
+            port: bound_port,
+            token: token.to_string(),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ApiServerState {
+    server: Mutex<Option<ApiServer>>,
+}
+
+/// Starts, restarts or stops the local API so it matches `configs`.
+pub fn apply_configs<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &ApiServerState,
+    configs: &Configs,
+) -> Result<(), KanbanError> {
+    let mut server = state
+        .server
+        .lock()
+        .map_err(|_| KanbanError::new(KanbanErrorKind::IoError, "Local API state is poisoned"))?;
+    if !configs.api_enabled {
+        *server = None;
+        return Ok(());
+    }
+    if let Some(running) = server.as_ref() {
+        if running.port() == configs.api_port && running.token == configs.api_token {
+            return Ok(());
+        }
+    }
+    // Release the port before binding it again
+    *server = None;
+    *server = Some(ApiServer::start(
+        app.clone(),
+        configs.api_port,
+        &configs.api_token,
+    )?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::{project, project_cache::ProjectCache};
+    use crate::test_support::{test_board, test_list};
+    use serial_test::serial;
+    use std::{io::Write, net::TcpStream};
+    use tauri::{test::MockRuntime, Manager};
+
+    fn post(port: u16, token: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("Failed to connect");
+        write!(
+            stream,
+            "POST /rpc HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            token,
+            body.len(),
+            body
+        )
+        .expect("Failed to send request");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Failed to read response");
+        let status: u16 = response[9..12].parse().expect("Invalid status line");
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    fn delete(app: &tauri::AppHandle<MockRuntime>, project_id: [u8; 16]) {
+        dispatch(
+            app,
+            "delete_project",
+            serde_json::json!({ "projectId": project_id }),
+        )
+        .expect("Failed to delete project");
+    }
+
+    #[test]
+    #[serial]
+    fn test_dispatch_create_and_read_project() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let value = dispatch(
+            app,
+            "create_project_command",
+            serde_json::json!({ "name": "Test Project", "description": "Test Description" }),
+        )
+        .expect("Failed to create project");
+        let project: Project = serde_json::from_value(value).unwrap();
+        let value = dispatch(
+            app,
+            "read_project",
+            serde_json::json!({ "projectId": project.id }),
+        )
+        .expect("Failed to read project");
+        assert_eq!(project, serde_json::from_value(value).unwrap());
+        // Archived projects are only listed when asked for
+        dispatch(
+            app,
+            "archive_project",
+            serde_json::json!({ "projectId": project.id }),
+        )
+        .expect("Failed to archive project");
+        let value = dispatch(app, "get_all_projects", Value::Null).unwrap();
+        let projects: Vec<Project> = serde_json::from_value(value).unwrap();
+        assert!(projects.iter().all(|p| p.id != project.id));
+        let value = dispatch(
+            app,
+            "get_all_projects",
+            serde_json::json!({ "includeArchived": true }),
+        )
+        .unwrap();
+        let projects: Vec<Project> = serde_json::from_value(value).unwrap();
+        assert!(projects.iter().any(|p| p.id == project.id && p.archived));
+        dispatch(
+            app,
+            "unarchive_project",
+            serde_json::json!({ "projectId": project.id }),
+        )
+        .expect("Failed to unarchive project");
+        delete(app, project.id);
+    }
+
+    #[test]
+    #[serial]
+    fn test_dispatch_save_reaches_app() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        app.manage(ProjectCache::default());
+        let value = dispatch(
+            app,
+            "create_project_command",
+            serde_json::json!({ "name": "Test Project", "description": "Test Description" }),
+        )
+        .unwrap();
+        let mut project: Project = serde_json::from_value(value).unwrap();
+        // Cached by the window before the API changes it
+        let read = || {
+            block_on(project_commands::read_project(
+                app.clone(),
+                project.id.to_vec(),
+            ))
+            .data
+            .expect("Failed to read project")
+        };
+        assert_eq!(project, read());
+        project.boards = vec![test_board("Board", vec![test_list("List", &["Item"])])];
+        let value = dispatch(
+            app,
+            "save_project",
+            serde_json::json!({ "project": project }),
+        )
+        .expect("Failed to save project");
+        let saved: Project = serde_json::from_value(value).unwrap();
+        assert_eq!(saved, read());
+        let board = test_board("Board", vec![test_list("List", &["Item", "Other"])]);
+        let value = dispatch(
+            app,
+            "save_board",
+            serde_json::json!({
+                "projectId": project.id,
+                "boardName": "Board",
+                "board": board,
+                "revision": saved.revision,
+            }),
+        )
+        .expect("Failed to save board");
+        assert_eq!(serde_json::json!(saved.revision + 1), value);
+        let stored = read();
+        assert_eq!(saved.revision + 1, stored.revision);
+        assert_eq!(vec![board], stored.boards);
+        delete(app, project.id);
+    }
+
+    #[test]
+    #[serial]
+    fn test_dispatch_save_project_conflict() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let project = project::create_project(app, "Test Project", "Test Description").unwrap();
+        let saved = project::save_project(app, &project).unwrap();
+        let err = dispatch(
+            app,
+            "save_project",
+            serde_json::json!({ "project": project }),
+        )
+        .unwrap_err();
+        assert_eq!(-32000, err.code);
+        assert_eq!(Some(serde_json::to_value(&saved).unwrap()), err.data);
+        delete(app, project.id);
+    }
+
+    #[test]
+    fn test_dispatch_errors() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let err = dispatch(app, "unknown", Value::Null).unwrap_err();
+        assert_eq!(-32601, err.code);
+        let err = dispatch(app, "read_project", serde_json::json!({})).unwrap_err();
+        assert_eq!(-32602, err.code);
+        let err = dispatch(
+            app,
+            "create_project_command",
+            serde_json::json!({ "name": "", "description": "" }),
+        )
+        .unwrap_err();
+        assert_eq!(-32000, err.code);
+        assert_eq!(
+            "name: Must not be empty; description: Must not be empty",
+            err.message
+        );
+        // Field errors travel in the error data
+        assert_eq!(
+            Some(serde_json::json!([
+                { "field": "name", "message": "Must not be empty" },
+                { "field": "description", "message": "Must not be empty" },
+            ])),
+            err.data
+        );
+    }
+
+    #[test]
+    fn test_server_requires_token() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let server = ApiServer::start(app.clone(), 0, "secret").unwrap();
+        let (status, _) = post(server.port(), "wrong", "{}");
+        assert_eq!(401, status);
+        assert!(ApiServer::start(app.clone(), 0, "").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_server_round_trip() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let server = ApiServer::start(app.clone(), 0, "secret").unwrap();
+        let (status, body) = post(
+            server.port(),
+            "secret",
+            "{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"create_project_command\",\"params\":{\"name\":\"Test Project\",\"description\":\"Test Description\"}}",
+        );
+        assert_eq!(200, status);
+        let response: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(7, response["id"]);
+        assert_eq!("Test Project", response["result"]["name"]);
+        let project: Project = serde_json::from_value(response["result"].clone()).unwrap();
+        delete(app, project.id);
+        let (_, body) = post(server.port(), "secret", "not json");
+        let response: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(-32700, response["error"]["code"]);
+    }
+}
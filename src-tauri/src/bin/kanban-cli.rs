@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    kanban_board_lib::cli::run()
+}
@@ -0,0 +1,701 @@
+mod table;
+
+use crate::{
+    commands::CommandResult,
+    errors::{
+        error_log,
+        kanban_error::{KanbanError, KanbanErrorKind},
+    },
+    kanban::{
+        board::Board,
+        board_list::BoardList,
+        config::{self, Configs},
+        data_dir, diff, export,
+        project::{self, AppPathProvider, PathProvider, Project},
+        snapshot, workspace,
+    },
+};
+use clap::{Parser, Subcommand};
+use std::{fs, io::Read, path::PathBuf, process::ExitCode};
+
+// Must match `identifier` in tauri.conf.json so the CLI and the app share data
+const APP_IDENTIFIER: &str = "com.kanban-board.app";
+
+#[derive(Parser)]
+#[command(
+    name = "kanban-cli",
+    version,
+    about = "Manage Kanban Board projects from the shell"
+)]
+struct Cli {
+    /// Data directory, defaults to the one used by the app
+    #[arg(long, env = "KANBAN_DATA_DIR", global = true)]
+    data_dir: Option<PathBuf>,
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List all projects
+    List {
+        /// Also list archived projects
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Create an empty project
+    Create { name: String, description: String },
+    /// Show a project, or one of its boards as a table
+    Show {
+        /// Project ID or name
+        project: String,
+        /// Board name or 1-based index
+        board: Option<String>,
+    },
+    /// Add an item to a list
+    AddItem {
+        project: String,
+        board: String,
+        /// List title or 1-based index
+        list: String,
+        content: String,
+        /// 1-based position in the list, defaults to the end
+        #[arg(long)]
+        position: Option<usize>,
+    },
+    /// Move an item to another list
+    MoveItem {
+        project: String,
+        board: String,
+        list: String,
+        /// 1-based index of the item in the list
+        item: usize,
+        /// Destination list title or 1-based index
+        to_list: String,
+        /// Destination board, defaults to the source board
+        #[arg(long)]
+        to_board: Option<String>,
+        /// 1-based position in the destination list, defaults to the end
+        #[arg(long)]
+        position: Option<usize>,
+    },
+    /// Remove an item from a list
+    RemoveItem {
+        project: String,
+        board: String,
+        list: String,
+        /// 1-based index of the item in the list
+        item: usize,
+    },
+    /// Show what changed in a project since another project or a snapshot
+    Diff {
+        project: String,
+        /// Project to compare against
+        #[arg(required_unless_present = "snapshot")]
+        against: Option<String>,
+        /// Timestamp of a snapshot of the project to compare against
+        #[arg(long, conflicts_with = "against")]
+        snapshot: Option<u64>,
+    },
+    /// Export a project as JSON
+    Export {
+        project: String,
+        /// Output file, defaults to stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Move a project into the archive
+    Archive { project: String },
+    /// Restore an archived project
+    Unarchive {
+        /// Archived project ID or name
+        project: String,
+    },
+    /// Import a project from a JSON export
+    Import {
+        /// Input file, "-" reads from stdin
+        file: PathBuf,
+        /// Assign a new ID instead of refusing to overwrite an existing project
+        #[arg(long)]
+        new_id: bool,
+    },
+}
+
+struct DataDir {
+    path: PathBuf,
+}
+
+impl AppPathProvider for DataDir {
+    type Path = DataDir;
+    fn path(&self) -> &Self::Path {
+        self
+    }
+}
+
+impl PathProvider for DataDir {
+    fn app_data_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.path.clone())
+    }
+}
+
+struct CliOutput {
+    data: serde_json::Value,
+    text: String,
+}
+
+impl CliOutput {
+    fn new<T: serde::Serialize>(data: &T, text: String) -> Result<Self, KanbanError> {
+        let data = serde_json::to_value(data)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+        Ok(CliOutput { data, text })
+    }
+}
+
+fn default_data_dir() -> Result<PathBuf, KanbanError> {
+    let default_dir = dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| {
+            KanbanError::new(
+                KanbanErrorKind::IoError,
+                "Unable to locate the data directory, please pass --data-dir",
+            )
+        })?;
+    // Same as the app, so a moved or portable data directory and the active
+    // workspace are found too
+    let location = data_dir::locate(&default_dir, data_dir::executable_dir().as_deref())?;
+    let workspaces = workspace::read_workspaces(&location.path)?;
+    Ok(workspace::workspace_dir(&location.path, &workspaces.active))
+}
+
+fn format_id(id: &[u8]) -> String {
+    id.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn parse_id(reference: &str) -> Option<[u8; 16]> {
+    if reference.len() != 32 || !reference.is_ascii() {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&reference[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(id)
+}
+
+fn find_project_id(projects: Vec<Project>, reference: &str) -> Result<[u8; 16], KanbanError> {
+    if let Some(id) = parse_id(reference) {
+        return Ok(id);
+    }
+    let matches: Vec<Project> = projects
+        .into_iter()
+        .filter(|p| p.name == reference)
+        .collect();
+    match matches.len() {
+        0 => Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!("Project not found: {}", reference),
+        )),
+        1 => Ok(matches[0].id),
+        _ => Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!("Ambiguous project name, use the project ID: {}", reference),
+        )),
+    }
+}
+
+fn find_project<P: AppPathProvider>(app: &P, reference: &str) -> Result<Project, KanbanError> {
+    let id = find_project_id(project::get_all_projects_info(app)?, reference)?;
+    project::read_project(app, &id)
+}
+
+fn find_index<'a, I: Iterator<Item = &'a str>>(
+    names: I,
+    reference: &str,
+    kind: &str,
+) -> Result<usize, KanbanError> {
+    let names: Vec<&str> = names.collect();
+    if let Some(index) = names.iter().position(|name| *name == reference) {
+        return Ok(index);
+    }
+    match reference.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= names.len() => Ok(n - 1),
+        _ => Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!("{} not found: {}", kind, reference),
+        )),
+    }
+}
+
+fn find_board(project: &Project, reference: &str) -> Result<usize, KanbanError> {
+    find_index(
+        project.boards.iter().map(|b| b.name.as_str()),
+        reference,
+        "Board",
+    )
+}
+
+fn find_list(board: &Board, reference: &str) -> Result<usize, KanbanError> {
+    find_index(
+        board.lists.iter().map(|l| l.title.as_str()),
+        reference,
+        "List",
+    )
+}
+
+fn insert_position(list: &BoardList, position: Option<usize>) -> Result<usize, KanbanError> {
+    match position {
+        None => Ok(list.items.len()),
+        Some(n) if n >= 1 && n <= list.items.len() + 1 => Ok(n - 1),
+        Some(n) => Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!("Invalid position {} in list {}", n, list.title),
+        )),
+    }
+}
+
+fn item_index(list: &BoardList, item: usize) -> Result<usize, KanbanError> {
+    if item == 0 || item > list.items.len() {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!("Item {} not found in list {}", item, list.title),
+        ));
+    }
+    Ok(item - 1)
+}
+
+fn add_item(
+    board: &mut Board,
+    list: &str,
+    content: &str,
+    position: Option<usize>,
+) -> Result<(), KanbanError> {
+    let list_index = find_list(board, list)?;
+    let list = &mut board.lists[list_index];
+    let index = insert_position(list, position)?;
+    list.items.insert(index, content.to_string());
+    Ok(())
+}
+
+fn move_item(
+    project: &mut Project,
+    from: (usize, &str, usize),
+    to: (usize, &str, Option<usize>),
+) -> Result<(), KanbanError> {
+    let (from_board, from_list, item) = from;
+    let (to_board, to_list, position) = to;
+    let from_list = find_list(&project.boards[from_board], from_list)?;
+    let to_list = find_list(&project.boards[to_board], to_list)?;
+    let item = item_index(&project.boards[from_board].lists[from_list], item)?;
+    let content = project.boards[from_board].lists[from_list]
+        .items
+        .remove(item);
+    let destination = &mut project.boards[to_board].lists[to_list];
+    match insert_position(destination, position) {
+        Ok(index) => {
+            destination.items.insert(index, content);
+            Ok(())
+        }
+        Err(err) => {
+            // Put the item back so the project is left untouched
+            project.boards[from_board].lists[from_list]
+                .items
+                .insert(item, content);
+            Err(err)
+        }
+    }
+}
+
+fn remove_item(board: &mut Board, list: &str, item: usize) -> Result<String, KanbanError> {
+    let list_index = find_list(board, list)?;
+    let list = &mut board.lists[list_index];
+    let index = item_index(list, item)?;
+    Ok(list.items.remove(index))
+}
+
+fn render_project(project: &Project) -> String {
+    let mut lines = vec![
+        format!("{} ({})", project.name, format_id(&project.id)),
+        project.description.clone(),
+    ];
+    for (i, board) in project.boards.iter().enumerate() {
+        let items: usize = board.lists.iter().map(|l| l.items.len()).sum();
+        lines.push(format!(
+            "  {}. {} ({} lists, {} items){}",
+            i + 1,
+            board.name,
+            board.lists.len(),
+            items,
+            if board.archived { " [archived]" } else { "" }
+        ));
+    }
+    lines.join("\n")
+}
+
+fn execute<P: AppPathProvider>(app: &P, command: Command) -> Result<CliOutput, KanbanError> {
+    match command {
+        Command::List { archived } => {
+            let mut projects = project::get_all_projects_info(app)?;
+            if archived {
+                projects.extend(project::get_archived_projects_info(app)?);
+            }
+            let text = projects
+                .iter()
+                .map(|p| match p.archived {
+                    true => format!("{}  {} [archived]", format_id(&p.id), p.name),
+                    false => format!("{}  {}", format_id(&p.id), p.name),
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            CliOutput::new(&projects, text)
+        }
+        Command::Create { name, description } => {
+            let project = project::create_project(app, &name, &description)?;
+            let text = format_id(&project.id);
+            CliOutput::new(&project, text)
+        }
+        Command::Show { project, board } => {
+            let project = find_project(app, &project)?;
+            match board {
+                None => CliOutput::new(&project, render_project(&project)),
+                Some(board) => {
+                    let board = &project.boards[find_board(&project, &board)?];
+                    CliOutput::new(board, table::render_board(board))
+                }
+            }
+        }
+        Command::AddItem {
+            project,
+            board,
+            list,
+            content,
+            position,
+        } => {
+            let mut project = find_project(app, &project)?;
+            let board_index = find_board(&project, &board)?;
+            add_item(&mut project.boards[board_index], &list, &content, position)?;
+            let project = project::save_project(app, &project)?;
+            let board = &project.boards[board_index];
+            CliOutput::new(board, table::render_board(board))
+        }
+        Command::MoveItem {
+            project,
+            board,
+            list,
+            item,
+            to_list,
+            to_board,
+            position,
+        } => {
+            let mut project = find_project(app, &project)?;
+            let from_board = find_board(&project, &board)?;
+            let to_board = match to_board {
+                Some(to_board) => find_board(&project, &to_board)?,
+                None => from_board,
+            };
+            move_item(
+                &mut project,
+                (from_board, &list, item),
+                (to_board, &to_list, position),
+            )?;
+            let project = project::save_project(app, &project)?;
+            let board = &project.boards[to_board];
+            CliOutput::new(board, table::render_board(board))
+        }
+        Command::RemoveItem {
+            project,
+            board,
+            list,
+            item,
+        } => {
+            let mut project = find_project(app, &project)?;
+            let board_index = find_board(&project, &board)?;
+            let removed = remove_item(&mut project.boards[board_index], &list, item)?;
+            project::save_project(app, &project)?;
+            CliOutput::new(&removed, format!("Removed: {}", removed))
+        }
+        Command::Diff {
+            project,
+            against,
+            snapshot,
+        } => {
+            let project = find_project(app, &project)?;
+            let old = match (against, snapshot) {
+                (_, Some(timestamp)) => snapshot::preview_snapshot(app, &project.id, timestamp)?,
+                (Some(against), None) => find_project(app, &against)?,
+                (None, None) => {
+                    return Err(KanbanError::new(
+                        KanbanErrorKind::ProjectError,
+                        "Nothing to compare against",
+                    ))
+                }
+            };
+            let changes = diff::diff_projects(&old, &project);
+            CliOutput::new(&changes, diff::render_text(&changes))
+        }
+        Command::Export { project, output } => {
+            let project = find_project(app, &project)?;
+            let json = export::export_project(app, &project.id)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, &json)
+                        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+                    CliOutput::new(
+                        &path,
+                        format!("Exported {} to {}", project.name, path.display()),
+                    )
+                }
+                None => {
+                    let data: serde_json::Value = serde_json::from_str(&json)
+                        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+                    CliOutput::new(&data, json)
+                }
+            }
+        }
+        Command::Import { file, new_id } => {
+            let mut json = String::new();
+            if file.as_os_str() == "-" {
+                std::io::stdin()
+                    .read_to_string(&mut json)
+                    .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+            } else {
+                json = fs::read_to_string(&file)
+                    .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+            }
+            let project = export::import_project(app, &json, new_id)?;
+            let text = format!("Imported {} ({})", project.name, format_id(&project.id));
+            CliOutput::new(&project, text)
+        }
+        Command::Archive { project } => {
+            let id = find_project_id(project::get_all_projects_info(app)?, &project)?;
+            project::archive_project(app, &id)?;
+            let text = format!("Archived {}", format_id(&id));
+            CliOutput::new(&format_id(&id), text)
+        }
+        Command::Unarchive { project } => {
+            let id = find_project_id(project::get_archived_projects_info(app)?, &project)?;
+            let project = project::unarchive_project(app, &id)?;
+            let text = format!("Restored {} ({})", project.name, format_id(&project.id));
+            CliOutput::new(&project, text)
+        }
+    }
+}
+
+/// Writes projects the way the app would, compressed, snapshotted and
+/// committed as its configs say.
+fn apply_configs(app: &DataDir) {
+    let configs = config::peek_configs(app).unwrap_or_else(|err| {
+        error_log::report("Configs could not be read, using the defaults", err);
+        Configs::default()
+    });
+    configs.apply_storage();
+}
+
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.data_dir {
+        Some(path) => Ok(DataDir { path }),
+        None => default_data_dir().map(|path| DataDir { path }),
+    }
+    .and_then(|app| {
+        apply_configs(&app);
+        execute(&app, cli.command)
+    });
+    // Such as a failed snapshot, which leaves the command itself done
+    for error in error_log::take() {
+        eprintln!("Warning: {}", error);
+    }
+    match result {
+        Ok(output) => {
+            if cli.json {
+                let result = CommandResult {
+                    success: true,
+                    data: Some(output.data),
+                    message: None,
+                    errors: None,
+                };
+                println!("{}", serde_json::to_string(&result).unwrap_or_default());
+            } else if !output.text.is_empty() {
+                println!("{}", output.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if cli.json {
+                let result: CommandResult<()> = CommandResult {
+                    success: false,
+                    data: None,
+                    message: Some(err.message),
+                    errors: None,
+                };
+                println!("{}", serde_json::to_string(&result).unwrap_or_default());
+            } else {
+                eprintln!("Error: {}", err.message);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{test_board, test_list, test_project};
+    use serial_test::serial;
+    use tempdir::TempDir;
+
+    fn sample_project() -> Project {
+        let done = BoardList {
+            color: [0x00, 0x00, 0x00],
+            ..test_list("Done", &["Item 3"])
+        };
+        let lists = vec![test_list("To Do", &["Item 1", "Item 2"]), done];
+        test_project([0x01; 16], vec![test_board("Test Board", lists)])
+    }
+
+    #[test]
+    fn test_parse_id() {
+        let id = parse_id("0102030405060708090A0B0C0D0E0F10").expect("Failed to parse ID");
+        assert_eq!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16], id);
+        assert_eq!("0102030405060708090A0B0C0D0E0F10", format_id(&id));
+        assert!(parse_id("0102030405060708090a0b0c0d0e0f10").is_some());
+        assert!(parse_id("Test Project").is_none());
+        assert!(parse_id("0102030405060708090A0B0C0D0E0FXX").is_none());
+    }
+
+    #[test]
+    fn test_find_list_by_name_and_index() {
+        let project = sample_project();
+        let board = &project.boards[0];
+        assert_eq!(1, find_list(board, "Done").unwrap());
+        assert_eq!(0, find_list(board, "1").unwrap());
+        let result = find_list(board, "3");
+        assert!(result.is_err());
+        assert_eq!("List not found: 3", result.unwrap_err().message);
+    }
+
+    #[test]
+    fn test_add_item() {
+        let mut project = sample_project();
+        add_item(&mut project.boards[0], "To Do", "Item 0", Some(1)).unwrap();
+        add_item(&mut project.boards[0], "To Do", "Item 4", None).unwrap();
+        assert_eq!(
+            ["Item 0", "Item 1", "Item 2", "Item 4"]
+                .map(|s| s.to_string())
+                .to_vec(),
+            project.boards[0].lists[0].items
+        );
+        assert!(add_item(&mut project.boards[0], "To Do", "Item 5", Some(6)).is_err());
+    }
+
+    #[test]
+    fn test_move_item() {
+        let mut project = sample_project();
+        move_item(&mut project, (0, "To Do", 1), (0, "Done", Some(1))).unwrap();
+        assert_eq!(
+            ["Item 2"].map(|s| s.to_string()).to_vec(),
+            project.boards[0].lists[0].items
+        );
+        assert_eq!(
+            ["Item 1", "Item 3"].map(|s| s.to_string()).to_vec(),
+            project.boards[0].lists[1].items
+        );
+        // Invalid destination leaves the project untouched
+        let before = project.clone();
+        assert!(move_item(&mut project, (0, "Done", 1), (0, "To Do", Some(5))).is_err());
+        assert_eq!(before, project);
+    }
+
+    #[test]
+    fn test_remove_item() {
+        let mut project = sample_project();
+        let removed = remove_item(&mut project.boards[0], "To Do", 2).unwrap();
+        assert_eq!("Item 2", removed);
+        assert_eq!(
+            ["Item 1"].map(|s| s.to_string()).to_vec(),
+            project.boards[0].lists[0].items
+        );
+        assert!(remove_item(&mut project.boards[0], "To Do", 2).is_err());
+    }
+
+    #[test]
+    fn test_execute_against_data_dir() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let app = DataDir {
+            path: dir.path().to_path_buf(),
+        };
+        project::save_project(&app, &sample_project()).expect("Failed to save project");
+        let output = execute(
+            &app,
+            Command::AddItem {
+                project: "Test Project".to_string(),
+                board: "1".to_string(),
+                list: "Done".to_string(),
+                content: "Item 4".to_string(),
+                position: None,
+            },
+        )
+        .expect("Failed to add item");
+        assert!(output.text.contains("Item 4"));
+        let saved = project::read_project(&app, &[0x01; 16]).unwrap();
+        assert_eq!(
+            ["Item 3", "Item 4"].map(|s| s.to_string()).to_vec(),
+            saved.boards[0].lists[1].items
+        );
+        let output = execute(
+            &app,
+            Command::Diff {
+                project: "Test Project".to_string(),
+                against: None,
+                snapshot: Some(1),
+            },
+        );
+        assert!(output.is_err());
+        let output =
+            execute(&app, Command::List { archived: false }).expect("Failed to list projects");
+        assert_eq!(1, output.data.as_array().unwrap().len());
+        execute(
+            &app,
+            Command::Archive {
+                project: "Test Project".to_string(),
+            },
+        )
+        .expect("Failed to archive project");
+        let output = execute(&app, Command::List { archived: false }).unwrap();
+        assert!(output.data.as_array().unwrap().is_empty());
+        let output = execute(&app, Command::List { archived: true }).unwrap();
+        assert!(output.text.ends_with("Test Project [archived]"));
+        execute(
+            &app,
+            Command::Unarchive {
+                project: "Test Project".to_string(),
+            },
+        )
+        .expect("Failed to unarchive project");
+        assert_eq!(saved, project::read_project(&app, &[0x01; 16]).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_apply_configs() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let app = DataDir {
+            path: dir.path().to_path_buf(),
+        };
+        fs::create_dir_all(dir.path().join(project::PROJECT_PATH)).unwrap();
+        let configs = Configs {
+            snapshots_enabled: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&configs).unwrap();
+        fs::write(dir.path().join("configs.json"), json).unwrap();
+        apply_configs(&app);
+        // Saved like in the app, with a snapshot
+        let saved = project::save_project(&app, &sample_project());
+        snapshot::set_policy(None);
+        let snapshots = snapshot::list_snapshots(&app, &saved.unwrap().id).unwrap();
+        assert_eq!(1, snapshots.len());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::kanban::board::Board;
+
+const MAX_COLUMN_WIDTH: usize = 32;
+
+fn truncate(text: &str, width: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = chars[..width - 1].iter().collect();
+    truncated.push('…');
+    truncated
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    format!("{}{}", text, " ".repeat(width.saturating_sub(length)))
+}
+
+fn separator(widths: &[usize]) -> String {
+    let cells: Vec<String> = widths.iter().map(|w| "-".repeat(w + 2)).collect();
+    format!("+{}+", cells.join("+"))
+}
+
+fn row(cells: &[String], widths: &[usize]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!(" {} ", pad(&truncate(cell, *width), *width)))
+        .collect();
+    format!("|{}|", cells.join("|"))
+}
+
+/// Renders a board with one column per list and one row per item position.
+pub(crate) fn render_board(board: &Board) -> String {
+    let mut lines: Vec<String> = vec![board.name.clone()];
+    if board.lists.is_empty() {
+        lines.push("(no lists)".to_string());
+        return lines.join("\n");
+    }
+    let widths: Vec<usize> = board
+        .lists
+        .iter()
+        .map(|list| {
+            list.items
+                .iter()
+                .map(|item| item.chars().count())
+                .chain([list.title.chars().count()])
+                .max()
+                .unwrap_or(0)
+                .clamp(1, MAX_COLUMN_WIDTH)
+        })
+        .collect();
+    let titles: Vec<String> = board.lists.iter().map(|l| l.title.clone()).collect();
+    let rows_count = board.lists.iter().map(|l| l.items.len()).max().unwrap_or(0);
+    lines.push(separator(&widths));
+    lines.push(row(&titles, &widths));
+    lines.push(separator(&widths));
+    for i in 0..rows_count {
+        let cells: Vec<String> = board
+            .lists
+            .iter()
+            .map(|l| l.items.get(i).cloned().unwrap_or_default())
+            .collect();
+        lines.push(row(&cells, &widths));
+    }
+    if rows_count > 0 {
+        lines.push(separator(&widths));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::board_list::BoardList;
+
+    #[test]
+    fn test_render_board() {
+        let board = Board {
+            name: "Test Board".to_string(),
+            lists: [
+                BoardList {
+                    title: "To Do".to_string(),
+                    color: [0xFF, 0xFF, 0xFF],
+                    items: ["Item 1", "Item 2"].map(|s| s.to_string()).to_vec(),
+                },
+                BoardList {
+                    title: "Done".to_string(),
+                    color: [0x00, 0x00, 0x00],
+                    items: ["Item 3"].map(|s| s.to_string()).to_vec(),
+                },
+            ]
+            .to_vec(),
+            archived: false,
+        };
+        let expected = [
+            "Test Board",
+            "+--------+--------+",
+            "| To Do  | Done   |",
+            "+--------+--------+",
+            "| Item 1 | Item 3 |",
+            "| Item 2 |        |",
+            "+--------+--------+",
+        ]
+        .join("\n");
+        assert_eq!(expected, render_board(&board));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!("Hello", truncate("Hello", 5));
+        assert_eq!("Hel…", truncate("Hello", 4));
+        assert_eq!("日本…", truncate("日本語テキスト", 3));
+    }
+}
@@ -1,9 +1,51 @@
 pub mod configs_commands;
+pub mod export_commands;
+pub mod group_commands;
+pub mod history_commands;
 pub mod project_commands;
+pub mod snapshot_commands;
+pub mod sync_commands;
+pub mod workspace_commands;
+
+use crate::{
+    errors::kanban_error::KanbanError,
+    kanban::validation::{self, FieldError},
+};
 
 #[derive(Debug, serde::Serialize)]
 pub struct CommandResult<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
+    /// Invalid fields, when the command failed validation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+}
+
+impl<T> CommandResult<T> {
+    pub(crate) fn from_error(err: KanbanError) -> Self {
+        CommandResult {
+            success: false,
+            data: None,
+            errors: validation::field_errors(&err).map(<[FieldError]>::to_vec),
+            message: Some(err.message),
+        }
+    }
+}
+
+/// Runs blocking file work on the blocking thread pool, so the UI thread stays free.
+pub(crate) async fn run_blocking<T, F>(f: F) -> CommandResult<T>
+where
+    F: FnOnce() -> CommandResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tauri::async_runtime::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(err) => CommandResult {
+            success: false,
+            data: None,
+            message: Some(err.to_string()),
+            errors: None,
+        },
+    }
 }
@@ -1,38 +1,210 @@
-use crate::{commands::CommandResult, kanban::config};
+use crate::{
+    api_server,
+    commands::{run_blocking, CommandResult},
+    errors::{
+        error_log::{self, LoggedError},
+        kanban_error::{KanbanError, KanbanErrorKind},
+    },
+    events,
+    kanban::{config, data_dir},
+    peer_sync, watcher, webdav,
+};
+use std::path::Path;
+use tauri::Manager;
 
+/// Puts `configs` into effect, the error says which service failed to start.
+pub(crate) fn apply_configs<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    configs: &config::Configs,
+) -> Result<(), String> {
+    configs.apply_storage();
+    events::emit_configs_changed(app);
+    if let Some(state) = app.try_state::<api_server::ApiServerState>() {
+        api_server::apply_configs(app, &state, configs)
+            .map_err(|err| format!("the local API failed to start: {}", err.message))?;
+    }
+    if let Some(state) = app.try_state::<peer_sync::PeerSyncState>() {
+        peer_sync::apply_configs(app, &state, configs)
+            .map_err(|err| format!("peer sync failed to start: {}", err.message))?;
+    }
+    if let Some(state) = app.try_state::<webdav::WebDavState>() {
+        webdav::apply_configs(app, &state, configs)
+            .map_err(|err| format!("WebDAV sync failed to start: {}", err.message))?;
+    }
+    Ok(())
+}
+
+/// Stops the watcher and every service that writes to the data directory in
+/// the background. Each one waits for what it was doing to finish.
+fn stop_services<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(state) = app.try_state::<watcher::WatcherState>() {
+        watcher::stop(&state);
+    }
+    // The defaults leave every service off, which cannot fail
+    let off = config::Configs::default();
+    if let Some(state) = app.try_state::<api_server::ApiServerState>() {
+        let _ = api_server::apply_configs(app, &state, &off);
+    }
+    if let Some(state) = app.try_state::<peer_sync::PeerSyncState>() {
+        let _ = peer_sync::apply_configs(app, &state, &off);
+    }
+    if let Some(state) = app.try_state::<webdav::WebDavState>() {
+        let _ = webdav::apply_configs(app, &state, &off);
+    }
+}
+
+/// Starts what `stop_services` stopped, as the configs say.
+fn restart_services<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(state) = app.try_state::<watcher::WatcherState>() {
+        if let Err(err) = watcher::start(app, &state) {
+            error_log::report("Failed to watch for external changes", err);
+        }
+    }
+    let result = config::read_configs(app)
+        .map_err(|err| err.message)
+        .and_then(|configs| apply_configs(app, &configs));
+    if let Err(reason) = result {
+        error_log::report("Failed to restart services", reason);
+    }
+}
+
+/// Errors kept since the window last asked. The window calls this once it
+/// listens for `error-logged`, later errors are sent to it straight away.
 #[tauri::command]
-pub fn save_configs<R: tauri::Runtime>(
+pub fn take_logged_errors<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
-    configs: config::Configs,
-) -> CommandResult<()> {
-    let result = config::save_configs(&app, &configs);
-    if result.is_err() {
-        return CommandResult {
-            success: false,
-            data: None,
-            message: Some(result.unwrap_err().message),
-        };
-    }
+) -> CommandResult<Vec<LoggedError>> {
+    error_log::set_listener(move |error| events::emit_error_logged(&app, error));
     CommandResult {
         success: true,
-        data: None,
+        data: Some(error_log::take()),
         message: None,
+        errors: None,
     }
 }
 
 #[tauri::command]
-pub fn get_configs<R: tauri::Runtime>(app: tauri::AppHandle<R>) -> CommandResult<config::Configs> {
-    let result = config::read_configs(&app);
-    if result.is_err() {
-        return CommandResult {
-            success: false,
+pub async fn save_configs<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    configs: config::Configs,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        let result = config::save_configs(&app, &configs);
+        if result.is_err() {
+            return CommandResult {
+                success: false,
+                data: None,
+                message: Some(result.unwrap_err().message),
+                errors: None,
+            };
+        }
+        watcher::remember_configs(&app);
+        if let Err(reason) = apply_configs(&app, &configs) {
+            return CommandResult {
+                success: false,
+                data: None,
+                message: Some(format!("Configs saved, but {}", reason)),
+                errors: None,
+            };
+        }
+        CommandResult {
+            success: true,
             data: None,
-            message: Some(result.unwrap_err().message),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_configs<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<config::Configs> {
+    run_blocking(move || {
+        let result = config::read_configs(&app);
+        if result.is_err() {
+            return CommandResult {
+                success: false,
+                data: None,
+                message: Some(result.unwrap_err().message),
+                errors: None,
+            };
+        }
+        CommandResult {
+            success: true,
+            data: Some(result.unwrap()),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_data_directory<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<data_dir::DataLocation> {
+    run_blocking(move || {
+        let location = match data_dir::current() {
+            Some(location) => Ok(location),
+            None => app
+                .path()
+                .app_data_dir()
+                .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))
+                .and_then(|dir| data_dir::locate(&dir, data_dir::executable_dir().as_deref())),
         };
-    }
-    CommandResult {
-        success: true,
-        data: Some(result.unwrap()),
-        message: None,
-    }
+        match location {
+            Ok(location) => CommandResult {
+                success: true,
+                data: Some(location),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+/// Moves all data to `path` and restarts the app, so everything picks up
+/// the new location.
+#[tauri::command]
+pub async fn move_data_directory<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    path: String,
+) -> CommandResult<data_dir::DataLocation> {
+    run_blocking(move || {
+        let result = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))
+            .and_then(|default_dir| {
+                let current = match data_dir::current() {
+                    Some(location) => location,
+                    None => data_dir::locate(&default_dir, data_dir::executable_dir().as_deref())?,
+                };
+                // Nothing else may write to the data while it is copied
+                stop_services(&app);
+                let moved = data_dir::move_data(&default_dir, &current, Path::new(&path));
+                if moved.is_err() {
+                    restart_services(&app);
+                }
+                moved
+            });
+        match result {
+            Ok(location) => {
+                data_dir::set_location(location.clone());
+                app.request_restart();
+                CommandResult {
+                    success: true,
+                    data: Some(location),
+                    message: None,
+                    errors: None,
+                }
+            }
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
 }
@@ -0,0 +1,93 @@
+use super::{run_blocking, CommandResult};
+use crate::events::{self, ProjectChange};
+use crate::kanban::{export, project};
+use crate::watcher;
+
+#[tauri::command]
+pub async fn export_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    progress_id: Option<String>,
+) -> CommandResult<String> {
+    run_blocking(move || {
+        let result = export::export_project_with_progress(&app, &project_id, |done, total| {
+            if let Some(task) = &progress_id {
+                events::emit_progress(&app, task, done, total);
+            }
+        });
+        match result {
+            Ok(json) => CommandResult {
+                success: true,
+                data: Some(json),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult {
+                success: false,
+                data: None,
+                message: Some(err.message),
+                errors: None,
+            },
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn import_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    json: String,
+    new_id: bool,
+    progress_id: Option<String>,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = export::import_project_with_progress(&app, &json, new_id, |done, total| {
+            if let Some(task) = &progress_id {
+                events::emit_progress(&app, task, done, total);
+            }
+        });
+        let project = match result {
+            Ok(project) => project,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        watcher::project_written(&app, &ProjectChange::Created(project.id));
+        CommandResult {
+            success: true,
+            data: Some(project),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_export_and_import_project() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let project = project::create_project(app, "Test Project", "Test Description")
+            .expect("Failed to create project");
+        let result = tauri::async_runtime::block_on(export_project(
+            app.clone(),
+            project.id.to_vec(),
+            Some("export".to_string()),
+        ));
+        assert!(result.success);
+        let json = result.data.unwrap();
+        let result =
+            tauri::async_runtime::block_on(import_project(app.clone(), json.clone(), false, None));
+        assert!(!result.success);
+        let result = tauri::async_runtime::block_on(import_project(app.clone(), json, true, None));
+        assert!(result.success);
+        let imported = result.data.unwrap();
+        assert_ne!(project.id, imported.id);
+        project::delete_project(app, &project.id).unwrap();
+        project::delete_project(app, &imported.id).unwrap();
+    }
+}
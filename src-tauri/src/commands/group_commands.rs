@@ -0,0 +1,92 @@
+use super::{run_blocking, CommandResult};
+use crate::errors::kanban_error::KanbanError;
+use crate::kanban::project_group::{self, Entry};
+
+fn entries_result(result: Result<Vec<Entry>, KanbanError>) -> CommandResult<Vec<Entry>> {
+    match result {
+        Ok(entries) => CommandResult {
+            success: true,
+            data: Some(entries),
+            message: None,
+            errors: None,
+        },
+        Err(err) => CommandResult::from_error(err),
+    }
+}
+
+/// Groups and projects in their sidebar order, with new projects at the end.
+#[tauri::command]
+pub async fn get_project_groups<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || entries_result(project_group::read_groups(&app))).await
+}
+
+#[tauri::command]
+pub async fn create_group<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    name: String,
+    parent_id: Option<String>,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || {
+        entries_result(project_group::create_group(
+            &app,
+            &name,
+            parent_id.as_deref(),
+        ))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_group<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    group_id: String,
+    name: String,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || entries_result(project_group::rename_group(&app, &group_id, &name))).await
+}
+
+#[tauri::command]
+pub async fn move_group<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    group_id: String,
+    parent_id: Option<String>,
+    index: usize,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || {
+        entries_result(project_group::move_group(
+            &app,
+            &group_id,
+            parent_id.as_deref(),
+            index,
+        ))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_group<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    group_id: String,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || entries_result(project_group::delete_group(&app, &group_id))).await
+}
+
+#[tauri::command]
+pub async fn move_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    group_id: Option<String>,
+    index: usize,
+) -> CommandResult<Vec<Entry>> {
+    run_blocking(move || {
+        entries_result(project_group::move_project(
+            &app,
+            &project_id,
+            group_id.as_deref(),
+            index,
+        ))
+    })
+    .await
+}
@@ -0,0 +1,94 @@
+use super::{run_blocking, CommandResult};
+use crate::events::ProjectChange;
+use crate::kanban::{diff::Change, history, project};
+use crate::watcher;
+
+#[tauri::command]
+pub async fn list_history<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<Vec<history::HistoryEntry>> {
+    run_blocking(move || match history::list_history(&app, &project_id) {
+        Ok(entries) => CommandResult {
+            success: true,
+            data: Some(entries),
+            message: None,
+            errors: None,
+        },
+        Err(err) => CommandResult::from_error(err),
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn preview_history<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    commit: String,
+) -> CommandResult<project::Project> {
+    run_blocking(
+        move || match history::project_at(&app, &project_id, &commit) {
+            Ok(project) => CommandResult {
+                success: true,
+                data: Some(project),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Changes from the project in `commit` to the stored project.
+#[tauri::command]
+pub async fn diff_history<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    commit: String,
+) -> CommandResult<Vec<Change>> {
+    run_blocking(
+        move || match history::diff_history(&app, &project_id, &commit) {
+            Ok(changes) => CommandResult {
+                success: true,
+                data: Some(changes),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Saves the project in `commit` over the project, `revision` being the
+/// revision the window holds. A conflict carries the stored copy like
+/// `save_project`.
+#[tauri::command]
+pub async fn checkout_history<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    commit: String,
+    revision: u64,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = history::checkout_history(&app, &project_id, &commit, revision);
+        let project = match result {
+            Ok(project) => project,
+            Err(err) => {
+                return CommandResult {
+                    data: project::conflict_copy(&err).cloned(),
+                    ..CommandResult::from_error(err)
+                }
+            }
+        };
+        watcher::project_written(&app, &ProjectChange::Updated(project.id));
+        CommandResult {
+            success: true,
+            data: Some(project),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
@@ -1,107 +1,439 @@
-use super::CommandResult;
-use crate::kanban::project;
+use super::{run_blocking, CommandResult};
+use crate::events::{self, ProjectChange};
+use crate::kanban::{board, diff, merge, project, project_cache::ProjectCache};
+use crate::watcher::{self, WatcherState};
+use tauri::Manager;
 
 #[tauri::command]
-pub fn create_project_command<R: tauri::Runtime>(
+pub async fn create_project_command<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
-    name: &str,
-    description: &str,
+    name: String,
+    description: String,
 ) -> CommandResult<project::Project> {
-    let result = project::create_project(&app, name, description);
-    if result.is_err() {
-        let err = result.unwrap_err();
-        return CommandResult {
-            success: false,
-            data: None,
-            message: Some(err.message),
-        };
-    }
-    let project = result.unwrap();
-    CommandResult {
-        success: true,
-        data: Some(project),
-        message: None,
-    }
+    run_blocking(move || {
+        let result = project::create_project(&app, &name, &description);
+        if result.is_err() {
+            return CommandResult::from_error(result.unwrap_err());
+        }
+        let project = result.unwrap();
+        watcher::project_written(&app, &ProjectChange::Created(project.id));
+        CommandResult {
+            success: true,
+            data: Some(project),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn get_all_projects<R: tauri::Runtime>(
+pub async fn get_all_projects<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
+    progress_id: Option<String>,
+    include_archived: Option<bool>,
 ) -> CommandResult<Vec<project::Project>> {
-    let result = project::get_all_projects_info(&app);
-    if result.is_err() {
-        let err = result.unwrap_err();
-        return CommandResult {
-            success: false,
-            data: None,
-            message: Some(err.message),
+    run_blocking(move || {
+        let result = project::get_all_projects_info_with_progress(&app, |done, total| {
+            if let Some(task) = &progress_id {
+                events::emit_progress(&app, task, done, total);
+            }
+        });
+        if result.is_err() {
+            return CommandResult::from_error(result.unwrap_err());
+        }
+        let mut projects = result.unwrap();
+        if include_archived.unwrap_or(false) {
+            match project::get_archived_projects_info(&app) {
+                Ok(archived) => projects.extend(archived),
+                Err(err) => return CommandResult::from_error(err),
+            }
+        }
+        CommandResult {
+            success: true,
+            data: Some(projects),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn read_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = match app.try_state::<ProjectCache>() {
+            Some(cache) => cache.read_project(&app, &project_id),
+            None => project::read_project(&app, &project_id),
         };
-    }
-    let projects = result.unwrap();
-    CommandResult {
-        success: true,
-        data: Some(projects),
-        message: None,
-    }
+        match result {
+            Ok(project) => CommandResult {
+                success: true,
+                data: Some(project),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn save_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project: project::Project,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = match app.try_state::<ProjectCache>() {
+            Some(cache) => cache.save_project(&app, &project),
+            None => project::save_project(&app, &project),
+        };
+        let saved = match result {
+            Ok(saved) => saved,
+            // On a conflict the stored copy is returned for the window to merge
+            Err(err) => {
+                return CommandResult {
+                    data: project::conflict_copy(&err).cloned(),
+                    ..CommandResult::from_error(err)
+                }
+            }
+        };
+        watcher::project_saved(&app, &project.id);
+        CommandResult {
+            success: true,
+            data: Some(saved),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Merges the window's copy of a project with the stored one, using the
+/// revision the window started from as the base. With `choices` the conflicts
+/// are resolved in order, otherwise they are returned for the window to pick.
+#[tauri::command]
+pub async fn merge_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project: project::Project,
+    choices: Option<Vec<merge::Side>>,
+) -> CommandResult<merge::Merge> {
+    run_blocking(move || {
+        let cache = app.try_state::<ProjectCache>();
+        let stored = match &cache {
+            Some(cache) => cache.read_project(&app, &project.id),
+            None => project::read_project(&app, &project.id),
+        };
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        let base = match cache.and_then(|c| c.revision(&project.id, project.revision)) {
+            Some(base) => Ok(base),
+            None => merge::find_revision(&app, &project.id, project.revision),
+        };
+        let base = match base {
+            Ok(base) => base,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        let result = match choices {
+            Some(choices) => merge::resolve_merge(&base, &project, &stored, &choices),
+            None => Ok(merge::merge_projects(&base, &project, &stored)),
+        };
+        match result {
+            Ok(merged) => CommandResult {
+                success: true,
+                data: Some(merged),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        let result = match app.try_state::<ProjectCache>() {
+            Some(cache) => cache.delete_project(&app, &project_id),
+            None => project::delete_project(&app, &project_id),
+        };
+        if let Err(err) = result {
+            return CommandResult::from_error(err);
+        }
+        if let Ok(id) = <[u8; 16]>::try_from(project_id.as_slice()) {
+            watcher::project_written(&app, &ProjectChange::Deleted(id));
+        }
+        CommandResult {
+            success: true,
+            data: None,
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Moves a project into the archive, out of the project list.
+#[tauri::command]
+pub async fn archive_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        if let Err(err) = project::archive_project(&app, &project_id) {
+            return CommandResult::from_error(err);
+        }
+        if let Ok(id) = <[u8; 16]>::try_from(project_id.as_slice()) {
+            watcher::project_written(&app, &ProjectChange::Deleted(id));
+        }
+        CommandResult {
+            success: true,
+            data: None,
+            message: None,
+            errors: None,
+        }
+    })
+    .await
 }
 
+/// Restores an archived project to the project list as it was archived.
 #[tauri::command]
-pub fn read_project<R: tauri::Runtime>(
+pub async fn unarchive_project<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
     project_id: Vec<u8>,
 ) -> CommandResult<project::Project> {
-    let result = project::read_project(&app, &project_id);
-    if result.is_err() {
+    run_blocking(move || {
+        let project = match project::unarchive_project(&app, &project_id) {
+            Ok(project) => project,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        watcher::project_written(&app, &ProjectChange::Created(project.id));
+        CommandResult {
+            success: true,
+            data: Some(project),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn read_board<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    board_name: String,
+) -> CommandResult<board::Board> {
+    run_blocking(move || {
+        let result = match app.try_state::<ProjectCache>() {
+            Some(cache) => cache.read_board(&app, &project_id, &board_name),
+            None => project::read_board(&app, &project_id, &board_name),
+        };
+        match result {
+            Ok(board) => CommandResult {
+                success: true,
+                data: Some(board),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+/// Saves the board stored as `board_name` on top of the project `revision` and
+/// returns the new revision.
+#[tauri::command]
+pub async fn save_board<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    board_name: String,
+    board: board::Board,
+    revision: u64,
+) -> CommandResult<u64> {
+    run_blocking(move || {
+        let result = match app.try_state::<ProjectCache>() {
+            Some(cache) => cache.save_board(&app, &project_id, &board_name, &board, revision),
+            None => project::save_board(&app, &project_id, &board_name, &board, revision),
+        };
+        let new_revision = match result {
+            Ok(new_revision) => new_revision,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        if let Ok(id) = <[u8; 16]>::try_from(project_id.as_slice()) {
+            watcher::project_saved(&app, &id);
+        }
+        CommandResult {
+            success: true,
+            data: Some(new_revision),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Lets the window report unsaved edits, so external changes to the project
+/// are flagged as conflicts instead of replacing them.
+#[tauri::command]
+pub fn set_project_dirty<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    dirty: bool,
+) -> CommandResult<()> {
+    let Ok(id) = <[u8; 16]>::try_from(project_id.as_slice()) else {
         return CommandResult {
             success: false,
             data: None,
-            message: Some(result.unwrap_err().message),
+            message: Some("Invalid project ID".to_string()),
+            errors: None,
         };
+    };
+    if let Some(state) = app.try_state::<WatcherState>() {
+        state.set_dirty(id, dirty);
     }
     CommandResult {
         success: true,
-        data: Some(result.unwrap()),
+        data: None,
         message: None,
+        errors: None,
     }
 }
 
 #[tauri::command]
-pub fn save_project<R: tauri::Runtime>(
+pub async fn unlock_project<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
-    project: project::Project,
+    project_id: Vec<u8>,
+    passphrase: String,
 ) -> CommandResult<project::Project> {
-    let result = project::save_project(&app, &project);
-    if result.is_err() {
+    run_blocking(
+        move || match project::unlock_project(&app, &project_id, &passphrase) {
+            Ok(project) => CommandResult {
+                success: true,
+                data: Some(project),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub fn lock_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<()> {
+    let Ok(id) = <[u8; 16]>::try_from(project_id.as_slice()) else {
         return CommandResult {
             success: false,
             data: None,
-            message: Some(result.unwrap_err().message),
+            message: Some("Invalid project ID".to_string()),
+            errors: None,
         };
+    };
+    project::lock_project(&id);
+    // The cached copies are decrypted, so they go with the key
+    if let Some(cache) = app.try_state::<ProjectCache>() {
+        cache.forget(&id);
     }
+    events::emit_project_change(&app, &ProjectChange::Updated(id));
     CommandResult {
         success: true,
-        data: Some(result.unwrap()),
+        data: None,
         message: None,
+        errors: None,
     }
 }
 
+/// Sets, changes or, with no `new_passphrase`, removes the passphrase of a
+/// project. `passphrase` is the current one, if the project has one.
 #[tauri::command]
-pub fn delete_project<R: tauri::Runtime>(
+pub async fn change_project_passphrase<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
     project_id: Vec<u8>,
+    passphrase: Option<String>,
+    new_passphrase: Option<String>,
 ) -> CommandResult<()> {
-    let result = project::delete_project(&app, &project_id);
-    if result.is_err() {
-        return CommandResult {
-            success: false,
+    run_blocking(move || {
+        let result = project::change_passphrase(
+            &app,
+            &project_id,
+            passphrase.as_deref(),
+            new_passphrase.as_deref(),
+        );
+        let project = match result {
+            Ok(project) => project,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        watcher::project_written(&app, &ProjectChange::Updated(project.id));
+        CommandResult {
+            success: true,
             data: None,
-            message: Some(result.unwrap_err().message),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Rewrites every project in the `compress_projects` setting, returning how
+/// many files changed.
+#[tauri::command]
+pub async fn recompress_projects<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    progress_id: Option<String>,
+) -> CommandResult<usize> {
+    run_blocking(move || {
+        let result = project::recompress_all_projects_with_progress(&app, |done, total| {
+            if let Some(task) = &progress_id {
+                events::emit_progress(&app, task, done, total);
+            }
+        });
+        let rewritten = match result {
+            Ok(rewritten) => rewritten,
+            Err(err) => return CommandResult::from_error(err),
         };
-    }
+        // Same content, so the window has nothing to reload
+        for id in rewritten.iter() {
+            watcher::remember_project(&app, id);
+            if let Some(cache) = app.try_state::<ProjectCache>() {
+                cache.invalidate(id);
+            }
+        }
+        CommandResult {
+            success: true,
+            data: Some(rewritten.len()),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Changes from `old` to `new`, such as a window's copy and a conflict copy.
+#[tauri::command]
+pub fn diff_projects(
+    old: project::Project,
+    new: project::Project,
+) -> CommandResult<Vec<diff::Change>> {
     CommandResult {
         success: true,
-        data: None,
+        data: Some(diff::diff_projects(&old, &new)),
         message: None,
+        errors: None,
     }
 }
 
@@ -119,7 +451,11 @@ mod test {
         let mock = tauri::test::mock_app();
         let app = mock.app_handle();
         // Test
-        let result = create_project_command(app.clone(), "Test Project", "Test Description");
+        let result = tauri::async_runtime::block_on(create_project_command(
+            app.clone(),
+            "Test Project".to_string(),
+            "Test Description".to_string(),
+        ));
         assert!(result.success);
         assert!(result.data.is_some());
         let project = result.data.unwrap();
@@ -143,14 +479,25 @@ mod test {
         let mock = tauri::test::mock_app();
         let app = mock.app_handle();
         // Test
-        let result = create_project_command(app.clone(), "", "");
+        let result = tauri::async_runtime::block_on(create_project_command(
+            app.clone(),
+            String::new(),
+            String::new(),
+        ));
         assert!(!result.success);
         assert!(result.data.is_none());
         assert!(result.message.is_some());
         assert_eq!(
-            "Empty Name or Description: The name and description of the project must not be empty",
+            "name: Must not be empty; description: Must not be empty",
             result.message.unwrap()
         );
+        let fields = result
+            .errors
+            .expect("Missing field errors")
+            .into_iter()
+            .map(|e| e.field)
+            .collect::<Vec<String>>();
+        assert_eq!(vec!["name", "description"], fields);
     }
 
     #[test]
@@ -170,7 +517,7 @@ mod test {
             .join("projects");
         let file_path = project_path.join("invalid_id");
         fs::write(file_path, Vec::<u8>::new()).expect("Failed failed to create test project");
-        let result = get_all_projects(app.clone());
+        let result = tauri::async_runtime::block_on(get_all_projects(app.clone(), None, None));
         assert!(result.success);
         assert!(result.message.is_none());
         assert!(result.data.is_some());
@@ -224,9 +571,25 @@ mod test {
         if fs::exists(&project_path).expect("Unable to check whether path exists") {
             fs::remove_dir(&project_path).expect("Failed to remove dir");
         }
-        let result = get_all_projects(app.clone());
+        let result = tauri::async_runtime::block_on(get_all_projects(app.clone(), None, None));
         assert!(!result.success);
         assert!(result.data.is_none());
         assert!(result.message.is_some());
     }
+
+    #[test]
+    fn test_set_project_dirty() {
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        app.manage(WatcherState::default());
+        let result = set_project_dirty(app.clone(), vec![0x01; 16], true);
+        assert!(result.success);
+        assert!(app.state::<WatcherState>().is_dirty(&[0x01; 16]));
+        let result = set_project_dirty(app.clone(), vec![0x01; 16], false);
+        assert!(result.success);
+        assert!(!app.state::<WatcherState>().is_dirty(&[0x01; 16]));
+        let result = set_project_dirty(app.clone(), vec![0x01; 4], true);
+        assert!(!result.success);
+        assert_eq!(Some("Invalid project ID".to_string()), result.message);
+    }
 }
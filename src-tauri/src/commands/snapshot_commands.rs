@@ -0,0 +1,93 @@
+use super::{run_blocking, CommandResult};
+use crate::events::ProjectChange;
+use crate::kanban::{diff::Change, project, snapshot};
+use crate::watcher;
+
+#[tauri::command]
+pub async fn list_snapshots<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+) -> CommandResult<Vec<snapshot::SnapshotInfo>> {
+    run_blocking(move || match snapshot::list_snapshots(&app, &project_id) {
+        Ok(snapshots) => CommandResult {
+            success: true,
+            data: Some(snapshots),
+            message: None,
+            errors: None,
+        },
+        Err(err) => CommandResult::from_error(err),
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn preview_snapshot<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    timestamp: u64,
+) -> CommandResult<project::Project> {
+    run_blocking(
+        move || match snapshot::preview_snapshot(&app, &project_id, timestamp) {
+            Ok(project) => CommandResult {
+                success: true,
+                data: Some(project),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Changes from the snapshot to the stored project.
+#[tauri::command]
+pub async fn diff_snapshot<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    timestamp: u64,
+) -> CommandResult<Vec<Change>> {
+    run_blocking(
+        move || match snapshot::diff_snapshot(&app, &project_id, timestamp) {
+            Ok(changes) => CommandResult {
+                success: true,
+                data: Some(changes),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Saves the snapshot over the project, `revision` being the revision the
+/// window holds. A conflict carries the stored copy like `save_project`.
+#[tauri::command]
+pub async fn restore_snapshot<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    timestamp: u64,
+    revision: u64,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = snapshot::restore_snapshot(&app, &project_id, timestamp, revision);
+        let project = match result {
+            Ok(project) => project,
+            Err(err) => {
+                return CommandResult {
+                    data: project::conflict_copy(&err).cloned(),
+                    ..CommandResult::from_error(err)
+                }
+            }
+        };
+        watcher::project_written(&app, &ProjectChange::Updated(project.id));
+        CommandResult {
+            success: true,
+            data: Some(project),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
@@ -0,0 +1,245 @@
+use super::{run_blocking, CommandResult};
+use crate::events::ProjectChange;
+use crate::kanban::{
+    crdt,
+    merge::{Merge, Side},
+    project,
+    revision_vector::RevisionVector,
+    sync_folder,
+};
+use crate::peer_sync::{self, PeerSyncState};
+use crate::watcher;
+use crate::webdav::{self, WebDavState};
+use tauri::Manager;
+
+/// Conflict copies waiting to be resolved, none outside sync folder mode.
+#[tauri::command]
+pub async fn list_conflict_copies<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<Vec<sync_folder::ConflictCopy>> {
+    run_blocking(move || {
+        if !sync_folder::enabled() {
+            return CommandResult {
+                success: true,
+                data: Some(Vec::new()),
+                message: None,
+                errors: None,
+            };
+        }
+        match sync_folder::list_conflict_copies(&app) {
+            Ok(copies) => CommandResult {
+                success: true,
+                data: Some(copies),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn read_conflict_copy<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    file_name: String,
+) -> CommandResult<project::Project> {
+    run_blocking(
+        move || match sync_folder::read_conflict_copy(&app, &project_id, &file_name) {
+            Ok(project) => CommandResult {
+                success: true,
+                data: Some(project),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Merges a conflict copy into the stored project without saving it. With
+/// `choices` the conflicts are resolved in order.
+#[tauri::command]
+pub async fn merge_conflict_copy<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    file_name: String,
+    choices: Option<Vec<Side>>,
+) -> CommandResult<Merge> {
+    run_blocking(move || {
+        let result =
+            sync_folder::merge_conflict_copy(&app, &project_id, &file_name, choices.as_deref());
+        match result {
+            Ok(merged) => CommandResult {
+                success: true,
+                data: Some(merged),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+/// Saves `project`, such as a merge or the copy itself, over the stored
+/// project and removes the conflict copy. A conflict carries the stored copy
+/// like `save_project`.
+#[tauri::command]
+pub async fn resolve_conflict_copy<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    file_name: String,
+    project: project::Project,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let result = sync_folder::resolve_conflict_copy(&app, &project_id, &file_name, &project);
+        let saved = match result {
+            Ok(saved) => saved,
+            Err(err) => {
+                return CommandResult {
+                    data: project::conflict_copy(&err).cloned(),
+                    ..CommandResult::from_error(err)
+                }
+            }
+        };
+        watcher::project_written(&app, &ProjectChange::Updated(saved.id));
+        CommandResult {
+            success: true,
+            data: Some(saved),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Removes a conflict copy, keeping the stored project.
+#[tauri::command]
+pub async fn discard_conflict_copy<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    file_name: String,
+) -> CommandResult<()> {
+    run_blocking(
+        move || match sync_folder::discard_conflict_copy(&app, &project_id, &file_name) {
+            Ok(()) => CommandResult {
+                success: true,
+                data: None,
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Syncs with every configured peer instance now, reporting on each.
+#[tauri::command]
+pub async fn sync_peers_now<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<Vec<peer_sync::PeerSyncResult>> {
+    run_blocking(move || {
+        let Some(state) = app.try_state::<PeerSyncState>() else {
+            return CommandResult {
+                success: false,
+                data: None,
+                message: Some("Peer sync is off".to_string()),
+                errors: None,
+            };
+        };
+        match peer_sync::sync_now(&app, &state) {
+            Ok(results) => CommandResult {
+                success: true,
+                data: Some(results),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+/// Operations of a project made after those counted in `known`, for another
+/// instance to apply.
+#[tauri::command]
+pub async fn get_project_operations<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    known: RevisionVector,
+) -> CommandResult<crdt::OperationBatch> {
+    run_blocking(
+        move || match crdt::operations_since(&app, &crdt::node(), &project_id, &known) {
+            Ok(batch) => CommandResult {
+                success: true,
+                data: Some(batch),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+/// Applies operations another instance made to a project and saves the
+/// result, creating the project when it is new here.
+#[tauri::command]
+pub async fn apply_project_operations<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Vec<u8>,
+    operations: Vec<crdt::Operation>,
+) -> CommandResult<project::Project> {
+    run_blocking(move || {
+        let existed = project::project_stamp(&app, &project_id).is_ok();
+        let result = crdt::apply_operations(&app, &crdt::node(), &project_id, operations);
+        let saved = match result {
+            Ok(saved) => saved,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        let change = if existed {
+            ProjectChange::Updated(saved.id)
+        } else {
+            ProjectChange::Created(saved.id)
+        };
+        watcher::project_written(&app, &change);
+        CommandResult {
+            success: true,
+            data: Some(saved),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Syncs with the WebDAV server now.
+#[tauri::command]
+pub async fn sync_webdav_now<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<webdav::WebDavReport> {
+    run_blocking(move || {
+        let Some(state) = app.try_state::<WebDavState>() else {
+            return CommandResult {
+                success: false,
+                data: None,
+                message: Some("WebDAV sync is off".to_string()),
+                errors: None,
+            };
+        };
+        match webdav::sync_now(&app, &state) {
+            Ok(report) => CommandResult {
+                success: true,
+                data: Some(report),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
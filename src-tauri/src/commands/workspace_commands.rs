@@ -0,0 +1,169 @@
+use super::{configs_commands, run_blocking, CommandResult};
+use crate::errors::error_log;
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{config, data_dir, project_cache::ProjectCache, workspace};
+use crate::watcher;
+use std::path::PathBuf;
+use tauri::Manager;
+
+// Workspaces sit in the data directory, not in the active workspace
+fn data_root<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, KanbanError> {
+    match data_dir::current() {
+        Some(location) => Ok(location.path),
+        None => app
+            .path()
+            .app_data_dir()
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e)),
+    }
+}
+
+#[tauri::command]
+pub async fn list_workspaces<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+) -> CommandResult<workspace::Workspaces> {
+    run_blocking(
+        move || match data_root(&app).and_then(|root| workspace::read_workspaces(&root)) {
+            Ok(workspaces) => CommandResult {
+                success: true,
+                data: Some(workspaces),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn create_workspace<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    name: String,
+) -> CommandResult<workspace::Workspace> {
+    run_blocking(move || {
+        match data_root(&app).and_then(|root| workspace::create_workspace(&root, &name)) {
+            Ok(workspace) => CommandResult {
+                success: true,
+                data: Some(workspace),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_workspace<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    workspace_id: String,
+    name: String,
+) -> CommandResult<workspace::Workspace> {
+    run_blocking(move || {
+        match data_root(&app)
+            .and_then(|root| workspace::rename_workspace(&root, &workspace_id, &name))
+        {
+            Ok(workspace) => CommandResult {
+                success: true,
+                data: Some(workspace),
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_workspace<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    workspace_id: String,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        match data_root(&app).and_then(|root| workspace::delete_workspace(&root, &workspace_id)) {
+            Ok(()) => CommandResult {
+                success: true,
+                data: None,
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
+
+/// Makes another workspace active and puts its configs into effect.
+#[tauri::command]
+pub async fn switch_workspace<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    workspace_id: String,
+) -> CommandResult<workspace::Workspace> {
+    run_blocking(move || {
+        let result = data_root(&app)
+            .and_then(|root| workspace::switch_workspace(&root, &workspace_id))
+            .and_then(|switched| {
+                workspace::set_active(&switched.id);
+                if let Some(cache) = app.try_state::<ProjectCache>() {
+                    cache.clear();
+                }
+                Ok((switched, config::read_configs(&app)?))
+            });
+        let (switched, configs) = match result {
+            Ok(result) => result,
+            Err(err) => return CommandResult::from_error(err),
+        };
+        if let Some(state) = app.try_state::<watcher::WatcherState>() {
+            if let Err(err) = watcher::start(&app, &state) {
+                error_log::report("Failed to watch for external changes", err);
+            }
+        }
+        if let Err(reason) = configs_commands::apply_configs(&app, &configs) {
+            return CommandResult {
+                success: false,
+                data: Some(switched),
+                message: Some(format!("Workspace opened, but {}", reason)),
+                errors: None,
+            };
+        }
+        CommandResult {
+            success: true,
+            data: Some(switched),
+            message: None,
+            errors: None,
+        }
+    })
+    .await
+}
+
+/// Remembers the project open in the active workspace, none when it was closed.
+#[tauri::command]
+pub async fn set_last_project<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    project_id: Option<Vec<u8>>,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        let project_id = match project_id.map(|id| <[u8; 16]>::try_from(id.as_slice())) {
+            Some(Err(_)) => {
+                return CommandResult::from_error(KanbanError::new(
+                    KanbanErrorKind::ProjectError,
+                    "Invalid project ID",
+                ))
+            }
+            Some(Ok(id)) => Some(id),
+            None => None,
+        };
+        match data_root(&app).and_then(|root| workspace::set_last_project(&root, project_id)) {
+            Ok(()) => CommandResult {
+                success: true,
+                data: None,
+                message: None,
+                errors: None,
+            },
+            Err(err) => CommandResult::from_error(err),
+        }
+    })
+    .await
+}
@@ -1 +1,2 @@
+pub mod error_log;
 pub mod kanban_error;
@@ -0,0 +1,87 @@
+//! Errors from work that must not fail the operation it runs alongside, such
+//! as the snapshot taken after a save or a background sync, kept until the
+//! window can show them.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Mutex, RwLock},
+};
+
+use crate::file_system::file_stamp;
+
+/// Oldest errors are dropped past this many, when nothing takes them.
+const MAX_ERRORS: usize = 100;
+
+#[derive(Debug, Clone, serde::Serialize, PartialEq)]
+pub struct LoggedError {
+    /// What was being done, such as "Failed to snapshot project".
+    pub context: String,
+    pub message: String,
+    pub time: u64,
+}
+
+impl fmt::Display for LoggedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.message)
+    }
+}
+
+type Listener = Box<dyn Fn(&LoggedError) -> bool + Send + Sync>;
+
+static ERRORS: Mutex<VecDeque<LoggedError>> = Mutex::new(VecDeque::new());
+static LISTENER: RwLock<Option<Listener>> = RwLock::new(None);
+
+/// Passes each error to `listener`, which returns whether it was delivered.
+/// Errors it could not deliver are kept for `take`.
+pub fn set_listener<F: Fn(&LoggedError) -> bool + Send + Sync + 'static>(listener: F) {
+    *LISTENER.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(listener));
+}
+
+pub fn report<E: fmt::Display>(context: &str, err: E) {
+    let error = LoggedError {
+        context: context.to_string(),
+        message: err.to_string(),
+        time: file_stamp::now_millis(),
+    };
+    let delivered = LISTENER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|listener| listener(&error));
+    if delivered {
+        return;
+    }
+    let mut errors = ERRORS.lock().unwrap_or_else(|e| e.into_inner());
+    if errors.len() == MAX_ERRORS {
+        errors.pop_front();
+    }
+    errors.push_back(error);
+}
+
+/// The errors not delivered yet, oldest first.
+pub fn take() -> Vec<LoggedError> {
+    ERRORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report_and_take() {
+        report("Failed to test error log", "Test Error");
+        let errors = take();
+        let error = errors
+            .iter()
+            .find(|e| e.context == "Failed to test error log")
+            .expect("Error not logged");
+        assert_eq!("Test Error", error.message);
+        assert_eq!("Failed to test error log: Test Error", error.to_string());
+        assert!(!take().contains(error));
+    }
+}
@@ -7,6 +7,12 @@ pub enum KanbanErrorKind {
     ProjectError,
     TextError,
     NumberError,
+    ConflictError,
+    ValidationError,
+    LockedError,
+    PassphraseError,
+    HistoryError,
+    SyncError,
 }
 
 #[derive(Debug)]
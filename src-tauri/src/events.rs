@@ -0,0 +1,178 @@
+use crate::{
+    errors::error_log::{self, LoggedError},
+    kanban::{project::Project, sync_folder::ConflictCopy},
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::Emitter;
+
+pub const PROJECT_CREATED: &str = "project-created";
+pub const PROJECT_UPDATED: &str = "project-updated";
+pub const PROJECT_DELETED: &str = "project-deleted";
+pub const PROJECT_CONFLICT: &str = "project-conflict";
+pub const CONFLICT_COPY: &str = "conflict-copy";
+pub const CONFIGS_CHANGED: &str = "configs-changed";
+pub const PROGRESS: &str = "progress";
+pub const ERROR_LOGGED: &str = "error-logged";
+
+// Unlike file times, which clocks and sync tools can move back or leave
+// unchanged, this only goes up while the app runs
+static VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Version for the next event, higher than that of every earlier event.
+pub fn next_version() -> u64 {
+    VERSION.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectChange {
+    Created([u8; 16]),
+    Updated([u8; 16]),
+    Deleted([u8; 16]),
+}
+
+impl ProjectChange {
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            ProjectChange::Created(_) => PROJECT_CREATED,
+            ProjectChange::Updated(_) => PROJECT_UPDATED,
+            ProjectChange::Deleted(_) => PROJECT_DELETED,
+        }
+    }
+
+    pub fn project_id(&self) -> [u8; 16] {
+        match self {
+            ProjectChange::Created(id)
+            | ProjectChange::Updated(id)
+            | ProjectChange::Deleted(id) => *id,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct ProjectChangedPayload {
+    pub id: [u8; 16],
+    pub version: u64,
+}
+
+/// Sent instead of an update when the window holds unsaved edits to the project.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct ProjectConflictPayload {
+    pub id: [u8; 16],
+    pub version: u64,
+    pub project: Project,
+}
+
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct ConfigsChangedPayload {
+    pub version: u64,
+}
+
+/// Progress of a long running command, `task` is the ID the caller passed in.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct ProgressPayload {
+    pub task: String,
+    pub done: usize,
+    pub total: usize,
+}
+
+pub fn project_changed_payload(change: &ProjectChange) -> ProjectChangedPayload {
+    ProjectChangedPayload {
+        id: change.project_id(),
+        version: next_version(),
+    }
+}
+
+pub fn emit_project_change<R: tauri::Runtime>(app: &tauri::AppHandle<R>, change: &ProjectChange) {
+    let payload = project_changed_payload(change);
+    if let Err(err) = app.emit(change.event_name(), payload) {
+        error_log::report(&format!("Failed to emit {}", change.event_name()), err);
+    }
+}
+
+pub fn emit_project_conflict<R: tauri::Runtime>(app: &tauri::AppHandle<R>, project: Project) {
+    let payload = ProjectConflictPayload {
+        id: project.id,
+        version: next_version(),
+        project,
+    };
+    if let Err(err) = app.emit(PROJECT_CONFLICT, payload) {
+        error_log::report(&format!("Failed to emit {}", PROJECT_CONFLICT), err);
+    }
+}
+
+pub fn emit_conflict_copy<R: tauri::Runtime>(app: &tauri::AppHandle<R>, copy: &ConflictCopy) {
+    if let Err(err) = app.emit(CONFLICT_COPY, copy) {
+        error_log::report(&format!("Failed to emit {}", CONFLICT_COPY), err);
+    }
+}
+
+pub fn emit_configs_changed<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    let payload = ConfigsChangedPayload {
+        version: next_version(),
+    };
+    if let Err(err) = app.emit(CONFIGS_CHANGED, payload) {
+        error_log::report(&format!("Failed to emit {}", CONFIGS_CHANGED), err);
+    }
+}
+
+pub fn emit_progress<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    task: &str,
+    done: usize,
+    total: usize,
+) {
+    let payload = ProgressPayload {
+        task: task.to_string(),
+        done,
+        total,
+    };
+    if let Err(err) = app.emit(PROGRESS, payload) {
+        error_log::report(&format!("Failed to emit {}", PROGRESS), err);
+    }
+}
+
+/// Sends a logged error to the window, returns whether that worked.
+pub fn emit_error_logged<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    error: &LoggedError,
+) -> bool {
+    // Not reported when it fails, the error is kept for the window instead
+    app.emit(ERROR_LOGGED, error).is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_project_changed_payload() {
+        let change = ProjectChange::Updated([0x01; 16]);
+        assert_eq!(PROJECT_UPDATED, change.event_name());
+        let payload = project_changed_payload(&change);
+        assert_eq!([0x01; 16], payload.id);
+        // Later events always carry a higher version
+        let deleted = project_changed_payload(&ProjectChange::Deleted([0x01; 16]));
+        assert_eq!(
+            PROJECT_DELETED,
+            ProjectChange::Deleted([0x01; 16]).event_name()
+        );
+        assert!(deleted.version > payload.version);
+        assert!(next_version() > deleted.version);
+    }
+
+    #[test]
+    fn test_payload_serialization() {
+        let payload = ProjectChangedPayload {
+            id: [0x01; 16],
+            version: 42,
+        };
+        assert_eq!(
+            "{\"id\":[1,1,1,1,1,1,1,1,1,1,1,1,1,1,1,1],\"version\":42}",
+            serde_json::to_string(&payload).unwrap()
+        );
+        assert_eq!(
+            "{\"version\":7}",
+            serde_json::to_string(&ConfigsChangedPayload { version: 7 }).unwrap()
+        );
+    }
+}
@@ -1,2 +1,5 @@
 pub mod binary_reader;
 pub mod binary_writer;
+pub mod corrupt_file;
+pub mod encoding;
+pub mod file_stamp;
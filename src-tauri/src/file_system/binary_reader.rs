@@ -1,33 +1,66 @@
 use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
-use std::{fs, path::Path, str};
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+};
 
+// Large enough that reading a project takes few system calls
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reads the project format from any seekable source. Files are read through
+/// a buffer instead of being loaded whole, and slices are read in place.
 #[derive(Debug)]
-pub struct BinaryReader {
-    bytes: Vec<u8>,
+pub struct BinaryReader<R: Read + Seek> {
+    reader: R,
+    length: usize,
     address: usize,
 }
 
-impl BinaryReader {
-    pub fn new(bytes: &[u8]) -> Self {
+/// Reader over bytes already in memory, without copying them.
+pub type SliceReader<'a> = BinaryReader<Cursor<&'a [u8]>>;
+
+impl<'a> BinaryReader<Cursor<&'a [u8]>> {
+    pub fn new(bytes: &'a [u8]) -> Self {
         BinaryReader {
-            bytes: bytes.to_vec(),
+            length: bytes.len(),
+            reader: Cursor::new(bytes),
             address: 0,
         }
     }
+}
+
+impl BinaryReader<BufReader<File>> {
+    pub fn read_from_file(path: &Path) -> io::Result<Self> {
+        BinaryReader::from_reader(BufReader::with_capacity(BUFFER_SIZE, File::open(path)?))
+    }
+}
 
-    pub fn read_from_file(path: &Path) -> std::io::Result<Self> {
-        let bytes = fs::read(path)?;
-        Ok(BinaryReader::new(&bytes))
+fn io_error(err: io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+impl<R: Read + Seek> BinaryReader<R> {
+    /// Reads from the current position of `reader` to its end.
+    pub fn from_reader(mut reader: R) -> io::Result<Self> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+        Ok(BinaryReader {
+            reader,
+            length: (end - start) as usize,
+            address: 0,
+        })
     }
 
     fn check_bound(&self, length: usize) -> Result<(), KanbanError> {
-        if self.address + length - 1 >= self.bytes.len() {
+        if self.address + length > self.length {
             return Err(KanbanError {
                 kind: KanbanErrorKind::ProjectError,
                 message: format!(
                     "Failed to read project file: Out of bound (reading {} out of {})",
                     self.address + length,
-                    self.bytes.len()
+                    self.length
                 ),
                 source: None,
             });
@@ -35,28 +68,37 @@ impl BinaryReader {
         Ok(())
     }
 
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), KanbanError> {
+        self.check_bound(buffer.len())?;
+        self.reader.read_exact(buffer).map_err(io_error)?;
+        self.address += buffer.len();
+        Ok(())
+    }
+
     pub fn next_byte(&mut self) -> Result<u8, KanbanError> {
-        self.check_bound(1)?;
-        let byte: u8 = self.bytes[self.address];
-        self.address += 1;
-        Ok(byte)
+        let mut byte = [0u8; 1];
+        self.read_exact(&mut byte)?;
+        Ok(byte[0])
     }
 
     pub fn next_bytes(&mut self, length: usize) -> Result<Vec<u8>, KanbanError> {
+        // Checked before allocating, so a corrupt length cannot exhaust memory
         self.check_bound(length)?;
-        let bytes: Vec<u8> = self.bytes[self.address..self.address + length].to_vec();
-        self.address += length;
+        let mut bytes = vec![0u8; length];
+        self.read_exact(&mut bytes)?;
         Ok(bytes)
     }
 
     pub fn next_string_by_length(&mut self, length: usize) -> Result<String, KanbanError> {
-        self.check_bound(length)?;
-        let bytes: &[u8] = &self.bytes[self.address..self.address + length];
-        let s = str::from_utf8(bytes)
-            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?
-            .to_string();
-        self.address += length;
-        Ok(s)
+        let previous_address: usize = self.address;
+        let bytes = self.next_bytes(length)?;
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(s),
+            Err(e) => {
+                self.seek(previous_address)?;
+                Err(KanbanError::from_source(KanbanErrorKind::TextError, e))
+            }
+        }
     }
 
     pub fn next_leb128_number(&mut self) -> Result<usize, KanbanError> {
@@ -66,7 +108,7 @@ impl BinaryReader {
         loop {
             let byte_result = self.next_byte();
             if byte_result.is_err() {
-                self.address = previous_address;
+                self.seek(previous_address)?;
                 return Err(KanbanError::new(
                     KanbanErrorKind::NumberError,
                     "Failed to parse LEB128 number: Badly formatted.",
@@ -81,6 +123,47 @@ impl BinaryReader {
         }
         Ok(result)
     }
+
+    pub fn next_u64(&mut self) -> Result<u64, KanbanError> {
+        let mut bytes = [0u8; 8];
+        self.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn position(&self) -> usize {
+        self.address
+    }
+
+    /// Number of bytes left after the current position.
+    pub fn remaining(&self) -> usize {
+        self.length - self.address
+    }
+
+    /// Hands out the rest of the source, such as a compressed payload, and
+    /// moves the position to the end.
+    pub fn take_rest(&mut self) -> io::Take<&mut R> {
+        let remaining = self.remaining() as u64;
+        self.address = self.length;
+        (&mut self.reader).take(remaining)
+    }
+
+    pub fn seek(&mut self, address: usize) -> Result<(), KanbanError> {
+        if address > self.length {
+            return Err(KanbanError::new(
+                KanbanErrorKind::ProjectError,
+                format!(
+                    "Failed to read project file: Out of bound (seeking {} out of {})",
+                    address, self.length
+                ),
+            ));
+        }
+        // Relative, so a buffered reader keeps its buffer for short jumps
+        let offset = address as i64 - self.address as i64;
+        self.reader.seek_relative(offset).map_err(io_error)?;
+        self.address = address;
+        Ok(())
+    }
+
     pub fn next_string(&mut self, is_256_max: bool) -> Result<String, KanbanError> {
         let previous_address: usize = self.address;
         let len: usize;
@@ -91,7 +174,7 @@ impl BinaryReader {
         }
         let result = self.next_string_by_length(len);
         if result.is_err() {
-            self.address = previous_address;
+            self.seek(previous_address)?;
             return Err(result.unwrap_err());
         }
         Ok(result.unwrap())
@@ -101,6 +184,7 @@ impl BinaryReader {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::fs;
     use tempdir::TempDir;
 
     #[test]
@@ -166,7 +250,8 @@ mod test {
             0x01, 0x02, 0x03, 0x48, 0x65, 0x6C, 0x6C, 0x6F, 0x20, 0x57, 0x6F, 0x72, 0x6C, 0x64,
             0x21, 0xFD, 0xFE, 0xFF, 0x04,
         ]);
-        let mut br = BinaryReader { bytes, address: 3 };
+        let mut br = BinaryReader::new(&bytes);
+        br.seek(3).expect("Failed to seek");
         assert_eq!(
             "Hello World!",
             br.next_string_by_length(12).expect("Failed to read string")
@@ -183,32 +268,32 @@ mod test {
     #[test]
     fn test_next_leb128_number() {
         // Test 0
-        let mut br: BinaryReader = BinaryReader::new(&[0x00]);
+        let mut br: SliceReader = BinaryReader::new(&[0x00]);
         assert_eq!(0, br.next_leb128_number().expect("Failed to parse number"));
 
         // Test 127
-        let mut br: BinaryReader = BinaryReader::new(&[0x7F]);
+        let mut br: SliceReader = BinaryReader::new(&[0x7F]);
         assert_eq!(
             127,
             br.next_leb128_number().expect("Failed to parse number")
         );
 
         // Test 128
-        let mut br: BinaryReader = BinaryReader::new(&[0x80, 0x01]);
+        let mut br: SliceReader = BinaryReader::new(&[0x80, 0x01]);
         assert_eq!(
             128,
             br.next_leb128_number().expect("Failed to parse number")
         );
 
         // Test 300
-        let mut br: BinaryReader = BinaryReader::new(&[0xAC, 0x02]);
+        let mut br: SliceReader = BinaryReader::new(&[0xAC, 0x02]);
         assert_eq!(
             300,
             br.next_leb128_number().expect("Failed to parse number")
         );
 
         // Test 16384
-        let mut br: BinaryReader = BinaryReader::new(&[0x80, 0x80, 0x01, 0x00, 0x01]);
+        let mut br: SliceReader = BinaryReader::new(&[0x80, 0x80, 0x01, 0x00, 0x01]);
         assert_eq!(
             16384,
             br.next_leb128_number().expect("Failed to parse number")
@@ -219,7 +304,7 @@ mod test {
 
     #[test]
     fn test_next_leb128_number_bad_format() {
-        let mut br: BinaryReader = BinaryReader::new(&[0x80, 0x81, 0x82]);
+        let mut br: SliceReader = BinaryReader::new(&[0x80, 0x81, 0x82]);
         let result = br.next_leb128_number();
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -245,4 +330,43 @@ mod test {
         assert_eq!(KanbanErrorKind::TextError, result.unwrap_err().kind);
         assert_eq!(0x03, br.next_byte().expect("Failed to read byte"))
     }
+
+    #[test]
+    fn test_next_u64_and_seek() {
+        let mut br = BinaryReader::new(&[0x01, 0x02, 0, 0, 0, 0, 0, 0, 0x03]);
+        assert_eq!(0x0201, br.next_u64().expect("Failed to read number"));
+        assert_eq!(8, br.position());
+        br.seek(1).expect("Failed to seek");
+        assert_eq!(0x02, br.next_byte().expect("Failed to read byte"));
+        assert!(br.seek(10).is_err());
+        assert!(br.next_u64().is_err());
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let mut cursor = Cursor::new(vec![0x01, 0x02, 0x03]);
+        cursor.seek(SeekFrom::Start(1)).unwrap();
+        let mut br = BinaryReader::from_reader(cursor).expect("Failed to create reader");
+        assert_eq!(0x02, br.next_byte().expect("Failed to read byte"));
+        assert_eq!(0x03, br.next_byte().expect("Failed to read byte"));
+        let result = br.next_byte();
+        assert!(result.is_err());
+        assert_eq!(
+            "Failed to read project file: Out of bound (reading 3 out of 2)",
+            result.unwrap_err().message
+        );
+    }
+
+    #[test]
+    fn test_take_rest() {
+        let mut br = BinaryReader::new(&[0x01, 0x02, 0x03]);
+        assert_eq!(0x01, br.next_byte().expect("Failed to read byte"));
+        let mut rest = Vec::new();
+        br.take_rest()
+            .read_to_end(&mut rest)
+            .expect("Failed to read rest");
+        assert_eq!(vec![0x02, 0x03], rest);
+        assert_eq!(0, br.remaining());
+        assert!(br.next_byte().is_err());
+    }
 }
@@ -1,24 +1,98 @@
-use std::cmp;
 use std::fs;
-use std::io::Seek;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
 
-pub struct BinaryWriter {
-    bytes: Vec<u8>,
+// Large enough that writing a project takes few system calls
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Writes the project format to any sink. Writes never fail on their own, the
+/// first error is kept and returned by `finish`.
+pub struct BinaryWriter<W: Write = Vec<u8>> {
+    inner: W,
+    length: usize,
+    error: Option<io::Error>,
+}
+
+impl Default for BinaryWriter {
+    fn default() -> Self {
+        BinaryWriter::new()
+    }
 }
 
 impl BinaryWriter {
     pub fn new() -> BinaryWriter {
-        return BinaryWriter { bytes: Vec::new() };
+        return BinaryWriter::from_writer(Vec::new());
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        return &self.inner;
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = create_file(path)?;
+        file.write_all(&self.inner)
+    }
+}
+
+fn create_file(path: &Path) -> io::Result<File> {
+    let parent_path_result = path.parent();
+    if parent_path_result.is_some() {
+        let parent_path = parent_path_result.unwrap();
+        if !fs::exists(path)? {
+            fs::create_dir_all(parent_path)?;
+        }
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
+
+impl BinaryWriter<BufWriter<File>> {
+    /// Streams straight into the file at `path` instead of building it in memory.
+    pub fn create_file(path: &Path) -> io::Result<Self> {
+        let file = create_file(path)?;
+        Ok(BinaryWriter::from_writer(BufWriter::with_capacity(
+            BUFFER_SIZE,
+            file,
+        )))
+    }
+}
+
+impl<W: Write> BinaryWriter<W> {
+    pub fn from_writer(inner: W) -> Self {
+        BinaryWriter {
+            inner,
+            length: 0,
+            error: None,
+        }
+    }
+
+    /// Number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
     }
 
     pub fn write_byte(&mut self, byte: u8) {
-        self.bytes.push(byte);
+        self.write_bytes(&[byte]);
     }
 
     pub fn write_bytes(&mut self, bytes: &[u8]) {
-        self.bytes.extend_from_slice(bytes);
+        if self.error.is_some() {
+            return;
+        }
+        match self.inner.write_all(bytes) {
+            Ok(()) => self.length += bytes.len(),
+            Err(err) => self.error = Some(err),
+        }
     }
 
     pub fn write_leb128(&mut self, mut value: u32) {
@@ -43,30 +117,43 @@ impl BinaryWriter {
         self.write_bytes(bytes);
     }
 
-    pub fn write_string_with_length(&mut self, string: &str, is_256_max: bool) {
+    /// Writes the length, in a single byte when `is_256_max`, then the string.
+    /// A string too long for its length is an error and nothing is written.
+    pub fn write_string_with_length(&mut self, string: &str, is_256_max: bool) -> io::Result<()> {
+        let too_long = |_| io::Error::new(io::ErrorKind::InvalidInput, "String is too long");
         if is_256_max {
-            self.write_byte(cmp::min(0xFF, string.len()) as u8); // Cap string length to 255
+            let length = u8::try_from(string.len()).map_err(too_long)?;
+            self.write_byte(length);
         } else {
-            self.write_leb128(string.len() as u32);
+            let length = u32::try_from(string.len()).map_err(too_long)?;
+            self.write_leb128(length);
         }
         self.write_string(string);
+        Ok(())
     }
 
-    pub fn as_bytes(&self) -> &[u8] {
-        return &self.bytes;
+    /// Flushes the writer, returning the first error of any write.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
     }
+}
 
-    pub fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
-        let parent_path_result = path.parent();
-        if parent_path_result.is_some() {
-            let parent_path = parent_path_result.unwrap();
-            if !fs::exists(path)? {
-                fs::create_dir_all(parent_path)?;
-            }
+// Lets an encoder, such as a compressor, write through the length count
+impl<W: Write> Write for BinaryWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_bytes(buf);
+        match &self.error {
+            Some(err) => Err(io::Error::new(err.kind(), err.to_string())),
+            None => Ok(buf.len()),
         }
-        let mut file = fs::OpenOptions::new().write(true).create(true).open(path)?;
-        file.seek(std::io::SeekFrom::Start(0)).unwrap();
-        file.write_all(&self.bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
 }
 
@@ -134,29 +221,32 @@ mod test {
         let test_string: &str = "This is a very very very very very very very very very very very very very very very very very very very very very very very very very very very very very very very very test string";
         // Test 256 max
         let mut bw: BinaryWriter = BinaryWriter::new();
-        bw.write_string_with_length(test_string, true);
+        bw.write_string_with_length(test_string, true).unwrap();
         let mut expected_bytes: Vec<u8> = Vec::new();
         expected_bytes.push(0xB5);
         expected_bytes.extend_from_slice(test_string.as_bytes());
         assert_eq!(expected_bytes, bw.as_bytes());
         // Test leb128
         let mut bw: BinaryWriter = BinaryWriter::new();
-        bw.write_string_with_length(test_string, false);
+        bw.write_string_with_length(test_string, false).unwrap();
         let mut expected_bytes: Vec<u8> = Vec::new();
         expected_bytes.extend_from_slice(&[0xB5, 0x01]);
         expected_bytes.extend_from_slice(test_string.as_bytes());
         assert_eq!(expected_bytes, bw.as_bytes());
+        // Too long for a single length byte
+        let mut bw: BinaryWriter = BinaryWriter::new();
+        let result = bw.write_string_with_length(&"X".repeat(256), true);
+        assert_eq!(std::io::ErrorKind::InvalidInput, result.unwrap_err().kind());
+        assert!(bw.is_empty());
     }
 
     #[test]
     fn test_as_bytes() {
         // Test Empty
-        let bw = BinaryWriter { bytes: Vec::new() };
+        let bw = BinaryWriter::new();
         assert_eq!(&Vec::<u8>::new(), bw.as_bytes());
         // Test with content
-        let bw = BinaryWriter {
-            bytes: [0x01, 0x02, 0x03].to_vec(),
-        };
+        let bw = BinaryWriter::from_writer([0x01, 0x02, 0x03].to_vec());
         assert_eq!(&[0x01, 0x02, 0x03], bw.as_bytes());
     }
 
@@ -192,4 +282,43 @@ mod test {
             result.unwrap_err().kind()
         );
     }
+
+    #[test]
+    fn test_create_file() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let path = dir.path().join("nested").join("test.bin");
+        let mut bw = BinaryWriter::create_file(&path).expect("Failed to create file");
+        bw.write_byte(0x01);
+        bw.write_string_with_length("Hello", true).unwrap();
+        assert_eq!(7, bw.len());
+        bw.finish().expect("Failed to write file");
+        assert_eq!(
+            vec![0x01, 0x05, 0x48, 0x65, 0x6C, 0x6C, 0x6F],
+            fs::read(&path).expect("Failed to read file")
+        );
+    }
+
+    #[test]
+    fn test_finish_reports_write_error() {
+        let mut buffer = [0u8; 2];
+        let mut bw = BinaryWriter::from_writer(&mut buffer[..]);
+        bw.write_bytes(&[0x01, 0x02, 0x03]);
+        bw.write_byte(0x04);
+        assert_eq!(0, bw.len());
+        let result = bw.finish();
+        assert!(result.is_err());
+        assert_eq!(std::io::ErrorKind::WriteZero, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_write_through_io_write() {
+        let mut bw = BinaryWriter::new();
+        bw.write_byte(0x01);
+        write!(bw, "AB").expect("Failed to write");
+        assert_eq!(3, bw.len());
+        assert_eq!(&[0x01, 0x41, 0x42], bw.as_bytes());
+        let mut buffer = [0u8; 1];
+        let mut bw = BinaryWriter::from_writer(&mut buffer[..]);
+        assert!(bw.write_all(&[0x01, 0x02]).is_err());
+    }
 }
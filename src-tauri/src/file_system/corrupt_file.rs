@@ -0,0 +1,33 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Renames a file that could not be parsed to `<name>.corrupt`, so the
+/// defaults written in its place do not overwrite what the user may want
+/// to repair. Returns the new path.
+pub fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".corrupt");
+    let corrupt_path = path.with_file_name(file_name);
+    fs::rename(path, &corrupt_path)?;
+    Ok(corrupt_path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_set_aside() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let path = dir.path().join("configs.json");
+        fs::write(&path, "{").expect("Failed to create test file");
+        let corrupt_path = set_aside(&path).expect("Failed to set file aside");
+        assert_eq!(dir.path().join("configs.json.corrupt"), corrupt_path);
+        assert!(!path.exists());
+        assert_eq!("{", fs::read_to_string(&corrupt_path).unwrap());
+    }
+}
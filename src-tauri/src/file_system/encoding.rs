@@ -0,0 +1,252 @@
+use crate::{
+    errors::kanban_error::{KanbanError, KanbanErrorKind},
+    file_system::{binary_reader::BinaryReader, binary_writer::BinaryWriter},
+};
+use std::io::{Read, Seek, Write};
+
+/// A value that can be written in the project file format.
+pub trait Encode {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError>;
+}
+
+/// A value that can be read back from the project file format.
+pub trait Decode: Sized {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError>;
+}
+
+impl Encode for u8 {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        bw.write_byte(*self);
+        Ok(())
+    }
+}
+
+impl Decode for u8 {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        br.next_byte()
+    }
+}
+
+// Little endian
+impl Encode for u64 {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        bw.write_bytes(&self.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl Decode for u64 {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        br.next_u64()
+    }
+}
+
+fn write_length<W: Write>(bw: &mut BinaryWriter<W>, length: usize) -> Result<(), KanbanError> {
+    bw.write_leb128(length.try_into().map_err(|_| {
+        KanbanError::new(
+            KanbanErrorKind::NumberError,
+            "Failed to convert usize to u32",
+        )
+    })?);
+    Ok(())
+}
+
+// Length in LEB128, then the UTF-8 bytes
+impl Encode for String {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        write_length(bw, self.len())?;
+        bw.write_string(self);
+        Ok(())
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        br.next_string(false)
+    }
+}
+
+/// Strings stored with a single length byte, such as names and titles.
+pub struct ShortString;
+
+impl ShortString {
+    pub fn encode<W: Write>(value: &str, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        bw.write_string_with_length(value, true)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+    }
+
+    pub fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<String, KanbanError> {
+        br.next_string(true)
+    }
+}
+
+// Fixed size, so no length is stored
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        for value in self.iter() {
+            value.encode(bw)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        let mut values = Vec::with_capacity(N);
+        for _ in 0..N {
+            values.push(T::decode(br)?);
+        }
+        values.try_into().map_err(|_| {
+            KanbanError::new(
+                KanbanErrorKind::NumberError,
+                format!("Failed to convert Vec to [T; {}]", N),
+            )
+        })
+    }
+}
+
+// Count in LEB128, then the values
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        write_length(bw, self.len())?;
+        for value in self.iter() {
+            value.encode(bw)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        let count = br.next_leb128_number()?;
+        // Every value takes at least a byte, so a corrupt count cannot over allocate
+        let mut values = Vec::with_capacity(count.min(br.remaining()));
+        for _ in 0..count {
+            values.push(T::decode(br)?);
+        }
+        Ok(values)
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! encode_field {
+    ($bw:expr, $value:expr) => {
+        $crate::file_system::encoding::Encode::encode($value, $bw)
+    };
+    ($bw:expr, $value:expr, $codec:ident) => {
+        $codec::encode($value, $bw)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! decode_field {
+    ($br:expr, $type:ty) => {
+        <$type as $crate::file_system::encoding::Decode>::decode($br)
+    };
+    ($br:expr, $type:ty, $codec:ident) => {
+        $codec::decode($br)
+    };
+}
+
+/// Implements `Encode` and `Decode` for a struct, writing its fields in the
+/// order listed. A field can name the codec to use with `as`, for example
+/// `name: String as ShortString`.
+///
+/// Fields are encoded and decoded from the same list, so adding one cannot
+/// leave the two sides out of step.
+#[macro_export]
+macro_rules! impl_codec {
+    ($name:ident { $($field:ident: $type:ty $(as $codec:ident)?),* $(,)? }) => {
+        impl $crate::file_system::encoding::Encode for $name {
+            fn encode<W: std::io::Write>(
+                &self,
+                bw: &mut $crate::file_system::binary_writer::BinaryWriter<W>,
+            ) -> Result<(), $crate::errors::kanban_error::KanbanError> {
+                $($crate::encode_field!(bw, &self.$field $(, $codec)?)?;)*
+                Ok(())
+            }
+        }
+
+        impl $crate::file_system::encoding::Decode for $name {
+            fn decode<R: std::io::Read + std::io::Seek>(
+                br: &mut $crate::file_system::binary_reader::BinaryReader<R>,
+            ) -> Result<Self, $crate::errors::kanban_error::KanbanError> {
+                // Struct fields are evaluated in the order written
+                Ok($name {
+                    $($field: $crate::decode_field!(br, $type $(, $codec)?)?,)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn round_trip<T: Encode + Decode>(value: &T) -> T {
+        let mut bw = BinaryWriter::new();
+        value.encode(&mut bw).expect("Failed to encode");
+        let mut br = BinaryReader::new(bw.as_bytes());
+        let decoded = T::decode(&mut br).expect("Failed to decode");
+        assert_eq!(bw.as_bytes().len(), br.position());
+        decoded
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestStruct {
+        name: String,
+        id: [u8; 4],
+        values: Vec<u64>,
+    }
+
+    impl_codec!(TestStruct {
+        name: String as ShortString,
+        id: [u8; 4],
+        values: Vec<u64>,
+    });
+
+    #[test]
+    fn test_impl_codec() {
+        let value = TestStruct {
+            name: "Test".to_string(),
+            id: [0x01, 0x02, 0x03, 0x04],
+            values: vec![1, 2],
+        };
+        let mut bw = BinaryWriter::new();
+        value.encode(&mut bw).expect("Failed to encode");
+        let mut expected_bytes: Vec<u8> = vec![0x04, 0x54, 0x65, 0x73, 0x74, 0x01, 0x02, 0x03];
+        expected_bytes.extend_from_slice(&[0x04, 0x02]);
+        expected_bytes.extend_from_slice(&1u64.to_le_bytes());
+        expected_bytes.extend_from_slice(&2u64.to_le_bytes());
+        assert_eq!(expected_bytes, bw.as_bytes());
+        assert_eq!(value, round_trip(&value));
+    }
+
+    #[test]
+    fn test_decode_vec_bad_count() {
+        // Claims far more values than there are bytes
+        let mut br = BinaryReader::new(&[0xFF, 0xFF, 0xFF, 0x7F, 0x01]);
+        let result = Vec::<u8>::decode(&mut br);
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::ProjectError, result.unwrap_err().kind);
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_primitives(byte: u8, number: u64, text: String, bytes: [u8; 16]) {
+            prop_assert_eq!(byte, round_trip(&byte));
+            prop_assert_eq!(number, round_trip(&number));
+            prop_assert_eq!(&text, &round_trip(&text));
+            prop_assert_eq!(bytes, round_trip(&bytes));
+        }
+
+        #[test]
+        fn test_round_trip_vec(values: Vec<String>) {
+            prop_assert_eq!(&values, &round_trip(&values));
+        }
+    }
+}
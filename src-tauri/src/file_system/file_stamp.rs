@@ -0,0 +1,76 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::Hasher,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Milliseconds since the Unix epoch, used as a version stamp for stored data.
+pub fn now_millis() -> u64 {
+    to_millis(SystemTime::now())
+}
+
+pub fn modified_millis(path: &Path) -> std::io::Result<u64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(to_millis(modified))
+}
+
+/// Hashes everything left in `reader` a buffer at a time, so large files are
+/// never read into memory only to be compared.
+pub fn content_hash<R: Read>(reader: R) -> io::Result<u64> {
+    let mut reader = BufReader::new(reader);
+    let mut hasher = DefaultHasher::new();
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(hasher.finish());
+        }
+        hasher.write(buffer);
+        let length = buffer.len();
+        reader.consume(length);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_modified_millis() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let path = dir.path().join("test.bin");
+        let before = now_millis();
+        fs::write(&path, [0x01]).expect("Failed to create test file");
+        let stamp = modified_millis(&path).expect("Failed to read stamp");
+        // Allow for coarse file system timestamps
+        assert!(stamp + 2000 >= before);
+        assert!(stamp <= now_millis());
+    }
+
+    #[test]
+    fn test_content_hash() {
+        // Larger than the read buffer, differing only in the last byte
+        let mut bytes = vec![0x01; 100_000];
+        let hash = content_hash(bytes.as_slice()).unwrap();
+        assert_eq!(hash, content_hash(bytes.as_slice()).unwrap());
+        bytes[99_999] = 0x02;
+        assert_ne!(hash, content_hash(bytes.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_modified_millis_not_exists() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let result = modified_millis(&dir.path().join("test.bin"));
+        assert!(result.is_err());
+        assert_eq!(std::io::ErrorKind::NotFound, result.unwrap_err().kind());
+    }
+}
@@ -1,4 +1,18 @@
-mod board;
-mod board_list;
+pub mod board;
+pub mod board_list;
 pub mod config;
+pub mod crdt;
+pub mod data_dir;
+pub mod diff;
+pub mod encryption;
+pub mod export;
+pub mod history;
+pub mod merge;
 pub mod project;
+pub mod project_cache;
+pub mod project_group;
+pub mod revision_vector;
+pub mod snapshot;
+pub mod sync_folder;
+pub mod validation;
+pub mod workspace;
@@ -1,56 +1,168 @@
 use crate::{
     errors::kanban_error::{KanbanError, KanbanErrorKind},
-    file_system::{binary_reader::BinaryReader, binary_writer::BinaryWriter},
-    kanban::board_list::{read_all_board_lists, write_all_board_lists, BoardList},
+    file_system::{
+        binary_reader::BinaryReader,
+        binary_writer::BinaryWriter,
+        encoding::{Decode, Encode, ShortString},
+    },
+    impl_codec,
+    kanban::board_list::BoardList,
 };
+use std::io::{self, Read, Seek, Write};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct Board {
     pub name: String,
     pub lists: Vec<BoardList>,
+    /// Hidden from the board list unless archived boards are asked for.
+    #[serde(default)]
+    pub archived: bool,
 }
 
-fn write_board(bw: &mut BinaryWriter, board: &Board) -> Result<(), KanbanError> {
-    // Write board name
-    bw.write_string_with_length(&board.name, true);
-    // Write board lists
-    write_all_board_lists(bw, &board.lists)?;
-    Ok(())
+// Flags byte at the end of a board since file version 4
+const FLAG_ARCHIVED: u8 = 0x01;
+
+/// Stores the archived state of a board in its flags byte.
+struct BoardFlags;
+
+impl BoardFlags {
+    fn encode<W: Write>(archived: &bool, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        let flags = if *archived { FLAG_ARCHIVED } else { 0 };
+        flags.encode(bw)
+    }
+
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<bool, KanbanError> {
+        let flags = u8::decode(br)?;
+        if flags & !FLAG_ARCHIVED != 0 {
+            return Err(KanbanError::new(
+                KanbanErrorKind::ProjectError,
+                "Board flags not supported",
+            ));
+        }
+        Ok(flags & FLAG_ARCHIVED != 0)
+    }
 }
 
-pub(crate) fn write_all_boards(bw: &mut BinaryWriter, boards: &[Board]) -> Result<(), KanbanError> {
-    // Write number of boards
-    bw.write_leb128(boards.len().try_into().map_err(|_| {
-        KanbanError::new(
-            KanbanErrorKind::NumberError,
-            "Failed to convert u32 to usize",
-        )
-    })?);
+impl_codec!(Board {
+    name: String as ShortString,
+    lists: Vec<BoardList>,
+    archived: bool as BoardFlags,
+});
+
+/// Decodes a board of a file in `version`, boards before version 4 have no
+/// flags and are never archived.
+pub(crate) fn decode_board<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    version: u8,
+) -> Result<Board, KanbanError> {
+    if version >= 4 {
+        return Board::decode(br);
+    }
+    Ok(Board {
+        name: ShortString::decode(br)?,
+        lists: Vec::<BoardList>::decode(br)?,
+        archived: false,
+    })
+}
+
+/// Decodes boards stored one after another, as before file version 2.
+pub(crate) fn read_sequential_boards<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    version: u8,
+) -> Result<Vec<Board>, KanbanError> {
+    let count = br.next_leb128_number()?;
+    let mut boards = Vec::with_capacity(count.min(br.remaining()));
+    for _ in 0..count {
+        boards.push(decode_board(br, version)?);
+    }
+    Ok(boards)
+}
+
+// Board offset and length, both u64
+pub(crate) const BOARD_ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BoardEntry {
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl_codec!(BoardEntry {
+    offset: u64,
+    length: u64,
+});
+
+/// Writes the board count, the offset table and the boards. Offsets are
+/// absolute, so `bw` must already hold everything that comes before.
+pub(crate) fn write_all_boards<W: Write>(
+    bw: &mut BinaryWriter<W>,
+    boards: &[Board],
+) -> Result<(), KanbanError> {
+    // Boards are encoded twice, once into a sink to learn their lengths for
+    // the table, so they never have to be held in memory
+    let mut count_bw = BinaryWriter::from_writer(io::sink());
+    count_bw.write_leb128(boards.len() as u32);
+    let mut offset = bw.len() + count_bw.len() + boards.len() * BOARD_ENTRY_SIZE;
+    let mut entries = Vec::<BoardEntry>::with_capacity(boards.len());
+    for board in boards.iter() {
+        let mut sink = BinaryWriter::from_writer(io::sink());
+        board.encode(&mut sink)?;
+        entries.push(BoardEntry {
+            offset: offset as u64,
+            length: sink.len() as u64,
+        });
+        offset += sink.len();
+    }
+    // Write number of boards and the offset table
+    entries.encode(bw)?;
     // Write boards
     for board in boards.iter() {
-        write_board(bw, board)?;
+        board.encode(bw)?;
     }
     Ok(())
 }
 
-fn read_board(br: &mut BinaryReader) -> Result<Board, KanbanError> {
-    let name = br.next_string(true)?;
-    let lists = read_all_board_lists(br)?;
-    Ok(Board { name, lists })
+pub(crate) fn read_board_table<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+) -> Result<Vec<BoardEntry>, KanbanError> {
+    Vec::<BoardEntry>::decode(br)
 }
 
-pub(crate) fn read_all_boards(br: &mut BinaryReader) -> Result<Vec<Board>, KanbanError> {
-    let boards_count = br.next_leb128_number()?;
-    let mut boards = Vec::<Board>::new();
-    for _ in 0..boards_count {
-        boards.push(read_board(br)?);
+/// Reads boards written by `write_all_boards`, following the offset table.
+pub(crate) fn read_indexed_boards<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    version: u8,
+) -> Result<Vec<Board>, KanbanError> {
+    let entries = read_board_table(br)?;
+    let mut boards = Vec::<Board>::with_capacity(entries.len());
+    for entry in entries {
+        br.seek(entry.offset as usize)?;
+        boards.push(decode_board(br, version)?);
     }
     Ok(boards)
 }
 
+/// Boards with names short enough for their single length byte.
+#[cfg(test)]
+pub(crate) fn arb_board() -> impl proptest::strategy::Strategy<Value = Board> {
+    use crate::kanban::board_list::arb_board_list;
+    use proptest::prelude::*;
+    (
+        "\\PC{0,60}",
+        prop::collection::vec(arb_board_list(), 0..4),
+        any::<bool>(),
+    )
+        .prop_map(|(name, lists, archived)| Board {
+            name,
+            lists,
+            archived,
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_write_board() {
@@ -75,8 +187,9 @@ mod test {
         let test_board = Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
-        let result = write_board(&mut bw, &test_board);
+        let result = test_board.encode(&mut bw);
         assert!(result.is_ok());
         let expected_bytes = &[
             10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x03, 0x0C, 0x54, 0x65,
@@ -86,7 +199,7 @@ mod test {
             0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00, 0x00, 0x00, 0x02, 0x06, 0x49, 0x74, 0x65,
             0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65, 0x73,
             0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06,
-            0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
+            0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x00,
         ];
         assert_eq!(expected_bytes, bw.as_bytes());
     }
@@ -114,8 +227,13 @@ mod test {
         let test_board = Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
-        let result = write_all_boards(&mut bw, &[test_board.clone(), test_board.clone()]);
+        let archived_board = Board {
+            archived: true,
+            ..test_board.clone()
+        };
+        let result = write_all_boards(&mut bw, &[test_board.clone(), archived_board.clone()]);
         assert!(result.is_ok());
         let expected_bytes = &[
             2, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x03, 0x0C, 0x54,
@@ -125,18 +243,34 @@ mod test {
             0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00, 0x00, 0x00, 0x02, 0x06, 0x49, 0x74,
             0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65,
             0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01,
-            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F,
-            0x61, 0x72, 0x64, 0x03, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72,
-            0x64, 0x20, 0x31, 0xFF, 0xFF, 0xFF, 0x03, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
-            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x33,
-            0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00,
-            0x00, 0x00, 0x02, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65,
-            0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64,
-            0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
+            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x00, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42,
+            0x6F, 0x61, 0x72, 0x64, 0x03, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61,
+            0x72, 0x64, 0x20, 0x31, 0xFF, 0xFF, 0xFF, 0x03, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20,
+            0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20,
+            0x33, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x32,
+            0x00, 0x00, 0x00, 0x02, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74,
+            0x65, 0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72,
+            0x64, 0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
+            0x01,
         ];
-        assert_eq!(expected_bytes, bw.as_bytes());
+        // The offset table goes between the count and the boards
+        let board_length = (expected_bytes.len() - 1) / 2;
+        let table_end = 1 + 2 * BOARD_ENTRY_SIZE;
+        let mut expected: Vec<u8> = vec![2];
+        for i in 0..2 {
+            expected.extend_from_slice(&((table_end + i * board_length) as u64).to_le_bytes());
+            expected.extend_from_slice(&(board_length as u64).to_le_bytes());
+        }
+        expected.extend_from_slice(&expected_bytes[1..]);
+        assert_eq!(expected, bw.as_bytes());
+        let mut br = BinaryReader::new(bw.as_bytes());
+        assert_eq!(
+            vec![test_board, archived_board],
+            read_indexed_boards(&mut br, 4).expect("Failed to read boards")
+        );
     }
 
+    // Written before file version 4, without the flags byte
     #[test]
     fn test_read_board() {
         let test_bytes = [
@@ -169,11 +303,27 @@ mod test {
         let expected_board = Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
         let mut br = BinaryReader::new(&test_bytes);
-        let result = read_board(&mut br);
+        let result = decode_board(&mut br, 3);
         assert!(result.is_ok());
         assert_eq!(expected_board, result.unwrap());
+        assert_eq!(test_bytes.len(), br.position());
+    }
+
+    #[test]
+    fn test_board_flags() {
+        let board = Board {
+            name: "Done".to_string(),
+            lists: Vec::new(),
+            archived: true,
+        };
+        let mut bw = BinaryWriter::new();
+        board.encode(&mut bw).unwrap();
+        assert_eq!(&[4, 0x44, 0x6F, 0x6E, 0x65, 0x00, 0x01], bw.as_bytes());
+        let unknown = [4, 0x44, 0x6F, 0x6E, 0x65, 0x00, 0x02];
+        assert!(Board::decode(&mut BinaryReader::new(&unknown)).is_err());
     }
 
     #[test]
@@ -215,11 +365,30 @@ mod test {
         let test_board = Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
         let expected_boards = [test_board.clone(), test_board.clone()];
         let mut br = BinaryReader::new(&test_bytes);
-        let result = read_all_boards(&mut br);
+        let result = read_sequential_boards(&mut br, 3);
         assert!(result.is_ok());
         assert_eq!(expected_boards.to_vec(), result.unwrap());
     }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(board in arb_board()) {
+            let mut bw = BinaryWriter::new();
+            board.encode(&mut bw).unwrap();
+            let mut br = BinaryReader::new(bw.as_bytes());
+            prop_assert_eq!(board, Board::decode(&mut br).unwrap());
+        }
+
+        #[test]
+        fn test_round_trip_indexed(boards in prop::collection::vec(arb_board(), 0..4)) {
+            let mut bw = BinaryWriter::new();
+            write_all_boards(&mut bw, &boards).unwrap();
+            let mut br = BinaryReader::new(bw.as_bytes());
+            prop_assert_eq!(boards, read_indexed_boards(&mut br, 4).unwrap());
+        }
+    }
 }
@@ -1,7 +1,4 @@
-use crate::{
-    errors::kanban_error::{KanbanError, KanbanErrorKind},
-    file_system::{binary_reader::BinaryReader, binary_writer::BinaryWriter},
-};
+use crate::{file_system::encoding::ShortString, impl_codec};
 
 #[derive(Debug, serde::Deserialize, serde::Serialize, Clone, PartialEq)]
 pub struct BoardList {
@@ -10,74 +7,37 @@ pub struct BoardList {
     pub items: Vec<String>,
 }
 
-fn write_board_list(bw: &mut BinaryWriter, board_list: &BoardList) -> Result<(), KanbanError> {
-    bw.write_string_with_length(&board_list.title, true);
-    bw.write_bytes(&board_list.color); // Colors
-                                       // Board Items
-    bw.write_leb128(board_list.items.len().try_into().map_err(|_| {
-        KanbanError::new(
-            KanbanErrorKind::NumberError,
-            "Failed to covert u32 to usize",
-        )
-    })?);
-    for item in board_list.items.iter() {
-        bw.write_string_with_length(item, false);
-    }
-    Ok(())
-}
-
-pub(crate) fn write_all_board_lists(
-    bw: &mut BinaryWriter,
-    board_lists: &[BoardList],
-) -> Result<(), KanbanError> {
-    bw.write_leb128(board_lists.len().try_into().map_err(|_| {
-        KanbanError::new(
-            KanbanErrorKind::NumberError,
-            "Failed to covert u32 to usize",
-        )
-    })?);
-    for board_list in board_lists.iter() {
-        write_board_list(bw, &board_list)?;
-    }
-    Ok(())
-}
-
-pub(crate) fn read_board_list(br: &mut BinaryReader) -> Result<BoardList, KanbanError> {
-    // Board list title
-    let title: String = br.next_string(true)?;
-    // Color
-    let color_vec = br.next_bytes(3)?;
-    let color: [u8; 3] = color_vec.try_into().map_err(|_| {
-        KanbanError::new(
-            KanbanErrorKind::NumberError,
-            "Failed to convert Vec<u8> to [u8; 3] for color",
-        )
-    })?;
-    // Board items
-    let items_count: usize = br.next_leb128_number()?;
-    let mut items: Vec<String> = Vec::with_capacity(items_count);
-    for _ in 0..items_count {
-        items.push(br.next_string(false)?);
-    }
-    Ok(BoardList {
-        title,
-        color,
-        items,
-    })
-}
+impl_codec!(BoardList {
+    title: String as ShortString,
+    color: [u8; 3],
+    items: Vec<String>,
+});
 
-pub(crate) fn read_all_board_lists(br: &mut BinaryReader) -> Result<Vec<BoardList>, KanbanError> {
-    let lists_count = br.next_leb128_number()?;
-    let mut lists = Vec::with_capacity(lists_count);
-    for _ in 0..lists_count {
-        lists.push(read_board_list(br)?);
-    }
-    Ok(lists)
+/// Lists with titles short enough for their single length byte.
+#[cfg(test)]
+pub(crate) fn arb_board_list() -> impl proptest::strategy::Strategy<Value = BoardList> {
+    use proptest::prelude::*;
+    (
+        "\\PC{0,60}",
+        any::<[u8; 3]>(),
+        prop::collection::vec(".*", 0..8),
+    )
+        .prop_map(|(title, color, items)| BoardList {
+            title,
+            color,
+            items,
+        })
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::file_system::{
+        binary_reader::BinaryReader,
+        binary_writer::BinaryWriter,
+        encoding::{Decode, Encode},
+    };
+    use proptest::prelude::*;
 
     #[test]
     fn test_write_board_list() {
@@ -96,7 +56,7 @@ mod test {
             0x20, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x33,
         ];
         let mut bw = BinaryWriter::new();
-        let result = write_board_list(&mut bw, &test_list);
+        let result = test_list.encode(&mut bw);
         assert!(result.is_ok());
         assert_eq!(&expected_data, bw.as_bytes());
     }
@@ -121,7 +81,7 @@ mod test {
             color: [0xaa, 0xaa, 0xaa],
             items: ["Item 1"].map(|s| s.to_string()).to_vec(),
         };
-        let result = write_all_board_lists(&mut bw, &[test_list_1, test_list_2, test_list_3]);
+        let result = vec![test_list_1, test_list_2, test_list_3].encode(&mut bw);
         assert!(result.is_ok());
         let expected_bytes = &[
             0x03, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x31,
@@ -152,7 +112,7 @@ mod test {
                 .collect::<Vec<String>>(),
         };
         let mut br = BinaryReader::new(&test_data);
-        let result = read_board_list(&mut br);
+        let result = BoardList::decode(&mut br);
         assert!(result.is_ok());
         assert_eq!(expected_list, result.unwrap());
     }
@@ -187,8 +147,18 @@ mod test {
         };
         let expected_lists = [test_list_1, test_list_2, test_list_3];
         let mut br = BinaryReader::new(&test_data);
-        let result = read_all_board_lists(&mut br);
+        let result = Vec::<BoardList>::decode(&mut br);
         assert!(result.is_ok());
         assert_eq!(expected_lists.to_vec(), result.unwrap());
     }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(list in arb_board_list()) {
+            let mut bw = BinaryWriter::new();
+            list.encode(&mut bw).unwrap();
+            let mut br = BinaryReader::new(bw.as_bytes());
+            prop_assert_eq!(list, BoardList::decode(&mut br).unwrap());
+        }
+    }
 }
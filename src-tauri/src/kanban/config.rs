@@ -1,24 +1,182 @@
 use std::fs;
 
-use tauri::Manager;
+use uuid::Uuid;
 
+use crate::errors::error_log;
 use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::file_system::corrupt_file;
+use crate::kanban::history::{self, HistoryLocation};
+use crate::kanban::project::{self, AppPathProvider, PathProvider};
+use crate::kanban::snapshot::{self, RetentionPolicy};
+use crate::kanban::{crdt, sync_folder};
 
-#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
 pub struct Configs {
     pub auto_save_interval: u32,
     pub new_list_default_color: String,
+    #[serde(default)]
+    pub api_enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
+    #[serde(default)]
+    pub api_token: String,
+    #[serde(default)]
+    pub compress_projects: bool,
+    #[serde(default = "default_true")]
+    pub snapshots_enabled: bool,
+    #[serde(default = "default_snapshot_hourly_hours")]
+    pub snapshot_hourly_hours: u32,
+    #[serde(default = "default_snapshot_daily_days")]
+    pub snapshot_daily_days: u32,
+    #[serde(default)]
+    pub history_enabled: bool,
+    /// Folder of the history repository, the data directory when empty.
+    #[serde(default)]
+    pub history_path: String,
+    /// Projects are synced by a tool that leaves conflict copies next to them.
+    #[serde(default)]
+    pub sync_folder: bool,
+    /// Identifies this instance to the others it syncs with.
+    #[serde(default)]
+    pub instance_id: String,
+    /// Saves are also recorded as operations other instances can apply.
+    #[serde(default)]
+    pub record_operations: bool,
+    #[serde(default)]
+    pub peer_sync_enabled: bool,
+    #[serde(default = "default_peer_sync_port")]
+    pub peer_sync_port: u16,
+    /// Pre-shared key every instance syncing together must use.
+    #[serde(default)]
+    pub peer_sync_key: String,
+    /// `host:port` of each instance to sync with.
+    #[serde(default)]
+    pub peer_sync_peers: Vec<String>,
+    /// Seconds between syncs with the peers, only on demand when zero.
+    #[serde(default = "default_peer_sync_interval")]
+    pub peer_sync_interval: u32,
+    #[serde(default)]
+    pub webdav_enabled: bool,
+    /// Collection on the WebDAV server the project files are kept in.
+    #[serde(default)]
+    pub webdav_url: String,
+    #[serde(default)]
+    pub webdav_username: String,
+    #[serde(default)]
+    pub webdav_password: String,
+    /// Seconds between syncs with the server, only on demand when zero.
+    #[serde(default = "default_webdav_interval")]
+    pub webdav_interval: u32,
+}
+
+fn default_api_port() -> u16 {
+    7410
+}
+
+fn default_peer_sync_port() -> u16 {
+    7411
+}
+
+fn default_peer_sync_interval() -> u32 {
+    60
+}
+
+fn default_webdav_interval() -> u32 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_snapshot_hourly_hours() -> u32 {
+    24
+}
+
+fn default_snapshot_daily_days() -> u32 {
+    30
+}
+
+impl Configs {
+    pub fn snapshot_policy(&self) -> Option<RetentionPolicy> {
+        self.snapshots_enabled.then_some(RetentionPolicy {
+            hourly_hours: self.snapshot_hourly_hours,
+            daily_days: self.snapshot_daily_days,
+        })
+    }
+
+    /// Whether conflict copies can turn up next to the projects, left by a
+    /// sync tool or by the WebDAV sync.
+    pub fn expects_conflict_copies(&self) -> bool {
+        self.sync_folder || self.webdav_enabled
+    }
+
+    pub fn history_location(&self) -> Option<HistoryLocation> {
+        if !self.history_enabled {
+            return None;
+        }
+        if self.history_path.is_empty() {
+            return Some(HistoryLocation::DataDir);
+        }
+        Some(HistoryLocation::Folder(self.history_path.clone().into()))
+    }
+
+    /// Puts the settings deciding how projects are written into effect, for
+    /// the app and the CLI alike.
+    pub fn apply_storage(&self) {
+        project::set_compression(self.compress_projects);
+        snapshot::set_policy(self.snapshot_policy());
+        history::set_location(self.history_location());
+        sync_folder::set_enabled(self.expects_conflict_copies());
+        if self.record_operations {
+            crdt::set_node(&self.instance_id);
+        } else {
+            crdt::set_node("");
+        }
+    }
+}
+
+impl Default for Configs {
+    fn default() -> Self {
+        Configs {
+            auto_save_interval: 60,
+            new_list_default_color: "#B6DFFF".to_string(),
+            api_enabled: false,
+            api_port: default_api_port(),
+            api_token: String::new(),
+            compress_projects: false,
+            snapshots_enabled: true,
+            snapshot_hourly_hours: default_snapshot_hourly_hours(),
+            snapshot_daily_days: default_snapshot_daily_days(),
+            history_enabled: false,
+            history_path: String::new(),
+            sync_folder: false,
+            instance_id: String::new(),
+            record_operations: false,
+            peer_sync_enabled: false,
+            peer_sync_port: default_peer_sync_port(),
+            peer_sync_key: String::new(),
+            peer_sync_peers: Vec::new(),
+            peer_sync_interval: default_peer_sync_interval(),
+            webdav_enabled: false,
+            webdav_url: String::new(),
+            webdav_username: String::new(),
+            webdav_password: String::new(),
+            webdav_interval: default_webdav_interval(),
+        }
+    }
 }
 
 pub fn save_configs<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
     configs: &Configs,
 ) -> Result<(), KanbanError> {
-    let config_path = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))?
-        .join("configs.json");
+    let config_path = configs_path(app)?;
+    // Nothing else creates the data directory on a fresh install
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    }
     let config_json = serde_json::to_string(&configs)
         .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
     fs::write(&config_path, config_json)
@@ -27,27 +185,66 @@ pub fn save_configs<R: tauri::Runtime>(
 }
 
 pub fn read_configs<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<Configs, KanbanError> {
-    let config_path = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))?
-        .join("configs.json");
+    let config_path = configs_path(app)?;
     if !fs::exists(&config_path)
         .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
     {
-        let default_configs = Configs {
-            auto_save_interval: 60,
-            new_list_default_color: "#B6DFFF".to_string(),
-        };
-        save_configs(&app, &default_configs)?;
+        save_configs(&app, &Configs::default())?;
     }
     let file_content = fs::read_to_string(&config_path)
         .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
-    let configs: Configs = serde_json::from_str(&file_content)
-        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let mut configs: Configs = match serde_json::from_str(&file_content) {
+        Ok(configs) => configs,
+        Err(err) => {
+            let corrupt_path = corrupt_file::set_aside(&config_path)
+                .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+            error_log::report(
+                &format!(
+                    "Configs could not be read, moved to {} and reset",
+                    corrupt_path.display()
+                ),
+                err,
+            );
+            Configs::default()
+        }
+    };
+    // Generate the local API token and instance ID once so they stay stable
+    // between launches
+    if configs.api_token.is_empty() || configs.instance_id.is_empty() {
+        if configs.api_token.is_empty() {
+            configs.api_token = Uuid::new_v4().simple().to_string();
+        }
+        if configs.instance_id.is_empty() {
+            configs.instance_id = Uuid::new_v4().simple().to_string();
+        }
+        save_configs(app, &configs)?;
+    }
     Ok(configs)
 }
 
+/// Reads the configs without creating or repairing the file, the defaults
+/// when there is none yet. For tools such as the CLI, which run next to the
+/// app and leave its files to it.
+pub fn peek_configs<P: AppPathProvider>(app: &P) -> Result<Configs, KanbanError> {
+    let config_path = configs_path(app)?;
+    if !fs::exists(&config_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+    {
+        return Ok(Configs::default());
+    }
+    let file_content = fs::read_to_string(&config_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    serde_json::from_str(&file_content)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+}
+
+pub(crate) fn configs_path<P: AppPathProvider>(app: &P) -> Result<std::path::PathBuf, KanbanError> {
+    // Through `PathProvider`, so it follows the data directory when moved
+    Ok(PathProvider::app_data_dir(app.path())
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join("configs.json"))
+}
+
 #[cfg(test)]
 mod test {
     use serial_test::serial;
@@ -63,18 +260,18 @@ mod test {
         let test_configs = Configs {
             auto_save_interval: 300,
             new_list_default_color: "#FFFFFF".to_string(),
+            ..Default::default()
         };
         let result = save_configs(app, &test_configs);
         assert!(result.is_ok());
-        let config_path = app
-            .path()
+        let config_path = tauri::Manager::path(app)
             .app_data_dir()
             .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))
             .expect("Failed to get path")
             .join("configs.json");
         let config_file_content = fs::read_to_string(&config_path).expect("Failed to read file");
         assert_eq!(
-            "{\"auto_save_interval\":300,\"new_list_default_color\":\"#FFFFFF\"}",
+            "{\"auto_save_interval\":300,\"new_list_default_color\":\"#FFFFFF\",\"api_enabled\":false,\"api_port\":7410,\"api_token\":\"\",\"compress_projects\":false,\"snapshots_enabled\":true,\"snapshot_hourly_hours\":24,\"snapshot_daily_days\":30,\"history_enabled\":false,\"history_path\":\"\",\"sync_folder\":false,\"instance_id\":\"\",\"record_operations\":false,\"peer_sync_enabled\":false,\"peer_sync_port\":7411,\"peer_sync_key\":\"\",\"peer_sync_peers\":[],\"peer_sync_interval\":60,\"webdav_enabled\":false,\"webdav_url\":\"\",\"webdav_username\":\"\",\"webdav_password\":\"\",\"webdav_interval\":60}",
             config_file_content
         );
         fs::remove_file(&config_path).expect("Failed to remove file");
@@ -89,17 +286,90 @@ mod test {
         let test_configs = Configs {
             auto_save_interval: 300,
             new_list_default_color: "#FFFFFF".to_string(),
+            api_token: "test-token".to_string(),
+            instance_id: "test-instance".to_string(),
+            ..Default::default()
         };
         save_configs(app, &test_configs).expect("Failed to save config");
         let result = read_configs(&app);
         assert!(result.is_ok());
         assert_eq!(test_configs, result.unwrap());
-        let config_path = app
-            .path()
+        let config_path = tauri::Manager::path(app)
             .app_data_dir()
             .map_err(|e| KanbanError::from_source(KanbanErrorKind::TauriError, e))
             .expect("Failed to get path")
             .join("configs.json");
         fs::remove_file(&config_path).expect("Failed to remove file");
     }
+
+    #[test]
+    #[serial]
+    fn test_read_configs_generates_api_token() {
+        // tauri env
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        // Configs written before the local API existed
+        let config_path = tauri::Manager::path(app)
+            .app_data_dir()
+            .expect("Failed to get path")
+            .join("configs.json");
+        fs::create_dir_all(config_path.parent().unwrap()).expect("Failed to create dir");
+        fs::write(
+            &config_path,
+            "{\"auto_save_interval\":300,\"new_list_default_color\":\"#FFFFFF\"}",
+        )
+        .expect("Failed to write file");
+        let configs = read_configs(app).expect("Failed to read configs");
+        assert!(!configs.api_enabled);
+        assert_eq!(7410, configs.api_port);
+        assert_eq!(32, configs.api_token.len());
+        assert_eq!(32, configs.instance_id.len());
+        assert_ne!(configs.api_token, configs.instance_id);
+        // The generated token is persisted
+        assert_eq!(configs, read_configs(app).expect("Failed to read configs"));
+        fs::remove_file(&config_path).expect("Failed to remove file");
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_configs_corrupt() {
+        // tauri env
+        let mock = tauri::test::mock_app();
+        let app = mock.app_handle();
+        let config_path = configs_path(app).expect("Failed to get path");
+        fs::create_dir_all(config_path.parent().unwrap()).expect("Failed to create dir");
+        fs::write(&config_path, "{").expect("Failed to write file");
+        let configs = read_configs(app).expect("Failed to read configs");
+        assert_eq!(
+            Configs::default().auto_save_interval,
+            configs.auto_save_interval
+        );
+        let corrupt_path = config_path.with_file_name("configs.json.corrupt");
+        assert_eq!("{", fs::read_to_string(&corrupt_path).unwrap());
+        fs::remove_file(&config_path).expect("Failed to remove file");
+        fs::remove_file(&corrupt_path).expect("Failed to remove file");
+    }
+
+    #[test]
+    fn test_peek_configs() {
+        let dir = tempdir::TempDir::new("kanban-test").expect("Failed to create directory");
+        let app = crate::test_support::mock_app(dir.path());
+        // Nothing is written while there are no configs yet
+        assert_eq!(Configs::default(), peek_configs(&app).unwrap());
+        assert!(!fs::exists(dir.path().join("configs.json")).unwrap());
+        let configs = Configs {
+            compress_projects: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&configs).unwrap();
+        fs::write(dir.path().join("configs.json"), json).unwrap();
+        assert_eq!(configs, peek_configs(&app).unwrap());
+        // Unreadable configs are left to the app to set aside
+        fs::write(dir.path().join("configs.json"), "{").unwrap();
+        assert_eq!(
+            KanbanErrorKind::TextError,
+            peek_configs(&app).unwrap_err().kind
+        );
+        assert!(fs::exists(dir.path().join("configs.json")).unwrap());
+    }
 }
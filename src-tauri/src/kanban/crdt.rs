@@ -0,0 +1,1284 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{
+    board::Board,
+    board_list::BoardList,
+    diff::{match_names, moved},
+    project::{self, AppPathProvider, PathProvider, Project},
+    revision_vector::RevisionVector,
+};
+use std::{
+    collections::HashSet,
+    fs,
+    mem::{self, Discriminant},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+pub(crate) const CRDT_PATH: &str = "crdt";
+
+// Instance ID local edits are recorded as, nothing is recorded while empty,
+// as when recording operations is off
+static NODE: Mutex<String> = Mutex::new(String::new());
+// Held while a replica file is read, changed and written back
+static REPLICA_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn set_node(node: &str) {
+    *NODE.lock().unwrap_or_else(|e| e.into_inner()) = node.to_string();
+}
+
+/// The instance ID local edits are recorded as.
+pub fn node() -> String {
+    NODE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Lamport timestamp of an operation, also the ID of whatever it inserted.
+/// Ordered by counter, then instance ID.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct Stamp {
+    pub counter: u64,
+    pub node: String,
+}
+
+/// Last-writer-wins register, holding the value set with the highest stamp.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Lww<T> {
+    pub value: T,
+    pub stamp: Stamp,
+}
+
+impl<T> Lww<T> {
+    fn new(value: T, stamp: &Stamp) -> Self {
+        Lww {
+            value,
+            stamp: stamp.clone(),
+        }
+    }
+
+    fn set(&mut self, value: T, stamp: &Stamp) {
+        if *stamp > self.stamp {
+            self.value = value;
+            self.stamp = stamp.clone();
+        }
+    }
+}
+
+/// Place of an element in a sequence. There is always room for another
+/// between two positions, and each digit names the instance that made it,
+/// so two instances never make the same position.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct Position(Vec<(u16, String)>);
+
+const DIGITS: u32 = 1 << 16;
+// Largest gap left after the last position, so appends keep positions short
+const APPEND_STEP: u32 = 1 << 8;
+
+impl Position {
+    /// A position after `lo` and before `hi`, open on the side given none.
+    fn between(lo: Option<&Position>, hi: Option<&Position>, node: &str) -> Position {
+        let lo = lo.map_or(&[][..], |p| &p.0[..]);
+        let mut hi = hi.map(|p| &p.0[..]).filter(|hi| *hi > lo);
+        let mut digits = Vec::new();
+        for i in 0.. {
+            let low = lo.get(i).map_or(0, |d| d.0 as u32);
+            let high = hi.map_or(DIGITS, |hi| hi.get(i).map_or(0, |d| d.0 as u32));
+            if high > low + 1 {
+                let gap = match hi {
+                    Some(_) => (high - low) / 2,
+                    None => ((high - low) / 2).min(APPEND_STEP),
+                };
+                digits.push(((low + gap) as u16, node.to_string()));
+                break;
+            }
+            // No room at this digit, so keep the one of either bound and look further
+            let digit = match (lo.get(i), hi.and_then(|hi| hi.get(i))) {
+                (Some(digit), _) => digit.clone(),
+                (None, Some(digit)) if digit.0 == 0 => digit.clone(),
+                (None, _) => (0, node.to_string()),
+            };
+            if hi.is_some_and(|hi| hi.get(i) != Some(&digit)) {
+                hi = None;
+            }
+            digits.push(digit);
+        }
+        Position(digits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Element<T> {
+    id: Stamp,
+    position: Lww<Position>,
+    /// Removed elements stay, so edits made to them elsewhere still apply.
+    removed: bool,
+    value: T,
+}
+
+/// Sequence CRDT: elements ordered by position, then ID, each moved by
+/// setting its position.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+struct Sequence<T> {
+    // Sorted by ID
+    elements: Vec<Element<T>>,
+}
+
+impl<T> Default for Sequence<T> {
+    fn default() -> Self {
+        Sequence {
+            elements: Vec::new(),
+        }
+    }
+}
+
+impl<T> Sequence<T> {
+    fn find(&self, id: &Stamp) -> Result<usize, usize> {
+        self.elements.binary_search_by(|element| element.id.cmp(id))
+    }
+
+    fn get(&self, id: &Stamp) -> Option<&T> {
+        let at = self.find(id).ok()?;
+        Some(&self.elements[at].value)
+    }
+
+    fn get_mut(&mut self, id: &Stamp) -> Option<&mut T> {
+        let at = self.find(id).ok()?;
+        Some(&mut self.elements[at].value)
+    }
+
+    fn insert(&mut self, id: &Stamp, position: &Position, value: T) {
+        if let Err(at) = self.find(id) {
+            let element = Element {
+                id: id.clone(),
+                position: Lww::new(position.clone(), id),
+                removed: false,
+                value,
+            };
+            self.elements.insert(at, element);
+        }
+    }
+
+    fn remove(&mut self, id: &Stamp) -> bool {
+        let Ok(at) = self.find(id) else {
+            return false;
+        };
+        self.elements[at].removed = true;
+        true
+    }
+
+    fn place(&mut self, id: &Stamp, position: &Position, stamp: &Stamp) -> bool {
+        let Ok(at) = self.find(id) else {
+            return false;
+        };
+        self.elements[at].position.set(position.clone(), stamp);
+        true
+    }
+
+    fn removed(&self, id: &Stamp) -> bool {
+        self.find(id).is_ok_and(|at| self.elements[at].removed)
+    }
+
+    fn visible(&self) -> Vec<&Element<T>> {
+        let mut visible: Vec<&Element<T>> = self.elements.iter().filter(|e| !e.removed).collect();
+        visible.sort_by(|a, b| (&a.position.value, &a.id).cmp(&(&b.position.value, &b.id)));
+        visible
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct CardReplica {
+    text: Lww<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ListReplica {
+    title: Lww<String>,
+    color: Lww<[u8; 3]>,
+    cards: Sequence<CardReplica>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct BoardReplica {
+    name: Lww<String>,
+    lists: Sequence<ListReplica>,
+    // Missing from replicas recorded before boards could be archived
+    #[serde(default)]
+    archived: Lww<bool>,
+}
+
+/// One edit to a project. Elements are named by the stamp of the operation
+/// that inserted them.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Edit {
+    SetName {
+        value: String,
+    },
+    SetDescription {
+        value: String,
+    },
+    InsertBoard {
+        position: Position,
+        name: String,
+    },
+    RemoveBoard {
+        board: Stamp,
+    },
+    MoveBoard {
+        board: Stamp,
+        position: Position,
+    },
+    RenameBoard {
+        board: Stamp,
+        value: String,
+    },
+    ArchiveBoard {
+        board: Stamp,
+        value: bool,
+    },
+    InsertList {
+        board: Stamp,
+        position: Position,
+        title: String,
+        color: [u8; 3],
+    },
+    RemoveList {
+        board: Stamp,
+        list: Stamp,
+    },
+    MoveList {
+        board: Stamp,
+        list: Stamp,
+        position: Position,
+    },
+    RenameList {
+        board: Stamp,
+        list: Stamp,
+        value: String,
+    },
+    RecolourList {
+        board: Stamp,
+        list: Stamp,
+        value: [u8; 3],
+    },
+    InsertCard {
+        board: Stamp,
+        list: Stamp,
+        position: Position,
+        text: String,
+    },
+    RemoveCard {
+        board: Stamp,
+        list: Stamp,
+        card: Stamp,
+    },
+    MoveCard {
+        board: Stamp,
+        list: Stamp,
+        card: Stamp,
+        position: Position,
+    },
+    EditCard {
+        board: Stamp,
+        list: Stamp,
+        card: Stamp,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Operation {
+    pub stamp: Stamp,
+    #[serde(flatten)]
+    pub edit: Edit,
+}
+
+/// Where an element of the new order comes from.
+enum Slot {
+    Kept(Stamp),
+    Moved(Stamp, Position),
+    New(Position),
+}
+
+/// Removed elements and a slot for each new one, given the visible elements
+/// as `(id, position)` and the `(old, new)` index pairs of the same element.
+fn plan(
+    old: &[(Stamp, Position)],
+    pairs: &[(usize, usize)],
+    new_len: usize,
+    node: &str,
+) -> (Vec<Stamp>, Vec<Slot>) {
+    let moved = moved(pairs);
+    let mut kept = vec![None; new_len];
+    let mut paired = vec![None; new_len];
+    let mut removed = vec![true; old.len()];
+    for (k, &(i, j)) in pairs.iter().enumerate() {
+        paired[j] = Some(i);
+        removed[i] = false;
+        if !moved.contains(&k) {
+            kept[j] = Some(i);
+        }
+    }
+    let removed = (0..old.len())
+        .filter(|&i| removed[i])
+        .map(|i| old[i].0.clone())
+        .collect();
+    let mut slots = Vec::with_capacity(new_len);
+    let mut previous: Option<Position> = None;
+    for j in 0..new_len {
+        if let Some(i) = kept[j] {
+            previous = Some(old[i].1.clone());
+            slots.push(Slot::Kept(old[i].0.clone()));
+            continue;
+        }
+        let next = kept[j + 1..].iter().flatten().next().map(|&i| &old[i].1);
+        let position = Position::between(previous.as_ref(), next, node);
+        previous = Some(position.clone());
+        slots.push(match paired[j] {
+            Some(i) => Slot::Moved(old[i].0.clone(), position),
+            None => Slot::New(position),
+        });
+    }
+    (removed, slots)
+}
+
+/// A project as a CRDT. Operations can be applied in any order, any number
+/// of times, and replicas with the same operations hold the same project.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProjectReplica {
+    pub id: [u8; 16],
+    name: Lww<String>,
+    description: Lww<String>,
+    boards: Sequence<BoardReplica>,
+    /// Highest counter seen, local operations are stamped above it.
+    clock: u64,
+    /// Highest counter of each instance.
+    seen: RevisionVector,
+    /// Every operation, sorted by stamp.
+    log: Vec<Operation>,
+    /// Operations on elements not inserted yet.
+    pending: Vec<Operation>,
+}
+
+impl ProjectReplica {
+    pub fn new(id: [u8; 16]) -> Self {
+        ProjectReplica {
+            id,
+            name: Lww::new(String::new(), &Stamp::default()),
+            description: Lww::new(String::new(), &Stamp::default()),
+            boards: Sequence::default(),
+            clock: 0,
+            seen: RevisionVector::default(),
+            log: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn seen(&self) -> &RevisionVector {
+        &self.seen
+    }
+
+    /// Operations made after those counted in `known`, in stamp order.
+    pub fn since(&self, known: &RevisionVector) -> Vec<Operation> {
+        self.log
+            .iter()
+            .filter(|op| op.stamp.counter > known.get(&op.stamp.node))
+            .cloned()
+            .collect()
+    }
+
+    /// Applies an operation, returning whether it was new here.
+    pub fn apply(&mut self, operation: Operation) -> bool {
+        match self
+            .log
+            .binary_search_by(|op| op.stamp.cmp(&operation.stamp))
+        {
+            Ok(_) => return false,
+            Err(at) => self.log.insert(at, operation.clone()),
+        }
+        self.clock = self.clock.max(operation.stamp.counter);
+        self.seen
+            .observe(&operation.stamp.node, operation.stamp.counter);
+        if !self.integrate(&operation) {
+            self.pending.push(operation);
+            return true;
+        }
+        // What waited may edit what was just inserted
+        loop {
+            let waiting = self.pending.len();
+            for operation in std::mem::take(&mut self.pending) {
+                if !self.integrate(&operation) {
+                    self.pending.push(operation);
+                }
+            }
+            if self.pending.len() == waiting {
+                return true;
+            }
+        }
+    }
+
+    fn list_mut(&mut self, board: &Stamp, list: &Stamp) -> Option<&mut ListReplica> {
+        self.boards.get_mut(board)?.lists.get_mut(list)
+    }
+
+    /// Whether the board, list or card the operation edits was removed, with
+    /// the board or list holding it.
+    fn edits_removed(&self, edit: &Edit) -> bool {
+        let (board, list, card) = match edit {
+            Edit::MoveBoard { board, .. }
+            | Edit::RenameBoard { board, .. }
+            | Edit::ArchiveBoard { board, .. } => (board, None, None),
+            Edit::MoveList { board, list, .. }
+            | Edit::RenameList { board, list, .. }
+            | Edit::RecolourList { board, list, .. } => (board, Some(list), None),
+            Edit::MoveCard {
+                board, list, card, ..
+            }
+            | Edit::EditCard {
+                board, list, card, ..
+            } => (board, Some(list), Some(card)),
+            _ => return false,
+        };
+        if self.boards.removed(board) {
+            return true;
+        }
+        let Some(lists) = self.boards.get(board).map(|b| &b.lists) else {
+            return false;
+        };
+        let Some(list) = list else { return false };
+        if lists.removed(list) {
+            return true;
+        }
+        card.is_some_and(|card| lists.get(list).is_some_and(|list| list.cards.removed(card)))
+    }
+
+    /// Drops operations that no longer change anything: values set again by
+    /// a later operation, and edits to what was removed. Inserts and removals
+    /// stay, as another instance may still hold what they name.
+    pub fn compact(&mut self) {
+        let mut set: HashSet<(Discriminant<Edit>, Vec<Stamp>)> = HashSet::new();
+        let mut log = mem::take(&mut self.log);
+        // Newest first, so the operation kept for each value is the one it holds
+        log.reverse();
+        log.retain(|operation| {
+            let edit = &operation.edit;
+            let target: Vec<Stamp> = match edit {
+                Edit::SetName { .. } | Edit::SetDescription { .. } => Vec::new(),
+                Edit::MoveBoard { board, .. }
+                | Edit::RenameBoard { board, .. }
+                | Edit::ArchiveBoard { board, .. } => vec![board.clone()],
+                Edit::MoveList { board, list, .. }
+                | Edit::RenameList { board, list, .. }
+                | Edit::RecolourList { board, list, .. } => vec![board.clone(), list.clone()],
+                Edit::MoveCard {
+                    board, list, card, ..
+                }
+                | Edit::EditCard {
+                    board, list, card, ..
+                } => vec![board.clone(), list.clone(), card.clone()],
+                _ => return true,
+            };
+            !self.edits_removed(edit) && set.insert((mem::discriminant(edit), target))
+        });
+        log.reverse();
+        self.log = log;
+    }
+
+    /// Changes the project as the operation says, false when what it edits
+    /// is not there yet.
+    fn integrate(&mut self, operation: &Operation) -> bool {
+        let stamp = &operation.stamp;
+        match &operation.edit {
+            Edit::SetName { value } => self.name.set(value.clone(), stamp),
+            Edit::SetDescription { value } => self.description.set(value.clone(), stamp),
+            Edit::InsertBoard { position, name } => {
+                let board = BoardReplica {
+                    name: Lww::new(name.clone(), stamp),
+                    lists: Sequence::default(),
+                    archived: Lww::new(false, stamp),
+                };
+                self.boards.insert(stamp, position, board);
+            }
+            Edit::RemoveBoard { board } => return self.boards.remove(board),
+            Edit::MoveBoard { board, position } => {
+                return self.boards.place(board, position, stamp)
+            }
+            Edit::RenameBoard { board, value } => match self.boards.get_mut(board) {
+                Some(board) => board.name.set(value.clone(), stamp),
+                None => return false,
+            },
+            Edit::ArchiveBoard { board, value } => match self.boards.get_mut(board) {
+                Some(board) => board.archived.set(*value, stamp),
+                None => return false,
+            },
+            Edit::InsertList {
+                board,
+                position,
+                title,
+                color,
+            } => match self.boards.get_mut(board) {
+                Some(board) => {
+                    let list = ListReplica {
+                        title: Lww::new(title.clone(), stamp),
+                        color: Lww::new(*color, stamp),
+                        cards: Sequence::default(),
+                    };
+                    board.lists.insert(stamp, position, list);
+                }
+                None => return false,
+            },
+            Edit::RemoveList { board, list } => {
+                return self
+                    .boards
+                    .get_mut(board)
+                    .is_some_and(|board| board.lists.remove(list))
+            }
+            Edit::MoveList {
+                board,
+                list,
+                position,
+            } => {
+                return self
+                    .boards
+                    .get_mut(board)
+                    .is_some_and(|board| board.lists.place(list, position, stamp))
+            }
+            Edit::RenameList { board, list, value } => match self.list_mut(board, list) {
+                Some(list) => list.title.set(value.clone(), stamp),
+                None => return false,
+            },
+            Edit::RecolourList { board, list, value } => match self.list_mut(board, list) {
+                Some(list) => list.color.set(*value, stamp),
+                None => return false,
+            },
+            Edit::InsertCard {
+                board,
+                list,
+                position,
+                text,
+            } => match self.list_mut(board, list) {
+                Some(list) => {
+                    let card = CardReplica {
+                        text: Lww::new(text.clone(), stamp),
+                    };
+                    list.cards.insert(stamp, position, card);
+                }
+                None => return false,
+            },
+            Edit::RemoveCard { board, list, card } => {
+                return self
+                    .list_mut(board, list)
+                    .is_some_and(|list| list.cards.remove(card))
+            }
+            Edit::MoveCard {
+                board,
+                list,
+                card,
+                position,
+            } => {
+                return self
+                    .list_mut(board, list)
+                    .is_some_and(|list| list.cards.place(card, position, stamp))
+            }
+            Edit::EditCard {
+                board,
+                list,
+                card,
+                value,
+            } => match self
+                .list_mut(board, list)
+                .and_then(|list| list.cards.get_mut(card))
+            {
+                Some(card) => card.text.set(value.clone(), stamp),
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// The project this replica holds, at revision 0.
+    pub fn project(&self) -> Project {
+        Project {
+            id: self.id,
+            revision: 0,
+            locked: false,
+            archived: false,
+            name: self.name.value.clone(),
+            description: self.description.value.clone(),
+            boards: self
+                .boards
+                .visible()
+                .into_iter()
+                .map(|board| Board {
+                    name: board.value.name.value.clone(),
+                    lists: board
+                        .value
+                        .lists
+                        .visible()
+                        .into_iter()
+                        .map(|list| BoardList {
+                            title: list.value.title.value.clone(),
+                            color: list.value.color.value,
+                            items: list
+                                .value
+                                .cards
+                                .visible()
+                                .into_iter()
+                                .map(|card| card.value.text.value.clone())
+                                .collect(),
+                        })
+                        .collect(),
+                    archived: board.value.archived.value,
+                })
+                .collect(),
+        }
+    }
+
+    /// Applies a new operation of `node` and adds it to `recorded`.
+    fn local(&mut self, node: &str, edit: Edit, recorded: &mut Vec<Operation>) -> Stamp {
+        let stamp = Stamp {
+            counter: self.clock + 1,
+            node: node.to_string(),
+        };
+        let operation = Operation {
+            stamp: stamp.clone(),
+            edit,
+        };
+        self.apply(operation.clone());
+        recorded.push(operation);
+        stamp
+    }
+
+    /// Turns the replica into `project` with new operations of `node`, and
+    /// returns them. Boards, lists and cards are matched by name like
+    /// `diff_projects`.
+    pub fn record(&mut self, node: &str, project: &Project) -> Vec<Operation> {
+        let mut recorded = Vec::new();
+        if self.name.value != project.name {
+            let edit = Edit::SetName {
+                value: project.name.clone(),
+            };
+            self.local(node, edit, &mut recorded);
+        }
+        if self.description.value != project.description {
+            let edit = Edit::SetDescription {
+                value: project.description.clone(),
+            };
+            self.local(node, edit, &mut recorded);
+        }
+        let old: Vec<(Stamp, Position, String)> = self
+            .boards
+            .visible()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.position.value.clone(),
+                    e.value.name.value.clone(),
+                )
+            })
+            .collect();
+        let old_names: Vec<&str> = old.iter().map(|(_, _, name)| name.as_str()).collect();
+        let new_names: Vec<&str> = project.boards.iter().map(|b| b.name.as_str()).collect();
+        let pairs = match_names(&old_names, &new_names);
+        let places: Vec<(Stamp, Position)> = old
+            .iter()
+            .map(|(id, p, _)| (id.clone(), p.clone()))
+            .collect();
+        let (removed, slots) = plan(&places, &pairs, project.boards.len(), node);
+        for board in removed {
+            self.local(node, Edit::RemoveBoard { board }, &mut recorded);
+        }
+        for (slot, board) in slots.into_iter().zip(&project.boards) {
+            let id = match slot {
+                Slot::Kept(id) => id,
+                Slot::Moved(id, position) => {
+                    let edit = Edit::MoveBoard {
+                        board: id.clone(),
+                        position,
+                    };
+                    self.local(node, edit, &mut recorded);
+                    id
+                }
+                Slot::New(position) => {
+                    let edit = Edit::InsertBoard {
+                        position,
+                        name: board.name.clone(),
+                    };
+                    self.local(node, edit, &mut recorded)
+                }
+            };
+            self.record_board(node, &id, board, &mut recorded);
+        }
+        recorded
+    }
+
+    fn record_board(
+        &mut self,
+        node: &str,
+        id: &Stamp,
+        board: &Board,
+        recorded: &mut Vec<Operation>,
+    ) {
+        let Some(replica) = self.boards.get_mut(id) else {
+            return;
+        };
+        let name = replica.name.value.clone();
+        let archived = replica.archived.value;
+        let old: Vec<(Stamp, Position, String)> = replica
+            .lists
+            .visible()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.position.value.clone(),
+                    e.value.title.value.clone(),
+                )
+            })
+            .collect();
+        if name != board.name {
+            let edit = Edit::RenameBoard {
+                board: id.clone(),
+                value: board.name.clone(),
+            };
+            self.local(node, edit, recorded);
+        }
+        if archived != board.archived {
+            let edit = Edit::ArchiveBoard {
+                board: id.clone(),
+                value: board.archived,
+            };
+            self.local(node, edit, recorded);
+        }
+        let old_titles: Vec<&str> = old.iter().map(|(_, _, title)| title.as_str()).collect();
+        let new_titles: Vec<&str> = board.lists.iter().map(|l| l.title.as_str()).collect();
+        let pairs = match_names(&old_titles, &new_titles);
+        let places: Vec<(Stamp, Position)> = old
+            .iter()
+            .map(|(id, p, _)| (id.clone(), p.clone()))
+            .collect();
+        let (removed, slots) = plan(&places, &pairs, board.lists.len(), node);
+        for list in removed {
+            let edit = Edit::RemoveList {
+                board: id.clone(),
+                list,
+            };
+            self.local(node, edit, recorded);
+        }
+        for (slot, list) in slots.into_iter().zip(&board.lists) {
+            let list_id = match slot {
+                Slot::Kept(list_id) => list_id,
+                Slot::Moved(list_id, position) => {
+                    let edit = Edit::MoveList {
+                        board: id.clone(),
+                        list: list_id.clone(),
+                        position,
+                    };
+                    self.local(node, edit, recorded);
+                    list_id
+                }
+                Slot::New(position) => {
+                    let edit = Edit::InsertList {
+                        board: id.clone(),
+                        position,
+                        title: list.title.clone(),
+                        color: list.color,
+                    };
+                    self.local(node, edit, recorded)
+                }
+            };
+            self.record_list(node, id, &list_id, list, recorded);
+        }
+    }
+
+    fn record_list(
+        &mut self,
+        node: &str,
+        board: &Stamp,
+        id: &Stamp,
+        list: &BoardList,
+        recorded: &mut Vec<Operation>,
+    ) {
+        let Some(replica) = self.list_mut(board, id) else {
+            return;
+        };
+        let (title, color) = (replica.title.value.clone(), replica.color.value);
+        let old: Vec<(Stamp, Position, String)> = replica
+            .cards
+            .visible()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.position.value.clone(),
+                    e.value.text.value.clone(),
+                )
+            })
+            .collect();
+        if title != list.title {
+            let edit = Edit::RenameList {
+                board: board.clone(),
+                list: id.clone(),
+                value: list.title.clone(),
+            };
+            self.local(node, edit, recorded);
+        }
+        if color != list.color {
+            let edit = Edit::RecolourList {
+                board: board.clone(),
+                list: id.clone(),
+                value: list.color,
+            };
+            self.local(node, edit, recorded);
+        }
+        let old_texts: Vec<&str> = old.iter().map(|(_, _, text)| text.as_str()).collect();
+        let new_texts: Vec<&str> = list.items.iter().map(|i| i.as_str()).collect();
+        let pairs = match_names(&old_texts, &new_texts);
+        let places: Vec<(Stamp, Position)> = old
+            .iter()
+            .map(|(id, p, _)| (id.clone(), p.clone()))
+            .collect();
+        let (removed, slots) = plan(&places, &pairs, list.items.len(), node);
+        for card in removed {
+            let edit = Edit::RemoveCard {
+                board: board.clone(),
+                list: id.clone(),
+                card,
+            };
+            self.local(node, edit, recorded);
+        }
+        let texts: Vec<(Stamp, String)> = old.into_iter().map(|(id, _, text)| (id, text)).collect();
+        for (slot, text) in slots.into_iter().zip(&list.items) {
+            let card = match slot {
+                Slot::Kept(card) => card,
+                Slot::Moved(card, position) => {
+                    let edit = Edit::MoveCard {
+                        board: board.clone(),
+                        list: id.clone(),
+                        card: card.clone(),
+                        position,
+                    };
+                    self.local(node, edit, recorded);
+                    card
+                }
+                Slot::New(position) => {
+                    let edit = Edit::InsertCard {
+                        board: board.clone(),
+                        list: id.clone(),
+                        position,
+                        text: text.clone(),
+                    };
+                    self.local(node, edit, recorded);
+                    continue;
+                }
+            };
+            // Cards paired with a different text were edited
+            let old_text = texts.iter().find(|(id, _)| *id == card).map(|(_, t)| t);
+            if old_text != Some(text) {
+                let edit = Edit::EditCard {
+                    board: board.clone(),
+                    list: id.clone(),
+                    card,
+                    value: text.clone(),
+                };
+                self.local(node, edit, recorded);
+            }
+        }
+    }
+}
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+fn replica_path<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<PathBuf, KanbanError> {
+    let project_path = project::project_file_path(app, project_id)?;
+    let file_name = project_path.file_name().unwrap_or_default();
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(CRDT_PATH)
+        .join(file_name)
+        .with_extension("json"))
+}
+
+fn read_replica<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<ProjectReplica, KanbanError> {
+    let path = replica_path(app, project_id)?;
+    if !fs::exists(&path).map_err(io_error)? {
+        let id: [u8; 16] = project_id
+            .try_into()
+            .map_err(|_| KanbanError::new(KanbanErrorKind::ProjectError, "Invalid project ID"))?;
+        return Ok(ProjectReplica::new(id));
+    }
+    let json = fs::read_to_string(&path).map_err(io_error)?;
+    serde_json::from_str(&json).map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+}
+
+/// Compacts the replica and writes it, unless the project is encrypted, as
+/// the replica would hold it in the clear.
+fn write_replica<P: AppPathProvider>(
+    app: &P,
+    replica: &mut ProjectReplica,
+) -> Result<(), KanbanError> {
+    if project::is_encrypted(app, &replica.id)? {
+        return Ok(());
+    }
+    replica.compact();
+    let path = replica_path(app, &replica.id)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_string(replica)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, json).map_err(io_error)?;
+    fs::rename(&temp_path, &path).map_err(io_error)
+}
+
+/// Records the edits made to the stored project since the last time as
+/// operations of `node`. Encrypted projects are left out, as their replica
+/// would hold them in the clear.
+fn record_as<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    node: &str,
+) -> Result<(), KanbanError> {
+    if project::project_stamp(app, project_id).is_err() || project::is_encrypted(app, project_id)? {
+        return Ok(());
+    }
+    let project = project::read_project(app, project_id)?;
+    let mut replica = read_replica(app, project_id)?;
+    if !replica.record(node, &project).is_empty() {
+        write_replica(app, &mut replica)?;
+    }
+    Ok(())
+}
+
+/// Records a saved project as operations of this instance.
+pub(crate) fn record_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let node = node();
+    if node.is_empty() {
+        return Ok(());
+    }
+    let _guard = REPLICA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    record_as(app, project_id, &node)
+}
+
+pub(crate) fn delete_replica<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let _guard = REPLICA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = replica_path(app, project_id)?;
+    if !fs::exists(&path).map_err(io_error)? {
+        return Ok(());
+    }
+    fs::remove_file(path).map_err(io_error)
+}
+
+/// Operations for another instance, with the counts of this one so it can
+/// send back what this one lacks.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OperationBatch {
+    pub operations: Vec<Operation>,
+    pub seen: RevisionVector,
+}
+
+fn check_syncable<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    if node.is_empty() {
+        return Err(KanbanError::new(
+            KanbanErrorKind::SyncError,
+            "Recording operations is off",
+        ));
+    }
+    if project::is_encrypted(app, project_id)? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::SyncError,
+            "Encrypted projects have no operations to exchange",
+        ));
+    }
+    Ok(())
+}
+
+/// Operations of a project that another instance, having those counted in
+/// `known`, lacks.
+pub fn operations_since<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    project_id: &[u8],
+    known: &RevisionVector,
+) -> Result<OperationBatch, KanbanError> {
+    check_syncable(app, node, project_id)?;
+    let _guard = REPLICA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    record_as(app, project_id, node)?;
+    let replica = read_replica(app, project_id)?;
+    Ok(OperationBatch {
+        operations: replica.since(known),
+        seen: replica.seen().clone(),
+    })
+}
+
+/// Applies operations made by other instances and saves the project they
+/// lead to over the stored one.
+pub fn apply_operations<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    project_id: &[u8],
+    operations: Vec<Operation>,
+) -> Result<Project, KanbanError> {
+    check_syncable(app, node, project_id)?;
+    let replica = {
+        let _guard = REPLICA_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // Edits not recorded yet would be lost under the saved result
+        record_as(app, project_id, node)?;
+        let mut replica = read_replica(app, project_id)?;
+        for operation in operations {
+            replica.apply(operation);
+        }
+        write_replica(app, &mut replica)?;
+        replica
+    };
+    let mut project = replica.project();
+    let current = match project::project_stamp(app, project_id) {
+        Ok(_) => Some(project::read_project(app, project_id)?),
+        Err(_) => None,
+    };
+    if let Some(current) = &current {
+        if (&current.name, &current.description, &current.boards)
+            == (&project.name, &project.description, &project.boards)
+        {
+            return Ok(current.clone());
+        }
+        project.revision = current.revision;
+    }
+    project::save_project(app, &project)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::board::arb_board;
+    use crate::test_support::{mock_app, test_project};
+    use proptest::prelude::*;
+    use tempdir::TempDir;
+
+    fn list(title: &str, items: &[&str]) -> BoardList {
+        BoardList {
+            title: title.to_string(),
+            color: [0xFF, 0xFF, 0xFF],
+            items: items.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Applies to `replica` every operation of `other` it lacks.
+    fn pull(replica: &mut ProjectReplica, other: &ProjectReplica) {
+        for operation in other.since(replica.seen()) {
+            replica.apply(operation);
+        }
+    }
+
+    #[test]
+    fn test_position_between() {
+        let first = Position::between(None, None, "a");
+        let last = Position::between(Some(&first), None, "a");
+        assert!(first < last);
+        assert!(Position::between(None, Some(&first), "a") < first);
+        // Always room just after the same position
+        let mut hi = last.clone();
+        for _ in 0..100 {
+            let position = Position::between(Some(&first), Some(&hi), "a");
+            assert!(first < position && position < hi);
+            hi = position;
+        }
+        // Positions of two instances in the same gap differ
+        let a = Position::between(Some(&first), Some(&last), "a");
+        let b = Position::between(Some(&first), Some(&last), "b");
+        assert_ne!(a, b);
+        let (lo, hi) = if a < b { (&a, &b) } else { (&b, &a) };
+        let position = Position::between(Some(lo), Some(hi), "c");
+        assert!(lo < &position && &position < hi);
+    }
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let base = test_project(
+            [7; 16],
+            vec![Board {
+                name: "Board 1".to_string(),
+                lists: vec![list("To Do", &["Item 1", "Item 2", "Item 3"])],
+                archived: false,
+            }],
+        );
+        let mut a = ProjectReplica::new(base.id);
+        a.record("a", &base);
+        let mut b = a.clone();
+        // A edits the second item and moves the first to the end
+        let mut ours = base.clone();
+        ours.boards[0].lists[0].items = ["Item 2 edited", "Item 3", "Item 1"]
+            .map(|s| s.to_string())
+            .to_vec();
+        let a_operations = a.record("a", &ours);
+        assert_eq!(2, a_operations.len());
+        // B renames the list and adds an item after the second
+        let mut theirs = base.clone();
+        theirs.boards[0].lists[0] = list("Doing", &["Item 1", "Item 2", "New Item", "Item 3"]);
+        let b_operations = b.record("b", &theirs);
+        assert_eq!(2, b_operations.len());
+        // Either way round, any order
+        for operation in b_operations.iter().rev() {
+            a.apply(operation.clone());
+        }
+        for operation in a_operations.iter().rev() {
+            b.apply(operation.clone());
+        }
+        assert_eq!(a.project(), b.project());
+        let merged = a.project();
+        assert_eq!("Doing", merged.boards[0].lists[0].title);
+        assert_eq!(
+            vec!["Item 2 edited", "New Item", "Item 3", "Item 1"],
+            merged.boards[0].lists[0].items
+        );
+        // Applying again changes nothing
+        assert!(!a.apply(b_operations[0].clone()));
+        assert!(a.record("a", &merged).is_empty());
+    }
+
+    #[test]
+    fn test_operations_exchange() {
+        let dirs = [
+            TempDir::new("test_crdt_a").unwrap(),
+            TempDir::new("test_crdt_b").unwrap(),
+        ];
+        let [a, b] = dirs.each_ref().map(|dir| mock_app(dir.path()));
+        fs::create_dir_all(dirs[0].path().join(project::PROJECT_PATH)).unwrap();
+        fs::create_dir_all(dirs[1].path().join(project::PROJECT_PATH)).unwrap();
+        let project = test_project(
+            [7; 16],
+            vec![Board {
+                name: "Board 1".to_string(),
+                lists: vec![list("To Do", &["Item 1"])],
+                archived: false,
+            }],
+        );
+        project::save_project(&a, &project).unwrap();
+        // B has nothing yet and takes the project whole
+        let batch = operations_since(&a, "a", &project.id, &RevisionVector::default()).unwrap();
+        let created = apply_operations(&b, "b", &project.id, batch.operations).unwrap();
+        assert_eq!(1, created.revision);
+        assert_eq!(project.boards, created.boards);
+        // An edit on B goes back to A, and nothing else does
+        let mut edited = created.clone();
+        edited.boards[0].lists[0].items.push("Item 2".to_string());
+        project::save_project(&b, &edited).unwrap();
+        let batch = operations_since(&b, "b", &project.id, &batch.seen).unwrap();
+        assert_eq!(1, batch.operations.len());
+        let updated = apply_operations(&a, "a", &project.id, batch.operations).unwrap();
+        assert_eq!(2, updated.revision);
+        assert_eq!(edited.boards, updated.boards);
+        let batch = operations_since(&a, "a", &project.id, &batch.seen).unwrap();
+        assert!(batch.operations.is_empty());
+        // No IDs, no operations
+        assert!(operations_since(&a, "", &project.id, &RevisionVector::default()).is_err());
+        // A passphrase takes the replica away with the clear project
+        let replica = replica_path(&a, &project.id).unwrap();
+        assert!(fs::exists(&replica).unwrap());
+        project::change_passphrase(&a, &project.id, None, Some("secret")).unwrap();
+        assert!(!fs::exists(&replica).unwrap());
+        record_as(&a, &project.id, "a").unwrap();
+        assert!(!fs::exists(&replica).unwrap());
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut project = test_project(
+            [7; 16],
+            vec![Board {
+                name: "Board 1".to_string(),
+                lists: vec![list("To Do", &["Item 1"]), list("Done", &["Item 2"])],
+                archived: false,
+            }],
+        );
+        let mut replica = ProjectReplica::new(project.id);
+        replica.record("a", &project);
+        let recorded = replica.log.len();
+        // Renamed twice, and a card edited in a list removed afterwards
+        for name in ["Renamed", "Renamed again"] {
+            project.name = name.to_string();
+            replica.record("a", &project);
+        }
+        project.boards[0].lists[1].items[0] = "Item 2 edited".to_string();
+        replica.record("a", &project);
+        project.boards[0].lists.pop();
+        replica.record("a", &project);
+        assert_eq!(recorded + 4, replica.log.len());
+        replica.compact();
+        // Only the last of the three names set is left, and the card edit
+        // goes, but the removal stays
+        assert_eq!(recorded + 1, replica.log.len());
+        assert_eq!(project.boards, replica.project().boards);
+        let mut rebuilt = ProjectReplica::new(project.id);
+        for operation in replica.since(&RevisionVector::default()) {
+            rebuilt.apply(operation);
+        }
+        assert_eq!(replica.project(), rebuilt.project());
+        assert_eq!("Renamed again", rebuilt.project().name);
+    }
+
+    proptest! {
+        #[test]
+        fn test_replicas_converge(
+            base in prop::collection::vec(arb_board(), 0..3),
+            ours in prop::collection::vec(arb_board(), 0..3),
+            theirs in prop::collection::vec(arb_board(), 0..3),
+        ) {
+            let mut a = ProjectReplica::new([7; 16]);
+            a.record("a", &test_project([7; 16], base));
+            let mut b = a.clone();
+            a.record("a", &test_project([7; 16], ours.clone()));
+            prop_assert_eq!(&ours, &a.project().boards);
+            b.record("b", &test_project([7; 16], theirs));
+            let (a_before, b_before) = (a.clone(), b.clone());
+            pull(&mut a, &b_before);
+            pull(&mut b, &a_before);
+            prop_assert_eq!(a.project(), b.project());
+            // The log alone rebuilds the project, in any order
+            let mut rebuilt = ProjectReplica::new([7; 16]);
+            for operation in a.since(&RevisionVector::default()).into_iter().rev() {
+                rebuilt.apply(operation);
+            }
+            prop_assert_eq!(a.project(), rebuilt.project());
+            // So does what is left once compacted
+            a.compact();
+            let mut rebuilt = ProjectReplica::new([7; 16]);
+            for operation in a.since(&RevisionVector::default()).into_iter().rev() {
+                rebuilt.apply(operation);
+            }
+            prop_assert_eq!(a.project(), rebuilt.project());
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::errors::error_log;
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// A file by this name next to the executable turns on portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+/// Folder next to the executable the data is kept in while portable.
+pub const PORTABLE_DATA_PATH: &str = "data";
+/// Kept in the default data directory, points at the one chosen instead.
+pub const LOCATION_FILE: &str = "data_location.json";
+
+static LOCATION: Mutex<Option<DataLocation>> = Mutex::new(None);
+// One move at a time
+static MOVE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationKind {
+    Default,
+    Custom,
+    Portable,
+}
+
+/// Where the projects, configs and everything else are stored.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DataLocation {
+    pub path: PathBuf,
+    pub kind: LocationKind,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LocationFile {
+    path: PathBuf,
+}
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+fn data_dir_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::IoError, message)
+}
+
+/// The data directory in use, none until it is set at start-up.
+pub fn current() -> Option<DataLocation> {
+    LOCATION.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn set_location(location: DataLocation) {
+    *LOCATION.lock().unwrap_or_else(|e| e.into_inner()) = Some(location);
+}
+
+/// The folder of the running executable.
+pub fn executable_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+}
+
+/// Finds the data directory: next to the executable in portable mode, the
+/// one chosen with `move_data` if any, otherwise `default_dir`. A chosen
+/// directory that has gone missing, such as an unmounted drive, is an error
+/// rather than a silent fallback to the default. An unreadable choice falls
+/// back to the default, reported each time until it is repaired.
+pub fn locate(
+    default_dir: &Path,
+    executable_dir: Option<&Path>,
+) -> Result<DataLocation, KanbanError> {
+    if let Some(dir) = executable_dir {
+        if fs::exists(dir.join(PORTABLE_MARKER)).map_err(io_error)? {
+            return Ok(DataLocation {
+                path: dir.join(PORTABLE_DATA_PATH),
+                kind: LocationKind::Portable,
+            });
+        }
+    }
+    let location_path = default_dir.join(LOCATION_FILE);
+    if !fs::exists(&location_path).map_err(io_error)? {
+        return Ok(DataLocation {
+            path: default_dir.to_path_buf(),
+            kind: LocationKind::Default,
+        });
+    }
+    let json = fs::read_to_string(&location_path).map_err(io_error)?;
+    let file: LocationFile = match serde_json::from_str(&json) {
+        Ok(file) => file,
+        Err(err) => {
+            error_log::report(
+                &format!(
+                    "{} could not be read, using the default data directory",
+                    location_path.display()
+                ),
+                err,
+            );
+            return Ok(DataLocation {
+                path: default_dir.to_path_buf(),
+                kind: LocationKind::Default,
+            });
+        }
+    };
+    if !fs::exists(&file.path).map_err(io_error)? {
+        return Err(data_dir_error(format!(
+            "Data directory {} is missing, reconnect it or delete {}",
+            file.path.display(),
+            location_path.display()
+        )));
+    }
+    Ok(DataLocation {
+        path: file.path,
+        kind: LocationKind::Custom,
+    })
+}
+
+// The path with symlinks and `..` resolved, as far as it exists
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => normalize(parent).join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn entries(dir: &Path) -> Result<Vec<fs::DirEntry>, KanbanError> {
+    if !fs::exists(dir).map_err(io_error)? {
+        return Ok(Vec::new());
+    }
+    fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map_err(io_error))
+        .collect()
+}
+
+/// Files under `dir`, relative to it. The location file is not data.
+fn data_files(dir: &Path) -> Result<Vec<PathBuf>, KanbanError> {
+    let mut files = Vec::new();
+    let mut folders = vec![PathBuf::new()];
+    while let Some(folder) = folders.pop() {
+        for entry in entries(&dir.join(&folder))? {
+            let relative = folder.join(entry.file_name());
+            if relative.as_os_str() == LOCATION_FILE {
+                continue;
+            }
+            if entry.file_type().map_err(io_error)?.is_dir() {
+                folders.push(relative);
+            } else {
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn copy_files(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), KanbanError> {
+    for file in files {
+        let target = to.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        fs::copy(from.join(file), &target).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+fn verify_files(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), KanbanError> {
+    if data_files(from)? != files {
+        return Err(data_dir_error(
+            "Data changed while it was being copied, please try again",
+        ));
+    }
+    for file in files {
+        if fs::read(from.join(file)).map_err(io_error)?
+            != fs::read(to.join(file)).map_err(io_error)?
+        {
+            return Err(data_dir_error(format!(
+                "{} differs from the original after copying",
+                file.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Removes everything in `dir` but the location file
+fn clear_data(dir: &Path) -> Result<(), KanbanError> {
+    for entry in entries(dir)? {
+        if entry.file_name() == LOCATION_FILE {
+            continue;
+        }
+        match entry.file_type().map_err(io_error)? {
+            kind if kind.is_dir() => fs::remove_dir_all(entry.path()),
+            _ => fs::remove_file(entry.path()),
+        }
+        .map_err(io_error)?;
+    }
+    Ok(())
+}
+
+// Takes back a failed copy, leaving `dir` as it was before
+fn remove_copy(dir: &Path, created: bool) {
+    if created {
+        let _ = fs::remove_dir_all(dir);
+        return;
+    }
+    let _ = clear_data(dir);
+}
+
+/// Points the default data directory at `target`, or back at itself when
+/// given none.
+fn write_location(default_dir: &Path, target: Option<&Path>) -> Result<(), KanbanError> {
+    let location_path = default_dir.join(LOCATION_FILE);
+    let Some(target) = target else {
+        if fs::exists(&location_path).map_err(io_error)? {
+            fs::remove_file(&location_path).map_err(io_error)?;
+        }
+        return Ok(());
+    };
+    fs::create_dir_all(default_dir).map_err(io_error)?;
+    let json = serde_json::to_string(&LocationFile {
+        path: target.to_path_buf(),
+    })
+    .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    // Replaced in one step so a crash never leaves half a path behind
+    let temp_path = location_path.with_extension("tmp");
+    fs::write(&temp_path, json).map_err(io_error)?;
+    fs::rename(&temp_path, &location_path).map_err(io_error)
+}
+
+/// Copies everything in `current` to the empty folder `target`, checks the
+/// copy matches and only then points the default data directory at it. The
+/// original is cleared out last, so a failed move loses nothing. Nothing may
+/// write to the data meanwhile.
+pub fn move_data(
+    default_dir: &Path,
+    current: &DataLocation,
+    target: &Path,
+) -> Result<DataLocation, KanbanError> {
+    let _guard = MOVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if current.kind == LocationKind::Portable {
+        return Err(data_dir_error(format!(
+            "Data stays next to the app in portable mode, remove the {} file to move it",
+            PORTABLE_MARKER
+        )));
+    }
+    if !target.is_absolute() {
+        return Err(data_dir_error(
+            "The new data directory must be an absolute path",
+        ));
+    }
+    let (from, to) = (normalize(&current.path), normalize(target));
+    if from == to {
+        return Err(data_dir_error("The data is already in that folder"));
+    }
+    if to.starts_with(&from) || from.starts_with(&to) {
+        return Err(data_dir_error(
+            "The new data directory cannot contain or be inside the current one",
+        ));
+    }
+    if entries(&to)?
+        .iter()
+        .any(|entry| entry.file_name() != LOCATION_FILE)
+    {
+        return Err(data_dir_error("Choose an empty folder for the data"));
+    }
+    let to_default = to == normalize(default_dir);
+    let created = !fs::exists(&to).map_err(io_error)?;
+    fs::create_dir_all(&to).map_err(io_error)?;
+    let files = data_files(&from)?;
+    let result = copy_files(&from, &to, &files)
+        .and_then(|_| verify_files(&from, &to, &files))
+        .and_then(|_| write_location(default_dir, (!to_default).then_some(to.as_path())));
+    if let Err(err) = result {
+        remove_copy(&to, created);
+        return Err(err);
+    }
+    // The copy is in use already, so what is left behind only takes up space
+    if let Err(err) = clear_data(&from) {
+        error_log::report(
+            &format!("Failed to clear out {} after moving", from.display()),
+            err,
+        );
+    }
+    let kind = if to_default {
+        LocationKind::Default
+    } else {
+        LocationKind::Custom
+    };
+    Ok(DataLocation { path: to, kind })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_locate() {
+        let dir = TempDir::new("test_locate").unwrap();
+        let default_dir = dir.path().join("default");
+        let exe_dir = dir.path().join("app");
+        fs::create_dir_all(&exe_dir).unwrap();
+        let location = locate(&default_dir, Some(&exe_dir)).unwrap();
+        assert_eq!(LocationKind::Default, location.kind);
+        assert_eq!(default_dir, location.path);
+        // A chosen directory, as long as it is there
+        let chosen = dir.path().join("chosen");
+        write_location(&default_dir, Some(&chosen)).unwrap();
+        assert!(locate(&default_dir, Some(&exe_dir)).is_err());
+        fs::create_dir_all(&chosen).unwrap();
+        let location = locate(&default_dir, None).unwrap();
+        assert_eq!(LocationKind::Custom, location.kind);
+        assert_eq!(chosen, location.path);
+        // The marker wins over everything
+        write(&exe_dir.join(PORTABLE_MARKER), "");
+        let location = locate(&default_dir, Some(&exe_dir)).unwrap();
+        assert_eq!(LocationKind::Portable, location.kind);
+        assert_eq!(exe_dir.join(PORTABLE_DATA_PATH), location.path);
+        // An unreadable choice is left for the user to repair
+        write(&default_dir.join(LOCATION_FILE), "{");
+        let location = locate(&default_dir, None).unwrap();
+        assert_eq!(LocationKind::Default, location.kind);
+        assert!(default_dir.join(LOCATION_FILE).exists());
+    }
+
+    #[test]
+    fn test_move_data() {
+        let dir = TempDir::new("test_move_data").unwrap();
+        let default_dir = dir.path().join("default");
+        write(&default_dir.join("configs.json"), "{}");
+        write(&default_dir.join("projects").join("0A"), "project");
+        write(
+            &default_dir.join("snapshots").join("0A").join("1"),
+            "snapshot",
+        );
+        let current = locate(&default_dir, None).unwrap();
+        let target = dir.path().join("synced").join("kanban");
+        let moved = move_data(&default_dir, &current, &target).unwrap();
+        assert_eq!(LocationKind::Custom, moved.kind);
+        assert_eq!(
+            "snapshot",
+            fs::read_to_string(moved.path.join("snapshots").join("0A").join("1")).unwrap()
+        );
+        assert_eq!(
+            vec![
+                PathBuf::from("configs.json"),
+                PathBuf::from("projects").join("0A"),
+                PathBuf::from("snapshots").join("0A").join("1"),
+            ],
+            data_files(&moved.path).unwrap()
+        );
+        assert_eq!(moved, locate(&default_dir, None).unwrap());
+        // Only the pointer to the new directory is left behind
+        assert!(data_files(&default_dir).unwrap().is_empty());
+        assert!(fs::exists(default_dir.join(LOCATION_FILE)).unwrap());
+        let back = move_data(&default_dir, &moved, &default_dir).unwrap();
+        assert_eq!(LocationKind::Default, back.kind);
+        assert!(!fs::exists(default_dir.join(LOCATION_FILE)).unwrap());
+        assert_eq!(
+            "{}",
+            fs::read_to_string(default_dir.join("configs.json")).unwrap()
+        );
+        assert!(data_files(&moved.path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_move_data_refused() {
+        let dir = TempDir::new("test_move_data_refused").unwrap();
+        let default_dir = dir.path().join("default");
+        write(&default_dir.join("configs.json"), "{}");
+        let current = locate(&default_dir, None).unwrap();
+        let occupied = dir.path().join("occupied");
+        write(&occupied.join("notes.txt"), "mine");
+        assert!(move_data(&default_dir, &current, &occupied).is_err());
+        assert!(move_data(&default_dir, &current, &default_dir.join("inner")).is_err());
+        assert!(move_data(&default_dir, &current, Path::new("relative")).is_err());
+        let portable = DataLocation {
+            path: default_dir.clone(),
+            kind: LocationKind::Portable,
+        };
+        assert!(move_data(&default_dir, &portable, &dir.path().join("new")).is_err());
+        // Nothing was touched
+        assert_eq!(
+            vec![PathBuf::from("notes.txt")],
+            data_files(&occupied).unwrap()
+        );
+        assert!(!fs::exists(default_dir.join(LOCATION_FILE)).unwrap());
+        assert!(!fs::exists(dir.path().join("new")).unwrap());
+    }
+}
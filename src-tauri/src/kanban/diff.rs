@@ -0,0 +1,703 @@
+use crate::kanban::{board::Board, project::Project};
+use std::{collections::HashMap, fmt};
+
+// Most item texts compared when pairing edits on a board, so a board rewritten
+// wholesale does not take quadratic time
+const SIMILAR_COMPARISONS_MAX: usize = 10_000;
+
+/// One difference between two versions of a project. Boards and lists are
+/// named as in the new version, or the old one when they were removed.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    ProjectRenamed {
+        from: String,
+        to: String,
+    },
+    DescriptionEdited {
+        from: String,
+        to: String,
+    },
+    BoardAdded {
+        board: String,
+    },
+    BoardRemoved {
+        board: String,
+    },
+    BoardRenamed {
+        from: String,
+        to: String,
+    },
+    BoardMoved {
+        board: String,
+        from: usize,
+        to: usize,
+    },
+    BoardArchived {
+        board: String,
+        archived: bool,
+    },
+    ListAdded {
+        board: String,
+        list: String,
+    },
+    ListRemoved {
+        board: String,
+        list: String,
+    },
+    ListRenamed {
+        board: String,
+        from: String,
+        to: String,
+    },
+    ListMoved {
+        board: String,
+        list: String,
+        from: usize,
+        to: usize,
+    },
+    ListRecoloured {
+        board: String,
+        list: String,
+        from: [u8; 3],
+        to: [u8; 3],
+    },
+    ItemAdded {
+        board: String,
+        list: String,
+        item: String,
+        position: usize,
+    },
+    ItemRemoved {
+        board: String,
+        list: String,
+        item: String,
+    },
+    ItemMoved {
+        board: String,
+        from_list: String,
+        to_list: String,
+        item: String,
+        position: usize,
+    },
+    ItemEdited {
+        board: String,
+        list: String,
+        from: String,
+        to: String,
+    },
+}
+
+fn hex(color: &[u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+}
+
+// Positions are shown 1-based, like in the CLI
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::ProjectRenamed { from, to } => {
+                write!(f, "Renamed project \"{}\" to \"{}\"", from, to)
+            }
+            Change::DescriptionEdited { .. } => write!(f, "Edited description"),
+            Change::BoardAdded { board } => write!(f, "Added board \"{}\"", board),
+            Change::BoardRemoved { board } => write!(f, "Removed board \"{}\"", board),
+            Change::BoardRenamed { from, to } => {
+                write!(f, "Renamed board \"{}\" to \"{}\"", from, to)
+            }
+            Change::BoardMoved { board, from, to } => write!(
+                f,
+                "Moved board \"{}\" from {} to {}",
+                board,
+                from + 1,
+                to + 1
+            ),
+            Change::BoardArchived { board, archived } => match archived {
+                true => write!(f, "Archived board \"{}\"", board),
+                false => write!(f, "Unarchived board \"{}\"", board),
+            },
+            Change::ListAdded { board, list } => {
+                write!(f, "{}: Added list \"{}\"", board, list)
+            }
+            Change::ListRemoved { board, list } => {
+                write!(f, "{}: Removed list \"{}\"", board, list)
+            }
+            Change::ListRenamed { board, from, to } => {
+                write!(f, "{}: Renamed list \"{}\" to \"{}\"", board, from, to)
+            }
+            Change::ListMoved {
+                board,
+                list,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: Moved list \"{}\" from {} to {}",
+                board,
+                list,
+                from + 1,
+                to + 1
+            ),
+            Change::ListRecoloured {
+                board,
+                list,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: Recoloured list \"{}\" from {} to {}",
+                board,
+                list,
+                hex(from),
+                hex(to)
+            ),
+            Change::ItemAdded {
+                board,
+                list,
+                item,
+                position,
+            } => write!(
+                f,
+                "{} / {}: Added \"{}\" at {}",
+                board,
+                list,
+                item,
+                position + 1
+            ),
+            Change::ItemRemoved { board, list, item } => {
+                write!(f, "{} / {}: Removed \"{}\"", board, list, item)
+            }
+            Change::ItemMoved {
+                board,
+                from_list,
+                to_list,
+                item,
+                position,
+            } => write!(
+                f,
+                "{}: Moved \"{}\" from {} to {} at {}",
+                board,
+                item,
+                from_list,
+                to_list,
+                position + 1
+            ),
+            Change::ItemEdited {
+                board,
+                list,
+                from,
+                to,
+            } => write!(f, "{} / {}: Edited \"{}\" to \"{}\"", board, list, from, to),
+        }
+    }
+}
+
+/// The changes as text, one per line.
+pub fn render_text(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return "No changes".to_string();
+    }
+    changes
+        .iter()
+        .map(|change| change.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Pairs the old and new names that stand for the same board or list, as
+/// `(old index, new index)`. Equal names pair first, then what is left on
+/// both sides pairs up in order and is taken as renamed, since boards and
+/// lists carry no IDs.
+pub(crate) fn match_names(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let mut unused: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, name) in old.iter().enumerate().rev() {
+        unused.entry(name).or_default().push(i);
+    }
+    let mut pairs = Vec::new();
+    let mut new_left = Vec::new();
+    for (j, name) in new.iter().enumerate() {
+        match unused.get_mut(name).and_then(|indices| indices.pop()) {
+            Some(i) => pairs.push((i, j)),
+            None => new_left.push(j),
+        }
+    }
+    let mut old_left: Vec<usize> = unused.into_values().flatten().collect();
+    old_left.sort_unstable();
+    pairs.extend(old_left.into_iter().zip(new_left));
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Of the `(old, new)` position pairs, sorted by old position, the indices
+/// of those that moved: the fewest that break the order of the rest.
+pub(crate) fn moved(pairs: &[(usize, usize)]) -> Vec<usize> {
+    // Longest increasing run of new positions, by patience sorting
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![usize::MAX; pairs.len()];
+    for (k, &(_, new)) in pairs.iter().enumerate() {
+        let at = tails.partition_point(|&t| pairs[t].1 < new);
+        if at > 0 {
+            previous[k] = tails[at - 1];
+        }
+        if at == tails.len() {
+            tails.push(k);
+        } else {
+            tails[at] = k;
+        }
+    }
+    let mut kept = vec![false; pairs.len()];
+    let mut k = tails.last().copied().unwrap_or(usize::MAX);
+    while k != usize::MAX {
+        kept[k] = true;
+        k = previous[k];
+    }
+    (0..pairs.len()).filter(|&k| !kept[k]).collect()
+}
+
+type Paired = (Vec<Option<usize>>, Vec<Option<usize>>);
+
+/// What each old index is paired with, and what each new index is.
+pub(crate) fn paired(pairs: &[(usize, usize)], old_len: usize, new_len: usize) -> Paired {
+    let mut old_to_new = vec![None; old_len];
+    let mut new_to_old = vec![None; new_len];
+    for &(i, j) in pairs {
+        old_to_new[i] = Some(j);
+        new_to_old[j] = Some(i);
+    }
+    (old_to_new, new_to_old)
+}
+
+/// Character pairs of a text, counted, and how many there are in all.
+struct Bigrams {
+    counts: HashMap<(char, char), usize>,
+    total: usize,
+}
+
+fn bigrams(text: &str) -> Bigrams {
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    let mut counts: HashMap<(char, char), usize> = HashMap::new();
+    for w in chars.windows(2) {
+        *counts.entry((w[0], w[1])).or_default() += 1;
+    }
+    Bigrams {
+        counts,
+        total: chars.len().saturating_sub(1),
+    }
+}
+
+/// Whether two texts share at least half their character pairs.
+fn similar(a: &Bigrams, b: &Bigrams) -> bool {
+    if a.total == 0 || b.total == 0 {
+        return false;
+    }
+    let shared: usize = a
+        .counts
+        .iter()
+        .map(|(pair, &count)| count.min(b.counts.get(pair).copied().unwrap_or(0)))
+        .sum();
+    shared * 4 >= a.total + b.total
+}
+
+/// Structured changes from `old` to `new`.
+pub fn diff_projects(old: &Project, new: &Project) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(Change::ProjectRenamed {
+            from: old.name.clone(),
+            to: new.name.clone(),
+        });
+    }
+    if old.description != new.description {
+        changes.push(Change::DescriptionEdited {
+            from: old.description.clone(),
+            to: new.description.clone(),
+        });
+    }
+    let old_names: Vec<&str> = old.boards.iter().map(|b| b.name.as_str()).collect();
+    let new_names: Vec<&str> = new.boards.iter().map(|b| b.name.as_str()).collect();
+    let pairs = match_names(&old_names, &new_names);
+    let (old_to_new, new_to_old) = paired(&pairs, old.boards.len(), new.boards.len());
+    for (i, board) in old.boards.iter().enumerate() {
+        if old_to_new[i].is_none() {
+            changes.push(Change::BoardRemoved {
+                board: board.name.clone(),
+            });
+        }
+    }
+    for (j, board) in new.boards.iter().enumerate() {
+        if new_to_old[j].is_none() {
+            changes.push(Change::BoardAdded {
+                board: board.name.clone(),
+            });
+        }
+    }
+    for &(i, j) in pairs.iter() {
+        if old.boards[i].name != new.boards[j].name {
+            changes.push(Change::BoardRenamed {
+                from: old.boards[i].name.clone(),
+                to: new.boards[j].name.clone(),
+            });
+        }
+    }
+    for k in moved(&pairs) {
+        let (i, j) = pairs[k];
+        changes.push(Change::BoardMoved {
+            board: new.boards[j].name.clone(),
+            from: i,
+            to: j,
+        });
+    }
+    for &(i, j) in pairs.iter() {
+        if old.boards[i].archived != new.boards[j].archived {
+            changes.push(Change::BoardArchived {
+                board: new.boards[j].name.clone(),
+                archived: new.boards[j].archived,
+            });
+        }
+    }
+    for &(i, j) in pairs.iter() {
+        diff_boards(&old.boards[i], &new.boards[j], &mut changes);
+    }
+    changes
+}
+
+fn diff_boards(old: &Board, new: &Board, changes: &mut Vec<Change>) {
+    let board = &new.name;
+    let old_titles: Vec<&str> = old.lists.iter().map(|l| l.title.as_str()).collect();
+    let new_titles: Vec<&str> = new.lists.iter().map(|l| l.title.as_str()).collect();
+    let pairs = match_names(&old_titles, &new_titles);
+    let (old_to_new, new_to_old) = paired(&pairs, old.lists.len(), new.lists.len());
+    for (i, list) in old.lists.iter().enumerate() {
+        if old_to_new[i].is_none() {
+            changes.push(Change::ListRemoved {
+                board: board.clone(),
+                list: list.title.clone(),
+            });
+        }
+    }
+    for (j, list) in new.lists.iter().enumerate() {
+        if new_to_old[j].is_none() {
+            changes.push(Change::ListAdded {
+                board: board.clone(),
+                list: list.title.clone(),
+            });
+        }
+    }
+    for &(i, j) in pairs.iter() {
+        let (old_list, new_list) = (&old.lists[i], &new.lists[j]);
+        if old_list.title != new_list.title {
+            changes.push(Change::ListRenamed {
+                board: board.clone(),
+                from: old_list.title.clone(),
+                to: new_list.title.clone(),
+            });
+        }
+        if old_list.color != new_list.color {
+            changes.push(Change::ListRecoloured {
+                board: board.clone(),
+                list: new_list.title.clone(),
+                from: old_list.color,
+                to: new_list.color,
+            });
+        }
+    }
+    for k in moved(&pairs) {
+        let (i, j) = pairs[k];
+        changes.push(Change::ListMoved {
+            board: board.clone(),
+            list: new.lists[j].title.clone(),
+            from: i,
+            to: j,
+        });
+    }
+    diff_items(old, new, &old_to_new, changes);
+}
+
+/// Items are matched by content across the whole board, preferring the same
+/// list, so an item carried to another list shows as moved. Items of added
+/// and removed lists are covered by the list change.
+fn diff_items(old: &Board, new: &Board, old_to_new: &[Option<usize>], changes: &mut Vec<Change>) {
+    let board = &new.name;
+    // (list, position) of each item, and the new item each old one became
+    let old_items: Vec<(usize, usize)> = old
+        .lists
+        .iter()
+        .enumerate()
+        .flat_map(|(l, list)| (0..list.items.len()).map(move |p| (l, p)))
+        .collect();
+    let text = |(l, p): (usize, usize)| old.lists[l].items[p].as_str();
+    let new_items: Vec<(usize, usize)> = new
+        .lists
+        .iter()
+        .enumerate()
+        .flat_map(|(l, list)| (0..list.items.len()).map(move |p| (l, p)))
+        .collect();
+    let new_text = |(l, p): (usize, usize)| new.lists[l].items[p].as_str();
+    let mut new_to_old_list = vec![None; new.lists.len()];
+    for (ol, nl) in old_to_new.iter().enumerate() {
+        if let Some(nl) = nl {
+            new_to_old_list[*nl] = Some(ol);
+        }
+    }
+    // Same list first, then any list, the first old item on top of each stack
+    let mut same_list: HashMap<(&str, usize), Vec<usize>> = HashMap::new();
+    for (k, &item) in old_items.iter().enumerate().rev() {
+        same_list.entry((text(item), item.0)).or_default().push(k);
+    }
+    let mut matches: Vec<Option<usize>> = new_items
+        .iter()
+        .map(|&(l, p)| {
+            let ol = new_to_old_list[l]?;
+            same_list.get_mut(&(new_text((l, p)), ol))?.pop()
+        })
+        .collect();
+    let mut old_matched = vec![false; old_items.len()];
+    for k in matches.iter().flatten() {
+        old_matched[*k] = true;
+    }
+    let mut any_list: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (k, &item) in old_items.iter().enumerate().rev() {
+        if !old_matched[k] {
+            any_list.entry(text(item)).or_default().push(k);
+        }
+    }
+    for (n, &item) in new_items.iter().enumerate() {
+        if matches[n].is_none() {
+            matches[n] = any_list.get_mut(new_text(item)).and_then(|c| c.pop());
+            if let Some(k) = matches[n] {
+                old_matched[k] = true;
+            }
+        }
+    }
+    // An unmatched item is an edit of the first similar unmatched item left
+    // in the same list. Past a bounded number of comparisons the rest are
+    // left as removed and added.
+    let mut unmatched_old: Vec<Vec<usize>> = vec![Vec::new(); old.lists.len()];
+    for (k, &(l, _)) in old_items.iter().enumerate() {
+        if !old_matched[k] {
+            unmatched_old[l].push(k);
+        }
+    }
+    let mut unmatched_new: Vec<Vec<(usize, Bigrams)>> = Vec::new();
+    unmatched_new.resize_with(new.lists.len(), Vec::new);
+    for (n, &item) in new_items.iter().enumerate() {
+        if matches[n].is_none() && new_to_old_list[item.0].is_some() {
+            unmatched_new[item.0].push((n, bigrams(new_text(item))));
+        }
+    }
+    let mut edited_new = vec![false; new_items.len()];
+    let mut edited_old = vec![false; old_items.len()];
+    let mut comparisons = 0;
+    'lists: for (ol, nl) in old_to_new.iter().enumerate() {
+        let Some(nl) = *nl else { continue };
+        for &k in &unmatched_old[ol] {
+            let old_bigrams = bigrams(text(old_items[k]));
+            let mut found = None;
+            for (n, new_bigrams) in &unmatched_new[nl] {
+                if edited_new[*n] {
+                    continue;
+                }
+                if comparisons == SIMILAR_COMPARISONS_MAX {
+                    break 'lists;
+                }
+                comparisons += 1;
+                if similar(&old_bigrams, new_bigrams) {
+                    found = Some(*n);
+                    break;
+                }
+            }
+            let Some(n) = found else { continue };
+            edited_old[k] = true;
+            edited_new[n] = true;
+            changes.push(Change::ItemEdited {
+                board: board.clone(),
+                list: new.lists[nl].title.clone(),
+                from: text(old_items[k]).to_string(),
+                to: new_text(new_items[n]).to_string(),
+            });
+        }
+    }
+    for (k, &(l, _)) in old_items.iter().enumerate() {
+        if !old_matched[k] && !edited_old[k] && old_to_new[l].is_some() {
+            changes.push(Change::ItemRemoved {
+                board: board.clone(),
+                list: old.lists[l].title.clone(),
+                item: text(old_items[k]).to_string(),
+            });
+        }
+    }
+    let list_added = |l: usize| !old_to_new.contains(&Some(l));
+    for (n, &(l, p)) in new_items.iter().enumerate() {
+        if matches[n].is_none() && !edited_new[n] && !list_added(l) {
+            changes.push(Change::ItemAdded {
+                board: board.clone(),
+                list: new.lists[l].title.clone(),
+                item: new.lists[l].items[p].clone(),
+                position: p,
+            });
+        }
+    }
+    // Moves between lists, then moves within a list that broke its order
+    let mut stayed: HashMap<usize, Vec<(usize, usize, usize)>> = HashMap::new();
+    for (n, &(l, p)) in new_items.iter().enumerate() {
+        let Some(k) = matches[n] else { continue };
+        let (ol, op) = old_items[k];
+        if old_to_new[ol] == Some(l) {
+            stayed.entry(l).or_default().push((op, p, n));
+            continue;
+        }
+        changes.push(Change::ItemMoved {
+            board: board.clone(),
+            from_list: old.lists[ol].title.clone(),
+            to_list: new.lists[l].title.clone(),
+            item: new.lists[l].items[p].clone(),
+            position: p,
+        });
+    }
+    let mut lists: Vec<usize> = stayed.keys().copied().collect();
+    lists.sort_unstable();
+    for l in lists {
+        let mut items = stayed.remove(&l).unwrap_or_default();
+        items.sort_unstable();
+        let pairs: Vec<(usize, usize)> = items.iter().map(|&(op, p, _)| (op, p)).collect();
+        for k in moved(&pairs) {
+            let title = &new.lists[l].title;
+            changes.push(Change::ItemMoved {
+                board: board.clone(),
+                from_list: title.clone(),
+                to_list: title.clone(),
+                item: new.lists[l].items[items[k].1].clone(),
+                position: items[k].1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{test_board, test_list, test_project};
+
+    #[test]
+    fn test_match_names_and_moved() {
+        assert_eq!(
+            vec![(0, 1), (1, 2), (2, 0)],
+            match_names(&["A", "B", "C"], &["X", "A", "B"])
+        );
+        assert_eq!(vec![(0, 0), (1, 1)], match_names(&["A", "A"], &["A", "A"]));
+        // Only C broke the order of the rest
+        assert_eq!(vec![2], moved(&[(0, 1), (1, 2), (2, 0)]));
+        assert!(moved(&[(0, 0), (1, 2), (2, 3)]).is_empty());
+    }
+
+    #[test]
+    fn test_diff_boards_and_lists() {
+        let old = test_project(
+            [0x01; 16],
+            vec![
+                test_board(
+                    "Board 1",
+                    vec![test_list("To Do", &[]), test_list("Done", &[])],
+                ),
+                test_board("Board 2", Vec::new()),
+            ],
+        );
+        let mut new = old.clone();
+        new.name = "Renamed Project".to_string();
+        new.boards.swap(0, 1);
+        new.boards[0].name = "Board 3".to_string();
+        new.boards[1].lists.swap(0, 1);
+        new.boards[1].lists[0].color = [0x00, 0x00, 0x00];
+        new.boards[1].lists.push(test_list("Later", &[]));
+        new.boards[1].archived = true;
+        assert_eq!(
+            vec![
+                "Renamed project \"Test Project\" to \"Renamed Project\"",
+                "Renamed board \"Board 2\" to \"Board 3\"",
+                "Moved board \"Board 1\" from 1 to 2",
+                "Archived board \"Board 1\"",
+                "Board 1: Added list \"Later\"",
+                "Board 1: Recoloured list \"Done\" from #FFFFFF to #000000",
+                "Board 1: Moved list \"To Do\" from 1 to 2",
+            ],
+            render_text(&diff_projects(&old, &new))
+                .lines()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("No changes", render_text(&diff_projects(&old, &old)));
+    }
+
+    #[test]
+    fn test_diff_items() {
+        let old = test_project(
+            [0x01; 16],
+            vec![test_board(
+                "Board 1",
+                vec![
+                    test_list("To Do", &["Write tests", "Fix bug", "Write docs", "Review"]),
+                    test_list("Done", &["Set up"]),
+                ],
+            )],
+        );
+        let mut new = old.clone();
+        // Tests done, docs edited, review to the top, bug removed
+        new.boards[0].lists[0].items = ["Review", "Write the docs", "Release"]
+            .map(|s| s.to_string())
+            .to_vec();
+        new.boards[0].lists[1].items.push("Write tests".to_string());
+        let changes = diff_projects(&old, &new);
+        assert_eq!(
+            vec![
+                Change::ItemEdited {
+                    board: "Board 1".to_string(),
+                    list: "To Do".to_string(),
+                    from: "Write docs".to_string(),
+                    to: "Write the docs".to_string(),
+                },
+                Change::ItemRemoved {
+                    board: "Board 1".to_string(),
+                    list: "To Do".to_string(),
+                    item: "Fix bug".to_string(),
+                },
+                Change::ItemAdded {
+                    board: "Board 1".to_string(),
+                    list: "To Do".to_string(),
+                    item: "Release".to_string(),
+                    position: 2,
+                },
+                Change::ItemMoved {
+                    board: "Board 1".to_string(),
+                    from_list: "To Do".to_string(),
+                    to_list: "Done".to_string(),
+                    item: "Write tests".to_string(),
+                    position: 1,
+                },
+            ],
+            changes
+        );
+        let json = serde_json::to_value(&changes[3]).unwrap();
+        assert_eq!("item_moved", json["kind"]);
+    }
+
+    #[test]
+    fn test_diff_items_bounded() {
+        // Unlike texts on both sides, with an edit only found past the bound
+        let mut old_items: Vec<String> = (0..150).map(|i| (1000 + i).to_string()).collect();
+        let mut new_items: Vec<String> = (0..150)
+            .map(|i| format!("{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char))
+            .collect();
+        old_items.push("Write docs".to_string());
+        new_items.push("Write the docs".to_string());
+        let board = |items: &[String]| {
+            let items: Vec<&str> = items.iter().map(String::as_str).collect();
+            test_board("Board 1", vec![test_list("To Do", &items)])
+        };
+        let old = test_project([0x01; 16], vec![board(&old_items)]);
+        let new = test_project([0x01; 16], vec![board(&new_items)]);
+        let changes = diff_projects(&old, &new);
+        assert_eq!(302, changes.len());
+        assert!(!changes
+            .iter()
+            .any(|c| matches!(c, Change::ItemEdited { .. })));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use std::{collections::BTreeMap, fmt, sync::Mutex};
+
+pub const SALT_SIZE: usize = 16;
+pub const CHECK_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 24;
+
+// Keys of the projects unlocked this session, by project ID
+static UNLOCKED: Mutex<BTreeMap<[u8; 16], ProjectKey>> = Mutex::new(BTreeMap::new());
+
+/// Key derived from a project passphrase with Argon2id. The check is derived
+/// along with the key and stored in the file, so a wrong passphrase can be
+/// told apart from a damaged file.
+#[derive(Clone)]
+pub struct ProjectKey {
+    pub salt: [u8; SALT_SIZE],
+    pub check: [u8; CHECK_SIZE],
+    key: Key,
+}
+
+// Leaves the key out of logs
+impl fmt::Debug for ProjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProjectKey")
+            .field("salt", &self.salt)
+            .finish_non_exhaustive()
+    }
+}
+
+pub fn locked_error() -> KanbanError {
+    KanbanError::new(KanbanErrorKind::LockedError, "Project is locked")
+}
+
+fn derive(passphrase: &str, salt: [u8; SALT_SIZE]) -> Result<ProjectKey, KanbanError> {
+    let mut output = [0u8; 32 + CHECK_SIZE];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+        .map_err(|e| {
+            KanbanError::new(
+                KanbanErrorKind::ProjectError,
+                format!("Failed to derive key: {}", e),
+            )
+        })?;
+    let mut check = [0u8; CHECK_SIZE];
+    check.copy_from_slice(&output[32..]);
+    Ok(ProjectKey {
+        salt,
+        check,
+        key: *Key::from_slice(&output[..32]),
+    })
+}
+
+impl ProjectKey {
+    /// Derives a key for a new passphrase, with a fresh salt.
+    pub fn new(passphrase: &str) -> Result<Self, KanbanError> {
+        if passphrase.is_empty() {
+            return Err(KanbanError::new(
+                KanbanErrorKind::ValidationError,
+                "Passphrase must not be empty",
+            ));
+        }
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        derive(passphrase, salt)
+    }
+
+    /// Derives the key for the salt and check stored in a file.
+    pub fn unlock(
+        passphrase: &str,
+        salt: [u8; SALT_SIZE],
+        check: &[u8],
+    ) -> Result<Self, KanbanError> {
+        let key = derive(passphrase, salt)?;
+        // Compared in full so the time taken does not hint at the check
+        let difference = key
+            .check
+            .iter()
+            .zip(check.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if difference != 0 || check.len() != CHECK_SIZE {
+            return Err(KanbanError::new(
+                KanbanErrorKind::PassphraseError,
+                "Wrong passphrase",
+            ));
+        }
+        Ok(key)
+    }
+
+    /// Whether this key was derived for the salt and check in a file.
+    pub fn matches(&self, salt: &[u8], check: &[u8]) -> bool {
+        self.salt == salt && self.check == check
+    }
+
+    /// Encrypts `plaintext` under a fresh nonce, binding it to `header`.
+    pub fn encrypt(
+        &self,
+        plaintext: &[u8],
+        header: &[u8],
+    ) -> Result<([u8; NONCE_SIZE], Vec<u8>), KanbanError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&self.key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| KanbanError::new(KanbanErrorKind::ProjectError, "Failed to encrypt"))?;
+        Ok((nonce.into(), ciphertext))
+    }
+
+    /// Decrypts data written by `encrypt`. With the right key, a failure means
+    /// the file was damaged or tampered with.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        ciphertext: &[u8],
+        header: &[u8],
+    ) -> Result<Vec<u8>, KanbanError> {
+        XChaCha20Poly1305::new(&self.key)
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| {
+                KanbanError::new(
+                    KanbanErrorKind::ProjectError,
+                    "Failed to read project file: Encrypted data is damaged",
+                )
+            })
+    }
+}
+
+pub fn remember_key(project_id: [u8; 16], key: ProjectKey) {
+    UNLOCKED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(project_id, key);
+}
+
+pub fn forget_key(project_id: &[u8]) {
+    if let Ok(id) = <[u8; 16]>::try_from(project_id) {
+        UNLOCKED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id);
+    }
+}
+
+pub fn key_for(project_id: &[u8]) -> Option<ProjectKey> {
+    let id = <[u8; 16]>::try_from(project_id).ok()?;
+    UNLOCKED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&id)
+        .cloned()
+}
+
+pub fn is_unlocked(project_id: &[u8]) -> bool {
+    key_for(project_id).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let key = ProjectKey::new("correct horse").expect("Failed to derive key");
+        let (nonce, ciphertext) = key.encrypt(b"Boards", b"Header").unwrap();
+        assert_ne!(b"Boards".to_vec(), ciphertext);
+        assert_eq!(
+            b"Boards".to_vec(),
+            key.decrypt(&nonce, &ciphertext, b"Header").unwrap()
+        );
+        // The header is bound to the data
+        let result = key.decrypt(&nonce, &ciphertext, b"Other header");
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::ProjectError, result.unwrap_err().kind);
+        let mut damaged = ciphertext.clone();
+        damaged[0] ^= 0x01;
+        assert!(key.decrypt(&nonce, &damaged, b"Header").is_err());
+    }
+
+    #[test]
+    fn test_unlock() {
+        let key = ProjectKey::new("correct horse").expect("Failed to derive key");
+        let unlocked = ProjectKey::unlock("correct horse", key.salt, &key.check).unwrap();
+        assert!(unlocked.matches(&key.salt, &key.check));
+        let result = ProjectKey::unlock("wrong horse", key.salt, &key.check);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(KanbanErrorKind::PassphraseError, err.kind);
+        assert_eq!("Wrong passphrase", err.message);
+        let result = ProjectKey::new("");
+        assert_eq!(
+            KanbanErrorKind::ValidationError,
+            result.expect_err("Empty passphrase accepted").kind
+        );
+    }
+}
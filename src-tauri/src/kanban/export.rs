@@ -0,0 +1,142 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::project::{self, AppPathProvider, Project};
+use crate::kanban::validation::Validate;
+use std::fs;
+use uuid::Uuid;
+
+pub fn export_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<String, KanbanError> {
+    export_project_with_progress(app, project_id, |_, _| {})
+}
+
+/// Like `export_project`, calling `on_progress(done, total)` after each step.
+pub fn export_project_with_progress<P: AppPathProvider, F: FnMut(usize, usize)>(
+    app: &P,
+    project_id: &[u8],
+    mut on_progress: F,
+) -> Result<String, KanbanError> {
+    let project = project::read_project(app, project_id)?;
+    on_progress(1, 2);
+    let json = serde_json::to_string_pretty(&project)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    on_progress(2, 2);
+    Ok(json)
+}
+
+pub fn import_project<P: AppPathProvider>(
+    app: &P,
+    json: &str,
+    new_id: bool,
+) -> Result<Project, KanbanError> {
+    import_project_with_progress(app, json, new_id, |_, _| {})
+}
+
+/// Like `import_project`, calling `on_progress(done, total)` after each step.
+pub fn import_project_with_progress<P: AppPathProvider, F: FnMut(usize, usize)>(
+    app: &P,
+    json: &str,
+    new_id: bool,
+    mut on_progress: F,
+) -> Result<Project, KanbanError> {
+    let mut project: Project = serde_json::from_str(json)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    on_progress(1, 2);
+    project.validate()?;
+    if new_id {
+        project.id = Uuid::new_v4().into_bytes();
+    } else if project_exists(app, &project.id)? {
+        // Never overwrite an existing project silently
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Project already exists: Import with a new ID to keep both copies",
+        ));
+    }
+    let project = project::save_project(app, &project)?;
+    on_progress(2, 2);
+    Ok(project)
+}
+
+/// Whether the file is there, even when it cannot be read, is locked or is
+/// archived, so it is never mistaken for a free ID.
+fn project_exists<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<bool, KanbanError> {
+    let path = project::project_file_path(app, project_id)?;
+    let exists =
+        fs::exists(&path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    Ok(exists || project::is_archived(app, project_id)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{mock_app, test_board, test_list, test_project};
+    use tempdir::TempDir;
+
+    fn sample_project() -> Project {
+        let list = test_list("Test List", &["Item 1", "Item 2"]);
+        test_project(
+            Uuid::new_v4().into_bytes(),
+            vec![test_board("Test Board", vec![list])],
+        )
+    }
+
+    #[test]
+    fn test_export_and_import_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let project = project::save_project(&mock_app, &sample_project()).unwrap();
+        let json = export_project(&mock_app, &project.id).expect("Failed to export project");
+        // Importing over the existing project is refused
+        let result = import_project(&mock_app, &json, false);
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::ProjectError, result.unwrap_err().kind);
+        // Importing with a new ID keeps both copies
+        let imported = import_project(&mock_app, &json, true).expect("Failed to import project");
+        assert_ne!(project.id, imported.id);
+        assert_eq!(project.boards, imported.boards);
+        let read_back = project::read_project(&mock_app, &imported.id).unwrap();
+        assert_eq!(imported, read_back);
+    }
+
+    #[test]
+    fn test_import_over_unreadable_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let project = project::save_project(&mock_app, &sample_project()).unwrap();
+        let json = export_project(&mock_app, &project.id).unwrap();
+        let path = project::project_file_path(&mock_app, &project.id).unwrap();
+        fs::write(&path, b"corrupt").unwrap();
+        let result = import_project(&mock_app, &json, false);
+        assert!(result.is_err());
+        assert_eq!(b"corrupt".to_vec(), fs::read(&path).unwrap());
+    }
+
+    #[test]
+    fn test_import_project_invalid_json() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let result = import_project(&mock_app, "{\"name\": 1}", false);
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::TextError, result.unwrap_err().kind);
+    }
+
+    #[test]
+    fn test_export_and_import_progress() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let project = project::save_project(&mock_app, &sample_project()).unwrap();
+        let mut steps = Vec::new();
+        let json = export_project_with_progress(&mock_app, &project.id, |done, total| {
+            steps.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(vec![(1, 2), (2, 2)], steps);
+        let mut steps = Vec::new();
+        import_project_with_progress(&mock_app, &json, true, |done, total| {
+            steps.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(vec![(1, 2), (2, 2)], steps);
+    }
+}
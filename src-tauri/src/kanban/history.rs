@@ -0,0 +1,452 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::file_system::{binary_reader::BinaryReader, encoding::Decode};
+use crate::kanban::diff::{self, Change};
+use crate::kanban::project::{self, AppPathProvider, PathProvider, Project, PROJECT_PATH};
+use git2::{Commit, Oid, Repository, Signature, Sort};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Where the history repository lives.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryLocation {
+    /// The app data directory itself, tracking the project files in place.
+    DataDir,
+    /// A folder of its own, the project files are copied into it.
+    Folder(PathBuf),
+}
+
+// Follows the history configs, no commits are made while unset
+static LOCATION: Mutex<Option<HistoryLocation>> = Mutex::new(None);
+
+pub fn set_location(location: Option<HistoryLocation>) {
+    *LOCATION.lock().unwrap_or_else(|e| e.into_inner()) = location;
+}
+
+fn location() -> Option<HistoryLocation> {
+    LOCATION.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// Hex ID of the commit.
+    pub commit: String,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub message: String,
+    /// Revision and name of the project in the commit, unset for a deletion.
+    pub revision: Option<u64>,
+    pub name: Option<String>,
+}
+
+fn git_error(e: git2::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::HistoryError, e)
+}
+
+fn history_disabled() -> KanbanError {
+    KanbanError::new(KanbanErrorKind::HistoryError, "Project history is off")
+}
+
+fn repository_dir<P: AppPathProvider>(
+    app: &P,
+    location: &HistoryLocation,
+) -> Result<PathBuf, KanbanError> {
+    match location {
+        HistoryLocation::DataDir => app
+            .path()
+            .app_data_dir()
+            .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e)),
+        HistoryLocation::Folder(path) => Ok(path.clone()),
+    }
+}
+
+fn open_or_init(dir: &Path) -> Result<Repository, KanbanError> {
+    match Repository::open(dir) {
+        Ok(repository) => Ok(repository),
+        Err(_) => {
+            fs::create_dir_all(dir)
+                .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+            Repository::init(dir).map_err(git_error)
+        }
+    }
+}
+
+// Same path in either location, so a folder can later become the data directory
+fn tracked_path(project_id: &[u8]) -> PathBuf {
+    let file_name: String = project_id.iter().map(|b| format!("{:02X}", b)).collect();
+    Path::new(PROJECT_PATH).join(file_name)
+}
+
+fn head_commit(repository: &Repository) -> Option<Commit<'_>> {
+    repository.head().ok()?.peel_to_commit().ok()
+}
+
+fn blob_at(repository: &Repository, commit: &Commit, path: &Path) -> Option<Vec<u8>> {
+    let entry = commit.tree().ok()?.get_path(path).ok()?;
+    Some(repository.find_blob(entry.id()).ok()?.content().to_vec())
+}
+
+fn blob_id_at(commit: &Commit, path: &Path) -> Option<Oid> {
+    Some(commit.tree().ok()?.get_path(path).ok()?.id())
+}
+
+fn decode(bytes: &[u8]) -> Result<Project, KanbanError> {
+    Project::decode(&mut BinaryReader::new(bytes))
+}
+
+fn commit_message(old: Option<&Project>, new: Option<&Project>, fallback: &str) -> String {
+    let Some(new) = new else {
+        return fallback.to_string();
+    };
+    let subject = format!("Save \"{}\" (revision {})", new.name, new.revision);
+    let Some(old) = old else {
+        return format!("{}\n\n- Created project", subject);
+    };
+    let changes = diff::diff_projects(old, new);
+    if changes.is_empty() {
+        return subject;
+    }
+    let body: Vec<String> = changes.iter().map(|c| format!("- {}", c)).collect();
+    format!("{}\n\n{}", subject, body.join("\n"))
+}
+
+fn signature(repository: &Repository) -> Result<Signature<'static>, KanbanError> {
+    // The user's git identity when there is one
+    match repository.signature() {
+        Ok(signature) => Ok(signature.to_owned()),
+        Err(_) => Signature::now("Kanban Board", "kanban-board@localhost").map_err(git_error),
+    }
+}
+
+fn commit_index(
+    repository: &Repository,
+    index: &mut git2::Index,
+    message: &str,
+) -> Result<(), KanbanError> {
+    index.write().map_err(git_error)?;
+    let tree_id = index.write_tree().map_err(git_error)?;
+    let tree = repository.find_tree(tree_id).map_err(git_error)?;
+    let signature = signature(repository)?;
+    let parent = head_commit(repository);
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repository
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .map_err(git_error)?;
+    Ok(())
+}
+
+/// Commits the stored project, with a message summarizing what changed since
+/// the last commit. Does nothing while history is off or nothing changed.
+pub(crate) fn commit_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let Some(location) = location() else {
+        return Ok(());
+    };
+    let dir = repository_dir(app, &location)?;
+    let repository = open_or_init(&dir)?;
+    let path = tracked_path(project_id);
+    let project_path = project::project_file_path(app, project_id)?;
+    let bytes = fs::read(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let head = head_commit(&repository);
+    let old_bytes = head.as_ref().and_then(|c| blob_at(&repository, c, &path));
+    if old_bytes.as_deref() == Some(bytes.as_slice()) {
+        return Ok(());
+    }
+    if location != HistoryLocation::DataDir {
+        let copy_path = dir.join(&path);
+        fs::create_dir_all(copy_path.parent().unwrap_or(&dir))
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+        fs::write(&copy_path, &bytes)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    }
+    // Encrypted on either side, the message only names the project so none of
+    // its content lands in the history in the clear
+    let encrypted = project::bytes_encrypted(&bytes)?
+        || old_bytes
+            .as_deref()
+            .is_some_and(|b| project::bytes_encrypted(b).unwrap_or(true));
+    let (old, new) = match encrypted {
+        true => (None, None),
+        false => (old_bytes.and_then(|b| decode(&b).ok()), decode(&bytes).ok()),
+    };
+    let info = project::read_project_info_bytes(&bytes)?;
+    let fallback = format!("Save \"{}\" (revision {})", info.name, info.revision);
+    let mut index = repository.index().map_err(git_error)?;
+    index.add_path(&path).map_err(git_error)?;
+    commit_index(
+        &repository,
+        &mut index,
+        &commit_message(old.as_ref(), new.as_ref(), &fallback),
+    )
+}
+
+/// Commits the removal of a deleted project.
+pub(crate) fn commit_deletion<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let Some(location) = location() else {
+        return Ok(());
+    };
+    let dir = repository_dir(app, &location)?;
+    let repository = open_or_init(&dir)?;
+    let path = tracked_path(project_id);
+    let Some(old_bytes) = head_commit(&repository).and_then(|c| blob_at(&repository, &c, &path))
+    else {
+        return Ok(());
+    };
+    if location != HistoryLocation::DataDir {
+        let copy_path = dir.join(&path);
+        if fs::exists(&copy_path)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+        {
+            fs::remove_file(&copy_path)
+                .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+        }
+    }
+    let name = project::read_project_info_bytes(&old_bytes)?.name;
+    let mut index = repository.index().map_err(git_error)?;
+    index.remove_path(&path).map_err(git_error)?;
+    commit_index(
+        &repository,
+        &mut index,
+        &format!("Delete project \"{}\"", name),
+    )
+}
+
+fn open_history<P: AppPathProvider>(app: &P) -> Result<Repository, KanbanError> {
+    let location = location().ok_or_else(history_disabled)?;
+    let dir = repository_dir(app, &location)?;
+    Repository::open(dir).map_err(git_error)
+}
+
+/// Commits that changed the project, newest first.
+pub fn list_history<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<Vec<HistoryEntry>, KanbanError> {
+    let repository = open_history(app)?;
+    let path = tracked_path(project_id);
+    if head_commit(&repository).is_none() {
+        return Ok(Vec::new());
+    }
+    let mut revwalk = repository.revwalk().map_err(git_error)?;
+    revwalk.push_head().map_err(git_error)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL).map_err(git_error)?;
+    let mut entries = Vec::new();
+    for oid in revwalk {
+        let commit = repository
+            .find_commit(oid.map_err(git_error)?)
+            .map_err(git_error)?;
+        let blob_id = blob_id_at(&commit, &path);
+        let parent_blob_id = commit
+            .parent(0)
+            .ok()
+            .and_then(|parent| blob_id_at(&parent, &path));
+        if blob_id == parent_blob_id {
+            continue;
+        }
+        let info = blob_at(&repository, &commit, &path)
+            .map(|bytes| project::read_project_info_bytes(&bytes))
+            .transpose()?;
+        entries.push(HistoryEntry {
+            commit: commit.id().to_string(),
+            timestamp: commit.time().seconds() * 1000,
+            message: commit.message().unwrap_or_default().to_string(),
+            revision: info.as_ref().map(|p| p.revision),
+            name: info.map(|p| p.name),
+        });
+    }
+    Ok(entries)
+}
+
+/// Decodes the project as it was in `commit`.
+pub fn project_at<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    commit: &str,
+) -> Result<Project, KanbanError> {
+    let repository = open_history(app)?;
+    let oid = Oid::from_str(commit).map_err(git_error)?;
+    let commit = repository.find_commit(oid).map_err(git_error)?;
+    let bytes = blob_at(&repository, &commit, &tracked_path(project_id)).ok_or_else(|| {
+        KanbanError::new(
+            KanbanErrorKind::HistoryError,
+            "Project does not exist in commit",
+        )
+    })?;
+    decode(&bytes)
+}
+
+/// What changed from the project in `commit` to the stored project.
+pub fn diff_history<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    commit: &str,
+) -> Result<Vec<Change>, KanbanError> {
+    let old = project_at(app, project_id, commit)?;
+    let current = project::read_project(app, project_id)?;
+    Ok(diff::diff_projects(&old, &current))
+}
+
+/// Saves the project in `commit` as the next revision, on top of `revision`
+/// like any other save.
+pub fn checkout_history<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    commit: &str,
+    revision: u64,
+) -> Result<Project, KanbanError> {
+    let mut project = project_at(app, project_id, commit)?;
+    project.revision = revision;
+    project::save_project(app, &project)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{mock_app, test_board, test_list};
+    use serial_test::serial;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_commit_message() {
+        let old = Project {
+            id: [0x01; 16],
+            revision: 1,
+            locked: false,
+            archived: false,
+            name: "Test Project".to_string(),
+            description: "Test Description".to_string(),
+            boards: vec![test_board("Board 1", vec![test_list("To Do", &["Item 1"])])],
+        };
+        let mut new = old.clone();
+        new.revision = 2;
+        new.boards[0].lists[0].items.push("Item 2".to_string());
+        new.boards
+            .push(test_board("Board 2", vec![test_list("To Do", &[])]));
+        assert_eq!(
+            "Save \"Test Project\" (revision 2)\n\n- Added board \"Board 2\"\n- Board 1 / To Do: Added \"Item 2\" at 2",
+            commit_message(Some(&old), Some(&new), "Fallback")
+        );
+        assert_eq!(
+            "Save \"Test Project\" (revision 1)\n\n- Created project",
+            commit_message(None, Some(&old), "Fallback")
+        );
+        assert_eq!(
+            "Save \"Test Project\" (revision 1)",
+            commit_message(Some(&old), Some(&old), "Fallback")
+        );
+        // A locked version cannot be decoded
+        assert_eq!("Fallback", commit_message(Some(&old), None, "Fallback"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_history() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let repository_dir = TempDir::new("kanban-history").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        set_location(Some(HistoryLocation::Folder(
+            repository_dir.path().to_path_buf(),
+        )));
+        let mut project =
+            project::create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project
+            .boards
+            .push(test_board("Board 1", vec![test_list("To Do", &["Item 1"])]));
+        let first = project::save_project(&mock_app, &project).unwrap();
+        let mut renamed = first.clone();
+        renamed.name = "Renamed Project".to_string();
+        let second = project::save_project(&mock_app, &renamed).unwrap();
+        let history = list_history(&mock_app, &project.id).unwrap();
+        assert_eq!(
+            vec![Some(2), Some(1)],
+            history.iter().map(|e| e.revision).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "Save \"Renamed Project\" (revision 2)\n\n- Renamed project \"Test Project\" to \"Renamed Project\"",
+            history[0].message
+        );
+        // The folder holds a copy git can read on its own
+        assert!(repository_dir
+            .path()
+            .join(tracked_path(&project.id))
+            .exists());
+        assert_eq!(
+            first,
+            project_at(&mock_app, &project.id, &history[1].commit).unwrap()
+        );
+        assert_eq!(
+            vec![Change::ProjectRenamed {
+                from: "Test Project".to_string(),
+                to: "Renamed Project".to_string(),
+            }],
+            diff_history(&mock_app, &project.id, &history[1].commit).unwrap()
+        );
+        // Checking out is a save, checked against the current revision
+        let result = checkout_history(&mock_app, &project.id, &history[1].commit, first.revision);
+        assert_eq!(KanbanErrorKind::ConflictError, result.unwrap_err().kind);
+        let checked_out =
+            checkout_history(&mock_app, &project.id, &history[1].commit, second.revision).unwrap();
+        assert_eq!(first.name, checked_out.name);
+        assert_eq!(3, checked_out.revision);
+        project::delete_project(&mock_app, &project.id).unwrap();
+        let history = list_history(&mock_app, &project.id).unwrap();
+        set_location(None);
+        assert_eq!(
+            vec![
+                "Delete project \"Test Project\"",
+                "Save \"Test Project\" (revision 3)"
+            ],
+            history
+                .iter()
+                .take(2)
+                .map(|e| e.message.lines().next().unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, history[0].revision);
+        let result = list_history(&mock_app, &project.id);
+        assert_eq!("Project history is off", result.unwrap_err().message);
+    }
+
+    #[test]
+    #[serial]
+    fn test_history_of_encrypted_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        set_location(Some(HistoryLocation::DataDir));
+        let project =
+            project::create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        let mut project =
+            project::change_passphrase(&mock_app, &project.id, None, Some("secret")).unwrap();
+        project.boards.push(test_board(
+            "Board 1",
+            vec![test_list("To Do", &["Secret Item"])],
+        ));
+        let saved = project::save_project(&mock_app, &project).unwrap();
+        let mut edited = saved.clone();
+        edited.boards[0].lists[0].items[0] = "Edited Secret".to_string();
+        project::save_project(&mock_app, &edited).unwrap();
+        let history = list_history(&mock_app, &project.id).unwrap();
+        set_location(None);
+        project::lock_project(&project.id);
+        assert_eq!("Save \"Test Project\" (revision 2)", history[0].message);
+        for entry in history.iter() {
+            assert!(!entry.message.contains("Secret"));
+            assert!(!entry.message.contains("Board 1"));
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{
+    board::Board,
+    board_list::BoardList,
+    diff::{match_names, paired},
+    history,
+    project::{AppPathProvider, Project},
+    snapshot,
+};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// A change both sides made differently. `location` is a path of names, such
+/// as `boards/Board 1/lists/To Do/items`.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MergeConflict {
+    /// A name, title or the description.
+    Text {
+        location: String,
+        base: String,
+        ours: String,
+        theirs: String,
+    },
+    Color {
+        location: String,
+        base: [u8; 3],
+        ours: [u8; 3],
+        theirs: [u8; 3],
+    },
+    /// The same run of items, as it was in the base and on each side.
+    Items {
+        location: String,
+        base: Vec<String>,
+        ours: Vec<String>,
+        theirs: Vec<String>,
+    },
+    /// A board or list one side removed and the other changed.
+    Removed { location: String, removed_by: Side },
+}
+
+/// The merged project and every conflict in it, each resolved as chosen or
+/// to our side.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct Merge {
+    pub project: Project,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+struct Merger<'a> {
+    conflicts: Vec<MergeConflict>,
+    choices: &'a [Side],
+}
+
+impl Merger<'_> {
+    fn conflict<T>(&mut self, conflict: MergeConflict, ours: T, theirs: T) -> T {
+        let side = self
+            .choices
+            .get(self.conflicts.len())
+            .copied()
+            .unwrap_or(Side::Ours);
+        self.conflicts.push(conflict);
+        match side {
+            Side::Ours => ours,
+            Side::Theirs => theirs,
+        }
+    }
+
+    fn text(&mut self, location: String, base: &str, ours: &str, theirs: &str) -> String {
+        if ours == theirs || theirs == base {
+            return ours.to_string();
+        }
+        if ours == base {
+            return theirs.to_string();
+        }
+        let conflict = MergeConflict::Text {
+            location,
+            base: base.to_string(),
+            ours: ours.to_string(),
+            theirs: theirs.to_string(),
+        };
+        self.conflict(conflict, ours.to_string(), theirs.to_string())
+    }
+
+    fn color(
+        &mut self,
+        location: String,
+        base: [u8; 3],
+        ours: [u8; 3],
+        theirs: [u8; 3],
+    ) -> [u8; 3] {
+        if ours == theirs || theirs == base {
+            return ours;
+        }
+        if ours == base {
+            return theirs;
+        }
+        let conflict = MergeConflict::Color {
+            location,
+            base,
+            ours,
+            theirs,
+        };
+        self.conflict(conflict, ours, theirs)
+    }
+
+    /// Three-way merge of item lists, like diff3: runs between items both
+    /// sides kept take whichever side changed them.
+    fn items(
+        &mut self,
+        location: String,
+        base: &[String],
+        ours: &[String],
+        theirs: &[String],
+    ) -> Vec<String> {
+        let (to_ours, to_theirs) = (kept_at(base, ours), kept_at(base, theirs));
+        let mut merged = Vec::new();
+        let (mut i, mut j, mut k) = (0, 0, 0);
+        loop {
+            // Next base item both sides kept, or the ends
+            let stable = (i..base.len()).find_map(|b| Some((b, to_ours[b]?, to_theirs[b]?)));
+            let (b, jb, kb) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+            let (base_run, ours_run, theirs_run) = (&base[i..b], &ours[j..jb], &theirs[k..kb]);
+            if ours_run == theirs_run || theirs_run == base_run {
+                merged.extend_from_slice(ours_run);
+            } else if ours_run == base_run {
+                merged.extend_from_slice(theirs_run);
+            } else {
+                let conflict = MergeConflict::Items {
+                    location: location.clone(),
+                    base: base_run.to_vec(),
+                    ours: ours_run.to_vec(),
+                    theirs: theirs_run.to_vec(),
+                };
+                merged.extend(self.conflict(conflict, ours_run.to_vec(), theirs_run.to_vec()));
+            }
+            let Some((b, jb, kb)) = stable else {
+                break;
+            };
+            merged.push(base[b].clone());
+            (i, j, k) = (b + 1, jb + 1, kb + 1);
+        }
+        merged
+    }
+}
+
+/// Where each base item sits on one side, if that side kept it.
+fn kept_at(base: &[String], side: &[String]) -> Vec<Option<usize>> {
+    let mut at = vec![None; base.len()];
+    for op in capture_diff_slices(Algorithm::Myers, base, side) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for d in 0..len {
+                at[old_index + d] = Some(new_index + d);
+            }
+        }
+    }
+    at
+}
+
+/// Boards and lists, which are matched by name like in a diff.
+trait Named: Clone + PartialEq {
+    fn name(&self) -> &str;
+    /// The same name with nothing in it, as the base of one both sides added.
+    fn emptied(&self) -> Self;
+    fn merge(merger: &mut Merger, location: &str, base: &Self, ours: &Self, theirs: &Self) -> Self;
+}
+
+impl Named for Board {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn emptied(&self) -> Self {
+        Board {
+            name: self.name.clone(),
+            lists: Vec::new(),
+            archived: self.archived,
+        }
+    }
+
+    fn merge(merger: &mut Merger, location: &str, base: &Self, ours: &Self, theirs: &Self) -> Self {
+        Board {
+            name: merger.text(
+                format!("{}/name", location),
+                &base.name,
+                &ours.name,
+                &theirs.name,
+            ),
+            lists: merge_named(
+                merger,
+                &format!("{}/lists", location),
+                &base.lists,
+                &ours.lists,
+                &theirs.lists,
+            ),
+            // Only one value to change to, so both sides never conflict
+            archived: if ours.archived != base.archived {
+                ours.archived
+            } else {
+                theirs.archived
+            },
+        }
+    }
+}
+
+impl Named for BoardList {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn emptied(&self) -> Self {
+        BoardList {
+            title: self.title.clone(),
+            color: self.color,
+            items: Vec::new(),
+        }
+    }
+
+    fn merge(merger: &mut Merger, location: &str, base: &Self, ours: &Self, theirs: &Self) -> Self {
+        BoardList {
+            title: merger.text(
+                format!("{}/title", location),
+                &base.title,
+                &ours.title,
+                &theirs.title,
+            ),
+            color: merger.color(
+                format!("{}/color", location),
+                base.color,
+                ours.color,
+                theirs.color,
+            ),
+            items: merger.items(
+                format!("{}/items", location),
+                &base.items,
+                &ours.items,
+                &theirs.items,
+            ),
+        }
+    }
+}
+
+fn names<T: Named>(values: &[T]) -> Vec<&str> {
+    values.iter().map(|v| v.name()).collect()
+}
+
+/// Merges boards or lists in our order, with the ones only theirs added
+/// placed after the one before them on their side.
+fn merge_named<T: Named>(
+    merger: &mut Merger,
+    location: &str,
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+) -> Vec<T> {
+    let (base_to_ours, ours_to_base) = paired(
+        &match_names(&names(base), &names(ours)),
+        base.len(),
+        ours.len(),
+    );
+    let (base_to_theirs, theirs_to_base) = paired(
+        &match_names(&names(base), &names(theirs)),
+        base.len(),
+        theirs.len(),
+    );
+    let mut merged: Vec<T> = Vec::new();
+    // Where each of theirs ended up, so the rest of theirs can follow it
+    let mut theirs_at: Vec<Option<usize>> = vec![None; theirs.len()];
+    for (j, value) in ours.iter().enumerate() {
+        let here = format!("{}/{}", location, value.name());
+        let Some(i) = ours_to_base[j] else {
+            // Added by us, and maybe by them under the same name
+            let same = (0..theirs.len()).find(|&k| {
+                theirs_to_base[k].is_none()
+                    && theirs_at[k].is_none()
+                    && theirs[k].name() == value.name()
+            });
+            match same {
+                Some(k) => {
+                    merged.push(T::merge(merger, &here, &value.emptied(), value, &theirs[k]));
+                    theirs_at[k] = Some(merged.len() - 1);
+                }
+                None => merged.push(value.clone()),
+            }
+            continue;
+        };
+        match base_to_theirs[i] {
+            Some(k) => {
+                merged.push(T::merge(merger, &here, &base[i], value, &theirs[k]));
+                theirs_at[k] = Some(merged.len() - 1);
+            }
+            None if *value == base[i] => {}
+            None => {
+                let conflict = MergeConflict::Removed {
+                    location: here,
+                    removed_by: Side::Theirs,
+                };
+                if merger.conflict(conflict, true, false) {
+                    merged.push(value.clone());
+                }
+            }
+        }
+    }
+    let mut after: Option<usize> = None;
+    for (k, value) in theirs.iter().enumerate() {
+        if let Some(m) = theirs_at[k] {
+            after = Some(m);
+            continue;
+        }
+        if let Some(i) = theirs_to_base[k] {
+            // Kept by them, removed by us
+            if base_to_ours[i].is_some() || *value == base[i] {
+                continue;
+            }
+            let conflict = MergeConflict::Removed {
+                location: format!("{}/{}", location, value.name()),
+                removed_by: Side::Ours,
+            };
+            if !merger.conflict(conflict, false, true) {
+                continue;
+            }
+        }
+        let at = after.map_or(0, |m| m + 1);
+        merged.insert(at, value.clone());
+        for m in theirs_at.iter_mut().flatten() {
+            if *m >= at {
+                *m += 1;
+            }
+        }
+        after = Some(at);
+    }
+    merged
+}
+
+fn merge_with(base: &Project, ours: &Project, theirs: &Project, choices: &[Side]) -> Merge {
+    let mut merger = Merger {
+        conflicts: Vec::new(),
+        choices,
+    };
+    let project = Project {
+        id: ours.id,
+        // Saved on top of theirs
+        revision: theirs.revision,
+        locked: false,
+        archived: false,
+        name: merger.text("name".to_string(), &base.name, &ours.name, &theirs.name),
+        description: merger.text(
+            "description".to_string(),
+            &base.description,
+            &ours.description,
+            &theirs.description,
+        ),
+        boards: merge_named(
+            &mut merger,
+            "boards",
+            &base.boards,
+            &ours.boards,
+            &theirs.boards,
+        ),
+    };
+    Merge {
+        project,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Merges the changes `ours` and `theirs` made to `base`. Changes that do not
+/// overlap are all kept, conflicts are listed and resolved to our side.
+pub fn merge_projects(base: &Project, ours: &Project, theirs: &Project) -> Merge {
+    merge_with(base, ours, theirs, &[])
+}
+
+/// Merges like `merge_projects`, resolving the conflicts it listed to the
+/// side chosen for each, in order.
+pub fn resolve_merge(
+    base: &Project,
+    ours: &Project,
+    theirs: &Project,
+    choices: &[Side],
+) -> Result<Merge, KanbanError> {
+    let merge = merge_with(base, ours, theirs, choices);
+    if merge.conflicts.len() != choices.len() {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            format!(
+                "Expected {} conflict choices, got {}",
+                merge.conflicts.len(),
+                choices.len()
+            ),
+        ));
+    }
+    Ok(merge)
+}
+
+/// Stands in for a missing common ancestor, so that every difference between
+/// the two sides is a conflict instead of a change of one side.
+pub(crate) fn empty_base(project_id: [u8; 16]) -> Project {
+    Project {
+        id: project_id,
+        revision: 0,
+        locked: false,
+        archived: false,
+        name: String::new(),
+        description: String::new(),
+        boards: Vec::new(),
+    }
+}
+
+/// A stored version of the project at `revision`, from its snapshots or its
+/// history, to merge against.
+pub fn find_revision<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    revision: u64,
+) -> Result<Project, KanbanError> {
+    let snapshots = snapshot::list_snapshots(app, project_id)?;
+    if let Some(found) = snapshots.iter().find(|s| s.revision == revision) {
+        return snapshot::preview_snapshot(app, project_id, found.timestamp);
+    }
+    // History is optional, so a failure only means no version was found there
+    if let Ok(entries) = history::list_history(app, project_id) {
+        if let Some(found) = entries.iter().find(|e| e.revision == Some(revision)) {
+            return history::project_at(app, project_id, &found.commit);
+        }
+    }
+    Err(KanbanError::new(
+        KanbanErrorKind::ProjectError,
+        format!("No stored copy of revision {} to merge from", revision),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{test_board, test_list, test_project};
+
+    fn items(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_items() {
+        let mut merger = Merger {
+            conflicts: Vec::new(),
+            choices: &[],
+        };
+        let base = items(&["A", "B", "C", "D"]);
+        // We edit B, they remove D and add E at the top
+        let merged = merger.items(
+            "items".to_string(),
+            &base,
+            &items(&["A", "B2", "C", "D"]),
+            &items(&["E", "A", "B", "C"]),
+        );
+        assert_eq!(items(&["E", "A", "B2", "C"]), merged);
+        assert!(merger.conflicts.is_empty());
+        // Both edit B
+        let merged = merger.items(
+            "items".to_string(),
+            &base,
+            &items(&["A", "B2", "C", "D"]),
+            &items(&["A", "B3", "C", "D"]),
+        );
+        assert_eq!(items(&["A", "B2", "C", "D"]), merged);
+        assert_eq!(
+            vec![MergeConflict::Items {
+                location: "items".to_string(),
+                base: items(&["B"]),
+                ours: items(&["B2"]),
+                theirs: items(&["B3"]),
+            }],
+            merger.conflicts
+        );
+    }
+
+    #[test]
+    fn test_merge_projects() {
+        let base = test_project(
+            [0x01; 16],
+            vec![
+                test_board(
+                    "Board 1",
+                    vec![
+                        test_list("To Do", &["Item 1", "Item 2"]),
+                        test_list("Done", &[]),
+                    ],
+                ),
+                test_board("Board 2", Vec::new()),
+            ],
+        );
+        let mut ours = base.clone();
+        ours.revision = 2;
+        ours.name = "Our Project".to_string();
+        ours.boards[0].lists[0].items.push("Item 3".to_string());
+        ours.boards.remove(1);
+        ours.boards.push(test_board("Board 3", Vec::new()));
+        let mut theirs = base.clone();
+        theirs.revision = 3;
+        theirs.boards[0].lists[1].color = [0x00, 0x00, 0x00];
+        theirs.boards[0].lists[0].items.remove(0);
+        theirs.boards[0].archived = true;
+        theirs.boards.insert(0, test_board("Board 0", Vec::new()));
+        let merge = merge_projects(&base, &ours, &theirs);
+        assert!(merge.conflicts.is_empty());
+        let project = merge.project;
+        assert_eq!("Our Project", project.name);
+        assert_eq!(3, project.revision);
+        assert_eq!(
+            vec!["Board 0", "Board 1", "Board 3"],
+            project
+                .boards
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            items(&["Item 2", "Item 3"]),
+            project.boards[1].lists[0].items
+        );
+        assert_eq!([0x00, 0x00, 0x00], project.boards[1].lists[1].color);
+        assert!(project.boards[1].archived);
+    }
+
+    #[test]
+    fn test_resolve_merge() {
+        let base = test_project(
+            [0x01; 16],
+            vec![test_board("Board 1", vec![test_list("To Do", &["Item 1"])])],
+        );
+        let mut ours = base.clone();
+        ours.description = "Our Description".to_string();
+        ours.boards[0].lists[0].items.push("Item 2".to_string());
+        let mut theirs = base.clone();
+        theirs.description = "Their Description".to_string();
+        theirs.boards.clear();
+        let merge = merge_projects(&base, &ours, &theirs);
+        assert_eq!(
+            vec![
+                MergeConflict::Text {
+                    location: "description".to_string(),
+                    base: "Test Description".to_string(),
+                    ours: "Our Description".to_string(),
+                    theirs: "Their Description".to_string(),
+                },
+                MergeConflict::Removed {
+                    location: "boards/Board 1".to_string(),
+                    removed_by: Side::Theirs,
+                },
+            ],
+            merge.conflicts
+        );
+        // Resolved to our side by default
+        assert_eq!(ours.boards, merge.project.boards);
+        let resolved = resolve_merge(&base, &ours, &theirs, &[Side::Theirs, Side::Theirs]).unwrap();
+        assert_eq!("Their Description", resolved.project.description);
+        assert!(resolved.project.boards.is_empty());
+        let result = resolve_merge(&base, &ours, &theirs, &[Side::Theirs]);
+        assert_eq!(
+            "Expected 2 conflict choices, got 1",
+            result.unwrap_err().message
+        );
+    }
+}
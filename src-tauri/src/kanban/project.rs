@@ -1,14 +1,45 @@
+use crate::errors::error_log;
 use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
 use crate::file_system::binary_reader::BinaryReader;
 use crate::file_system::binary_writer::BinaryWriter;
+use crate::file_system::encoding::{Decode, Encode, ShortString};
+use crate::file_system::file_stamp;
 use crate::kanban::board;
+use crate::kanban::crdt;
+use crate::kanban::data_dir;
+use crate::kanban::encryption::{self, ProjectKey};
+use crate::kanban::history;
+use crate::kanban::project_group;
+use crate::kanban::snapshot;
+use crate::kanban::sync_folder;
+use crate::kanban::validation::{self, Validate};
+use crate::kanban::workspace;
+use crate::peer_sync;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use serde;
-use std::fs;
+use std::{
+    fmt, fs,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
 use uuid::Uuid;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct Project {
     pub id: [u8; 16],
+    /// Incremented on every save, a save based on an older revision is rejected.
+    #[serde(default)]
+    pub revision: u64,
+    /// Stored encrypted and not unlocked this session, so only the header was read.
+    #[serde(default)]
+    pub locked: bool,
+    /// Kept compressed in the archive folder, so only the header was read.
+    #[serde(default)]
+    pub archived: bool,
     pub name: String,
     pub description: String,
     pub boards: Vec<board::Board>,
@@ -32,22 +63,115 @@ impl<R: tauri::Runtime> AppPathProvider for tauri::AppHandle<R> {
 
 impl<R: tauri::Runtime> PathProvider for tauri::path::PathResolver<R> {
     fn app_data_dir(&self) -> Result<std::path::PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-        self.app_data_dir().map_err(|e| Box::new(e) as _)
+        // Moved elsewhere or portable, otherwise Tauri's default
+        let data_dir = match data_dir::current() {
+            Some(location) => location.path,
+            None => self.app_data_dir()?,
+        };
+        Ok(workspace::active_dir(&data_dir))
+    }
+}
+
+/// Returned as the source of a `ConflictError`, carrying the stored copy.
+#[derive(Debug)]
+pub struct ProjectConflict {
+    pub current: Project,
+}
+
+impl fmt::Display for ProjectConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Conflict: The project was changed elsewhere (revision {})",
+            self.current.revision
+        )
     }
 }
 
-const FILE_VERSION: u8 = 0;
-const PROJECT_PATH: &str = "projects";
+impl std::error::Error for ProjectConflict {}
+
+/// The stored copy of the project, if the error is a save conflict.
+pub fn conflict_copy(err: &KanbanError) -> Option<&Project> {
+    err.source
+        .as_ref()?
+        .downcast_ref::<ProjectConflict>()
+        .map(|conflict| &conflict.current)
+}
+
+// Version 1 added the revision after the project ID,
+// version 2 the board offset table after the board count,
+// version 3 the flags after the revision,
+// version 4 the flags at the end of each board
+pub(crate) const FILE_VERSION: u8 = 4;
+// Revision sits right after the version byte and the project ID
+const REVISION_OFFSET: u64 = 17;
+// Everything after the description is deflate compressed, board offsets then
+// count from the start of the decompressed payload
+const FLAG_COMPRESSED: u8 = 0x01;
+// Everything after the description is the key salt and check, a nonce and the
+// payload encrypted with XChaCha20-Poly1305, bound to the header
+const FLAG_ENCRYPTED: u8 = 0x02;
+pub(crate) const PROJECT_PATH: &str = "projects";
+// Archived project files, deflate compressed as they were stored
+pub(crate) const ARCHIVE_PATH: &str = "archive";
+
+// Keeps the revision check and the write of a save together
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+// Follows the `compress_projects` config
+static COMPRESS_PROJECTS: AtomicBool = AtomicBool::new(false);
+
+/// Holds off saves while a project file is replaced outside this module, so
+/// none lands between checking the stored revision and the replace.
+pub(crate) fn lock_saves() -> MutexGuard<'static, ()> {
+    SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Whether projects are compressed when they are next written in full.
+pub fn set_compression(enabled: bool) {
+    COMPRESS_PROJECTS.store(enabled, Ordering::Relaxed);
+}
+
+fn compression_enabled() -> bool {
+    COMPRESS_PROJECTS.load(Ordering::Relaxed)
+}
+
+/// What the header of a project file says about the rest of it.
+struct FileHeader {
+    version: u8,
+    flags: u8,
+}
+
+impl FileHeader {
+    fn compressed(&self) -> bool {
+        self.flags & FLAG_COMPRESSED != 0
+    }
 
-fn write_project_header(bw: &mut BinaryWriter, id: &Uuid, name: &str, description: &str) {
+    fn encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+}
+
+fn write_project_header<W: Write>(
+    bw: &mut BinaryWriter<W>,
+    id: &Uuid,
+    revision: u64,
+    flags: u8,
+    name: &str,
+    description: &str,
+) -> Result<(), KanbanError> {
     // Version
     bw.write_byte(FILE_VERSION);
     // ID
     bw.write_bytes(id.as_bytes());
+    // Revision
+    bw.write_bytes(&revision.to_le_bytes());
+    // Flags
+    bw.write_byte(flags);
     // Name
-    bw.write_string_with_length(name, true);
+    ShortString::encode(name, bw)?;
     // Description
-    bw.write_string_with_length(description, false);
+    bw.write_string_with_length(description, false)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
 }
 
 fn write_project_to_file<P: AppPathProvider>(
@@ -85,31 +209,119 @@ pub fn create_project<P: AppPathProvider>(
     name: &str,
     description: &str,
 ) -> Result<Project, KanbanError> {
+    let id: Uuid = Uuid::new_v4();
+    let project = Project {
+        id: id.as_bytes().clone(),
+        revision: 0,
+        locked: false,
+        archived: false,
+        name: name.to_string(),
+        description: description.to_string(),
+        boards: Vec::new(),
+    };
     // Check project name and description
-    if name.len() == 0 || description.len() == 0 {
+    project.validate()?;
+    let mut bw: BinaryWriter = BinaryWriter::new();
+    write_project_header(&mut bw, &id, 0, 0, name, description)?;
+    bw.write_byte(0x00); // Write initial board count
+    write_project_to_file(app, &bw)?;
+    Ok(project)
+}
+
+/// Reads the header, returning the file version and flags along with the
+/// project info.
+fn read_project_header<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+) -> Result<(FileHeader, Project), KanbanError> {
+    // Version
+    let version: u8 = br.next_byte()?;
+    if version > FILE_VERSION {
         return Err(KanbanError::new(
             KanbanErrorKind::ProjectError,
-            "Empty Name or Description: The name and description of the project must not be empty",
+            "Project version not supported",
         ));
     }
-    if name.len() > 256 {
+    let id = <[u8; 16]>::decode(br)?;
+    let revision = if version >= 1 { u64::decode(br)? } else { 0 };
+    let flags = if version >= 3 { u8::decode(br)? } else { 0 };
+    if flags & !(FLAG_COMPRESSED | FLAG_ENCRYPTED) != 0 {
         return Err(KanbanError::new(
             KanbanErrorKind::ProjectError,
-            "Name too long: Project name must be between 1 and 256 characters",
+            "Project flags not supported",
         ));
     }
-    let mut bw: BinaryWriter = BinaryWriter::new();
-    let id: Uuid = Uuid::new_v4();
-    write_project_header(&mut bw, &id, name, description);
-    bw.write_byte(0x00); // Write initial board count
-    write_project_to_file(app, &bw)?;
     let project = Project {
-        id: id.as_bytes().clone(),
-        name: name.to_string(),
-        description: description.to_string(),
+        id,
+        revision,
+        locked: flags & FLAG_ENCRYPTED != 0 && !encryption::is_unlocked(&id),
+        archived: false,
+        name: ShortString::decode(br)?,
+        description: String::decode(br)?,
         boards: Vec::new(),
     };
-    Ok(project)
+    Ok((FileHeader { version, flags }, project))
+}
+
+/// Decompresses everything after the header.
+fn read_compressed_payload<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+) -> Result<Vec<u8>, KanbanError> {
+    let mut payload = Vec::new();
+    DeflateDecoder::new(br.take_rest())
+        .read_to_end(&mut payload)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::ProjectError, e))?;
+    Ok(payload)
+}
+
+/// Reads the salt and check of an encrypted project, right after the header.
+fn read_key_params<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+) -> Result<([u8; encryption::SALT_SIZE], Vec<u8>), KanbanError> {
+    let salt = <[u8; encryption::SALT_SIZE]>::decode(br)?;
+    let check = br.next_bytes(encryption::CHECK_SIZE)?;
+    Ok((salt, check))
+}
+
+/// Everything after the header as it was before compression and encryption,
+/// or `None` when it is stored plain and can be read in place.
+fn read_payload<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    header: &FileHeader,
+    project_id: &[u8],
+) -> Result<Option<Vec<u8>>, KanbanError> {
+    if !header.encrypted() {
+        if header.compressed() {
+            return read_compressed_payload(br).map(Some);
+        }
+        return Ok(None);
+    }
+    let key = encryption::key_for(project_id).ok_or_else(encryption::locked_error)?;
+    let header_length = br.position();
+    br.seek(0)?;
+    let header_bytes = br.next_bytes(header_length)?;
+    let (salt, check) = read_key_params(br)?;
+    if !key.matches(&salt, &check) {
+        // Written under another passphrase, such as a change made elsewhere
+        return Err(encryption::locked_error());
+    }
+    let nonce = br.next_bytes(encryption::NONCE_SIZE)?;
+    let ciphertext = br.next_bytes(br.remaining())?;
+    let payload = key.decrypt(&nonce, &ciphertext, &header_bytes)?;
+    if header.compressed() {
+        return read_compressed_payload(&mut BinaryReader::new(&payload)).map(Some);
+    }
+    Ok(Some(payload))
+}
+
+fn read_boards<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    version: u8,
+) -> Result<Vec<board::Board>, KanbanError> {
+    if version < 2 {
+        // Boards stored one after another
+        return board::read_sequential_boards(br, version);
+    }
+    board::read_indexed_boards(br, version)
 }
 
 fn read_project_info<P: AppPathProvider>(
@@ -129,36 +341,31 @@ fn read_project_info<P: AppPathProvider>(
         .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
         .join(PROJECT_PATH)
         .join(project_id);
-    let mut br = BinaryReader::read_from_file(&project_path)
+    read_project_info_file(&project_path)
+}
+
+/// Reads only the header of the project file at `path`, which is never
+/// compressed or encrypted.
+pub(crate) fn read_project_info_file(path: &Path) -> Result<Project, KanbanError> {
+    let mut br = BinaryReader::read_from_file(path)
         .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
-    // Version
-    let version: u8 = br.next_byte()?;
-    if version != 0x00 {
-        return Err(KanbanError::new(
-            KanbanErrorKind::ProjectError,
-            "Project version not supported",
-        ));
-    }
-    // Project ID
-    let project_id: Vec<u8> = br.next_bytes(16)?;
-    // Project Name
-    let project_name: String = br.next_string(true)?;
-    // Project Description
-    let project_description: String = br.next_string(false)?;
-    Ok(Project {
-        id: <[u8; 16]>::try_from(project_id).map_err(|_| {
-            KanbanError::new(
-                KanbanErrorKind::ProjectError,
-                "Invalid project ID length".to_string(),
-            )
-        })?,
-        name: project_name,
-        description: project_description,
-        boards: Vec::new(),
-    })
+    read_project_header(&mut br).map(|(_, project)| project)
+}
+
+/// Reads only the header of a project file held in memory.
+pub(crate) fn read_project_info_bytes(bytes: &[u8]) -> Result<Project, KanbanError> {
+    read_project_header(&mut BinaryReader::new(bytes)).map(|(_, project)| project)
 }
 
 pub fn get_all_projects_info<P: AppPathProvider>(app: &P) -> Result<Vec<Project>, KanbanError> {
+    get_all_projects_info_with_progress(app, |_, _| {})
+}
+
+/// Like `get_all_projects_info`, calling `on_progress(done, total)` after each file.
+pub fn get_all_projects_info_with_progress<P: AppPathProvider, F: FnMut(usize, usize)>(
+    app: &P,
+    mut on_progress: F,
+) -> Result<Vec<Project>, KanbanError> {
     let project_dir = app
         .path()
         .app_data_dir()
@@ -169,28 +376,183 @@ pub fn get_all_projects_info<P: AppPathProvider>(app: &P) -> Result<Vec<Project>
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| entry.file_name().into_string().ok())
         .collect::<Vec<String>>();
+    let total = project_ids.len();
     let projects = project_ids
         .iter()
-        .map(|id| read_project_info(app, id))
+        .enumerate()
+        .map(|(i, id)| {
+            let project = read_project_info(app, id);
+            on_progress(i + 1, total);
+            project
+        })
         .filter_map(|p| p.ok())
         .collect::<Vec<Project>>();
     Ok(projects)
 }
 
+/// Rewrites every project not stored in the current file version and
+/// compression setting, keeping its revision. Calls `on_progress(done, total)`
+/// after each project and returns the IDs of the ones rewritten.
+pub fn recompress_all_projects_with_progress<P: AppPathProvider, F: FnMut(usize, usize)>(
+    app: &P,
+    mut on_progress: F,
+) -> Result<Vec<[u8; 16]>, KanbanError> {
+    let compress = compression_enabled();
+    let projects = get_all_projects_info(app)?;
+    let total = projects.len();
+    let mut rewritten = Vec::new();
+    for (i, info) in projects.iter().enumerate() {
+        if recompress_project(app, &info.id, compress)? {
+            rewritten.push(info.id);
+        }
+        on_progress(i + 1, total);
+    }
+    Ok(rewritten)
+}
+
+/// Returns whether the project had to be rewritten.
+fn recompress_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    compress: bool,
+) -> Result<bool, KanbanError> {
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let project_path = project_file_path(app, project_id)?;
+    let project = {
+        let mut br = BinaryReader::read_from_file(&project_path)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+        let (header, project) = read_project_header(&mut br)?;
+        // Locked projects are left as they are until they are unlocked
+        if project.locked || header.version == FILE_VERSION && header.compressed() == compress {
+            return Ok(false);
+        }
+        br.seek(0)?;
+        Project::decode(&mut br)?
+    };
+    let key = encryption::key_for(project_id);
+    stream_project_to_file(&project_path, &project, compress, key.as_ref())?;
+    Ok(true)
+}
+
 pub fn save_project<P: AppPathProvider>(
     app: &P,
     project: &Project,
 ) -> Result<Project, KanbanError> {
-    let mut bw: BinaryWriter = BinaryWriter::new();
-    write_project_header(
-        &mut bw,
-        &Uuid::from_bytes(project.id),
-        &project.name,
-        &project.description,
-    );
-    board::write_all_boards(&mut bw, &project.boards)?;
-    write_project_to_file(app, &bw)?;
-    Ok(project.clone())
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let current = stored_project(app, &project.id)?;
+    write_next_revision(app, project, current)
+}
+
+/// Saves like `save_project`, checking the revision against a stored copy the
+/// caller already holds instead of reading it from disk. The copy is only
+/// trusted while the file still matches `stamp`.
+pub(crate) fn save_project_over<P: AppPathProvider>(
+    app: &P,
+    project: &Project,
+    current: Project,
+    stamp: ProjectStamp,
+) -> Result<Project, KanbanError> {
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let current = match project_stamp(app, &project.id) {
+        Ok(stored) if stored == stamp => Some(current),
+        _ => stored_project(app, &project.id)?,
+    };
+    write_next_revision(app, project, current)
+}
+
+fn stored_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<Option<Project>, KanbanError> {
+    let project_path = project_file_path(app, project_id)?;
+    if !fs::exists(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+    {
+        return Ok(None);
+    }
+    Ok(Some(read_project(app, project_id)?))
+}
+
+fn write_next_revision<P: AppPathProvider>(
+    app: &P,
+    project: &Project,
+    current: Option<Project>,
+) -> Result<Project, KanbanError> {
+    if let Some(current) = current.as_ref().filter(|c| c.revision != project.revision) {
+        return Err(KanbanError::from_source(
+            KanbanErrorKind::ConflictError,
+            ProjectConflict {
+                current: current.clone(),
+            },
+        ));
+    }
+    // Not stored in the file, so a save could never keep it
+    if project.archived {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Archived projects cannot be saved, restore the project first",
+        ));
+    }
+    // Checked here so no save path can write a field the format cannot hold
+    validation::validate_update(project, current.as_ref())?;
+    let mut saved = project.clone();
+    saved.revision = project.revision + 1;
+    let project_path = project_file_path(app, &saved.id)?;
+    let key = encryption::key_for(&saved.id);
+    // Never write a locked project out in the clear
+    if key.is_none() && stored_encrypted(&project_path)? {
+        return Err(encryption::locked_error());
+    }
+    stream_project_to_file(&project_path, &saved, compression_enabled(), key.as_ref())?;
+    record_save(app, &saved.id);
+    Ok(saved)
+}
+
+/// Whether the stored project is encrypted, unlocked or not.
+pub(crate) fn is_encrypted<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<bool, KanbanError> {
+    stored_encrypted(&project_file_path(app, project_id)?)
+}
+
+/// Whether the project file held in `bytes` is encrypted.
+pub(crate) fn bytes_encrypted(bytes: &[u8]) -> Result<bool, KanbanError> {
+    Ok(read_project_header(&mut BinaryReader::new(bytes))?
+        .0
+        .encrypted())
+}
+
+fn stored_encrypted(project_path: &Path) -> Result<bool, KanbanError> {
+    if !fs::exists(project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+    {
+        return Ok(false);
+    }
+    let mut br = BinaryReader::read_from_file(project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    Ok(read_project_header(&mut br)?.0.encrypted())
+}
+
+/// Streams the project into a file next to `project_path` and renames it over
+/// the old one, so a failed save leaves the old file intact.
+fn stream_project_to_file(
+    project_path: &Path,
+    project: &Project,
+    compress: bool,
+    key: Option<&ProjectKey>,
+) -> Result<(), KanbanError> {
+    let temp_path = project_path.with_extension("tmp");
+    let io_error = |e| KanbanError::from_source(KanbanErrorKind::IoError, e);
+    let write = || -> Result<(), KanbanError> {
+        let mut bw = BinaryWriter::create_file(&temp_path).map_err(io_error)?;
+        encode_project(&mut bw, project, compress, key)?;
+        bw.finish().map_err(io_error)?;
+        fs::rename(&temp_path, project_path).map_err(io_error)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
 }
 
 pub fn read_project<P: AppPathProvider>(
@@ -212,36 +574,332 @@ pub fn read_project<P: AppPathProvider>(
         .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
         .join(PROJECT_PATH)
         .join(file_name);
-    // Read project file
+    read_project_file(&project_path)
+}
+
+/// Decodes the project file at `path`, such as a stored project or a snapshot.
+pub(crate) fn read_project_file(path: &Path) -> Result<Project, KanbanError> {
+    let mut br = BinaryReader::read_from_file(path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    Project::decode(&mut br)
+}
+
+// A failed snapshot or commit leaves the save that triggered it in place
+fn record_save<P: AppPathProvider>(app: &P, project_id: &[u8]) {
+    if let Err(err) = snapshot::take_snapshot(app, project_id) {
+        error_log::report("Failed to snapshot project", err);
+    }
+    if let Err(err) = history::commit_project(app, project_id) {
+        error_log::report("Failed to commit project", err);
+    }
+    if let Err(err) = sync_folder::keep_base(app, project_id) {
+        error_log::report("Failed to keep merge base", err);
+    }
+    if let Err(err) = crdt::record_project(app, project_id) {
+        error_log::report("Failed to record project operations", err);
+    }
+}
+
+/// Writes the project in the current file version, compressing the boards
+/// when `compress` is set and encrypting them when given a key.
+pub fn encode_project<W: Write>(
+    bw: &mut BinaryWriter<W>,
+    project: &Project,
+    compress: bool,
+    key: Option<&ProjectKey>,
+) -> Result<(), KanbanError> {
+    let mut flags = if compress { FLAG_COMPRESSED } else { 0 };
+    if key.is_some() {
+        flags |= FLAG_ENCRYPTED;
+    }
+    let Some(key) = key else {
+        write_project_header(
+            bw,
+            &Uuid::from_bytes(project.id),
+            project.revision,
+            flags,
+            &project.name,
+            &project.description,
+        )?;
+        return write_payload(bw, &project.boards, compress);
+    };
+    // Sealed in one piece, so the payload is built in memory
+    let mut header = BinaryWriter::new();
+    write_project_header(
+        &mut header,
+        &Uuid::from_bytes(project.id),
+        project.revision,
+        flags,
+        &project.name,
+        &project.description,
+    )?;
+    let mut payload = BinaryWriter::new();
+    write_payload(&mut payload, &project.boards, compress)?;
+    let (nonce, ciphertext) = key.encrypt(payload.as_bytes(), header.as_bytes())?;
+    bw.write_bytes(header.as_bytes());
+    bw.write_bytes(&key.salt);
+    bw.write_bytes(&key.check);
+    bw.write_bytes(&nonce);
+    bw.write_bytes(&ciphertext);
+    Ok(())
+}
+
+fn write_payload<W: Write>(
+    bw: &mut BinaryWriter<W>,
+    boards: &[board::Board],
+    compress: bool,
+) -> Result<(), KanbanError> {
+    if !compress {
+        return board::write_all_boards(bw, boards);
+    }
+    let io_error = |e| KanbanError::from_source(KanbanErrorKind::IoError, e);
+    let mut payload =
+        BinaryWriter::from_writer(DeflateEncoder::new(&mut *bw, Compression::default()));
+    board::write_all_boards(&mut payload, boards)?;
+    payload
+        .finish()
+        .and_then(|encoder| encoder.finish())
+        .map_err(io_error)?;
+    Ok(())
+}
+
+// Written uncompressed in the current file version, read in any supported one
+impl Encode for Project {
+    fn encode<W: Write>(&self, bw: &mut BinaryWriter<W>) -> Result<(), KanbanError> {
+        encode_project(bw, self, false, None)
+    }
+}
+
+impl Decode for Project {
+    fn decode<R: Read + Seek>(br: &mut BinaryReader<R>) -> Result<Self, KanbanError> {
+        let (header, mut project) = read_project_header(br)?;
+        // Boards
+        project.boards = match read_payload(br, &header, &project.id)? {
+            Some(payload) => read_boards(&mut BinaryReader::new(&payload), header.version)?,
+            None => read_boards(br, header.version)?,
+        };
+        Ok(project)
+    }
+}
+
+/// Checks the passphrase of an encrypted project and keeps its key for this
+/// session, so the project can be read and saved until it is locked again.
+pub fn unlock_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    passphrase: &str,
+) -> Result<Project, KanbanError> {
+    let project_path = project_file_path(app, project_id)?;
     let mut br = BinaryReader::read_from_file(&project_path)
         .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
-    // Version
-    let version: u8 = br.next_byte()?;
-    if version != 0x00 {
-        return Err(KanbanError::new(
-            KanbanErrorKind::ProjectError,
-            "Project version not supported",
+    let (header, stored) = read_project_header(&mut br)?;
+    if header.encrypted() {
+        let (salt, check) = read_key_params(&mut br)?;
+        let key = ProjectKey::unlock(passphrase, salt, &check)?;
+        encryption::remember_key(stored.id, key);
+    }
+    br.seek(0)?;
+    Project::decode(&mut br)
+}
+
+/// Forgets the key of a project, so it has to be unlocked again.
+pub fn lock_project(project_id: &[u8]) {
+    encryption::forget_key(project_id);
+}
+
+/// Encrypts the project with `new_passphrase`, or stores it unencrypted when
+/// that is `None`. An encrypted project needs its current passphrase, even
+/// when it is unlocked. The revision is kept, as the content is unchanged.
+pub fn change_passphrase<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    passphrase: Option<&str>,
+    new_passphrase: Option<&str>,
+) -> Result<Project, KanbanError> {
+    let new_key = new_passphrase.map(ProjectKey::new).transpose()?;
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let project_path = project_file_path(app, project_id)?;
+    let mut br = BinaryReader::read_from_file(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let (header, stored) = read_project_header(&mut br)?;
+    let current_key = if header.encrypted() {
+        let passphrase = passphrase.ok_or_else(|| {
+            KanbanError::new(KanbanErrorKind::PassphraseError, "Wrong passphrase")
+        })?;
+        let (salt, check) = read_key_params(&mut br)?;
+        Some(ProjectKey::unlock(passphrase, salt, &check)?)
+    } else {
+        None
+    };
+    if let Some(key) = current_key {
+        encryption::remember_key(stored.id, key);
+    }
+    br.seek(0)?;
+    let project = Project::decode(&mut br)?;
+    drop(br);
+    stream_project_to_file(
+        &project_path,
+        &project,
+        compression_enabled(),
+        new_key.as_ref(),
+    )?;
+    match new_key {
+        Some(key) => encryption::remember_key(project.id, key),
+        None => encryption::forget_key(project_id),
+    }
+    // Kept beside the project as it was encoded before
+    peer_sync::forget_base(app, project_id)?;
+    crdt::delete_replica(app, project_id)?;
+    Ok(project)
+}
+
+/// Decodes the board named `board_name`, seeking to it through the offset
+/// table. Boards are found by name like in the window, so a board moved by
+/// another save is not mistaken for the one asked for.
+pub fn read_board<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    board_name: &str,
+) -> Result<board::Board, KanbanError> {
+    let project_path = project_file_path(app, project_id)?;
+    let mut br = BinaryReader::read_from_file(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let (header, _) = read_project_header(&mut br)?;
+    match read_payload(&mut br, &header, project_id)? {
+        Some(payload) => {
+            read_board_at(&mut BinaryReader::new(&payload), header.version, board_name)
+        }
+        None => read_board_at(&mut br, header.version, board_name),
+    }
+}
+
+/// Decodes a single board from the boards part of a project file.
+fn read_board_at<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    version: u8,
+    board_name: &str,
+) -> Result<board::Board, KanbanError> {
+    if version < 2 {
+        let boards = board::read_sequential_boards(br, version)?;
+        return boards
+            .into_iter()
+            .find(|b| b.name == board_name)
+            .ok_or_else(board_not_found);
+    }
+    let entries = board::read_board_table(br)?;
+    let index = find_board_entry(br, &entries, board_name)?;
+    br.seek(entries[index].offset as usize)?;
+    board::decode_board(br, version)
+}
+
+/// Index of the table entry of the board named `board_name`, reading only the
+/// names the boards start with.
+fn find_board_entry<R: Read + Seek>(
+    br: &mut BinaryReader<R>,
+    entries: &[board::BoardEntry],
+    board_name: &str,
+) -> Result<usize, KanbanError> {
+    for (index, entry) in entries.iter().enumerate() {
+        br.seek(entry.offset as usize)?;
+        if ShortString::decode(br)? == board_name {
+            return Ok(index);
+        }
+    }
+    Err(board_not_found())
+}
+
+fn board_not_found() -> KanbanError {
+    KanbanError::new(KanbanErrorKind::ProjectError, "Board does not exist")
+}
+
+/// Saves the board stored as `board_name` on top of `revision` and returns the
+/// new revision. `board` may carry a new name. The board is appended to a copy
+/// of the file and the table and revision patched there, without decoding the
+/// other boards. The copy then replaces the file in one rename, so a failed
+/// save leaves the old board in place. Once
+/// the replaced boards outweigh the live ones, and for older, compressed and
+/// encrypted files, the project is rewritten in full instead.
+pub fn save_board<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    board_name: &str,
+    board: &board::Board,
+    revision: u64,
+) -> Result<u64, KanbanError> {
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let project_path = project_file_path(app, project_id)?;
+    let mut br = BinaryReader::read_from_file(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let (header, stored) = read_project_header(&mut br)?;
+    if stored.revision != revision {
+        return Err(KanbanError::from_source(
+            KanbanErrorKind::ConflictError,
+            ProjectConflict {
+                current: read_project(app, project_id)?,
+            },
         ));
     }
-    // Project ID
-    let project_id: Vec<u8> = br.next_bytes(16)?;
-    // Project Name
-    let name: String = br.next_string(true)?;
-    // Project Description
-    let description: String = br.next_string(false)?;
-    // Boards
-    let boards = board::read_all_boards(&mut br)?;
-    Ok(Project {
-        id: <[u8; 16]>::try_from(project_id).map_err(|_| {
-            KanbanError::new(
-                KanbanErrorKind::ProjectError,
-                "Invalid project ID length".to_string(),
-            )
-        })?,
-        name,
-        description,
-        boards,
-    })
+    let rewrite = |app: &P| -> Result<u64, KanbanError> {
+        let stored = read_project(app, project_id)?;
+        let mut project = stored.clone();
+        let slot = project
+            .boards
+            .iter_mut()
+            .find(|b| b.name == board_name)
+            .ok_or_else(board_not_found)?;
+        *slot = board.clone();
+        Ok(write_next_revision(app, &project, Some(stored))?.revision)
+    };
+    if header.version < FILE_VERSION || header.compressed() || header.encrypted() {
+        return rewrite(app);
+    }
+    let entries = board::read_board_table(&mut br)?;
+    let table_end = br.position();
+    let index = find_board_entry(&mut br, &entries, board_name)?;
+    br.seek(entries[index].offset as usize)?;
+    let stored_board = board::decode_board(&mut br, header.version)?;
+    let path = format!("boards[{}]", index);
+    validation::validate_board_update(board, &path, Some(&stored_board))?;
+    let mut board_bw = BinaryWriter::new();
+    board.encode(&mut board_bw)?;
+    let length = board_bw.as_bytes().len() as u64;
+    let file_length = fs::metadata(&project_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+        .len();
+    let live = entries.iter().map(|e| e.length).sum::<u64>() - entries[index].length + length;
+    let dead = (file_length + length).saturating_sub(table_end as u64 + live);
+    if dead > live {
+        return rewrite(app);
+    }
+    let new_revision = revision + 1;
+    let mut table_entry = file_length.to_le_bytes().to_vec();
+    table_entry.extend_from_slice(&length.to_le_bytes());
+    let table_offset = table_end - entries.len() * board::BOARD_ENTRY_SIZE;
+    let temp_path = project_path.with_extension("tmp");
+    let write = || -> std::io::Result<()> {
+        fs::copy(&project_path, &temp_path)?;
+        let mut file = fs::OpenOptions::new().write(true).open(&temp_path)?;
+        let mut patch = |position: u64, bytes: &[u8]| {
+            file.seek(SeekFrom::Start(position))
+                .and_then(|_| file.write_all(bytes))
+        };
+        patch(file_length, board_bw.as_bytes())?;
+        patch(
+            (table_offset + index * board::BOARD_ENTRY_SIZE) as u64,
+            &table_entry,
+        )?;
+        patch(REVISION_OFFSET, &new_revision.to_le_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, &project_path)
+    };
+    write()
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    record_save(app, project_id);
+    Ok(new_revision)
 }
 
 pub fn delete_project<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<(), KanbanError> {
@@ -264,6 +922,15 @@ pub fn delete_project<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<
     {
         fs::remove_file(&project_path)
             .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+        snapshot::delete_snapshots(app, project_id)?;
+        sync_folder::delete_bases(app, project_id)?;
+        crdt::delete_replica(app, project_id)?;
+        if let Err(err) = project_group::forget_project(app, project_id) {
+            error_log::report("Failed to remove project from its group", err);
+        }
+        if let Err(err) = history::commit_deletion(app, project_id) {
+            error_log::report("Failed to commit project", err);
+        }
     } else {
         return Err(KanbanError::new(
             KanbanErrorKind::IoError,
@@ -273,11 +940,232 @@ pub fn delete_project<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<
     Ok(())
 }
 
+pub(crate) fn project_file_path<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<std::path::PathBuf, KanbanError> {
+    if project_id.len() != 16 {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Invalid project ID",
+        ));
+    }
+    let file_name: String = project_id.iter().map(|b| format!("{:02X}", b)).collect();
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(PROJECT_PATH)
+        .join(file_name))
+}
+
+fn archive_file_path<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<std::path::PathBuf, KanbanError> {
+    let project_path = project_file_path(app, project_id)?;
+    let file_name = project_path.file_name().unwrap_or_default().to_owned();
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(ARCHIVE_PATH)
+        .join(file_name))
+}
+
+/// The project file as it was before it was archived.
+fn inflate_archive(archive_path: &Path) -> Result<Vec<u8>, KanbanError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let mut bytes = Vec::new();
+    DeflateDecoder::new(file)
+        .read_to_end(&mut bytes)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::ProjectError, e))?;
+    Ok(bytes)
+}
+
+/// Bytes inflated at first to read the header of an archive. Older projects
+/// may keep longer descriptions, so this doubles until the header fits.
+const ARCHIVE_HEADER_CHUNK: u64 = 4096;
+
+/// The header of an archived project, inflating little more than the header.
+fn read_archive_header(archive_path: &Path) -> Result<Project, KanbanError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let mut decoder = DeflateDecoder::new(file);
+    let mut bytes = Vec::new();
+    let mut chunk = ARCHIVE_HEADER_CHUNK;
+    loop {
+        let read = (&mut decoder)
+            .take(chunk)
+            .read_to_end(&mut bytes)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::ProjectError, e))?;
+        match read_project_info_bytes(&bytes) {
+            Ok(mut project) => {
+                project.archived = true;
+                return Ok(project);
+            }
+            // The whole project was inflated and still has no valid header
+            Err(err) if (read as u64) < chunk => return Err(err),
+            Err(_) => chunk = bytes.len() as u64,
+        }
+    }
+}
+
+/// Whether the project is in the archive folder.
+pub fn is_archived<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<bool, KanbanError> {
+    fs::exists(archive_file_path(app, project_id)?)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))
+}
+
+/// Moves a project into the archive folder, deflate compressed as it is
+/// stored, so it leaves the project list. The archive is read back and
+/// checked against the project before the project is removed. Snapshots,
+/// history and sync state are kept for when it is restored.
+pub fn archive_project<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<(), KanbanError> {
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let project_path = project_file_path(app, project_id)?;
+    let archive_path = archive_file_path(app, project_id)?;
+    let io_error = |e| KanbanError::from_source(KanbanErrorKind::IoError, e);
+    if !fs::exists(&project_path).map_err(io_error)? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::IoError,
+            "Project does not exist",
+        ));
+    }
+    let bytes = fs::read(&project_path).map_err(io_error)?;
+    if let Some(dir) = archive_path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let temp_path = archive_path.with_extension("tmp");
+    let write = || -> Result<(), KanbanError> {
+        let file = fs::File::create(&temp_path).map_err(io_error)?;
+        let mut encoder = DeflateEncoder::new(file, Compression::best());
+        encoder.write_all(&bytes).map_err(io_error)?;
+        encoder.finish().map_err(io_error)?;
+        if inflate_archive(&temp_path)? != bytes {
+            return Err(KanbanError::new(
+                KanbanErrorKind::ProjectError,
+                "Archived copy does not match the project",
+            ));
+        }
+        fs::rename(&temp_path, &archive_path).map_err(io_error)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })?;
+    fs::remove_file(&project_path).map_err(io_error)
+}
+
+/// Moves an archived project back into the project list, byte for byte as
+/// it was archived.
+pub fn unarchive_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<Project, KanbanError> {
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let project_path = project_file_path(app, project_id)?;
+    let archive_path = archive_file_path(app, project_id)?;
+    let io_error = |e| KanbanError::from_source(KanbanErrorKind::IoError, e);
+    if !fs::exists(&archive_path).map_err(io_error)? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::IoError,
+            "Project is not archived",
+        ));
+    }
+    // Such as one synced back in meanwhile, which is not overwritten
+    if fs::exists(&project_path).map_err(io_error)? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "A project with this ID is already in the project list",
+        ));
+    }
+    let bytes = inflate_archive(&archive_path)?;
+    let project = read_project_info_bytes(&bytes)?;
+    if let Some(dir) = project_path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let temp_path = project_path.with_extension("tmp");
+    fs::write(&temp_path, &bytes)
+        .and_then(|_| fs::rename(&temp_path, &project_path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+        .map_err(io_error)?;
+    fs::remove_file(&archive_path).map_err(io_error)?;
+    Ok(project)
+}
+
+/// Headers of the archived projects, which are not in `get_all_projects_info`.
+pub fn get_archived_projects_info<P: AppPathProvider>(
+    app: &P,
+) -> Result<Vec<Project>, KanbanError> {
+    let archive_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(ARCHIVE_PATH);
+    let entries = match fs::read_dir(&archive_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(KanbanError::from_source(KanbanErrorKind::IoError, e)),
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().len() == 32)
+        .filter_map(|entry| {
+            read_archive_header(&entry.path())
+                .inspect_err(|e| error_log::report("Failed to read archived project", e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Identifies what is stored for a project without decoding it. Every save
+/// moves the revision, the length and the content hash tell apart files
+/// other tools wrote at the same revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectStamp {
+    pub revision: u64,
+    pub length: u64,
+    pub hash: u64,
+}
+
+/// Reads the stamp of the stored project from its header, metadata and
+/// contents. Hashing the file still costs far less than decoding it.
+pub fn project_stamp<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<ProjectStamp, KanbanError> {
+    let project_path = project_file_path(app, project_id)?;
+    let io_error = |e| KanbanError::from_source(KanbanErrorKind::IoError, e);
+    let mut file = fs::File::open(&project_path).map_err(io_error)?;
+    let length = file.metadata().map_err(io_error)?.len();
+    let mut version = [0u8; 1];
+    file.read_exact(&mut version).map_err(io_error)?;
+    let mut revision = [0u8; 8];
+    if version[0] >= 1 {
+        file.seek(SeekFrom::Start(REVISION_OFFSET))
+            .and_then(|_| file.read_exact(&mut revision))
+            .map_err(io_error)?;
+    }
+    file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+    let hash = file_stamp::content_hash(file).map_err(io_error)?;
+    Ok(ProjectStamp {
+        revision: u64::from_le_bytes(revision),
+        length,
+        hash,
+    })
+}
+
 #[cfg(test)]
 mod test {
     use crate::kanban::board_list::BoardList;
 
     use super::*;
+    use crate::kanban::board::arb_board;
+    use crate::test_support::mock_app;
+    use proptest::prelude::*;
     use serial_test::serial;
     use std::{fs, os::unix::fs::PermissionsExt};
     use tauri::Manager;
@@ -287,10 +1175,12 @@ mod test {
     fn test_write_project_header() {
         let mut bw: BinaryWriter = BinaryWriter::new();
         let id: Uuid = Uuid::new_v4();
-        write_project_header(&mut bw, &id, "Test Name", "Test Description");
+        write_project_header(&mut bw, &id, 0, 0, "Test Name", "Test Description").unwrap();
         let mut expected_bytes: Vec<u8> = Vec::new();
-        expected_bytes.push(0x00);
+        expected_bytes.push(FILE_VERSION);
         expected_bytes.extend_from_slice(id.as_bytes());
+        expected_bytes.extend_from_slice(&[0x00; 8]);
+        expected_bytes.push(0x00);
         expected_bytes.extend_from_slice(&[
             0x09, 0x54, 0x65, 0x73, 0x74, 0x20, 0x4E, 0x61, 0x6D, 0x65, 0x10, 0x54, 0x65, 0x73,
             0x74, 0x20, 0x44, 0x65, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74, 0x69, 0x6F, 0x6E,
@@ -307,7 +1197,7 @@ mod test {
         // Test data
         let mut bw: BinaryWriter = BinaryWriter::new();
         let id: Uuid = Uuid::new_v4();
-        write_project_header(&mut bw, &id, "Test Project", "Test Description");
+        write_project_header(&mut bw, &id, 0, 0, "Test Project", "Test Description").unwrap();
         // Test result
         let result = write_project_to_file(app, &bw);
         assert!(result.is_ok());
@@ -359,7 +1249,7 @@ mod test {
         let mock_app = MockAppPathProvider { path: MockPath };
         let mut bw = BinaryWriter::new();
         let id = Uuid::new_v4();
-        write_project_header(&mut bw, &id, "Test Project", "Test Description");
+        write_project_header(&mut bw, &id, 0, 0, "Test Project", "Test Description").unwrap();
         let result = write_project_to_file(&mock_app, &bw);
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -369,40 +1259,15 @@ mod test {
 
     #[test]
     fn test_write_project_to_file_readonly_dir() {
-        // Mock app handle
-        struct MockAppPathProvider {
-            path: MockPath,
-        }
-
-        impl AppPathProvider for MockAppPathProvider {
-            type Path = MockPath;
-            fn path(&self) -> &Self::Path {
-                &self.path
-            }
-        }
-        struct MockPath {
-            path: std::path::PathBuf,
-        }
-        impl PathProvider for MockPath {
-            fn app_data_dir(
-                &self,
-            ) -> Result<std::path::PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-                Ok(self.path.clone())
-            }
-        }
         // Create readonly dir
         let readonly_dir = TempDir::new("kanban-test").expect("Failed to create directory");
         fs::set_permissions(readonly_dir.path(), fs::Permissions::from_mode(0o555))
             .expect("Failed to set permission");
         // Test
-        let mock_app = MockAppPathProvider {
-            path: MockPath {
-                path: readonly_dir.path().to_path_buf(),
-            },
-        };
+        let mock_app = mock_app(readonly_dir.path());
         let mut bw = BinaryWriter::new();
         let id = Uuid::new_v4();
-        write_project_header(&mut bw, &id, "Test Project", "Test Description");
+        write_project_header(&mut bw, &id, 0, 0, "Test Project", "Test Description").unwrap();
         let result = write_project_to_file(&mock_app, &bw);
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -442,20 +1307,14 @@ mod test {
         let result = create_project(app, "", "Test Description");
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert_eq!(KanbanErrorKind::ProjectError, error.kind);
-        assert_eq!(
-            "Empty Name or Description: The name and description of the project must not be empty",
-            error.message
-        );
+        assert_eq!(KanbanErrorKind::ValidationError, error.kind);
+        assert_eq!("name: Must not be empty", error.message);
         // Test data (empty description)
         let result = create_project(app, "Test Project", "");
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert_eq!(KanbanErrorKind::ProjectError, error.kind);
-        assert_eq!(
-            "Empty Name or Description: The name and description of the project must not be empty",
-            error.message
-        );
+        assert_eq!(KanbanErrorKind::ValidationError, error.kind);
+        assert_eq!("description: Must not be empty", error.message);
     }
 
     #[test]
@@ -471,10 +1330,14 @@ mod test {
         );
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert_eq!(KanbanErrorKind::ProjectError, error.kind);
+        assert_eq!(KanbanErrorKind::ValidationError, error.kind);
+        assert_eq!("name: Must be at most 255 bytes", error.message);
+        // Counted in bytes, 128 characters of 2 bytes do not fit
+        let result = create_project(app, &"é".repeat(128), "Test Description");
+        assert!(result.is_err());
         assert_eq!(
-            "Name too long: Project name must be between 1 and 256 characters",
-            error.message
+            "name: Must be at most 255 bytes",
+            result.unwrap_err().message
         );
     }
 
@@ -557,10 +1420,10 @@ mod test {
         let app = mock.app_handle();
         let mut bw = BinaryWriter::new();
         let id = Uuid::new_v4();
-        write_project_header(&mut bw, &id, "Test Name", "Test Description");
+        write_project_header(&mut bw, &id, 0, 0, "Test Name", "Test Description").unwrap();
         let bytes = bw.as_bytes();
         let mut bw = BinaryWriter::new();
-        bw.write_byte(0x01);
+        bw.write_byte(FILE_VERSION + 1);
         bw.write_bytes(&bytes[1..]);
         write_project_to_file(app, &bw).expect("Failed to create project");
         let file_name: String = id.as_bytes().iter().map(|b| format!("{:02X}", b)).collect();
@@ -704,12 +1567,16 @@ mod test {
         let test_board = board::Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
         let test_project = Project {
             name: "Test Project 1".to_string(),
             description: "Description for Test Project 1".to_string(),
             boards: [test_board.clone(), test_board.clone()].to_vec(),
             id: Uuid::new_v4().into_bytes(),
+            revision: 0,
+            locked: false,
+            archived: false,
         };
         let result = save_project(app, &test_project);
         assert!(result.is_ok());
@@ -724,31 +1591,43 @@ mod test {
             .join(file_name);
         let bytes = fs::read(&project_path).expect("Failed to read file");
         let mut expected_bytes: Vec<u8> = Vec::new();
-        expected_bytes.push(0x00);
+        expected_bytes.push(FILE_VERSION);
         expected_bytes.extend_from_slice(&test_project.id);
+        expected_bytes.extend_from_slice(&1u64.to_le_bytes());
+        expected_bytes.push(0x00);
         expected_bytes.extend_from_slice(&[
             0x0E, 0x54, 0x65, 0x73, 0x74, 0x20, 0x50, 0x72, 0x6F, 0x6A, 0x65, 0x63, 0x74, 0x20,
             0x31, 0x1E, 0x44, 0x65, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74, 0x69, 0x6F, 0x6E, 0x20,
             0x66, 0x6F, 0x72, 0x20, 0x54, 0x65, 0x73, 0x74, 0x20, 0x50, 0x72, 0x6F, 0x6A, 0x65,
             0x63, 0x74, 0x20, 0x31,
         ]);
-        expected_bytes.extend_from_slice(&[
-            2, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x03, 0x0C, 0x54,
-            0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x31, 0xFF, 0xFF, 0xFF,
-            0x03, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20,
-            0x32, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x33, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20,
-            0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00, 0x00, 0x00, 0x02, 0x06, 0x49, 0x74,
-            0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65,
-            0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01,
-            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F,
+        let boards_bytes: &[u8] = &[
+            10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x03, 0x0C, 0x54, 0x65,
+            0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x31, 0xFF, 0xFF, 0xFF, 0x03,
+            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32,
+            0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x33, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42,
+            0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00, 0x00, 0x00, 0x02, 0x06, 0x49, 0x74, 0x65,
+            0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65, 0x73,
+            0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06,
+            0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x00, 10, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F,
             0x61, 0x72, 0x64, 0x03, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72,
             0x64, 0x20, 0x31, 0xFF, 0xFF, 0xFF, 0x03, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
             0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x32, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x33,
             0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64, 0x20, 0x32, 0x00,
             0x00, 0x00, 0x02, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x06, 0x49, 0x74, 0x65,
             0x6D, 0x20, 0x32, 0x0C, 0x54, 0x65, 0x73, 0x74, 0x20, 0x42, 0x6F, 0x61, 0x72, 0x64,
-            0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31,
-        ]);
+            0x20, 0x33, 0xaa, 0xaa, 0xaa, 0x01, 0x06, 0x49, 0x74, 0x65, 0x6D, 0x20, 0x31, 0x00,
+        ];
+        // Board count and offset table
+        expected_bytes.push(2);
+        let board_length = boards_bytes.len() / 2;
+        let boards_offset = expected_bytes.len() + 2 * board::BOARD_ENTRY_SIZE;
+        for i in 0..2 {
+            expected_bytes
+                .extend_from_slice(&((boards_offset + i * board_length) as u64).to_le_bytes());
+            expected_bytes.extend_from_slice(&(board_length as u64).to_le_bytes());
+        }
+        expected_bytes.extend_from_slice(boards_bytes);
         assert_eq!(expected_bytes, bytes);
         if fs::exists(&project_path).expect("Failed to check whether file exists") {
             fs::remove_file(&project_path).expect("Failed to remove file");
@@ -780,12 +1659,16 @@ mod test {
         let test_board = board::Board {
             name: "Test Board".to_string(),
             lists: [test_list_1, test_list_2, test_list_3].to_vec(),
+            archived: false,
         };
         let test_project = Project {
             name: "Test Project 1".to_string(),
             description: "Description for Test Project 1".to_string(),
             boards: [test_board.clone(), test_board.clone()].to_vec(),
             id: Uuid::new_v4().into_bytes(),
+            revision: 0,
+            locked: false,
+            archived: false,
         };
         let expected_project = save_project(app, &test_project).expect("Failed to save project");
         let project = read_project(app, &expected_project.id);
@@ -805,6 +1688,35 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_project_stamp() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        let stamp = project_stamp(&mock_app, &project.id).unwrap();
+        assert_eq!(0, stamp.revision);
+        let saved = save_project(&mock_app, &project).unwrap();
+        let saved_stamp = project_stamp(&mock_app, &project.id).unwrap();
+        assert_eq!(saved.revision, saved_stamp.revision);
+        // Rewritten at the same revision, told apart by the length
+        let mut rewritten = saved.clone();
+        rewritten.name = "Longer Project Name".to_string();
+        stream_project_to_file(
+            &project_file_path(&mock_app, &project.id).unwrap(),
+            &rewritten,
+            false,
+            None,
+        )
+        .unwrap();
+        assert_ne!(saved_stamp, project_stamp(&mock_app, &project.id).unwrap());
+        let result = project_stamp(&mock_app, &[0x01; 16]);
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::IoError, result.unwrap_err().kind);
+        let result = project_stamp(&mock_app, &[0x01; 3]);
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::ProjectError, result.unwrap_err().kind);
+    }
+
     #[test]
     fn test_delete_project() {
         let mock = tauri::test::mock_app();
@@ -820,4 +1732,411 @@ mod test {
             .join(&project_id);
         assert!(!fs::exists(project_path).unwrap());
     }
+
+    #[test]
+    fn test_archive_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let mut project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project.boards = vec![board::Board {
+            name: "Test Board".to_string(),
+            lists: vec![BoardList {
+                title: "Test List".to_string(),
+                color: [0xFF, 0xFF, 0xFF],
+                items: vec!["A long and repetitive item".repeat(20); 50],
+            }],
+            archived: true,
+        }];
+        let project = save_project(&mock_app, &project).unwrap();
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        let stored = fs::read(&project_path).unwrap();
+        archive_project(&mock_app, &project.id).unwrap();
+        assert!(!fs::exists(&project_path).unwrap());
+        assert!(is_archived(&mock_app, &project.id).unwrap());
+        assert!(get_all_projects_info(&mock_app).unwrap().is_empty());
+        let archived = get_archived_projects_info(&mock_app).unwrap();
+        assert_eq!(1, archived.len());
+        assert!(archived[0].archived);
+        assert_eq!(project.name, archived[0].name);
+        // Saving the header back would bring the project back without its boards
+        assert!(save_project(&mock_app, &archived[0]).is_err());
+        assert!(!fs::exists(&project_path).unwrap());
+        let archive_path = dir
+            .path()
+            .join(ARCHIVE_PATH)
+            .join(project_path.file_name().unwrap());
+        assert!(fs::read(&archive_path).unwrap().len() * 10 < stored.len());
+        assert!(archive_project(&mock_app, &project.id).is_err());
+        // Restored byte for byte, archived boards and revision included
+        let restored = unarchive_project(&mock_app, &project.id).unwrap();
+        assert_eq!(project.revision, restored.revision);
+        assert_eq!(stored, fs::read(&project_path).unwrap());
+        assert!(!is_archived(&mock_app, &project.id).unwrap());
+        assert_eq!(project, read_project(&mock_app, &project.id).unwrap());
+        assert!(unarchive_project(&mock_app, &project.id).is_err());
+        assert!(get_archived_projects_info(&mock_app).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_archive_project_with_long_description() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let created = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        // Kept from before the limit, so written past the validation
+        let mut project = created.clone();
+        project.description = "A long description. ".repeat(validation::TEXT_MAX_CHARS);
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        stream_project_to_file(&project_path, &project, false, None).unwrap();
+        archive_project(&mock_app, &project.id).unwrap();
+        let archived = get_archived_projects_info(&mock_app).unwrap();
+        assert_eq!(1, archived.len());
+        assert_eq!(project.description, archived[0].description);
+        let restored = unarchive_project(&mock_app, &project.id).unwrap();
+        assert_eq!(project.description, restored.description);
+        assert_eq!(project, read_project(&mock_app, &project.id).unwrap());
+    }
+
+    #[test]
+    fn test_save_project_conflict() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        let mut first = project.clone();
+        first.name = "First Window".to_string();
+        let first = save_project(&mock_app, &first).expect("Failed to save project");
+        assert_eq!(1, first.revision);
+        // The second window still holds revision 0
+        let mut second = project.clone();
+        second.name = "Second Window".to_string();
+        let result = save_project(&mock_app, &second);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(KanbanErrorKind::ConflictError, err.kind);
+        assert_eq!(Some(&first), conflict_copy(&err));
+        // Saving on top of the current revision goes through
+        second.revision = first.revision;
+        let saved = save_project(&mock_app, &second).expect("Failed to save project");
+        assert_eq!(2, saved.revision);
+        assert_eq!(saved, read_project(&mock_app, &project.id).unwrap());
+    }
+
+    #[test]
+    fn test_read_project_version_0() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let id = Uuid::new_v4();
+        let mut bw = BinaryWriter::new();
+        bw.write_byte(0x00);
+        bw.write_bytes(id.as_bytes());
+        bw.write_string_with_length("Test Project", true).unwrap();
+        bw.write_string_with_length("Test Description", false)
+            .unwrap();
+        bw.write_byte(0x00);
+        write_project_to_file(&mock_app, &bw).expect("Failed to write project");
+        let project = read_project(&mock_app, id.as_bytes()).expect("Failed to read project");
+        assert_eq!(0, project.revision);
+        assert_eq!("Test Project", project.name);
+        assert_eq!("Test Description", project.description);
+    }
+
+    #[test]
+    fn test_read_and_save_board() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let board_with = |name: &str, items: &[&str]| board::Board {
+            name: name.to_string(),
+            lists: vec![BoardList {
+                title: "Test List".to_string(),
+                color: [0xFF, 0xFF, 0xFF],
+                items: items.iter().map(|s| s.to_string()).collect(),
+            }],
+            archived: false,
+        };
+        let mut project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project.boards = vec![
+            board_with("Board 1", &["Item 1"]),
+            board_with("Board 2", &["Item 2", "Item 3"]),
+        ];
+        let project = save_project(&mock_app, &project).unwrap();
+        let board = read_board(&mock_app, &project.id, "Board 2").unwrap();
+        assert_eq!(project.boards[1], board);
+        let result = read_board(&mock_app, &project.id, "Board 3");
+        assert!(result.is_err());
+        assert_eq!("Board does not exist", result.unwrap_err().message);
+        // Boards are appended, the replaced ones stay until the file is rewritten
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        let full_length = fs::metadata(&project_path).unwrap().len();
+        let smaller = board_with("Board 1", &[]);
+        let revision = save_board(
+            &mock_app,
+            &project.id,
+            "Board 1",
+            &smaller,
+            project.revision,
+        )
+        .unwrap();
+        let larger = board_with("Renamed Board", &["Item 2", "Item 3", "Item 4"]);
+        let revision = save_board(&mock_app, &project.id, "Board 2", &larger, revision).unwrap();
+        assert_eq!(project.revision + 2, revision);
+        assert!(fs::metadata(&project_path).unwrap().len() > full_length);
+        let read_back = read_project(&mock_app, &project.id).unwrap();
+        assert_eq!(revision, read_back.revision);
+        assert_eq!(vec![smaller.clone(), larger.clone()], read_back.boards);
+        assert_eq!(
+            larger,
+            read_board(&mock_app, &project.id, "Renamed Board").unwrap()
+        );
+        // Invalid boards are rejected before anything is written
+        let result = save_board(
+            &mock_app,
+            &project.id,
+            "Renamed Board",
+            &board_with(" ", &[]),
+            revision,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            "boards[1].name: Must not be empty",
+            result.unwrap_err().message
+        );
+        // Saving on a stale revision is a conflict
+        let result = save_board(
+            &mock_app,
+            &project.id,
+            "Board 1",
+            &smaller,
+            project.revision,
+        );
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(KanbanErrorKind::ConflictError, err.kind);
+        assert_eq!(Some(&read_back), conflict_copy(&err));
+    }
+
+    #[test]
+    fn test_save_board_compacts() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let mut project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project.boards = vec![
+            board::Board {
+                name: "Board 1".to_string(),
+                lists: Vec::new(),
+                archived: false,
+            },
+            board::Board {
+                name: "Board 2".to_string(),
+                lists: Vec::new(),
+                archived: false,
+            },
+        ];
+        let project = save_project(&mock_app, &project).unwrap();
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        let full_length = fs::metadata(&project_path).unwrap().len();
+        // Replaced boards never outweigh the live ones
+        let mut revision = project.revision;
+        for _ in 0..10 {
+            revision = save_board(
+                &mock_app,
+                &project.id,
+                "Board 1",
+                &project.boards[0],
+                revision,
+            )
+            .unwrap();
+            let length = fs::metadata(&project_path).unwrap().len();
+            assert!(length < full_length * 2);
+        }
+        // A full save drops them
+        let read_back = read_project(&mock_app, &project.id).unwrap();
+        assert_eq!(project.boards, read_back.boards);
+        save_project(&mock_app, &read_back).unwrap();
+        assert_eq!(full_length, fs::metadata(&project_path).unwrap().len());
+    }
+
+    #[test]
+    fn test_save_board_version_1() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        // Boards stored one after another, without an offset table
+        let id = Uuid::new_v4();
+        let mut bw = BinaryWriter::new();
+        bw.write_byte(0x01);
+        bw.write_bytes(id.as_bytes());
+        bw.write_bytes(&3u64.to_le_bytes());
+        bw.write_string_with_length("Test Project", true).unwrap();
+        bw.write_string_with_length("Test Description", false)
+            .unwrap();
+        bw.write_byte(0x01);
+        bw.write_string_with_length("Test Board", true).unwrap();
+        bw.write_byte(0x00);
+        write_project_to_file(&mock_app, &bw).expect("Failed to write project");
+        let board = read_board(&mock_app, id.as_bytes(), "Test Board").unwrap();
+        assert_eq!("Test Board", board.name);
+        let renamed = board::Board {
+            name: "Renamed Board".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        };
+        assert_eq!(
+            4,
+            save_board(&mock_app, id.as_bytes(), "Test Board", &renamed, 3).unwrap()
+        );
+        let project = read_project(&mock_app, id.as_bytes()).unwrap();
+        assert_eq!(vec![renamed], project.boards);
+    }
+
+    #[test]
+    #[serial]
+    fn test_compressed_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let mut project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project.boards = vec![board::Board {
+            name: "Test Board".to_string(),
+            lists: vec![BoardList {
+                title: "Test List".to_string(),
+                color: [0xFF, 0xFF, 0xFF],
+                items: vec!["A long and repetitive item".repeat(20); 50],
+            }],
+            archived: false,
+        }];
+        set_compression(true);
+        let project = save_project(&mock_app, &project).unwrap();
+        set_compression(false);
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(FLAG_COMPRESSED, bytes[REVISION_OFFSET as usize + 8]);
+        let mut bw = BinaryWriter::new();
+        project.encode(&mut bw).unwrap();
+        assert!(bytes.len() * 10 < bw.as_bytes().len());
+        // The header stays readable without decompressing
+        let file_name: String = project.id.iter().map(|b| format!("{:02X}", b)).collect();
+        let info = read_project_info(&mock_app, &file_name).unwrap();
+        assert_eq!(project.name, info.name);
+        assert_eq!(project, read_project(&mock_app, &project.id).unwrap());
+        assert_eq!(
+            project.boards[0],
+            read_board(&mock_app, &project.id, "Test Board").unwrap()
+        );
+        // Compressed files are rewritten in full, in the current setting
+        let revision = save_board(
+            &mock_app,
+            &project.id,
+            "Test Board",
+            &project.boards[0],
+            project.revision,
+        )
+        .unwrap();
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(0x00, bytes[REVISION_OFFSET as usize + 8]);
+        assert_eq!(project.revision + 1, revision);
+        // Recompressing keeps the revision and skips files already in the setting
+        set_compression(true);
+        let rewritten = recompress_all_projects_with_progress(&mock_app, |_, _| {});
+        set_compression(false);
+        assert_eq!(vec![project.id], rewritten.unwrap());
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(FLAG_COMPRESSED, bytes[REVISION_OFFSET as usize + 8]);
+        let read_back = read_project(&mock_app, &project.id).unwrap();
+        assert_eq!(revision, read_back.revision);
+        assert_eq!(project.boards, read_back.boards);
+        let mut progress = Vec::new();
+        let rewritten = recompress_all_projects_with_progress(&mock_app, |done, total| {
+            progress.push((done, total))
+        });
+        assert_eq!(vec![project.id], rewritten.unwrap());
+        assert_eq!(vec![(1, 1)], progress);
+    }
+
+    #[test]
+    fn test_encrypted_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let mut project = create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        project.boards = vec![board::Board {
+            name: "Secret Board".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        }];
+        let project = save_project(&mock_app, &project).unwrap();
+        let encrypted = change_passphrase(&mock_app, &project.id, None, Some("secret")).unwrap();
+        assert_eq!(project, encrypted);
+        let project_path = project_file_path(&mock_app, &project.id).unwrap();
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(FLAG_ENCRYPTED, bytes[REVISION_OFFSET as usize + 8]);
+        assert!(!bytes.windows(12).any(|w| w == b"Secret Board"));
+        // Locked projects only expose their header
+        lock_project(&project.id);
+        let file_name: String = project.id.iter().map(|b| format!("{:02X}", b)).collect();
+        let info = read_project_info(&mock_app, &file_name).unwrap();
+        assert!(info.locked);
+        assert_eq!(project.name, info.name);
+        for err in [
+            read_project(&mock_app, &project.id).unwrap_err(),
+            read_board(&mock_app, &project.id, "Secret Board").unwrap_err(),
+            save_project(&mock_app, &project).unwrap_err(),
+        ] {
+            assert_eq!(KanbanErrorKind::LockedError, err.kind);
+        }
+        let err = unlock_project(&mock_app, &project.id, "wrong").unwrap_err();
+        assert_eq!(KanbanErrorKind::PassphraseError, err.kind);
+        assert_eq!(
+            project,
+            unlock_project(&mock_app, &project.id, "secret").unwrap()
+        );
+        assert!(!read_project_info(&mock_app, &file_name).unwrap().locked);
+        // Saves stay encrypted while unlocked
+        let saved = save_project(&mock_app, &project).unwrap();
+        assert_eq!(saved, read_project(&mock_app, &project.id).unwrap());
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(FLAG_ENCRYPTED, bytes[REVISION_OFFSET as usize + 8]);
+        // Damage is reported apart from a wrong passphrase
+        let mut damaged = bytes.clone();
+        *damaged.last_mut().unwrap() ^= 0x01;
+        fs::write(&project_path, &damaged).expect("Failed to write file");
+        lock_project(&project.id);
+        let err = unlock_project(&mock_app, &project.id, "secret").unwrap_err();
+        assert_eq!(KanbanErrorKind::ProjectError, err.kind);
+        assert_eq!(
+            "Failed to read project file: Encrypted data is damaged",
+            err.message
+        );
+        fs::write(&project_path, &bytes).expect("Failed to write file");
+        // Removing the passphrase needs the current one
+        let err = change_passphrase(&mock_app, &project.id, None, None).unwrap_err();
+        assert_eq!(KanbanErrorKind::PassphraseError, err.kind);
+        change_passphrase(&mock_app, &project.id, Some("secret"), None).unwrap();
+        let bytes = fs::read(&project_path).expect("Failed to read file");
+        assert_eq!(0x00, bytes[REVISION_OFFSET as usize + 8]);
+        assert_eq!(saved, read_project(&mock_app, &project.id).unwrap());
+    }
+
+    #[test]
+    fn test_read_project_unknown_flags() {
+        let mut bw = BinaryWriter::new();
+        write_project_header(&mut bw, &Uuid::new_v4(), 0, 0x80, "Test", "Test").unwrap();
+        bw.write_byte(0x00);
+        let result = Project::decode(&mut BinaryReader::new(bw.as_bytes()));
+        assert!(result.is_err());
+        assert_eq!("Project flags not supported", result.unwrap_err().message);
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(
+            id: [u8; 16],
+            revision: u64,
+            name in "\\PC{1,60}",
+            description in ".+",
+            boards in prop::collection::vec(arb_board(), 0..4),
+            compress: bool,
+        ) {
+            let project = Project { id, revision, locked: false, archived: false, name, description, boards };
+            let mut bw = BinaryWriter::new();
+            encode_project(&mut bw, &project, compress, None).unwrap();
+            let mut br = BinaryReader::new(bw.as_bytes());
+            prop_assert_eq!(project, Project::decode(&mut br).unwrap());
+        }
+    }
 }
@@ -0,0 +1,390 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{
+    board::Board,
+    project::{self, AppPathProvider, Project, ProjectStamp},
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+const DEFAULT_CAPACITY: usize = 8;
+// Revisions kept per project as merge bases, after the entry itself is replaced
+const RECENT_REVISIONS: usize = 4;
+
+struct CacheEntry {
+    project: Project,
+    // Stamp of the file the project was decoded from or written as
+    stamp: ProjectStamp,
+}
+
+struct LruCache {
+    capacity: usize,
+    entries: HashMap<[u8; 16], CacheEntry>,
+    // Least recently used first
+    order: VecDeque<[u8; 16]>,
+    recent: HashMap<[u8; 16], VecDeque<Project>>,
+}
+
+impl LruCache {
+    fn touch(&mut self, id: &[u8; 16]) {
+        self.order.retain(|x| x != id);
+        self.order.push_back(*id);
+    }
+
+    fn get(&mut self, id: &[u8; 16], stamp: ProjectStamp) -> Option<Project> {
+        let entry = self.entries.get(id)?;
+        if entry.stamp != stamp {
+            // Written by someone else since it was cached
+            self.remove(id);
+            return None;
+        }
+        let project = entry.project.clone();
+        self.touch(id);
+        Some(project)
+    }
+
+    fn put(&mut self, project: Project, stamp: ProjectStamp) {
+        let id = project.id;
+        let recent = self.recent.entry(id).or_default();
+        recent.retain(|p| p.revision != project.revision);
+        recent.push_back(project.clone());
+        while recent.len() > RECENT_REVISIONS {
+            recent.pop_front();
+        }
+        self.entries.insert(id, CacheEntry { project, stamp });
+        self.touch(&id);
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, id: &[u8; 16]) {
+        self.entries.remove(id);
+        self.order.retain(|x| x != id);
+    }
+
+    fn revision(&self, id: &[u8; 16], revision: u64) -> Option<Project> {
+        let recent = self.recent.get(id)?;
+        recent.iter().find(|p| p.revision == revision).cloned()
+    }
+}
+
+/// Decoded projects kept in memory, so switching boards and autosaving do not
+/// have to read and decode the whole file again. Saves are written through.
+pub struct ProjectCache {
+    cache: Mutex<LruCache>,
+}
+
+impl Default for ProjectCache {
+    fn default() -> Self {
+        ProjectCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+fn to_project_id(project_id: &[u8]) -> Result<[u8; 16], KanbanError> {
+    <[u8; 16]>::try_from(project_id)
+        .map_err(|_| KanbanError::new(KanbanErrorKind::ProjectError, "Invalid project ID"))
+}
+
+impl ProjectCache {
+    pub fn new(capacity: usize) -> Self {
+        ProjectCache {
+            cache: Mutex::new(LruCache {
+                capacity: capacity.max(1),
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                recent: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn invalidate(&self, project_id: &[u8; 16]) {
+        self.cache.lock().unwrap().remove(project_id);
+    }
+
+    /// Drops the project and its recent revisions.
+    pub fn forget(&self, project_id: &[u8; 16]) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.remove(project_id);
+        cache.recent.remove(project_id);
+    }
+
+    /// Drops every project, such as when another workspace is opened.
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.entries.clear();
+        cache.order.clear();
+        cache.recent.clear();
+    }
+
+    /// A copy of the project at `revision`, if it was read or saved recently.
+    pub fn revision(&self, project_id: &[u8; 16], revision: u64) -> Option<Project> {
+        self.cache.lock().unwrap().revision(project_id, revision)
+    }
+
+    /// The cached copy of the project, if the stored file still matches it.
+    fn current<P: AppPathProvider>(
+        &self,
+        app: &P,
+        id: &[u8; 16],
+    ) -> Result<(Option<Project>, ProjectStamp), KanbanError> {
+        // The header is enough to tell whether the cached copy is still current
+        let stamp = project::project_stamp(app, id)?;
+        Ok((self.cache.lock().unwrap().get(id, stamp), stamp))
+    }
+
+    /// Caches `project` as written, unless the file moved on meanwhile.
+    fn put_saved<P: AppPathProvider>(&self, app: &P, project: Project) {
+        let mut cache = self.cache.lock().unwrap();
+        match project::project_stamp(app, &project.id) {
+            Ok(stamp) if stamp.revision == project.revision => cache.put(project, stamp),
+            _ => cache.remove(&project.id),
+        }
+    }
+
+    pub fn read_project<P: AppPathProvider>(
+        &self,
+        app: &P,
+        project_id: &[u8],
+    ) -> Result<Project, KanbanError> {
+        let id = to_project_id(project_id)?;
+        let (cached, stamp) = self.current(app, &id)?;
+        if let Some(project) = cached {
+            return Ok(project);
+        }
+        // Decode without holding the lock, so other projects can be read meanwhile
+        let project = project::read_project(app, &id)?;
+        self.cache.lock().unwrap().put(project.clone(), stamp);
+        Ok(project)
+    }
+
+    // Writes without holding the lock, the save lock of the project module
+    // keeps them in order and checks the cached copy is still current
+    pub fn save_project<P: AppPathProvider>(
+        &self,
+        app: &P,
+        project: &Project,
+    ) -> Result<Project, KanbanError> {
+        let result = match self.current(app, &project.id) {
+            Ok((Some(current), stamp)) => project::save_project_over(app, project, current, stamp),
+            _ => project::save_project(app, project),
+        };
+        match result {
+            Ok(saved) => {
+                self.put_saved(app, saved.clone());
+                Ok(saved)
+            }
+            Err(err) => {
+                self.invalidate(&project.id);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn read_board<P: AppPathProvider>(
+        &self,
+        app: &P,
+        project_id: &[u8],
+        board_name: &str,
+    ) -> Result<Board, KanbanError> {
+        let id = to_project_id(project_id)?;
+        let (cached, _) = self.current(app, &id)?;
+        let board = cached.and_then(|p| p.boards.into_iter().find(|b| b.name == board_name));
+        match board {
+            Some(board) => Ok(board),
+            None => project::read_board(app, &id, board_name),
+        }
+    }
+
+    pub fn save_board<P: AppPathProvider>(
+        &self,
+        app: &P,
+        project_id: &[u8],
+        board_name: &str,
+        board: &Board,
+        revision: u64,
+    ) -> Result<u64, KanbanError> {
+        let id = to_project_id(project_id)?;
+        let cached = self.current(app, &id).ok().and_then(|(cached, _)| cached);
+        let new_revision = match project::save_board(app, &id, board_name, board, revision) {
+            Ok(new_revision) => new_revision,
+            Err(err) => {
+                self.invalidate(&id);
+                return Err(err);
+            }
+        };
+        // Patch the cached copy instead of decoding the file again. The save
+        // checked the stored revision, so a copy at that revision was current.
+        let patched = cached
+            .filter(|p| p.revision == revision)
+            .and_then(|mut project| {
+                let slot = project.boards.iter_mut().find(|b| b.name == board_name)?;
+                *slot = board.clone();
+                project.revision = new_revision;
+                Some(project)
+            });
+        match patched {
+            Some(project) => self.put_saved(app, project),
+            None => self.invalidate(&id),
+        }
+        Ok(new_revision)
+    }
+
+    pub fn delete_project<P: AppPathProvider>(
+        &self,
+        app: &P,
+        project_id: &[u8],
+    ) -> Result<(), KanbanError> {
+        let id = to_project_id(project_id)?;
+        let mut cache = self.cache.lock().unwrap();
+        cache.remove(&id);
+        cache.recent.remove(&id);
+        project::delete_project(app, &id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{mock_app, test_project};
+    use std::fs;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = ProjectCache::new(2);
+        let mut lru = cache.cache.lock().unwrap();
+        let stamp = |length| ProjectStamp {
+            revision: 0,
+            length,
+            hash: 0,
+        };
+        lru.put(test_project([1; 16], Vec::new()), stamp(1));
+        lru.put(test_project([2; 16], Vec::new()), stamp(1));
+        // Using project 1 makes project 2 the least recently used
+        assert!(lru.get(&[1; 16], stamp(1)).is_some());
+        lru.put(test_project([3; 16], Vec::new()), stamp(1));
+        assert!(lru.get(&[2; 16], stamp(1)).is_none());
+        assert!(lru.get(&[1; 16], stamp(1)).is_some());
+        assert!(lru.get(&[3; 16], stamp(1)).is_some());
+        // A different file stamp drops the entry
+        assert!(lru.get(&[3; 16], stamp(2)).is_none());
+        assert!(lru.get(&[3; 16], stamp(1)).is_none());
+        // Recent revisions outlive the entries
+        assert_eq!(
+            Some(test_project([2; 16], Vec::new())),
+            lru.revision(&[2; 16], 0)
+        );
+        assert!(lru.revision(&[2; 16], 1).is_none());
+    }
+
+    #[test]
+    fn test_read_and_save_project() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let cache = ProjectCache::default();
+        let created = project::create_project(&mock_app, "Test Project", "Test Description")
+            .expect("Failed to create project");
+        let read = cache.read_project(&mock_app, &created.id).unwrap();
+        assert_eq!(created, read);
+        let mut updated = read.clone();
+        updated.name = "Updated Project".to_string();
+        let saved = cache.save_project(&mock_app, &updated).unwrap();
+        assert_eq!(1, saved.revision);
+        assert_eq!(saved, cache.read_project(&mock_app, &created.id).unwrap());
+        assert_eq!(
+            saved,
+            project::read_project(&mock_app, &created.id).unwrap()
+        );
+        // Saving the stale copy again is a conflict
+        let result = cache.save_project(&mock_app, &updated);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(KanbanErrorKind::ConflictError, err.kind);
+        assert_eq!(Some(&saved), project::conflict_copy(&err));
+    }
+
+    #[test]
+    fn test_external_change_is_not_served_from_cache() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let cache = ProjectCache::default();
+        let created = project::create_project(&mock_app, "Test Project", "Test Description")
+            .expect("Failed to create project");
+        cache.read_project(&mock_app, &created.id).unwrap();
+        let mut external = created.clone();
+        external.name = "Changed Elsewhere".to_string();
+        let external = project::save_project(&mock_app, &external).unwrap();
+        assert_eq!(
+            external,
+            cache.read_project(&mock_app, &created.id).unwrap()
+        );
+        cache.delete_project(&mock_app, &created.id).unwrap();
+        assert!(cache.read_project(&mock_app, &created.id).is_err());
+    }
+
+    #[test]
+    fn test_rewrite_at_same_revision_and_length_is_not_served_from_cache() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let cache = ProjectCache::default();
+        let created = project::create_project(&mock_app, "Test Project", "Test Description")
+            .expect("Failed to create project");
+        cache.read_project(&mock_app, &created.id).unwrap();
+        // Another writer stores the same revision with a name of the same length
+        let project_path = project::project_file_path(&mock_app, &created.id).unwrap();
+        let mut bytes = fs::read(&project_path).unwrap();
+        let name = bytes
+            .windows(created.name.len())
+            .position(|w| w == created.name.as_bytes())
+            .expect("Name not in header");
+        bytes[name..][..created.name.len()].copy_from_slice(b"Test Other!!");
+        fs::write(&project_path, &bytes).unwrap();
+        let read = cache.read_project(&mock_app, &created.id).unwrap();
+        assert_eq!(created.revision, read.revision);
+        assert_eq!("Test Other!!", read.name);
+    }
+
+    #[test]
+    fn test_read_and_save_board() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let cache = ProjectCache::default();
+        let mut project = test_project([1; 16], Vec::new());
+        project.boards = vec![Board {
+            name: "Test Board".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        }];
+        let project = project::save_project(&mock_app, &project).unwrap();
+        cache.read_project(&mock_app, &project.id).unwrap();
+        let board = Board {
+            name: "Renamed Board".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        };
+        let revision = cache
+            .save_board(
+                &mock_app,
+                &project.id,
+                "Test Board",
+                &board,
+                project.revision,
+            )
+            .unwrap();
+        assert_eq!(project.revision + 1, revision);
+        assert_eq!(
+            board,
+            cache
+                .read_board(&mock_app, &project.id, "Renamed Board")
+                .unwrap()
+        );
+        let cached = cache.read_project(&mock_app, &project.id).unwrap();
+        assert_eq!(
+            cached,
+            project::read_project(&mock_app, &project.id).unwrap()
+        );
+    }
+}
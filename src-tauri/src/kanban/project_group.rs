@@ -0,0 +1,451 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{
+    project::{self, AppPathProvider, PathProvider},
+    validation::Validate,
+};
+use std::{collections::HashSet, fs, path::PathBuf, sync::Mutex};
+use uuid::Uuid;
+
+/// Kept in the workspace folder, next to its `configs.json`.
+pub const GROUPS_FILE: &str = "project_groups.json";
+
+// Every change reads, edits and writes the whole file
+static GROUPS_LOCK: Mutex<()> = Mutex::new(());
+
+/// A group or project in the sidebar, in the order they are shown.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    Group(Group),
+    Project { id: [u8; 16] },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Group {
+    pub id: String,
+    pub name: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Layout {
+    entries: Vec<Entry>,
+}
+
+fn group_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::ProjectError, message)
+}
+
+fn not_found() -> KanbanError {
+    group_error("Group not found")
+}
+
+fn groups_path<P: AppPathProvider>(app: &P) -> Result<PathBuf, KanbanError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(GROUPS_FILE))
+}
+
+fn find_group<'a>(entries: &'a mut [Entry], id: &str) -> Option<&'a mut Group> {
+    for entry in entries {
+        if let Entry::Group(group) = entry {
+            if group.id == id {
+                return Some(group);
+            }
+            if let Some(found) = find_group(&mut group.entries, id) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+fn contains_group(entries: &[Entry], id: &str) -> bool {
+    entries.iter().any(|entry| match entry {
+        Entry::Group(group) => group.id == id || contains_group(&group.entries, id),
+        Entry::Project { .. } => false,
+    })
+}
+
+/// The entries of the group, or the top level when given none.
+fn entries_of<'a>(
+    entries: &'a mut Vec<Entry>,
+    group_id: Option<&str>,
+) -> Result<&'a mut Vec<Entry>, KanbanError> {
+    match group_id {
+        Some(id) => Ok(&mut find_group(entries, id).ok_or_else(not_found)?.entries),
+        None => Ok(entries),
+    }
+}
+
+/// Takes the first entry `matches` accepts out of the tree.
+fn take(entries: &mut Vec<Entry>, matches: &dyn Fn(&Entry) -> bool) -> Option<Entry> {
+    if let Some(i) = entries.iter().position(matches) {
+        return Some(entries.remove(i));
+    }
+    entries.iter_mut().find_map(|entry| match entry {
+        Entry::Group(group) => take(&mut group.entries, matches),
+        Entry::Project { .. } => None,
+    })
+}
+
+/// Whether the window shows the entry, projects not there right now are not.
+fn shown(entry: &Entry, known: &HashSet<[u8; 16]>) -> bool {
+    match entry {
+        Entry::Group(_) => true,
+        Entry::Project { id } => known.contains(id),
+    }
+}
+
+/// Inserts the entry at `index` among those shown.
+fn insert(entries: &mut Vec<Entry>, index: usize, entry: Entry, known: &HashSet<[u8; 16]>) {
+    let at = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| shown(entry, known))
+        .nth(index)
+        .map_or(entries.len(), |(i, _)| i);
+    entries.insert(at, entry);
+}
+
+/// Drops repeats of the same project, keeping the first.
+fn dedupe(entries: &mut Vec<Entry>, seen: &mut HashSet<[u8; 16]>) {
+    entries.retain_mut(|entry| match entry {
+        Entry::Group(group) => {
+            dedupe(&mut group.entries, seen);
+            true
+        }
+        Entry::Project { id } => seen.insert(*id),
+    });
+}
+
+/// The entries as the window shows them.
+fn visible(entries: &[Entry], known: &HashSet<[u8; 16]>) -> Vec<Entry> {
+    entries
+        .iter()
+        .filter(|entry| shown(entry, known))
+        .map(|entry| match entry {
+            Entry::Group(group) => Entry::Group(Group {
+                id: group.id.clone(),
+                name: group.name.clone(),
+                entries: visible(&group.entries, known),
+            }),
+            entry => entry.clone(),
+        })
+        .collect()
+}
+
+fn read_stored<P: AppPathProvider>(app: &P) -> Result<Layout, KanbanError> {
+    let path = groups_path(app)?;
+    if !fs::exists(&path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Ok(Layout::default());
+    }
+    let json = fs::read_to_string(&path)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    serde_json::from_str(&json).map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+}
+
+/// The stored layout with the current projects, and the IDs of those. New
+/// ones are added at the end of the top level by name. Ones missing keep
+/// their place, as they may only be archived or not synced yet, and are
+/// dropped when deleted here.
+fn read_layout<P: AppPathProvider>(app: &P) -> Result<(Layout, HashSet<[u8; 16]>), KanbanError> {
+    let mut layout = read_stored(app)?;
+    let mut projects = project::get_all_projects_info(app)?;
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    let known: HashSet<[u8; 16]> = projects.iter().map(|p| p.id).collect();
+    let mut seen = HashSet::new();
+    dedupe(&mut layout.entries, &mut seen);
+    for project in projects {
+        if !seen.contains(&project.id) {
+            layout.entries.push(Entry::Project { id: project.id });
+        }
+    }
+    Ok((layout, known))
+}
+
+fn write_layout<P: AppPathProvider>(app: &P, layout: &Layout) -> Result<(), KanbanError> {
+    let json = serde_json::to_string(layout)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    let path = groups_path(app)?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, json)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    fs::rename(&temp_path, &path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))
+}
+
+/// Applies `change` to the layout and stores it, returning the new one as
+/// shown. `change` is given the IDs of the current projects.
+fn change_layout<P: AppPathProvider>(
+    app: &P,
+    change: impl FnOnce(&mut Vec<Entry>, &HashSet<[u8; 16]>) -> Result<(), KanbanError>,
+) -> Result<Vec<Entry>, KanbanError> {
+    let _guard = GROUPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (mut layout, known) = read_layout(app)?;
+    change(&mut layout.entries, &known)?;
+    write_layout(app, &layout)?;
+    Ok(visible(&layout.entries, &known))
+}
+
+/// Groups and projects of the active workspace in their custom order.
+pub fn read_groups<P: AppPathProvider>(app: &P) -> Result<Vec<Entry>, KanbanError> {
+    let _guard = GROUPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    read_layout(app).map(|(layout, known)| visible(&layout.entries, &known))
+}
+
+/// Drops a deleted project from the layout.
+pub(crate) fn forget_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let _guard = GROUPS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut layout = read_stored(app)?;
+    let found = take(
+        &mut layout.entries,
+        &|entry| matches!(entry, Entry::Project { id } if id.as_slice() == project_id),
+    );
+    if found.is_none() {
+        return Ok(());
+    }
+    write_layout(app, &layout)
+}
+
+fn new_group(name: &str) -> Result<Group, KanbanError> {
+    let group = Group {
+        id: Uuid::new_v4().simple().to_string(),
+        name: name.trim().to_string(),
+        entries: Vec::new(),
+    };
+    group.validate()?;
+    Ok(group)
+}
+
+/// Adds an empty group at the end of `parent_id`, or of the top level.
+pub fn create_group<P: AppPathProvider>(
+    app: &P,
+    name: &str,
+    parent_id: Option<&str>,
+) -> Result<Vec<Entry>, KanbanError> {
+    let group = new_group(name)?;
+    change_layout(app, |entries, _| {
+        entries_of(entries, parent_id)?.push(Entry::Group(group));
+        Ok(())
+    })
+}
+
+pub fn rename_group<P: AppPathProvider>(
+    app: &P,
+    group_id: &str,
+    name: &str,
+) -> Result<Vec<Entry>, KanbanError> {
+    change_layout(app, |entries, _| {
+        let group = find_group(entries, group_id).ok_or_else(not_found)?;
+        group.name = name.trim().to_string();
+        group.validate()
+    })
+}
+
+/// Moves a group with everything in it to `index` in `parent_id`, or in the
+/// top level. A group cannot go inside itself.
+pub fn move_group<P: AppPathProvider>(
+    app: &P,
+    group_id: &str,
+    parent_id: Option<&str>,
+    index: usize,
+) -> Result<Vec<Entry>, KanbanError> {
+    change_layout(app, |entries, known| {
+        let group = take(
+            entries,
+            &|entry| matches!(entry, Entry::Group(group) if group.id == group_id),
+        )
+        .ok_or_else(not_found)?;
+        if let (Some(parent_id), Entry::Group(moved)) = (parent_id, &group) {
+            if parent_id == group_id || contains_group(&moved.entries, parent_id) {
+                return Err(group_error("A group cannot be moved into itself"));
+            }
+        }
+        insert(entries_of(entries, parent_id)?, index, group, known);
+        Ok(())
+    })
+}
+
+/// Deletes a group but not its projects, which take its place along with
+/// any groups inside it.
+pub fn delete_group<P: AppPathProvider>(
+    app: &P,
+    group_id: &str,
+) -> Result<Vec<Entry>, KanbanError> {
+    change_layout(app, |entries, _| {
+        fn replace(entries: &mut Vec<Entry>, group_id: &str) -> bool {
+            let position = entries
+                .iter()
+                .position(|entry| matches!(entry, Entry::Group(group) if group.id == group_id));
+            if let Some(i) = position {
+                if let Entry::Group(group) = entries.remove(i) {
+                    entries.splice(i..i, group.entries);
+                }
+                return true;
+            }
+            entries.iter_mut().any(|entry| match entry {
+                Entry::Group(group) => replace(&mut group.entries, group_id),
+                Entry::Project { .. } => false,
+            })
+        }
+        if !replace(entries, group_id) {
+            return Err(not_found());
+        }
+        Ok(())
+    })
+}
+
+/// Moves a project to `index` in `group_id`, or in the top level. Also
+/// reorders it within the group it is in.
+pub fn move_project<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    group_id: Option<&str>,
+    index: usize,
+) -> Result<Vec<Entry>, KanbanError> {
+    let project_id = <[u8; 16]>::try_from(project_id)
+        .map_err(|_| KanbanError::new(KanbanErrorKind::ProjectError, "Invalid project ID"))?;
+    change_layout(app, |entries, known| {
+        // Checked first so a missing group leaves the project where it was
+        entries_of(entries, group_id)?;
+        let project = take(
+            entries,
+            &|entry| matches!(entry, Entry::Project { id } if *id == project_id),
+        )
+        .ok_or_else(|| KanbanError::new(KanbanErrorKind::ProjectError, "Project not found"))?;
+        insert(entries_of(entries, group_id)?, index, project, known);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::project::Project;
+    use crate::test_support::{mock_projects_app, MockAppPathProvider};
+    use tempdir::TempDir;
+
+    fn create(app: &MockAppPathProvider, name: &str) -> Project {
+        project::create_project(app, name, "Test Description").unwrap()
+    }
+
+    fn group_id(entries: &[Entry], i: usize) -> String {
+        match &entries[i] {
+            Entry::Group(group) => group.id.clone(),
+            entry => panic!("Not a group: {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn test_groups_keep_order() {
+        let dir = TempDir::new("test_groups_keep_order").unwrap();
+        let app = mock_projects_app(dir.path());
+        let b = create(&app, "B");
+        let a = create(&app, "A");
+        // New projects are listed by name
+        assert_eq!(
+            vec![Entry::Project { id: a.id }, Entry::Project { id: b.id }],
+            read_groups(&app).unwrap()
+        );
+        let entries = create_group(&app, "Work", None).unwrap();
+        let work = group_id(&entries, 2);
+        let entries = create_group(&app, "Clients", Some(&work)).unwrap();
+        let Entry::Group(group) = &entries[2] else {
+            panic!("Not a group")
+        };
+        let clients = group_id(&group.entries, 0);
+        move_project(&app, &b.id, Some(&clients), 0).unwrap();
+        move_project(&app, &a.id, Some(&work), 0).unwrap();
+        let c = create(&app, "C");
+        let expected = vec![
+            Entry::Group(Group {
+                id: work.clone(),
+                name: "Work".to_string(),
+                entries: vec![
+                    Entry::Project { id: a.id },
+                    Entry::Group(Group {
+                        id: clients.clone(),
+                        name: "Clients".to_string(),
+                        entries: vec![Entry::Project { id: b.id }],
+                    }),
+                ],
+            }),
+            Entry::Project { id: c.id },
+        ];
+        assert_eq!(expected, read_groups(&app).unwrap());
+        // Reordering within the top level
+        let entries = move_project(&app, &c.id, None, 0).unwrap();
+        assert_eq!(Entry::Project { id: c.id }, entries[0]);
+        // Deleted projects drop out
+        project::delete_project(&app, &c.id).unwrap();
+        assert_eq!(1, read_groups(&app).unwrap().len());
+    }
+
+    #[test]
+    fn test_missing_project_keeps_its_place() {
+        let dir = TempDir::new("test_missing_project_keeps_its_place").unwrap();
+        let app = mock_projects_app(dir.path());
+        let a = create(&app, "A");
+        let b = create(&app, "B");
+        let entries = create_group(&app, "Work", None).unwrap();
+        let work = group_id(&entries, 2);
+        move_project(&app, &a.id, Some(&work), 0).unwrap();
+        // A goes missing for a while, such as before a sync brings it back
+        let path = project::project_file_path(&app, &a.id).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let entries = move_project(&app, &b.id, Some(&work), 0).unwrap();
+        assert_eq!(
+            vec![Entry::Group(Group {
+                id: work.clone(),
+                name: "Work".to_string(),
+                entries: vec![Entry::Project { id: b.id }],
+            })],
+            entries
+        );
+        // Back where it was, ahead of what was moved in after it went missing
+        fs::write(&path, bytes).unwrap();
+        let Entry::Group(group) = &read_groups(&app).unwrap()[0] else {
+            panic!("Not a group")
+        };
+        assert_eq!(
+            vec![Entry::Project { id: a.id }, Entry::Project { id: b.id }],
+            group.entries
+        );
+    }
+
+    #[test]
+    fn test_group_changes() {
+        let dir = TempDir::new("test_group_changes").unwrap();
+        let app = mock_projects_app(dir.path());
+        let a = create(&app, "A");
+        let entries = create_group(&app, "Outer", None).unwrap();
+        let outer = group_id(&entries, 1);
+        let entries = create_group(&app, "Inner", None).unwrap();
+        let inner = group_id(&entries, 2);
+        move_project(&app, &a.id, Some(&inner), 0).unwrap();
+        // Only the outer group is left at the top
+        let entries = move_group(&app, &inner, Some(&outer), 0).unwrap();
+        assert_eq!(1, entries.len());
+        assert!(move_group(&app, &outer, Some(&inner), 0).is_err());
+        assert!(move_group(&app, &outer, Some(&outer), 0).is_err());
+        assert!(create_group(&app, " ", None).is_err());
+        assert!(rename_group(&app, "missing", "Name").is_err());
+        assert!(move_project(&app, &a.id, Some("missing"), 0).is_err());
+        let entries = rename_group(&app, &outer, "Renamed").unwrap();
+        let Entry::Group(group) = &entries[0] else {
+            panic!("Not a group")
+        };
+        assert_eq!("Renamed", group.name);
+        // Deleting a group keeps what was in it, in its place
+        delete_group(&app, &outer).unwrap();
+        let entries = delete_group(&app, &inner).unwrap();
+        assert_eq!(vec![Entry::Project { id: a.id }], entries);
+        assert!(delete_group(&app, &inner).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+/// How two revision vectors relate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Causality {
+    Equal,
+    /// Every change in this vector is in the other one too.
+    Before,
+    /// This vector has every change of the other one and more.
+    After,
+    /// Both have changes the other lacks.
+    Concurrent,
+}
+
+/// Number of versions each instance has made of a project, by instance ID.
+/// A missing instance counts as zero.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct RevisionVector(BTreeMap<String, u64>);
+
+impl RevisionVector {
+    pub fn get(&self, node: &str) -> u64 {
+        self.0.get(node).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|&count| count == 0)
+    }
+
+    /// Counts a new version made by `node`.
+    pub fn bump(&mut self, node: &str) {
+        *self.0.entry(node.to_string()).or_insert(0) += 1;
+    }
+
+    /// Raises the count of `node` to at least `count`.
+    pub fn observe(&mut self, node: &str, count: u64) {
+        let entry = self.0.entry(node.to_string()).or_insert(0);
+        *entry = (*entry).max(count);
+    }
+
+    /// Takes the larger count of each instance, so the result has every
+    /// change of both.
+    pub fn join(&mut self, other: &RevisionVector) {
+        for (node, &count) in &other.0 {
+            let entry = self.0.entry(node.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+
+    pub fn compare(&self, other: &RevisionVector) -> Causality {
+        let nodes = self.0.keys().chain(other.0.keys());
+        let (mut ahead, mut behind) = (false, false);
+        for node in nodes {
+            let (mine, theirs) = (self.get(node), other.get(node));
+            ahead |= mine > theirs;
+            behind |= mine < theirs;
+        }
+        match (ahead, behind) {
+            (false, false) => Causality::Equal,
+            (false, true) => Causality::Before,
+            (true, false) => Causality::After,
+            (true, true) => Causality::Concurrent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vector(counts: &[(&str, u64)]) -> RevisionVector {
+        RevisionVector(
+            counts
+                .iter()
+                .map(|(node, count)| (node.to_string(), *count))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = vector(&[("a", 2), ("b", 1)]);
+        assert_eq!(Causality::Equal, a.compare(&a.clone()));
+        assert_eq!(
+            Causality::Equal,
+            RevisionVector::default().compare(&vector(&[("a", 0)]))
+        );
+        assert_eq!(Causality::After, a.compare(&vector(&[("a", 1), ("b", 1)])));
+        assert_eq!(
+            Causality::Before,
+            a.compare(&vector(&[("a", 2), ("b", 1), ("c", 1)]))
+        );
+        assert_eq!(
+            Causality::Concurrent,
+            a.compare(&vector(&[("a", 1), ("b", 2)]))
+        );
+        assert_eq!(Causality::After, a.compare(&RevisionVector::default()));
+    }
+
+    #[test]
+    fn test_bump_and_join() {
+        let mut a = vector(&[("a", 1)]);
+        let mut b = a.clone();
+        a.bump("a");
+        b.bump("b");
+        assert_eq!(Causality::Concurrent, a.compare(&b));
+        let mut joined = a.clone();
+        joined.join(&b);
+        assert_eq!(vector(&[("a", 2), ("b", 1)]), joined);
+        assert_eq!(Causality::After, joined.compare(&a));
+        assert_eq!(Causality::After, joined.compare(&b));
+        assert!(!joined.is_empty());
+        assert!(RevisionVector::default().is_empty());
+    }
+}
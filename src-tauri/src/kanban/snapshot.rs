@@ -0,0 +1,306 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::file_system::file_stamp;
+use crate::kanban::diff::{self, Change};
+use crate::kanban::project::{self, AppPathProvider, PathProvider, Project};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub(crate) const SNAPSHOT_PATH: &str = "snapshots";
+const HOUR_MILLIS: u64 = 60 * 60 * 1000;
+const DAY_MILLIS: u64 = 24 * HOUR_MILLIS;
+
+/// How many snapshots are kept besides the newest one: the newest of each of
+/// the last `hourly_hours` hours and of each of the last `daily_days` days.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionPolicy {
+    pub hourly_hours: u32,
+    pub daily_days: u32,
+}
+
+// Follows the snapshot configs, no snapshots are taken while unset
+static POLICY: Mutex<Option<RetentionPolicy>> = Mutex::new(None);
+
+pub fn set_policy(policy: Option<RetentionPolicy>) {
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+fn policy() -> Option<RetentionPolicy> {
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct SnapshotInfo {
+    /// Milliseconds since the Unix epoch, also the ID of the snapshot.
+    pub timestamp: u64,
+    pub revision: u64,
+    pub name: String,
+}
+
+fn snapshot_dir<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<PathBuf, KanbanError> {
+    let project_path = project::project_file_path(app, project_id)?;
+    let file_name = project_path.file_name().unwrap_or_default();
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(SNAPSHOT_PATH)
+        .join(file_name))
+}
+
+fn timestamps(dir: &Path) -> Result<Vec<u64>, KanbanError> {
+    if !fs::exists(dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_dir(dir)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.parse::<u64>().ok())
+        .collect())
+}
+
+/// The newest snapshot in each of the `count` buckets of `size` before `now`.
+fn newest_per_bucket(timestamps: &[u64], now: u64, size: u64, count: u32) -> Vec<u64> {
+    let mut newest: BTreeMap<u64, u64> = BTreeMap::new();
+    for &timestamp in timestamps {
+        if now.saturating_sub(timestamp) < size * count as u64 {
+            let slot = newest.entry(timestamp / size).or_insert(timestamp);
+            *slot = (*slot).max(timestamp);
+        }
+    }
+    newest.into_values().collect()
+}
+
+/// Snapshots to keep under `policy`, the newest one always among them.
+fn retained(timestamps: &[u64], now: u64, policy: &RetentionPolicy) -> BTreeSet<u64> {
+    let mut keep = BTreeSet::new();
+    keep.extend(timestamps.iter().max());
+    keep.extend(newest_per_bucket(
+        timestamps,
+        now,
+        HOUR_MILLIS,
+        policy.hourly_hours,
+    ));
+    keep.extend(newest_per_bucket(
+        timestamps,
+        now,
+        DAY_MILLIS,
+        policy.daily_days,
+    ));
+    keep
+}
+
+/// Copies the stored project into its snapshots and drops the ones the
+/// retention policy no longer keeps. Does nothing while snapshots are off.
+pub(crate) fn take_snapshot<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let Some(policy) = policy() else {
+        return Ok(());
+    };
+    let dir = snapshot_dir(app, project_id)?;
+    fs::create_dir_all(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let now = file_stamp::now_millis();
+    // Copied as stored, so compressed and encrypted projects stay that way
+    fs::copy(
+        project::project_file_path(app, project_id)?,
+        dir.join(now.to_string()),
+    )
+    .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let all = timestamps(&dir)?;
+    let keep = retained(&all, now, &policy);
+    for timestamp in all.iter().filter(|t| !keep.contains(t)) {
+        fs::remove_file(dir.join(timestamp.to_string()))
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    }
+    Ok(())
+}
+
+/// Snapshots of the project, newest first.
+pub fn list_snapshots<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<Vec<SnapshotInfo>, KanbanError> {
+    let dir = snapshot_dir(app, project_id)?;
+    let mut all = timestamps(&dir)?;
+    all.sort_unstable_by(|a, b| b.cmp(a));
+    all.into_iter()
+        .map(|timestamp| {
+            let info = project::read_project_info_file(&dir.join(timestamp.to_string()))?;
+            Ok(SnapshotInfo {
+                timestamp,
+                revision: info.revision,
+                name: info.name,
+            })
+        })
+        .collect()
+}
+
+/// Decodes a snapshot like a stored project.
+pub fn preview_snapshot<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    timestamp: u64,
+) -> Result<Project, KanbanError> {
+    let path = snapshot_dir(app, project_id)?.join(timestamp.to_string());
+    if !fs::exists(&path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Snapshot does not exist",
+        ));
+    }
+    project::read_project_file(&path)
+}
+
+/// What changed from the snapshot to the stored project.
+pub fn diff_snapshot<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    timestamp: u64,
+) -> Result<Vec<Change>, KanbanError> {
+    let old = preview_snapshot(app, project_id, timestamp)?;
+    let current = project::read_project(app, project_id)?;
+    Ok(diff::diff_projects(&old, &current))
+}
+
+/// Saves the snapshot as the next revision of the project, on top of
+/// `revision` like any other save.
+pub fn restore_snapshot<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    timestamp: u64,
+    revision: u64,
+) -> Result<Project, KanbanError> {
+    let mut project = preview_snapshot(app, project_id, timestamp)?;
+    project.revision = revision;
+    project::save_project(app, &project)
+}
+
+pub(crate) fn delete_snapshots<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let dir = snapshot_dir(app, project_id)?;
+    if fs::exists(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        fs::remove_dir_all(&dir)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::board::Board;
+    use crate::test_support::mock_app;
+    use serial_test::serial;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_retained() {
+        let policy = RetentionPolicy {
+            hourly_hours: 24,
+            daily_days: 30,
+        };
+        // Half past noon, so no pair below straddles an hour
+        let now = 100 * DAY_MILLIS + 12 * HOUR_MILLIS + HOUR_MILLIS / 2;
+        let timestamps = [
+            // Two in the current hour, only the newest is kept
+            now - 10,
+            now - 20,
+            // Two in an earlier hour of today
+            now - 2 * HOUR_MILLIS,
+            now - 2 * HOUR_MILLIS - 10,
+            // Two on a day within the month, and one past it
+            now - 5 * DAY_MILLIS,
+            now - 5 * DAY_MILLIS - 10,
+            now - 40 * DAY_MILLIS,
+        ];
+        let keep = retained(&timestamps, now, &policy);
+        assert_eq!(
+            BTreeSet::from([now - 10, now - 2 * HOUR_MILLIS, now - 5 * DAY_MILLIS]),
+            keep
+        );
+        // The newest is kept even when the policy keeps nothing
+        let none = RetentionPolicy {
+            hourly_hours: 0,
+            daily_days: 0,
+        };
+        assert_eq!(
+            BTreeSet::from([now - 40 * DAY_MILLIS]),
+            retained(&[now - 40 * DAY_MILLIS], now, &none)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_snapshots() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        let mut project =
+            project::create_project(&mock_app, "Test Project", "Test Description").unwrap();
+        set_policy(Some(RetentionPolicy {
+            hourly_hours: 24,
+            daily_days: 30,
+        }));
+        project.boards.push(Board {
+            name: "Board 1".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        });
+        let first = project::save_project(&mock_app, &project).unwrap();
+        // Age the snapshot by two hours, so the next one does not replace it
+        let snapshots = list_snapshots(&mock_app, &project.id).unwrap();
+        assert_eq!(1, snapshots.len());
+        let old_timestamp = snapshots[0].timestamp - 2 * HOUR_MILLIS;
+        let snapshot_dir = snapshot_dir(&mock_app, &project.id).unwrap();
+        fs::rename(
+            snapshot_dir.join(snapshots[0].timestamp.to_string()),
+            snapshot_dir.join(old_timestamp.to_string()),
+        )
+        .expect("Failed to rename snapshot");
+        let mut renamed = first.clone();
+        renamed.name = "Renamed Project".to_string();
+        let second = project::save_project(&mock_app, &renamed).unwrap();
+        let third = project::save_project(&mock_app, &second).unwrap();
+        set_policy(None);
+        // The second save was replaced by the third, in the same hour
+        let snapshots = list_snapshots(&mock_app, &project.id).unwrap();
+        assert_eq!(
+            vec![(3, "Renamed Project"), (1, "Test Project")],
+            snapshots
+                .iter()
+                .map(|s| (s.revision, s.name.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            first,
+            preview_snapshot(&mock_app, &project.id, old_timestamp).unwrap()
+        );
+        assert_eq!(
+            vec![Change::ProjectRenamed {
+                from: "Test Project".to_string(),
+                to: "Renamed Project".to_string(),
+            }],
+            diff_snapshot(&mock_app, &project.id, old_timestamp).unwrap()
+        );
+        let result = preview_snapshot(&mock_app, &project.id, 1);
+        assert_eq!("Snapshot does not exist", result.unwrap_err().message);
+        // Restoring is a save, checked against the current revision
+        let result = restore_snapshot(&mock_app, &project.id, old_timestamp, second.revision);
+        assert_eq!(KanbanErrorKind::ConflictError, result.unwrap_err().kind);
+        let restored =
+            restore_snapshot(&mock_app, &project.id, old_timestamp, third.revision).unwrap();
+        assert_eq!(third.revision + 1, restored.revision);
+        assert_eq!(first.name, restored.name);
+        assert_eq!(first.boards, restored.boards);
+        // Deleting the project deletes its snapshots
+        project::delete_project(&mock_app, &project.id).unwrap();
+        assert!(list_snapshots(&mock_app, &project.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,383 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::file_system::file_stamp;
+use crate::kanban::{
+    history,
+    merge::{self, Merge, Side},
+    project::{self, AppPathProvider, PathProvider, Project},
+    snapshot,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+pub(crate) const BASE_PATH: &str = "sync-bases";
+// Revisions kept per project as merge bases
+const KEPT_BASES: usize = 16;
+
+// Follows the sync folder configs, conflict copies are not reported while unset
+static SYNC_FOLDER: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    SYNC_FOLDER.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    SYNC_FOLDER.load(Ordering::Relaxed)
+}
+
+/// A second version of a project a sync tool kept next to it, instead of
+/// picking one of two edits made on different devices.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct ConflictCopy {
+    pub project_id: [u8; 16],
+    pub file_name: String,
+    /// Milliseconds since the Unix epoch the copy was last written.
+    pub modified: u64,
+    pub revision: u64,
+    pub name: String,
+}
+
+fn parse_project_id(name: &str) -> Option<[u8; 16]> {
+    let mut id = [0u8; 16];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(name.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(id)
+}
+
+/// The project a conflict copy belongs to, going by the names Syncthing
+/// (`<id>.sync-conflict-<date>-<time>-<device>`), Nextcloud
+/// (`<id> (conflicted copy <date> <time>)`) and Dropbox
+/// (`<id> (<device>'s conflicted copy <date>)`) give them. Names with a path
+/// separator are never copies, so a name cannot reach outside the directory.
+pub(crate) fn conflict_copy_of(file_name: &str) -> Option<[u8; 16]> {
+    // Both separators, as Windows takes either
+    if file_name.contains(['/', '\\'])
+        || Path::new(file_name).file_name() != Some(file_name.as_ref())
+    {
+        return None;
+    }
+    let rest = file_name.get(32..)?;
+    let is_copy = rest.starts_with(".sync-conflict-")
+        || (rest.starts_with(" (") && rest.ends_with(')') && rest.contains("conflicted copy"));
+    if !is_copy {
+        return None;
+    }
+    parse_project_id(&file_name[..32])
+}
+
+fn projects_dir<P: AppPathProvider>(app: &P) -> Result<PathBuf, KanbanError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(project::PROJECT_PATH))
+}
+
+/// Reads the header of the conflict copy at `path`, if it is one.
+pub(crate) fn conflict_copy_at(path: &Path) -> Option<ConflictCopy> {
+    let file_name = path.file_name()?.to_str()?;
+    let project_id = conflict_copy_of(file_name)?;
+    let info = project::read_project_info_file(path).ok()?;
+    Some(ConflictCopy {
+        project_id,
+        file_name: file_name.to_string(),
+        modified: file_stamp::modified_millis(path).ok()?,
+        revision: info.revision,
+        name: info.name,
+    })
+}
+
+/// Every conflict copy in the projects directory, oldest first. Copies that
+/// are still being written or are not project files are left out.
+pub fn list_conflict_copies<P: AppPathProvider>(app: &P) -> Result<Vec<ConflictCopy>, KanbanError> {
+    let dir = projects_dir(app)?;
+    if !fs::exists(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Ok(Vec::new());
+    }
+    let mut copies = fs::read_dir(&dir)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| conflict_copy_at(&entry.path()))
+        .collect::<Vec<ConflictCopy>>();
+    copies.sort_by_key(|copy| copy.modified);
+    Ok(copies)
+}
+
+fn base_dir<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<PathBuf, KanbanError> {
+    let project_path = project::project_file_path(app, project_id)?;
+    let file_name = project_path.file_name().unwrap_or_default();
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(BASE_PATH)
+        .join(file_name))
+}
+
+/// Revisions kept in `dir`, newest first.
+fn kept_revisions(dir: &Path) -> Result<Vec<u64>, KanbanError> {
+    let mut revisions = fs::read_dir(dir)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.parse::<u64>().ok())
+        .collect::<Vec<u64>>();
+    revisions.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(revisions)
+}
+
+/// Keeps the stored project under its revision, as a common ancestor for
+/// conflict copies made from it later. Snapshots thin out too quickly for
+/// that. Does nothing outside sync folder mode.
+pub(crate) fn keep_base<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<(), KanbanError> {
+    if !enabled() {
+        return Ok(());
+    }
+    let project_path = project::project_file_path(app, project_id)?;
+    let revision = project::read_project_info_file(&project_path)?.revision;
+    let dir = base_dir(app, project_id)?;
+    fs::create_dir_all(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    fs::copy(&project_path, dir.join(revision.to_string()))
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    for old in kept_revisions(&dir)?.iter().skip(KEPT_BASES) {
+        fs::remove_file(dir.join(old.to_string()))
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_bases<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    let dir = base_dir(app, project_id)?;
+    if !fs::exists(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Ok(());
+    }
+    fs::remove_dir_all(&dir).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))
+}
+
+fn copy_path<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    file_name: &str,
+) -> Result<PathBuf, KanbanError> {
+    // Also keeps the name from reaching outside the projects directory
+    if conflict_copy_of(file_name).is_none_or(|id| id != project_id) {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Not a conflict copy of this project",
+        ));
+    }
+    let path = projects_dir(app)?.join(file_name);
+    if !fs::exists(&path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))? {
+        return Err(KanbanError::new(
+            KanbanErrorKind::IoError,
+            "Conflict copy does not exist",
+        ));
+    }
+    Ok(path)
+}
+
+/// Decodes a conflict copy the same way as the project it belongs to.
+pub fn read_conflict_copy<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    file_name: &str,
+) -> Result<Project, KanbanError> {
+    let path = copy_path(app, project_id, file_name)?;
+    project::read_project_file(&path)
+}
+
+/// Where an older version of a project is kept.
+enum BaseSource {
+    Kept(PathBuf),
+    Snapshot(u64),
+    Commit(String),
+}
+
+/// The newest kept version older than both sides, or an empty project. The
+/// kept bases, snapshots and history are each listed once.
+fn common_base<P: AppPathProvider>(app: &P, stored: &Project, copy: &Project) -> Project {
+    let oldest = stored.revision.min(copy.revision);
+    let id = stored.id.as_slice();
+    let mut sources: Vec<(u64, BaseSource)> = Vec::new();
+    if let Ok(dir) = base_dir(app, id) {
+        for revision in kept_revisions(&dir).unwrap_or_default() {
+            sources.push((revision, BaseSource::Kept(dir.join(revision.to_string()))));
+        }
+    }
+    for found in snapshot::list_snapshots(app, id).unwrap_or_default() {
+        sources.push((found.revision, BaseSource::Snapshot(found.timestamp)));
+    }
+    // History is optional, so a failure only means no version was found there
+    for entry in history::list_history(app, id).unwrap_or_default() {
+        if let Some(revision) = entry.revision {
+            sources.push((revision, BaseSource::Commit(entry.commit)));
+        }
+    }
+    sources.retain(|(revision, _)| *revision < oldest);
+    // Stable, so each revision is still looked up in the order listed above
+    sources.sort_by_key(|(revision, _)| std::cmp::Reverse(*revision));
+    sources
+        .into_iter()
+        .find_map(|(_, source)| match source {
+            BaseSource::Kept(path) => project::read_project_file(&path).ok(),
+            BaseSource::Snapshot(timestamp) => snapshot::preview_snapshot(app, id, timestamp).ok(),
+            BaseSource::Commit(commit) => history::project_at(app, id, &commit).ok(),
+        })
+        .unwrap_or_else(|| merge::empty_base(stored.id))
+}
+
+/// Merges a conflict copy into the stored project, which is our side. With
+/// `choices` the conflicts are resolved in order. Nothing is saved.
+pub fn merge_conflict_copy<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    file_name: &str,
+    choices: Option<&[Side]>,
+) -> Result<Merge, KanbanError> {
+    let copy = read_conflict_copy(app, project_id, file_name)?;
+    let stored = project::read_project(app, project_id)?;
+    let base = common_base(app, &stored, &copy);
+    let mut merged = match choices {
+        Some(choices) => merge::resolve_merge(&base, &stored, &copy, choices)?,
+        None => merge::merge_projects(&base, &stored, &copy),
+    };
+    // Saved on top of the stored project
+    merged.project.revision = stored.revision;
+    Ok(merged)
+}
+
+/// Saves `resolved` over the stored project and removes the conflict copy.
+/// `resolved` must be based on the stored revision, like any save.
+pub fn resolve_conflict_copy<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    file_name: &str,
+    resolved: &Project,
+) -> Result<Project, KanbanError> {
+    let path = copy_path(app, project_id, file_name)?;
+    if resolved.id.as_slice() != project_id {
+        return Err(KanbanError::new(
+            KanbanErrorKind::ProjectError,
+            "Resolved project does not match the conflict copy",
+        ));
+    }
+    let saved = project::save_project(app, resolved)?;
+    discard(&path)?;
+    Ok(saved)
+}
+
+/// Removes a conflict copy, keeping the stored project as it is.
+pub fn discard_conflict_copy<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    file_name: &str,
+) -> Result<(), KanbanError> {
+    let path = copy_path(app, project_id, file_name)?;
+    discard(&path)
+}
+
+fn discard(path: &Path) -> Result<(), KanbanError> {
+    fs::remove_file(path).map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::board::Board;
+    use crate::test_support::mock_app;
+    use serial_test::serial;
+    use tempdir::TempDir;
+
+    const ID: &str = "000102030405060708090A0B0C0D0E0F";
+
+    #[test]
+    fn test_conflict_copy_of() {
+        let id: [u8; 16] = std::array::from_fn(|i| i as u8);
+        let copies = [
+            format!("{}.sync-conflict-20240101-120000-ABCDEFG", ID),
+            format!("{} (conflicted copy 2024-01-01 120000)", ID),
+            format!("{} (conflicted copy alice 2024-01-01 120000)", ID),
+            format!("{} (Laptop's conflicted copy 2024-01-01)", ID),
+        ];
+        for copy in copies {
+            assert_eq!(Some(id), conflict_copy_of(&copy), "{}", copy);
+        }
+        let others = [
+            ID.to_string(),
+            format!("{}.tmp", ID),
+            format!("{} (copy)", ID),
+            "../../configs.json.sync-conflict-20240101-120000-ABCDEFG".to_string(),
+            "not-a-project.sync-conflict-20240101-120000-ABCDEFG".to_string(),
+            format!("{}.sync-conflict-/../../configs.json", ID),
+            format!("{} (conflicted copy\\..\\..\\configs.json)", ID),
+            format!("{} (conflicted copy/../../configs.json)", ID),
+            format!("{}.sync-conflict-20240101-120000-ABCDEFG/..", ID),
+        ];
+        for other in others {
+            assert_eq!(None, conflict_copy_of(&other), "{}", other);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_merge_conflict_copy() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let mock_app = mock_app(dir.path());
+        set_enabled(true);
+        let mut created = project::create_project(&mock_app, "Test Project", "Test Description")
+            .expect("Failed to create project");
+        created.boards.push(Board {
+            name: "Board 1".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        });
+        let base = project::save_project(&mock_app, &created).unwrap();
+        let project_path = project::project_file_path(&mock_app, &base.id).unwrap();
+        let base_bytes = fs::read(&project_path).unwrap();
+        let mut ours = base.clone();
+        ours.name = "Our Project".to_string();
+        let ours = project::save_project(&mock_app, &ours).unwrap();
+        let ours_bytes = fs::read(&project_path).unwrap();
+        // Another device saved its own edit on top of the same revision
+        fs::write(&project_path, base_bytes).unwrap();
+        let mut theirs = base.clone();
+        theirs.boards.push(Board {
+            name: "Board 2".to_string(),
+            lists: Vec::new(),
+            archived: false,
+        });
+        let theirs = project::save_project(&mock_app, &theirs).unwrap();
+        let file_name = format!(
+            "{}.sync-conflict-20240101-120000-ABCDEFG",
+            project_path.file_name().unwrap().to_str().unwrap()
+        );
+        fs::rename(&project_path, project_path.with_file_name(&file_name)).unwrap();
+        fs::write(&project_path, ours_bytes).unwrap();
+
+        let copies = list_conflict_copies(&mock_app).unwrap();
+        assert_eq!(1, copies.len());
+        assert_eq!(file_name, copies[0].file_name);
+        assert_eq!(theirs.revision, copies[0].revision);
+        let merged = merge_conflict_copy(&mock_app, &base.id, &file_name, None).unwrap();
+        assert!(merged.conflicts.is_empty());
+        assert_eq!("Our Project", merged.project.name);
+        assert_eq!(2, merged.project.boards.len());
+        assert_eq!(ours.revision, merged.project.revision);
+
+        let saved =
+            resolve_conflict_copy(&mock_app, &base.id, &file_name, &merged.project).unwrap();
+        assert_eq!(ours.revision + 1, saved.revision);
+        assert!(list_conflict_copies(&mock_app).unwrap().is_empty());
+        let result = discard_conflict_copy(&mock_app, &base.id, &file_name);
+        assert!(result.is_err());
+        let result = read_conflict_copy(&mock_app, &base.id, "../configs.json");
+        assert!(result.is_err());
+        set_enabled(false);
+    }
+}
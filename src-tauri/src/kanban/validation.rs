@@ -0,0 +1,343 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::kanban::{
+    board::Board, board_list::BoardList, project::Project, project_group::Group,
+    workspace::Workspace,
+};
+use std::{collections::HashSet, fmt};
+
+// Names and titles are stored with a single length byte
+pub const NAME_MAX_BYTES: usize = 255;
+// Only checked on new texts, projects may hold longer ones from before
+pub const TEXT_MAX_CHARS: usize = 10_000;
+
+/// A problem with one field, `field` is a path such as `boards[0].lists[1].title`.
+#[derive(Debug, serde::Serialize, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Source of a `ValidationError`, holding every invalid field.
+#[derive(Debug)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect::<Vec<String>>();
+        write!(f, "{}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Field errors of a failed validation, if `err` is one.
+pub fn field_errors(err: &KanbanError) -> Option<&[FieldError]> {
+    err.source
+        .as_ref()?
+        .downcast_ref::<ValidationErrors>()
+        .map(|e| e.errors.as_slice())
+}
+
+pub trait Validate {
+    /// Adds an error for every invalid field, with field paths under `path`.
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>);
+
+    fn validate(&self) -> Result<(), KanbanError> {
+        self.validate_at("")
+    }
+
+    /// Validates a value that sits at `path` inside a project.
+    fn validate_at(&self, path: &str) -> Result<(), KanbanError> {
+        let mut errors = Vec::new();
+        self.check(path, &mut errors);
+        into_result(errors)
+    }
+}
+
+fn into_result(errors: Vec<FieldError>) -> Result<(), KanbanError> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(KanbanError::from_source(
+        KanbanErrorKind::ValidationError,
+        ValidationErrors { errors },
+    ))
+}
+
+/// Validates a project about to replace `stored`. Texts over the length limit
+/// are only rejected when `stored` does not hold them already, so projects
+/// written before the limit can still be saved.
+pub fn validate_update(project: &Project, stored: Option<&Project>) -> Result<(), KanbanError> {
+    let known: HashSet<&str> = stored
+        .map(|stored| {
+            let items = stored.boards.iter().flat_map(board_texts);
+            items.chain([stored.description.as_str()]).collect()
+        })
+        .unwrap_or_default();
+    let mut errors = Vec::new();
+    check_project(project, "", &|text| !known.contains(text), &mut errors);
+    into_result(errors)
+}
+
+/// Like `validate_update`, for a board at `path` replacing `stored`.
+pub fn validate_board_update(
+    board: &Board,
+    path: &str,
+    stored: Option<&Board>,
+) -> Result<(), KanbanError> {
+    let known: HashSet<&str> = stored.map(|b| board_texts(b).collect()).unwrap_or_default();
+    let mut errors = Vec::new();
+    check_board(board, path, &|text| !known.contains(text), &mut errors);
+    into_result(errors)
+}
+
+fn board_texts(board: &Board) -> impl Iterator<Item = &str> {
+    board
+        .lists
+        .iter()
+        .flat_map(|list| list.items.iter().map(String::as_str))
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn push(errors: &mut Vec<FieldError>, field: String, message: String) {
+    errors.push(FieldError { field, message });
+}
+
+/// Names and titles: not blank, single line, and short enough in UTF-8 bytes.
+fn check_name(value: &str, field: String, errors: &mut Vec<FieldError>) {
+    if value.trim().is_empty() {
+        push(errors, field, "Must not be empty".to_string());
+    } else if value.chars().any(char::is_control) {
+        push(
+            errors,
+            field,
+            "Must not contain control characters".to_string(),
+        );
+    } else if value.len() > NAME_MAX_BYTES {
+        push(
+            errors,
+            field,
+            format!("Must be at most {} bytes", NAME_MAX_BYTES),
+        );
+    }
+}
+
+/// Long texts, limited when `is_new` says the text is not already stored.
+fn check_text(
+    value: &str,
+    field: String,
+    is_new: &dyn Fn(&str) -> bool,
+    errors: &mut Vec<FieldError>,
+) {
+    if value.chars().count() > TEXT_MAX_CHARS && is_new(value) {
+        push(
+            errors,
+            field,
+            format!("Must be at most {} characters", TEXT_MAX_CHARS),
+        );
+    }
+}
+
+fn check_project(
+    project: &Project,
+    path: &str,
+    is_new: &dyn Fn(&str) -> bool,
+    errors: &mut Vec<FieldError>,
+) {
+    check_name(&project.name, field_path(path, "name"), errors);
+    let description = field_path(path, "description");
+    if project.description.trim().is_empty() {
+        push(errors, description, "Must not be empty".to_string());
+    } else {
+        check_text(&project.description, description, is_new, errors);
+    }
+    for (i, board) in project.boards.iter().enumerate() {
+        let board_path = field_path(path, &format!("boards[{}]", i));
+        check_board(board, &board_path, is_new, errors);
+    }
+}
+
+fn check_board(
+    board: &Board,
+    path: &str,
+    is_new: &dyn Fn(&str) -> bool,
+    errors: &mut Vec<FieldError>,
+) {
+    check_name(&board.name, field_path(path, "name"), errors);
+    for (i, list) in board.lists.iter().enumerate() {
+        check_list(
+            list,
+            &field_path(path, &format!("lists[{}]", i)),
+            is_new,
+            errors,
+        );
+    }
+}
+
+fn check_list(
+    list: &BoardList,
+    path: &str,
+    is_new: &dyn Fn(&str) -> bool,
+    errors: &mut Vec<FieldError>,
+) {
+    check_name(&list.title, field_path(path, "title"), errors);
+    for (i, item) in list.items.iter().enumerate() {
+        let item_path = field_path(path, &format!("items[{}]", i));
+        check_text(item, item_path, is_new, errors);
+    }
+}
+
+impl Validate for Project {
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>) {
+        check_project(self, path, &|_| true, errors);
+    }
+}
+
+impl Validate for Board {
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>) {
+        check_board(self, path, &|_| true, errors);
+    }
+}
+
+impl Validate for BoardList {
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>) {
+        check_list(self, path, &|_| true, errors);
+    }
+}
+
+impl Validate for Group {
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>) {
+        check_name(&self.name, field_path(path, "name"), errors);
+    }
+}
+
+impl Validate for Workspace {
+    fn check(&self, path: &str, errors: &mut Vec<FieldError>) {
+        check_name(&self.name, field_path(path, "name"), errors);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_support::{test_board, test_list, test_project};
+
+    #[test]
+    fn test_check_name() {
+        let mut errors = Vec::new();
+        check_name("Test", "name".to_string(), &mut errors);
+        // 255 characters of 1 byte, and 85 characters of 3 bytes, both fit
+        check_name(&"X".repeat(255), "name".to_string(), &mut errors);
+        check_name(&"漢".repeat(85), "name".to_string(), &mut errors);
+        assert!(errors.is_empty());
+        check_name("  ", "a".to_string(), &mut errors);
+        check_name("Line\nBreak", "b".to_string(), &mut errors);
+        check_name(&"X".repeat(256), "c".to_string(), &mut errors);
+        check_name(&"漢".repeat(86), "d".to_string(), &mut errors);
+        assert_eq!(
+            vec![
+                ("a", "Must not be empty"),
+                ("b", "Must not contain control characters"),
+                ("c", "Must be at most 255 bytes"),
+                ("d", "Must be at most 255 bytes"),
+            ],
+            errors
+                .iter()
+                .map(|e| (e.field.as_str(), e.message.as_str()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_validate_project() {
+        let board = test_board(
+            "Test Board",
+            vec![
+                test_list("To Do", &["Item 1"]),
+                test_list("Done", &["Item 1"]),
+            ],
+        );
+        assert!(test_project([0x01; 16], vec![board.clone()])
+            .validate()
+            .is_ok());
+        let mut invalid = board.clone();
+        invalid.name = "Board\t1".to_string();
+        invalid.lists[1].title = "é".repeat(128);
+        invalid.lists[0].items.push("X".repeat(TEXT_MAX_CHARS + 1));
+        let mut project = test_project([0x01; 16], vec![board, invalid]);
+        project.description = String::new();
+        let err = project.validate().unwrap_err();
+        assert_eq!(KanbanErrorKind::ValidationError, err.kind);
+        let fields = field_errors(&err)
+            .expect("Missing field errors")
+            .iter()
+            .map(|e| e.field.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "description",
+                "boards[1].name",
+                "boards[1].lists[0].items[1]",
+                "boards[1].lists[1].title",
+            ],
+            fields
+        );
+        assert!(err.message.starts_with(
+            "description: Must not be empty; boards[1].name: Must not contain control characters"
+        ));
+    }
+
+    #[test]
+    fn test_validate_update() {
+        let long = "X".repeat(TEXT_MAX_CHARS + 1);
+        let mut stored = test_project([0x01; 16], vec![test_board("Test Board", Vec::new())]);
+        stored.boards[0].lists.push(test_list("To Do", &["Item 1"]));
+        stored.boards[0].lists[0].items.push(long.clone());
+        // Stored before the limit, the long item can be kept and moved
+        let mut project = stored.clone();
+        project.boards[0].lists.push(test_list("Done", &["Item 1"]));
+        let moved = project.boards[0].lists[0].items.pop().unwrap();
+        project.boards[0].lists[1].items.push(moved);
+        assert!(validate_update(&project, Some(&stored)).is_ok());
+        assert!(
+            validate_board_update(&project.boards[0], "boards[0]", Some(&stored.boards[0])).is_ok()
+        );
+        // But not edited, nor saved as new
+        project.boards[0].lists[1].items[1].push('X');
+        for err in [
+            validate_update(&project, Some(&stored)).unwrap_err(),
+            validate_board_update(&project.boards[0], "boards[0]", Some(&stored.boards[0]))
+                .unwrap_err(),
+            validate_update(&stored, None).unwrap_err(),
+        ] {
+            assert_eq!(KanbanErrorKind::ValidationError, err.kind);
+        }
+        assert_eq!(
+            "boards[0].lists[1].items[1]: Must be at most 10000 characters",
+            validate_update(&project, Some(&stored))
+                .unwrap_err()
+                .message
+        );
+    }
+
+    #[test]
+    fn test_validate_at() {
+        let board = test_board("", Vec::new());
+        let err = board.validate_at("boards[3]").unwrap_err();
+        assert_eq!("boards[3].name: Must not be empty", err.message);
+        assert!(field_errors(&KanbanError::new(KanbanErrorKind::ProjectError, "")).is_none());
+    }
+}
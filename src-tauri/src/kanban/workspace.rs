@@ -0,0 +1,318 @@
+use crate::errors::error_log;
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::file_system::corrupt_file;
+use crate::kanban::{project, validation::Validate};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use uuid::Uuid;
+
+/// Folder in the data directory holding every workspace but the default one.
+pub const WORKSPACES_PATH: &str = "workspaces";
+/// The workspace that lives in the data directory itself, as all data did
+/// before there were workspaces.
+pub const DEFAULT_WORKSPACE: &str = "default";
+const INDEX_FILE: &str = "workspaces.json";
+
+// ID of the active workspace, the default one until set
+static ACTIVE: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    /// Reopened when the workspace is, none if no project was left open.
+    #[serde(default)]
+    pub last_project: Option<[u8; 16]>,
+}
+
+/// Kept in `workspaces.json` in the data directory.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Workspaces {
+    pub active: String,
+    pub workspaces: Vec<Workspace>,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        Workspaces {
+            active: DEFAULT_WORKSPACE.to_string(),
+            workspaces: vec![Workspace {
+                id: DEFAULT_WORKSPACE.to_string(),
+                name: "Default".to_string(),
+                last_project: None,
+            }],
+        }
+    }
+}
+
+impl Workspaces {
+    fn get_mut(&mut self, id: &str) -> Result<&mut Workspace, KanbanError> {
+        self.workspaces
+            .iter_mut()
+            .find(|workspace| workspace.id == id)
+            .ok_or_else(|| workspace_error("Workspace not found"))
+    }
+
+    fn check_name(&self, id: Option<&str>, name: &str) -> Result<(), KanbanError> {
+        let workspace = Workspace {
+            id: String::new(),
+            name: name.to_string(),
+            last_project: None,
+        };
+        workspace.validate()?;
+        let taken = self.workspaces.iter().any(|workspace| {
+            Some(workspace.id.as_str()) != id && workspace.name.eq_ignore_ascii_case(name.trim())
+        });
+        if taken {
+            return Err(workspace_error(format!(
+                "A workspace named {} already exists",
+                name.trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+fn workspace_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::ProjectError, message)
+}
+
+pub fn active() -> String {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+pub fn set_active(id: &str) {
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = Some(id.to_string());
+}
+
+/// Folder of a workspace's projects, configs and everything else.
+pub fn workspace_dir(data_dir: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_WORKSPACE {
+        data_dir.to_path_buf()
+    } else {
+        data_dir.join(WORKSPACES_PATH).join(id)
+    }
+}
+
+/// Folder of the active workspace, what every path is resolved against.
+pub fn active_dir(data_dir: &Path) -> PathBuf {
+    workspace_dir(data_dir, &active())
+}
+
+/// The workspaces in `data_dir`, just the default one when there is no index
+/// yet or it is unreadable. An active workspace that has gone missing falls
+/// back to the default.
+pub fn read_workspaces(data_dir: &Path) -> Result<Workspaces, KanbanError> {
+    let path = data_dir.join(INDEX_FILE);
+    if !fs::exists(&path).map_err(io_error)? {
+        return Ok(Workspaces::default());
+    }
+    let json = fs::read_to_string(&path).map_err(io_error)?;
+    let mut workspaces: Workspaces = match serde_json::from_str(&json) {
+        Ok(workspaces) => workspaces,
+        Err(err) => {
+            // The other workspaces stay on disk for when the index is repaired
+            let corrupt_path = corrupt_file::set_aside(&path).map_err(io_error)?;
+            error_log::report(
+                &format!(
+                    "Workspaces could not be read, moved to {}",
+                    corrupt_path.display()
+                ),
+                err,
+            );
+            return Ok(Workspaces::default());
+        }
+    };
+    if !workspaces
+        .workspaces
+        .iter()
+        .any(|workspace| workspace.id == DEFAULT_WORKSPACE)
+    {
+        workspaces
+            .workspaces
+            .insert(0, Workspaces::default().workspaces.remove(0));
+    }
+    if !workspaces
+        .workspaces
+        .iter()
+        .any(|workspace| workspace.id == workspaces.active)
+    {
+        workspaces.active = DEFAULT_WORKSPACE.to_string();
+    }
+    Ok(workspaces)
+}
+
+fn write_workspaces(data_dir: &Path, workspaces: &Workspaces) -> Result<(), KanbanError> {
+    fs::create_dir_all(data_dir).map_err(io_error)?;
+    let json = serde_json::to_string(workspaces)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    let path = data_dir.join(INDEX_FILE);
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, json).map_err(io_error)?;
+    fs::rename(&temp_path, &path).map_err(io_error)
+}
+
+/// Adds an empty workspace, it still has to be switched to.
+pub fn create_workspace(data_dir: &Path, name: &str) -> Result<Workspace, KanbanError> {
+    let mut workspaces = read_workspaces(data_dir)?;
+    workspaces.check_name(None, name)?;
+    let workspace = Workspace {
+        id: Uuid::new_v4().simple().to_string(),
+        name: name.trim().to_string(),
+        last_project: None,
+    };
+    fs::create_dir_all(workspace_dir(data_dir, &workspace.id).join(project::PROJECT_PATH))
+        .map_err(io_error)?;
+    workspaces.workspaces.push(workspace.clone());
+    write_workspaces(data_dir, &workspaces)?;
+    Ok(workspace)
+}
+
+pub fn rename_workspace(data_dir: &Path, id: &str, name: &str) -> Result<Workspace, KanbanError> {
+    let mut workspaces = read_workspaces(data_dir)?;
+    workspaces.check_name(Some(id), name)?;
+    let workspace = workspaces.get_mut(id)?;
+    workspace.name = name.trim().to_string();
+    let workspace = workspace.clone();
+    write_workspaces(data_dir, &workspaces)?;
+    Ok(workspace)
+}
+
+/// Deletes a workspace and all its projects. The default and the active
+/// workspace cannot be deleted.
+pub fn delete_workspace(data_dir: &Path, id: &str) -> Result<(), KanbanError> {
+    let mut workspaces = read_workspaces(data_dir)?;
+    if id == DEFAULT_WORKSPACE {
+        return Err(workspace_error("The default workspace cannot be deleted"));
+    }
+    if id == workspaces.active {
+        return Err(workspace_error(
+            "Switch to another workspace before deleting this one",
+        ));
+    }
+    workspaces.get_mut(id)?;
+    workspaces.workspaces.retain(|workspace| workspace.id != id);
+    // Out of the index first, so a failed removal leaves no dangling entry
+    write_workspaces(data_dir, &workspaces)?;
+    let dir = workspace_dir(data_dir, id);
+    if fs::exists(&dir).map_err(io_error)? {
+        fs::remove_dir_all(&dir).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Records `id` as the active workspace, to be set with `set_active`.
+pub fn switch_workspace(data_dir: &Path, id: &str) -> Result<Workspace, KanbanError> {
+    let mut workspaces = read_workspaces(data_dir)?;
+    let workspace = workspaces.get_mut(id)?.clone();
+    fs::create_dir_all(workspace_dir(data_dir, id)).map_err(io_error)?;
+    workspaces.active = workspace.id.clone();
+    write_workspaces(data_dir, &workspaces)?;
+    Ok(workspace)
+}
+
+/// Remembers the project open in the active workspace.
+pub fn set_last_project(data_dir: &Path, project_id: Option<[u8; 16]>) -> Result<(), KanbanError> {
+    let mut workspaces = read_workspaces(data_dir)?;
+    let active = workspaces.active.clone();
+    let workspace = workspaces.get_mut(&active)?;
+    if workspace.last_project == project_id {
+        return Ok(());
+    }
+    workspace.last_project = project_id;
+    write_workspaces(data_dir, &workspaces)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_workspaces() {
+        let dir = TempDir::new("test_workspaces").unwrap();
+        let data_dir = dir.path();
+        assert_eq!(Workspaces::default(), read_workspaces(data_dir).unwrap());
+        let work = create_workspace(data_dir, " Work ").unwrap();
+        assert_eq!("Work", work.name);
+        let work_dir = workspace_dir(data_dir, &work.id);
+        assert!(fs::exists(work_dir.join(project::PROJECT_PATH)).unwrap());
+        assert!(create_workspace(data_dir, "work").is_err());
+        assert!(create_workspace(data_dir, "  ").is_err());
+        // Each workspace remembers its own last project
+        set_last_project(data_dir, Some([1; 16])).unwrap();
+        switch_workspace(data_dir, &work.id).unwrap();
+        set_last_project(data_dir, Some([2; 16])).unwrap();
+        let workspaces = read_workspaces(data_dir).unwrap();
+        assert_eq!(work.id, workspaces.active);
+        let last_projects: Vec<Option<[u8; 16]>> = workspaces
+            .workspaces
+            .iter()
+            .map(|workspace| workspace.last_project)
+            .collect();
+        assert_eq!(vec![Some([1; 16]), Some([2; 16])], last_projects);
+        // Renaming keeps the ID and the folder
+        let renamed = rename_workspace(data_dir, &work.id, "Office").unwrap();
+        assert_eq!(
+            (work.id.clone(), "Office"),
+            (renamed.id, renamed.name.as_str())
+        );
+        assert!(rename_workspace(data_dir, DEFAULT_WORKSPACE, "office").is_err());
+        assert!(rename_workspace(data_dir, "missing", "Other").is_err());
+        // Only an inactive, non-default workspace can be deleted
+        assert!(delete_workspace(data_dir, &work.id).is_err());
+        assert!(delete_workspace(data_dir, DEFAULT_WORKSPACE).is_err());
+        switch_workspace(data_dir, DEFAULT_WORKSPACE).unwrap();
+        delete_workspace(data_dir, &work.id).unwrap();
+        assert!(!fs::exists(&work_dir).unwrap());
+        assert_eq!(1, read_workspaces(data_dir).unwrap().workspaces.len());
+    }
+
+    #[test]
+    fn test_workspace_dir() {
+        let data_dir = Path::new("/data");
+        assert_eq!(data_dir, workspace_dir(data_dir, DEFAULT_WORKSPACE));
+        assert_eq!(
+            Path::new("/data/workspaces/0a1b"),
+            workspace_dir(data_dir, "0a1b")
+        );
+    }
+
+    #[test]
+    fn test_read_workspaces_repairs_index() {
+        let dir = TempDir::new("test_read_workspaces_repairs_index").unwrap();
+        fs::write(
+            dir.path().join(INDEX_FILE),
+            r#"{"active":"gone","workspaces":[{"id":"0a1b","name":"Work"}]}"#,
+        )
+        .unwrap();
+        let workspaces = read_workspaces(dir.path()).unwrap();
+        assert_eq!(DEFAULT_WORKSPACE, workspaces.active);
+        let ids: Vec<&str> = workspaces
+            .workspaces
+            .iter()
+            .map(|w| w.id.as_str())
+            .collect();
+        assert_eq!(vec![DEFAULT_WORKSPACE, "0a1b"], ids);
+    }
+
+    #[test]
+    fn test_read_workspaces_corrupt() {
+        let dir = TempDir::new("test_read_workspaces_corrupt").unwrap();
+        fs::write(dir.path().join(INDEX_FILE), "{").unwrap();
+        assert_eq!(Workspaces::default(), read_workspaces(dir.path()).unwrap());
+        let corrupt_path = dir.path().join("workspaces.json.corrupt");
+        assert_eq!("{", fs::read_to_string(corrupt_path).unwrap());
+    }
+}
@@ -1,20 +1,119 @@
+mod api_server;
+pub mod cli;
 mod commands;
 mod errors;
-mod file_system;
-mod kanban;
+mod events;
+pub mod file_system;
+pub mod kanban;
+pub mod peer_sync;
+#[cfg(test)]
+mod test_support;
+mod watcher;
+pub mod webdav;
+
+use errors::error_log;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            app.manage(kanban::project_cache::ProjectCache::default());
+            app.manage(api_server::ApiServerState::default());
+            let location = kanban::data_dir::locate(
+                &app.path().app_data_dir()?,
+                kanban::data_dir::executable_dir().as_deref(),
+            )?;
+            let workspaces =
+                kanban::workspace::read_workspaces(&location.path).unwrap_or_else(|err| {
+                    error_log::report("Failed to read workspaces", err);
+                    kanban::workspace::Workspaces::default()
+                });
+            kanban::workspace::set_active(&workspaces.active);
+            std::fs::create_dir_all(kanban::workspace::active_dir(&location.path))?;
+            kanban::data_dir::set_location(location);
+            // The window still opens, showing what went wrong
+            let configs = kanban::config::read_configs(app.handle()).unwrap_or_else(|err| {
+                error_log::report("Failed to read configs", err);
+                kanban::config::Configs::default()
+            });
+            configs.apply_storage();
+            let state = app.state::<api_server::ApiServerState>();
+            if let Err(err) = api_server::apply_configs(app.handle(), &state, &configs) {
+                // The app stays usable without the local API
+                error_log::report("Failed to start local API", err);
+            }
+            app.manage(peer_sync::PeerSyncState::default());
+            let state = app.state::<peer_sync::PeerSyncState>();
+            if let Err(err) = peer_sync::apply_configs(app.handle(), &state, &configs) {
+                error_log::report("Failed to start peer sync", err);
+            }
+            app.manage(webdav::WebDavState::default());
+            let state = app.state::<webdav::WebDavState>();
+            if let Err(err) = webdav::apply_configs(app.handle(), &state, &configs) {
+                error_log::report("Failed to start WebDAV sync", err);
+            }
+            app.manage(watcher::WatcherState::default());
+            let state = app.state::<watcher::WatcherState>();
+            if let Err(err) = watcher::start(app.handle(), &state) {
+                error_log::report("Failed to watch for external changes", err);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::project_commands::create_project_command,
             commands::project_commands::get_all_projects,
             commands::project_commands::read_project,
             commands::project_commands::save_project,
+            commands::project_commands::merge_project,
             commands::project_commands::delete_project,
+            commands::project_commands::archive_project,
+            commands::project_commands::unarchive_project,
+            commands::project_commands::read_board,
+            commands::project_commands::save_board,
+            commands::project_commands::set_project_dirty,
+            commands::project_commands::recompress_projects,
+            commands::project_commands::unlock_project,
+            commands::project_commands::lock_project,
+            commands::project_commands::change_project_passphrase,
+            commands::project_commands::diff_projects,
+            commands::snapshot_commands::list_snapshots,
+            commands::snapshot_commands::preview_snapshot,
+            commands::snapshot_commands::diff_snapshot,
+            commands::snapshot_commands::restore_snapshot,
+            commands::history_commands::list_history,
+            commands::history_commands::preview_history,
+            commands::history_commands::diff_history,
+            commands::history_commands::checkout_history,
+            commands::sync_commands::list_conflict_copies,
+            commands::sync_commands::read_conflict_copy,
+            commands::sync_commands::merge_conflict_copy,
+            commands::sync_commands::resolve_conflict_copy,
+            commands::sync_commands::discard_conflict_copy,
+            commands::sync_commands::sync_peers_now,
+            commands::sync_commands::get_project_operations,
+            commands::sync_commands::apply_project_operations,
+            commands::sync_commands::sync_webdav_now,
+            commands::export_commands::export_project,
+            commands::export_commands::import_project,
             commands::configs_commands::save_configs,
             commands::configs_commands::get_configs,
+            commands::configs_commands::take_logged_errors,
+            commands::configs_commands::get_data_directory,
+            commands::configs_commands::move_data_directory,
+            commands::group_commands::get_project_groups,
+            commands::group_commands::create_group,
+            commands::group_commands::rename_group,
+            commands::group_commands::move_group,
+            commands::group_commands::delete_group,
+            commands::group_commands::move_project,
+            commands::workspace_commands::list_workspaces,
+            commands::workspace_commands::create_workspace,
+            commands::workspace_commands::rename_workspace,
+            commands::workspace_commands::delete_workspace,
+            commands::workspace_commands::switch_workspace,
+            commands::workspace_commands::set_last_project,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
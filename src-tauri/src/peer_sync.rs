@@ -0,0 +1,617 @@
+mod channel;
+mod replica;
+
+use crate::{
+    errors::{
+        error_log,
+        kanban_error::{KanbanError, KanbanErrorKind},
+    },
+    events::ProjectChange,
+    kanban::{
+        config::Configs,
+        project::{AppPathProvider, PathProvider},
+        revision_vector::Causality,
+    },
+    watcher,
+};
+use chacha20poly1305::Key;
+use channel::Channel;
+use replica::{ManifestEntry, Update};
+use std::{
+    collections::BTreeSet,
+    io,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const PROTOCOL_VERSION: u32 = 1;
+// How often the listener checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Data directories with a sync running, so two syncs never apply at once
+static SYNCING: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Hello {
+        node: String,
+        version: u32,
+    },
+    /// The instance is already syncing with another one.
+    Busy,
+    Manifest {
+        entries: Vec<ManifestEntry>,
+    },
+    Updates {
+        updates: Vec<Update>,
+    },
+    Done,
+}
+
+/// What a sync with another instance did here.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
+pub struct SyncReport {
+    pub peer: String,
+    /// Projects created, changed or deleted here.
+    pub received: usize,
+    /// Projects sent to the other instance.
+    pub sent: usize,
+    /// Projects changed on both instances and merged here.
+    pub merged: usize,
+    /// Conflicts in those merges, resolved to this instance's side.
+    pub conflicts: usize,
+    /// Projects that could not be applied here, with why.
+    pub failed: Vec<String>,
+}
+
+type OnChange = Arc<dyn Fn(&ProjectChange) + Send + Sync>;
+
+struct SyncGuard(PathBuf);
+
+impl Drop for SyncGuard {
+    fn drop(&mut self) {
+        SYNCING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+fn begin_sync<P: AppPathProvider>(app: &P) -> Result<Option<SyncGuard>, KanbanError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?;
+    let mut syncing = SYNCING.lock().unwrap_or_else(|e| e.into_inner());
+    if !syncing.insert(dir.clone()) {
+        return Ok(None);
+    }
+    Ok(Some(SyncGuard(dir)))
+}
+
+fn sync_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::SyncError, message)
+}
+
+fn unexpected() -> KanbanError {
+    sync_error("Peer sent an unexpected message")
+}
+
+fn apply_update<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    update: &Update,
+    merge: bool,
+    on_change: &dyn Fn(&ProjectChange),
+    report: &mut SyncReport,
+) {
+    match replica::apply(app, node, update, merge) {
+        Ok(applied) => {
+            if let Some(change) = &applied.change {
+                report.received += 1;
+                on_change(change);
+            }
+            report.merged += applied.merged as usize;
+            report.conflicts += applied.conflicts;
+        }
+        // One project failing leaves the rest to sync
+        Err(err) => {
+            let id: String = update.id.iter().map(|b| format!("{:02X}", b)).collect();
+            report.failed.push(format!("{}: {}", id, err.message));
+        }
+    }
+}
+
+/// Drops what peer sync keeps of a project besides the project itself that
+/// was encoded under its previous passphrase.
+pub(crate) fn forget_base<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    replica::forget_base(app, project_id)
+}
+
+/// Syncs every project with the instance listening at `peer`. Projects only
+/// one side changed are copied over, projects both changed are merged here
+/// and sent back, so both end up with the same version.
+pub fn sync_with<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    key: &Key,
+    peer: &str,
+    on_change: &dyn Fn(&ProjectChange),
+) -> Result<SyncReport, KanbanError> {
+    let Some(_guard) = begin_sync(app)? else {
+        return Err(sync_error("A sync is already running"));
+    };
+    let address = peer
+        .to_socket_addrs()
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?
+        .next()
+        .ok_or_else(|| sync_error(format!("Unknown peer address: {}", peer)))?;
+    let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+    let mut channel = Channel::connect(stream, key)?;
+    channel.send(&Message::Hello {
+        node: node.to_string(),
+        version: PROTOCOL_VERSION,
+    })?;
+    match channel.receive()? {
+        Message::Hello { version, .. } if version != PROTOCOL_VERSION => {
+            return Err(sync_error("Peer runs an incompatible version"))
+        }
+        Message::Hello { node: theirs, .. } if theirs == node => {
+            return Err(sync_error("Cannot sync an instance with itself"))
+        }
+        Message::Hello { .. } => {}
+        Message::Busy => return Err(sync_error("Peer is busy syncing, try again later")),
+        _ => return Err(unexpected()),
+    }
+    channel.send(&Message::Manifest {
+        entries: replica::manifest(app, node)?,
+    })?;
+    let Message::Manifest { entries: theirs } = channel.receive()? else {
+        return Err(unexpected());
+    };
+    let Message::Updates { updates } = channel.receive()? else {
+        return Err(unexpected());
+    };
+    let mut report = SyncReport {
+        peer: peer.to_string(),
+        ..SyncReport::default()
+    };
+    for update in &updates {
+        apply_update(app, node, update, true, on_change, &mut report);
+    }
+    // What the peer lacks, now including the merges
+    let outgoing = replica::manifest(app, node)?
+        .into_iter()
+        .filter(|entry| {
+            entry
+                .vector
+                .compare(&replica::vector_of(&theirs, &entry.id))
+                == Causality::After
+        })
+        .collect::<Vec<ManifestEntry>>();
+    let outgoing = replica::updates(app, &outgoing)?;
+    channel.send(&Message::Updates {
+        updates: outgoing.clone(),
+    })?;
+    let Message::Done = channel.receive()? else {
+        return Err(unexpected());
+    };
+    report.sent = outgoing.len();
+    for update in &outgoing {
+        replica::mark_shared(app, update)?;
+    }
+    Ok(report)
+}
+
+/// The other side of `sync_with`. Concurrent changes are left for the
+/// connecting instance to merge, so only one side ever picks a winner.
+fn serve<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    key: &Key,
+    stream: TcpStream,
+    on_change: &dyn Fn(&ProjectChange),
+) -> Result<(), KanbanError> {
+    let mut channel = Channel::accept(stream, key)?;
+    let Message::Hello { node: peer, .. } = channel.receive()? else {
+        return Err(unexpected());
+    };
+    let Some(_guard) = begin_sync(app)? else {
+        return channel.send(&Message::Busy);
+    };
+    channel.send(&Message::Hello {
+        node: node.to_string(),
+        version: PROTOCOL_VERSION,
+    })?;
+    let Message::Manifest { entries: theirs } = channel.receive()? else {
+        return Err(unexpected());
+    };
+    let mine = replica::manifest(app, node)?;
+    let outgoing = mine
+        .iter()
+        .filter(|entry| {
+            matches!(
+                entry
+                    .vector
+                    .compare(&replica::vector_of(&theirs, &entry.id)),
+                Causality::After | Causality::Concurrent
+            )
+        })
+        .cloned()
+        .collect::<Vec<ManifestEntry>>();
+    let outgoing = replica::updates(app, &outgoing)?;
+    channel.send(&Message::Manifest { entries: mine })?;
+    channel.send(&Message::Updates {
+        updates: outgoing.clone(),
+    })?;
+    let Message::Updates { updates } = channel.receive()? else {
+        return Err(unexpected());
+    };
+    let mut report = SyncReport {
+        peer,
+        ..SyncReport::default()
+    };
+    for update in &updates {
+        apply_update(app, node, update, false, on_change, &mut report);
+    }
+    // Nobody waits on this side's report, so its failures are only logged
+    for failure in &report.failed {
+        error_log::report("Failed to sync project", failure);
+    }
+    channel.send(&Message::Done)?;
+    // Concurrent ones came back merged, which set their base already
+    for update in &outgoing {
+        replica::mark_shared(app, update)?;
+    }
+    Ok(())
+}
+
+/// Accepts syncs from other instances until dropped.
+pub struct PeerSyncServer {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+}
+
+impl PeerSyncServer {
+    pub fn start<P, F>(
+        app: P,
+        host: &str,
+        port: u16,
+        node: &str,
+        key: Key,
+        on_change: F,
+    ) -> Result<Self, KanbanError>
+    where
+        P: AppPathProvider + Send + 'static,
+        F: Fn(&ProjectChange) + Send + 'static,
+    {
+        let listener = TcpListener::bind((host, port))
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+        // Polled, so the thread notices when to stop
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+        let bound_port = listener.local_addr().map(|a| a.port()).unwrap_or(port);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let node = node.to_string();
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                let stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                let result = stream
+                    .set_nonblocking(false)
+                    .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))
+                    .and_then(|_| serve(&app, &node, &key, stream, &on_change));
+                if let Err(err) = result {
+                    error_log::report("Peer sync failed", err);
+                }
+            }
+        });
+        Ok(PeerSyncServer {
+            stop,
+            thread: Some(thread),
+            port: bound_port,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for PeerSyncServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Settings {
+    node: String,
+    port: u16,
+    shared_key: String,
+    peers: Vec<String>,
+    interval: u32,
+}
+
+impl Settings {
+    fn from_configs(configs: &Configs) -> Self {
+        Settings {
+            node: configs.instance_id.clone(),
+            port: configs.peer_sync_port,
+            shared_key: configs.peer_sync_key.clone(),
+            peers: configs.peer_sync_peers.clone(),
+            interval: configs.peer_sync_interval,
+        }
+    }
+}
+
+/// The listener and the periodic syncs with the configured peers.
+struct PeerSyncService {
+    settings: Settings,
+    key: Key,
+    on_change: OnChange,
+    _server: PeerSyncServer,
+    // Dropping the sender stops the periodic syncs
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for PeerSyncService {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PeerSyncState {
+    service: Mutex<Option<PeerSyncService>>,
+}
+
+/// The outcome of syncing with one configured peer.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PeerSyncResult {
+    pub peer: String,
+    pub report: Option<SyncReport>,
+    pub error: Option<String>,
+}
+
+fn sync_all<P: AppPathProvider>(
+    app: &P,
+    settings: &Settings,
+    key: &Key,
+    on_change: &OnChange,
+) -> Vec<PeerSyncResult> {
+    settings
+        .peers
+        .iter()
+        .map(|peer| {
+            let result = sync_with(app, &settings.node, key, peer, on_change.as_ref());
+            PeerSyncResult {
+                peer: peer.clone(),
+                error: result.as_ref().err().map(|e| e.message.clone()),
+                report: result.ok(),
+            }
+        })
+        .collect()
+}
+
+/// Starts, restarts or stops peer sync so it matches `configs`.
+pub fn apply_configs<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &PeerSyncState,
+    configs: &Configs,
+) -> Result<(), KanbanError> {
+    let mut service = state
+        .service
+        .lock()
+        .map_err(|_| sync_error("Peer sync state is poisoned"))?;
+    if !configs.peer_sync_enabled {
+        *service = None;
+        return Ok(());
+    }
+    let settings = Settings::from_configs(configs);
+    if service.as_ref().is_some_and(|s| s.settings == settings) {
+        return Ok(());
+    }
+    // Release the port before binding it again
+    *service = None;
+    let key = channel::derive_key(&settings.shared_key)?;
+    let handle = app.clone();
+    let on_change: OnChange =
+        Arc::new(move |change: &ProjectChange| watcher::notify_synced_change(&handle, change));
+    let server_on_change = on_change.clone();
+    let server = PeerSyncServer::start(
+        app.clone(),
+        "0.0.0.0",
+        settings.port,
+        &settings.node,
+        key,
+        move |change| server_on_change(change),
+    )?;
+    let (stop, thread) = if settings.interval > 0 && !settings.peers.is_empty() {
+        let (sender, receiver) = mpsc::channel::<()>();
+        let interval = Duration::from_secs(settings.interval as u64);
+        let (handle, thread_settings, thread_on_change) =
+            (app.clone(), settings.clone(), on_change.clone());
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                for result in sync_all(&handle, &thread_settings, &key, &thread_on_change) {
+                    if let Some(err) = result.error {
+                        error_log::report(&format!("Failed to sync with {}", result.peer), err);
+                    }
+                }
+            }
+        });
+        (Some(sender), Some(thread))
+    } else {
+        (None, None)
+    };
+    *service = Some(PeerSyncService {
+        settings,
+        key,
+        on_change,
+        _server: server,
+        stop,
+        thread,
+    });
+    Ok(())
+}
+
+/// Syncs with every configured peer right away.
+pub fn sync_now<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &PeerSyncState,
+) -> Result<Vec<PeerSyncResult>, KanbanError> {
+    let (settings, key, on_change) = {
+        let service = state
+            .service
+            .lock()
+            .map_err(|_| sync_error("Peer sync state is poisoned"))?;
+        let Some(service) = service.as_ref() else {
+            return Err(sync_error("Peer sync is off"));
+        };
+        (
+            service.settings.clone(),
+            service.key,
+            service.on_change.clone(),
+        )
+    };
+    Ok(sync_all(app, &settings, &key, &on_change))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::{board::Board, project};
+    use crate::test_support::mock_app;
+    use std::{fs, sync::mpsc};
+    use tempdir::TempDir;
+
+    fn board(name: &str) -> Board {
+        Board {
+            name: name.to_string(),
+            lists: Vec::new(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_two_instances_converge() {
+        let key = channel::derive_key("shared secret").unwrap();
+        let (dir_a, dir_b) = (
+            TempDir::new("kanban-test").expect("Failed to create directory"),
+            TempDir::new("kanban-test").expect("Failed to create directory"),
+        );
+        let (app_a, app_b) = (mock_app(dir_a.path()), mock_app(dir_b.path()));
+        let (sender, receiver) = mpsc::channel();
+        let server = PeerSyncServer::start(app_b.clone(), "127.0.0.1", 0, "b", key, move |c| {
+            sender.send(c.clone()).unwrap();
+        })
+        .unwrap();
+        let peer = format!("127.0.0.1:{}", server.port());
+
+        // A new project reaches the other instance
+        let created = project::create_project(&app_a, "Test Project", "Test Description").unwrap();
+        let report = sync_with(&app_a, "a", &key, &peer, &|_| {}).unwrap();
+        assert_eq!(1, report.sent);
+        assert_eq!(ProjectChange::Created(created.id), receiver.recv().unwrap());
+        assert_eq!(
+            created.name,
+            project::read_project(&app_b, &created.id).unwrap().name
+        );
+
+        // Concurrent edits are merged and end up on both
+        let mut ours = project::read_project(&app_a, &created.id).unwrap();
+        ours.boards.push(board("Board A"));
+        project::save_project(&app_a, &ours).unwrap();
+        let mut theirs = project::read_project(&app_b, &created.id).unwrap();
+        theirs.name = "Renamed on B".to_string();
+        theirs.boards.push(board("Board B"));
+        project::save_project(&app_b, &theirs).unwrap();
+        let report = sync_with(&app_a, "a", &key, &peer, &|_| {}).unwrap();
+        assert_eq!(1, report.merged);
+        assert_eq!(0, report.conflicts);
+        assert_eq!(1, report.sent);
+        let a = project::read_project(&app_a, &created.id).unwrap();
+        let b = project::read_project(&app_b, &created.id).unwrap();
+        assert_eq!("Renamed on B", a.name);
+        // Their board had nothing before it, so it goes first
+        assert_eq!(
+            vec!["Board B", "Board A"],
+            a.boards.iter().map(|b| b.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!((&a.name, &a.boards), (&b.name, &b.boards));
+
+        // Nothing left to exchange
+        let report = sync_with(&app_a, "a", &key, &peer, &|_| {}).unwrap();
+        assert_eq!((0, 0, 0), (report.received, report.sent, report.merged));
+        assert!(report.failed.is_empty());
+
+        // The merge base is kept as stored, and dropped with a new passphrase
+        let bases = |dir: &TempDir| {
+            fs::read_dir(dir.path().join(replica::PEER_SYNC_PATH))
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().extension().is_some_and(|e| e == "base"))
+                .count()
+        };
+        assert_eq!(1, bases(&dir_a));
+        project::change_passphrase(&app_a, &created.id, None, Some("secret")).unwrap();
+        assert_eq!(0, bases(&dir_a));
+        project::change_passphrase(&app_a, &created.id, Some("secret"), None).unwrap();
+
+        // A deletion on the other instance comes back
+        project::delete_project(&app_b, &created.id).unwrap();
+        let report = sync_with(&app_a, "a", &key, &peer, &|_| {}).unwrap();
+        assert_eq!(1, report.received);
+        assert!(project::read_project(&app_a, &created.id).is_err());
+    }
+
+    #[test]
+    fn test_sync_with_wrong_key() {
+        let key = channel::derive_key("shared secret").unwrap();
+        let wrong = channel::derive_key("other secret").unwrap();
+        let (dir_a, dir_b) = (
+            TempDir::new("kanban-test").expect("Failed to create directory"),
+            TempDir::new("kanban-test").expect("Failed to create directory"),
+        );
+        let server =
+            PeerSyncServer::start(mock_app(dir_b.path()), "127.0.0.1", 0, "b", key, |_| {})
+                .unwrap();
+        let peer = format!("127.0.0.1:{}", server.port());
+        let result = sync_with(&mock_app(dir_a.path()), "a", &wrong, &peer, &|_| {});
+        assert!(result.is_err());
+        assert_eq!(KanbanErrorKind::SyncError, result.unwrap_err().kind);
+        // An instance refuses to sync with itself
+        let result = sync_with(&mock_app(dir_a.path()), "b", &key, &peer, &|_| {});
+        assert!(result.is_err());
+    }
+}
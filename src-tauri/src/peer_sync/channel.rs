@@ -0,0 +1,247 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+// Sent first by the connecting side, so a stray connection fails early
+const MAGIC: &[u8; 8] = b"KBSYNC01";
+const CHALLENGE_SIZE: usize = 32;
+const NONCE_SIZE: usize = 24;
+// Fixed, as both instances must derive the same key from the shared key alone
+const KEY_SALT: &[u8] = b"kanban-board-peer-sync";
+// Large enough for any project, small enough to refuse garbage
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+// Until a message from the peer decrypts it is not known to hold the key, so
+// only a greeting is read
+const MAX_FIRST_FRAME_SIZE: usize = 64 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::SyncError, err)
+}
+
+/// Derives the key both instances encrypt with from the pre-shared key.
+pub(crate) fn derive_key(shared_key: &str) -> Result<Key, KanbanError> {
+    if shared_key.is_empty() {
+        return Err(KanbanError::new(
+            KanbanErrorKind::SyncError,
+            "Peer sync requires a shared key",
+        ));
+    }
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(shared_key.as_bytes(), KEY_SALT, &mut key)
+        .map_err(|e| {
+            KanbanError::new(
+                KanbanErrorKind::SyncError,
+                format!("Failed to derive key: {}", e),
+            )
+        })?;
+    Ok(key)
+}
+
+/// A connection to another instance. Every message is encrypted under the
+/// shared key and bound to both sides' challenges, its direction and its
+/// position, so only a holder of the key can take part and nothing can be
+/// replayed or reordered.
+pub(crate) struct Channel {
+    stream: TcpStream,
+    cipher: XChaCha20Poly1305,
+    challenges: [u8; CHALLENGE_SIZE * 2],
+    initiator: bool,
+    sent: u64,
+    received: u64,
+}
+
+impl Channel {
+    fn new(
+        stream: TcpStream,
+        key: &Key,
+        challenges: [u8; CHALLENGE_SIZE * 2],
+        initiator: bool,
+    ) -> Self {
+        Channel {
+            stream,
+            cipher: XChaCha20Poly1305::new(key),
+            challenges,
+            initiator,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    fn prepare(stream: &TcpStream) -> Result<(), KanbanError> {
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(io_error)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(io_error)
+    }
+
+    /// Opens a channel on a connection this side made.
+    pub(crate) fn connect(mut stream: TcpStream, key: &Key) -> Result<Self, KanbanError> {
+        Channel::prepare(&stream)?;
+        let mut challenges = [0u8; CHALLENGE_SIZE * 2];
+        OsRng.fill_bytes(&mut challenges[..CHALLENGE_SIZE]);
+        stream.write_all(MAGIC).map_err(io_error)?;
+        stream
+            .write_all(&challenges[..CHALLENGE_SIZE])
+            .map_err(io_error)?;
+        stream
+            .read_exact(&mut challenges[CHALLENGE_SIZE..])
+            .map_err(io_error)?;
+        Ok(Channel::new(stream, key, challenges, true))
+    }
+
+    /// Opens a channel on a connection the other side made.
+    pub(crate) fn accept(mut stream: TcpStream, key: &Key) -> Result<Self, KanbanError> {
+        Channel::prepare(&stream)?;
+        let mut magic = [0u8; MAGIC.len()];
+        stream.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != MAGIC {
+            return Err(KanbanError::new(
+                KanbanErrorKind::SyncError,
+                "Not a kanban board peer",
+            ));
+        }
+        let mut challenges = [0u8; CHALLENGE_SIZE * 2];
+        stream
+            .read_exact(&mut challenges[..CHALLENGE_SIZE])
+            .map_err(io_error)?;
+        OsRng.fill_bytes(&mut challenges[CHALLENGE_SIZE..]);
+        stream
+            .write_all(&challenges[CHALLENGE_SIZE..])
+            .map_err(io_error)?;
+        Ok(Channel::new(stream, key, challenges, false))
+    }
+
+    fn associated_data(&self, from_initiator: bool, position: u64) -> Vec<u8> {
+        let mut aad = self.challenges.to_vec();
+        aad.push(from_initiator as u8);
+        aad.extend_from_slice(&position.to_be_bytes());
+        aad
+    }
+
+    pub(crate) fn send<T: serde::Serialize>(&mut self, message: &T) -> Result<(), KanbanError> {
+        let plaintext = serde_json::to_vec(message)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+        let aad = self.associated_data(self.initiator, self.sent);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| KanbanError::new(KanbanErrorKind::SyncError, "Failed to encrypt"))?;
+        let length = (NONCE_SIZE + ciphertext.len()) as u32;
+        self.stream
+            .write_all(&length.to_be_bytes())
+            .map_err(io_error)?;
+        self.stream.write_all(&nonce).map_err(io_error)?;
+        self.stream.write_all(&ciphertext).map_err(io_error)?;
+        self.stream.flush().map_err(io_error)?;
+        self.sent += 1;
+        Ok(())
+    }
+
+    pub(crate) fn receive<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, KanbanError> {
+        let mut length = [0u8; 4];
+        self.stream.read_exact(&mut length).map_err(io_error)?;
+        let length = u32::from_be_bytes(length) as usize;
+        let max_size = match self.received {
+            0 => MAX_FIRST_FRAME_SIZE,
+            _ => MAX_FRAME_SIZE,
+        };
+        if !(NONCE_SIZE..=max_size).contains(&length) {
+            return Err(KanbanError::new(
+                KanbanErrorKind::SyncError,
+                "Peer sent an invalid message",
+            ));
+        }
+        let mut frame = vec![0u8; length];
+        self.stream.read_exact(&mut frame).map_err(io_error)?;
+        let aad = self.associated_data(!self.initiator, self.received);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(&frame[..NONCE_SIZE]),
+                Payload {
+                    msg: &frame[NONCE_SIZE..],
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                KanbanError::new(
+                    KanbanErrorKind::SyncError,
+                    "Peer could not be verified, check that both use the same shared key",
+                )
+            })?;
+        self.received += 1;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    fn pair(server_key: &Key, client_key: &Key) -> (Channel, Result<Channel, KanbanError>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server_key = *server_key;
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            Channel::accept(stream, &server_key)
+        });
+        let client =
+            Channel::connect(TcpStream::connect(("127.0.0.1", port)).unwrap(), client_key).unwrap();
+        (client, server.join().unwrap())
+    }
+
+    #[test]
+    fn test_channel() {
+        let key = derive_key("shared secret").unwrap();
+        let (mut client, server) = pair(&key, &key);
+        let mut server = server.unwrap();
+        client.send(&"Hello".to_string()).unwrap();
+        client.send(&vec![1, 2, 3]).unwrap();
+        assert_eq!("Hello", server.receive::<String>().unwrap());
+        assert_eq!(vec![1, 2, 3], server.receive::<Vec<u8>>().unwrap());
+        server.send(&true).unwrap();
+        assert!(client.receive::<bool>().unwrap());
+        assert!(derive_key("").is_err());
+    }
+
+    #[test]
+    fn test_channel_with_wrong_key() {
+        let key = derive_key("shared secret").unwrap();
+        let wrong = derive_key("other secret").unwrap();
+        let (mut client, server) = pair(&key, &wrong);
+        let mut server = server.unwrap();
+        client.send(&"Hello".to_string()).unwrap();
+        let err = server.receive::<String>().unwrap_err();
+        assert_eq!(KanbanErrorKind::SyncError, err.kind);
+    }
+
+    #[test]
+    fn test_channel_limits_first_frame() {
+        let key = derive_key("shared secret").unwrap();
+        let (mut client, server) = pair(&key, &key);
+        let mut server = server.unwrap();
+        // Announced before the peer proved it holds the key
+        let length = (MAX_FIRST_FRAME_SIZE + 1) as u32;
+        client.stream.write_all(&length.to_be_bytes()).unwrap();
+        let err = server.receive::<String>().unwrap_err();
+        assert_eq!("Peer sent an invalid message", err.message);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::events::ProjectChange;
+use crate::kanban::{
+    merge,
+    project::{self, AppPathProvider, PathProvider, Project},
+    revision_vector::{Causality, RevisionVector},
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub(crate) const PEER_SYNC_PATH: &str = "peer-sync";
+
+/// What this instance knows about a project's place in the sync, kept in
+/// `peer-sync/<id>.json`. Deleted projects keep theirs, so the deletion can
+/// reach other instances too.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct SyncState {
+    vector: RevisionVector,
+    /// Stored revision the vector counts, none once the project is deleted.
+    revision: Option<u64>,
+    /// Revision of the project as last shared with another instance, the
+    /// common ancestor of the next merge. Its file is kept next to this state
+    /// as stored, so it is never kept decrypted.
+    #[serde(default)]
+    base_revision: Option<u64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ManifestEntry {
+    pub id: [u8; 16],
+    pub vector: RevisionVector,
+}
+
+/// A project as one instance has it, none when it was deleted there.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Update {
+    pub id: [u8; 16],
+    pub vector: RevisionVector,
+    pub project: Option<Project>,
+}
+
+/// What applying an update did to the stored project.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Applied {
+    pub change: Option<ProjectChange>,
+    pub merged: bool,
+    pub conflicts: usize,
+}
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+fn state_dir<P: AppPathProvider>(app: &P) -> Result<PathBuf, KanbanError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(PEER_SYNC_PATH))
+}
+
+fn state_path<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<PathBuf, KanbanError> {
+    let project_path = project::project_file_path(app, project_id)?;
+    let file_name = project_path.file_name().unwrap_or_default();
+    Ok(state_dir(app)?.join(file_name).with_extension("json"))
+}
+
+fn base_path<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<PathBuf, KanbanError> {
+    Ok(state_path(app, project_id)?.with_extension("base"))
+}
+
+/// Keeps the stored project file as the base of the next merge, if it is
+/// still at `revision`. Returns the revision kept.
+fn keep_base<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    revision: Option<u64>,
+) -> Result<Option<u64>, KanbanError> {
+    let path = base_path(app, project_id)?;
+    let Some(revision) = revision else {
+        remove_base(&path)?;
+        return Ok(None);
+    };
+    fs::create_dir_all(state_dir(app)?).map_err(io_error)?;
+    fs::copy(project::project_file_path(app, project_id)?, &path).map_err(io_error)?;
+    if project::read_project_info_file(&path)?.revision != revision {
+        // Saved again meanwhile, so it is not what was shared
+        remove_base(&path)?;
+        return Ok(None);
+    }
+    Ok(Some(revision))
+}
+
+fn remove_base(path: &Path) -> Result<(), KanbanError> {
+    if fs::exists(path).map_err(io_error)? {
+        fs::remove_file(path).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+fn read_base<P: AppPathProvider>(app: &P, project_id: &[u8; 16], state: &SyncState) -> Project {
+    let kept = state.base_revision.and_then(|revision| {
+        let base = project::read_project_file(&base_path(app, project_id).ok()?).ok()?;
+        (base.revision == revision).then_some(base)
+    });
+    kept.unwrap_or_else(|| merge::empty_base(*project_id))
+}
+
+/// Drops the kept base of a project, which was encoded under its previous
+/// passphrase. The next merge is against an empty project instead.
+pub(crate) fn forget_base<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+) -> Result<(), KanbanError> {
+    remove_base(&base_path(app, project_id)?)?;
+    if !fs::exists(state_path(app, project_id)?).map_err(io_error)? {
+        return Ok(());
+    }
+    // Rewritten even without a base, as older versions kept it in the state
+    let mut state = read_state(app, project_id)?;
+    state.base_revision = None;
+    write_state(app, project_id, &state)
+}
+
+fn read_state<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<SyncState, KanbanError> {
+    let path = state_path(app, project_id)?;
+    if !fs::exists(&path).map_err(io_error)? {
+        return Ok(SyncState::default());
+    }
+    let json = fs::read_to_string(&path).map_err(io_error)?;
+    serde_json::from_str(&json).map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+}
+
+fn write_state<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8],
+    state: &SyncState,
+) -> Result<(), KanbanError> {
+    fs::create_dir_all(state_dir(app)?).map_err(io_error)?;
+    let json = serde_json::to_string(state)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    fs::write(state_path(app, project_id)?, json).map_err(io_error)
+}
+
+fn stored<P: AppPathProvider>(app: &P, project_id: &[u8]) -> Result<Option<Project>, KanbanError> {
+    let path = project::project_file_path(app, project_id)?;
+    if !fs::exists(&path).map_err(io_error)? {
+        return Ok(None);
+    }
+    project::read_project(app, project_id).map(Some)
+}
+
+/// Revision vectors of every project this instance syncs, counting a new
+/// version of `node` for each project saved or deleted since the last look.
+/// Encrypted projects are never synced.
+pub(crate) fn manifest<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+) -> Result<Vec<ManifestEntry>, KanbanError> {
+    // A new instance has no projects folder yet, and gets its projects this way
+    let projects_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))?
+        .join(project::PROJECT_PATH);
+    fs::create_dir_all(projects_dir).map_err(io_error)?;
+    let mut vectors: BTreeMap<[u8; 16], RevisionVector> = BTreeMap::new();
+    for info in project::get_all_projects_info(app)? {
+        if project::is_encrypted(app, &info.id)? {
+            continue;
+        }
+        let mut state = read_state(app, &info.id)?;
+        if state.revision != Some(info.revision) {
+            state.vector.bump(node);
+            state.revision = Some(info.revision);
+            write_state(app, &info.id, &state)?;
+        }
+        vectors.insert(info.id, state.vector);
+    }
+    let dir = state_dir(app)?;
+    let known = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+    for name in known {
+        let Some(id) = name.strip_suffix(".json").and_then(hex_to_id) else {
+            continue;
+        };
+        // Archived projects are not deleted, just no longer synced
+        if vectors.contains_key(&id)
+            || project::project_stamp(app, &id).is_ok()
+            || project::is_archived(app, &id)?
+        {
+            continue;
+        }
+        let mut state = read_state(app, &id)?;
+        // Deleted here since the last look
+        if state.revision.is_some() {
+            state.vector.bump(node);
+            state.revision = None;
+            state.base_revision = keep_base(app, &id, None)?;
+            write_state(app, &id, &state)?;
+        }
+        vectors.insert(id, state.vector);
+    }
+    Ok(vectors
+        .into_iter()
+        .map(|(id, vector)| ManifestEntry { id, vector })
+        .collect())
+}
+
+fn hex_to_id(hex: &str) -> Option<[u8; 16]> {
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(id)
+}
+
+/// The vector `entries` holds for a project, empty when it is not there.
+pub(crate) fn vector_of(entries: &[ManifestEntry], project_id: &[u8; 16]) -> RevisionVector {
+    entries
+        .iter()
+        .find(|entry| &entry.id == project_id)
+        .map(|entry| entry.vector.clone())
+        .unwrap_or_default()
+}
+
+/// The projects of `entries` as stored, to send to another instance.
+pub(crate) fn updates<P: AppPathProvider>(
+    app: &P,
+    entries: &[ManifestEntry],
+) -> Result<Vec<Update>, KanbanError> {
+    entries
+        .iter()
+        .map(|entry| {
+            Ok(Update {
+                id: entry.id,
+                vector: entry.vector.clone(),
+                project: stored(app, &entry.id)?,
+            })
+        })
+        .collect()
+}
+
+/// Records that another instance now has the project as in `update`.
+pub(crate) fn mark_shared<P: AppPathProvider>(app: &P, update: &Update) -> Result<(), KanbanError> {
+    let mut state = read_state(app, &update.id)?;
+    if state.vector != update.vector {
+        // Changed by another sync meanwhile, which kept its own base
+        return Ok(());
+    }
+    let revision = update.project.as_ref().map(|p| p.revision);
+    state.base_revision = keep_base(app, &update.id, revision)?;
+    write_state(app, &update.id, &state)
+}
+
+/// Writes `project` as the next revision of the stored one, or deletes the
+/// stored one. Returns the stored revision afterwards.
+fn store<P: AppPathProvider>(
+    app: &P,
+    project_id: &[u8; 16],
+    current: Option<&Project>,
+    project: Option<&Project>,
+) -> Result<(Option<u64>, Option<ProjectChange>), KanbanError> {
+    match (current, project) {
+        (current, Some(project)) => {
+            let mut project = project.clone();
+            project.revision = current.map_or(0, |c| c.revision);
+            let saved = project::save_project(app, &project)?;
+            let change = match current {
+                Some(_) => ProjectChange::Updated(*project_id),
+                None => ProjectChange::Created(*project_id),
+            };
+            Ok((Some(saved.revision), Some(change)))
+        }
+        (Some(_), None) => {
+            project::delete_project(app, project_id)?;
+            Ok((None, Some(ProjectChange::Deleted(*project_id))))
+        }
+        (None, None) => Ok((None, None)),
+    }
+}
+
+/// Applies a project another instance sent. Newer versions replace the
+/// stored one. Concurrent ones are merged with it when `merge` is set, the
+/// stored side winning conflicts, and skipped otherwise.
+pub(crate) fn apply<P: AppPathProvider>(
+    app: &P,
+    node: &str,
+    update: &Update,
+    merge: bool,
+) -> Result<Applied, KanbanError> {
+    if project::is_archived(app, &update.id)? {
+        // Left as archived until it is restored here
+        return Ok(Applied::default());
+    }
+    let mut state = read_state(app, &update.id)?;
+    let current = stored(app, &update.id)?;
+    if current.as_ref().map(|c| c.revision) != state.revision {
+        // Saved after the manifest was made, left for the next sync
+        return Ok(Applied::default());
+    }
+    let causality = state.vector.compare(&update.vector);
+    if causality == Causality::Before {
+        let (revision, change) = store(app, &update.id, current.as_ref(), update.project.as_ref())?;
+        state.vector = update.vector.clone();
+        state.revision = revision;
+        state.base_revision = keep_base(app, &update.id, revision)?;
+        write_state(app, &update.id, &state)?;
+        return Ok(Applied {
+            change,
+            ..Applied::default()
+        });
+    }
+    if causality != Causality::Concurrent || !merge {
+        return Ok(Applied::default());
+    }
+    let mut applied = Applied {
+        merged: true,
+        ..Applied::default()
+    };
+    // An edit made on one side wins over a deletion on the other
+    let merged = match (&current, &update.project) {
+        (Some(ours), Some(theirs)) => {
+            let base = read_base(app, &update.id, &state);
+            let merged = merge::merge_projects(&base, ours, theirs);
+            applied.conflicts = merged.conflicts.len();
+            Some(merged.project)
+        }
+        (ours, theirs) => ours.clone().or(theirs.clone()),
+    };
+    let (revision, change) = if merged
+        .as_ref()
+        .map(|p| (&p.name, &p.description, &p.boards))
+        == current
+            .as_ref()
+            .map(|p| (&p.name, &p.description, &p.boards))
+    {
+        (state.revision, None)
+    } else {
+        store(app, &update.id, current.as_ref(), merged.as_ref())?
+    };
+    applied.change = change;
+    state.vector.join(&update.vector);
+    state.vector.bump(node);
+    state.revision = revision;
+    state.base_revision = keep_base(app, &update.id, revision)?;
+    write_state(app, &update.id, &state)?;
+    Ok(applied)
+}
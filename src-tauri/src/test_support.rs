@@ -0,0 +1,78 @@
+//! Fixtures shared by the test modules.
+
+use crate::kanban::{
+    board::Board,
+    board_list::BoardList,
+    project::{self, AppPathProvider, PathProvider, Project},
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Keeps the data directory at a fixed path, such as a `TempDir`.
+#[derive(Clone)]
+pub(crate) struct MockAppPathProvider {
+    path: MockPath,
+}
+
+impl AppPathProvider for MockAppPathProvider {
+    type Path = MockPath;
+    fn path(&self) -> &Self::Path {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct MockPath {
+    path: PathBuf,
+}
+
+impl PathProvider for MockPath {
+    fn app_data_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.path.clone())
+    }
+}
+
+pub(crate) fn mock_app(data_dir: &Path) -> MockAppPathProvider {
+    MockAppPathProvider {
+        path: MockPath {
+            path: data_dir.to_path_buf(),
+        },
+    }
+}
+
+/// Like `mock_app`, with the projects folder already there.
+pub(crate) fn mock_projects_app(data_dir: &Path) -> MockAppPathProvider {
+    fs::create_dir_all(data_dir.join(project::PROJECT_PATH)).unwrap();
+    mock_app(data_dir)
+}
+
+pub(crate) fn test_project(id: [u8; 16], boards: Vec<Board>) -> Project {
+    Project {
+        id,
+        revision: 0,
+        locked: false,
+        archived: false,
+        name: "Test Project".to_string(),
+        description: "Test Description".to_string(),
+        boards,
+    }
+}
+
+pub(crate) fn test_board(name: &str, lists: Vec<BoardList>) -> Board {
+    Board {
+        name: name.to_string(),
+        lists,
+        archived: false,
+    }
+}
+
+/// A white list holding `items`.
+pub(crate) fn test_list(title: &str, items: &[&str]) -> BoardList {
+    BoardList {
+        title: title.to_string(),
+        color: [0xFF, 0xFF, 0xFF],
+        items: items.iter().map(|s| s.to_string()).collect(),
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{
+    errors::{
+        error_log,
+        kanban_error::{KanbanError, KanbanErrorKind},
+    },
+    events::{self, ProjectChange},
+    file_system::file_stamp,
+    kanban::{config, project, project_cache::ProjectCache, sync_folder},
+};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+use tauri::Manager;
+
+/// Events arriving within this window are handled together, so a file written
+/// in several steps is only reported once.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileChange {
+    Project(ProjectChange),
+    Configs,
+}
+
+#[derive(Debug, Clone)]
+struct WatchedPaths {
+    projects_dir: PathBuf,
+    configs_path: PathBuf,
+}
+
+impl WatchedPaths {
+    fn project_id(&self, path: &Path) -> Option<[u8; 16]> {
+        if path.parent()? != self.projects_dir {
+            return None;
+        }
+        let name = path.file_name()?.to_str()?;
+        if name.len() != 32 {
+            return None;
+        }
+        let mut id = [0u8; 16];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(name.get(i * 2..i * 2 + 2)?, 16).ok()?;
+        }
+        Some(id)
+    }
+}
+
+#[derive(Default)]
+pub struct WatcherState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    // Hash of the last contents seen for every watched file
+    known: Mutex<HashMap<PathBuf, u64>>,
+    dirty: Mutex<HashSet<[u8; 16]>>,
+}
+
+impl WatcherState {
+    pub fn set_dirty(&self, project_id: [u8; 16], dirty: bool) {
+        let mut projects = self.dirty.lock().unwrap();
+        if dirty {
+            projects.insert(project_id);
+        } else {
+            projects.remove(&project_id);
+        }
+    }
+
+    pub fn is_dirty(&self, project_id: &[u8; 16]) -> bool {
+        self.dirty.lock().unwrap().contains(project_id)
+    }
+
+    /// Records the current contents of a file the app wrote itself, so the
+    /// event it causes is not reported as an external change.
+    pub fn remember(&self, path: &Path) {
+        let mut known = self.known.lock().unwrap();
+        match content_hash(path) {
+            Ok(Some(hash)) => {
+                known.insert(path.to_path_buf(), hash);
+            }
+            Ok(None) => {
+                known.remove(path);
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn detect_change(&self, paths: &WatchedPaths, path: &Path) -> Option<FileChange> {
+        let project_id = paths.project_id(path);
+        if project_id.is_none() && path != paths.configs_path {
+            return None;
+        }
+        // Unreadable files are retried on the next event
+        let hash = content_hash(path).ok()?;
+        let mut known = self.known.lock().unwrap();
+        let previous = match hash {
+            Some(hash) => known.insert(path.to_path_buf(), hash),
+            None => known.remove(path),
+        };
+        if previous == hash {
+            return None;
+        }
+        let Some(id) = project_id else {
+            return Some(FileChange::Configs);
+        };
+        let change = match (previous, hash) {
+            (None, _) => ProjectChange::Created(id),
+            (Some(_), Some(_)) => ProjectChange::Updated(id),
+            (Some(_), None) => ProjectChange::Deleted(id),
+        };
+        Some(FileChange::Project(change))
+    }
+}
+
+fn content_hash(path: &Path) -> io::Result<Option<u64>> {
+    match fs::File::open(path) {
+        Ok(file) => file_stamp::content_hash(file).map(Some),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Stops watching, until `start` is called again.
+pub fn stop(state: &WatcherState) {
+    // Dropping the watcher closes the channel, which ends the thread
+    state.watcher.lock().unwrap().take();
+}
+
+/// Starts watching the projects directory and `configs.json` for changes made
+/// by other programs, such as sync tools or a second instance.
+pub fn start<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &WatcherState,
+) -> Result<(), KanbanError> {
+    let configs_path = config::configs_path(app)?;
+    let data_dir = configs_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let paths = WatchedPaths {
+        projects_dir: data_dir.join(project::PROJECT_PATH),
+        configs_path,
+    };
+    fs::create_dir_all(&paths.projects_dir)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    let entries = fs::read_dir(&paths.projects_dir)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        state.remember(&entry.path());
+    }
+    state.remember(&paths.configs_path);
+
+    let (sender, receiver) = mpsc::channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !matches!(event.kind, EventKind::Access(_)) {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        }
+    })
+    .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    watcher
+        .watch(&paths.projects_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+    // Editors often replace configs.json instead of writing to it, so watch its directory
+    watcher
+        .watch(&data_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::IoError, e))?;
+
+    let handle = app.clone();
+    thread::spawn(move || {
+        // The channel closes when the watcher is dropped
+        while let Ok(path) = receiver.recv() {
+            let mut pending = HashSet::from([path]);
+            while let Ok(path) = receiver.recv_timeout(DEBOUNCE) {
+                pending.insert(path);
+            }
+            let Some(state) = handle.try_state::<WatcherState>() else {
+                continue;
+            };
+            for path in pending {
+                if sync_folder::enabled() {
+                    if let Some(copy) = sync_folder::conflict_copy_at(&path) {
+                        events::emit_conflict_copy(&handle, &copy);
+                        continue;
+                    }
+                }
+                match state.detect_change(&paths, &path) {
+                    Some(FileChange::Project(change)) => notify_project_change(&handle, &change),
+                    Some(FileChange::Configs) => events::emit_configs_changed(&handle),
+                    None => {}
+                }
+            }
+        }
+    });
+    *state.watcher.lock().unwrap() = Some(watcher);
+    Ok(())
+}
+
+pub fn remember_project<R: tauri::Runtime>(app: &tauri::AppHandle<R>, project_id: &[u8]) {
+    if let (Some(state), Ok(path)) = (
+        app.try_state::<WatcherState>(),
+        project::project_file_path(app, project_id),
+    ) {
+        state.remember(&path);
+    }
+}
+
+pub fn remember_configs<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if let (Some(state), Ok(path)) = (app.try_state::<WatcherState>(), config::configs_path(app)) {
+        state.remember(&path);
+    }
+}
+
+/// After the window saved a project through the cache: the watcher skips the
+/// write, the project has no unsaved edits left and the window is told.
+pub fn project_saved<R: tauri::Runtime>(app: &tauri::AppHandle<R>, project_id: &[u8; 16]) {
+    remember_project(app, project_id);
+    if let Some(state) = app.try_state::<WatcherState>() {
+        state.set_dirty(*project_id, false);
+    }
+    events::emit_project_change(app, &ProjectChange::Updated(*project_id));
+}
+
+/// After the app wrote a project besides the cache, such as an import or a
+/// restore: the watcher skips the write, the cached copy goes and the window
+/// is told.
+pub fn project_written<R: tauri::Runtime>(app: &tauri::AppHandle<R>, change: &ProjectChange) {
+    let id = change.project_id();
+    remember_project(app, &id);
+    if let Some(cache) = app.try_state::<ProjectCache>() {
+        match change {
+            ProjectChange::Deleted(_) => cache.forget(&id),
+            _ => cache.invalidate(&id),
+        }
+    }
+    events::emit_project_change(app, change);
+}
+
+/// Like `notify_project_change`, for a change a sync wrote itself.
+pub fn notify_synced_change<R: tauri::Runtime>(app: &tauri::AppHandle<R>, change: &ProjectChange) {
+    remember_project(app, &change.project_id());
+    notify_project_change(app, change);
+}
+
+/// Tells the window about a project changed outside of it. When the window has
+/// unsaved edits to that project it gets the stored copy as a conflict instead.
+pub fn notify_project_change<R: tauri::Runtime>(app: &tauri::AppHandle<R>, change: &ProjectChange) {
+    let id = change.project_id();
+    if let Some(cache) = app.try_state::<ProjectCache>() {
+        cache.invalidate(&id);
+    }
+    if matches!(change, ProjectChange::Deleted(_)) {
+        events::emit_project_change(app, change);
+        return;
+    }
+    // Files still being written by a sync tool are picked up by a later event
+    let Ok(project) = project::read_project(app, &id) else {
+        return;
+    };
+    // A synced version can be the common ancestor of a later conflict copy
+    if let Err(err) = sync_folder::keep_base(app, &id) {
+        error_log::report("Failed to keep merge base", err);
+    }
+    let dirty = app
+        .try_state::<WatcherState>()
+        .is_some_and(|state| state.is_dirty(&id));
+    if dirty {
+        events::emit_project_conflict(app, project);
+    } else {
+        events::emit_project_change(app, change);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn watched_paths(dir: &TempDir) -> WatchedPaths {
+        let paths = WatchedPaths {
+            projects_dir: dir.path().join("projects"),
+            configs_path: dir.path().join("configs.json"),
+        };
+        fs::create_dir_all(&paths.projects_dir).expect("Failed to create directory");
+        paths
+    }
+
+    #[test]
+    fn test_project_id() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let paths = watched_paths(&dir);
+        assert_eq!(
+            Some([0xAB; 16]),
+            paths.project_id(&paths.projects_dir.join("AB".repeat(16)))
+        );
+        assert_eq!(
+            None,
+            paths.project_id(&paths.projects_dir.join("notes.txt"))
+        );
+        assert_eq!(None, paths.project_id(&dir.path().join("AB".repeat(16))));
+    }
+
+    #[test]
+    fn test_detect_change() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let paths = watched_paths(&dir);
+        let state = WatcherState::default();
+        let project_path = paths.projects_dir.join("01".repeat(16));
+        fs::write(&project_path, [0x00]).unwrap();
+        assert_eq!(
+            Some(FileChange::Project(ProjectChange::Created([0x01; 16]))),
+            state.detect_change(&paths, &project_path)
+        );
+        // Repeated events for the same contents are ignored
+        assert_eq!(None, state.detect_change(&paths, &project_path));
+        fs::write(&project_path, [0x01]).unwrap();
+        assert_eq!(
+            Some(FileChange::Project(ProjectChange::Updated([0x01; 16]))),
+            state.detect_change(&paths, &project_path)
+        );
+        fs::remove_file(&project_path).unwrap();
+        assert_eq!(
+            Some(FileChange::Project(ProjectChange::Deleted([0x01; 16]))),
+            state.detect_change(&paths, &project_path)
+        );
+        fs::write(&paths.configs_path, "{}").unwrap();
+        assert_eq!(
+            Some(FileChange::Configs),
+            state.detect_change(&paths, &paths.configs_path)
+        );
+        let other_path = dir.path().join("notes.txt");
+        fs::write(&other_path, "notes").unwrap();
+        assert_eq!(None, state.detect_change(&paths, &other_path));
+    }
+
+    #[test]
+    fn test_remembered_writes_are_ignored() {
+        let dir = TempDir::new("kanban-test").expect("Failed to create directory");
+        let paths = watched_paths(&dir);
+        let state = WatcherState::default();
+        let project_path = paths.projects_dir.join("01".repeat(16));
+        fs::write(&project_path, [0x00]).unwrap();
+        state.remember(&project_path);
+        assert_eq!(None, state.detect_change(&paths, &project_path));
+        fs::remove_file(&project_path).unwrap();
+        state.remember(&project_path);
+        assert_eq!(None, state.detect_change(&paths, &project_path));
+    }
+
+    #[test]
+    fn test_set_dirty() {
+        let state = WatcherState::default();
+        assert!(!state.is_dirty(&[0x01; 16]));
+        state.set_dirty([0x01; 16], true);
+        assert!(state.is_dirty(&[0x01; 16]));
+        state.set_dirty([0x01; 16], false);
+        assert!(!state.is_dirty(&[0x01; 16]));
+    }
+}
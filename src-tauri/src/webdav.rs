@@ -0,0 +1,303 @@
+use crate::{
+    errors::{
+        error_log,
+        kanban_error::{KanbanError, KanbanErrorKind},
+    },
+    events::ProjectChange,
+    kanban::{config::Configs, project::AppPathProvider},
+    watcher,
+};
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+pub mod client;
+#[cfg(test)]
+mod stand_in;
+mod store;
+
+pub use client::WebDavClient;
+pub use store::{pending_writes, WebDavReport};
+
+#[derive(Clone, PartialEq)]
+struct Settings {
+    url: String,
+    username: String,
+    password: String,
+    interval: u32,
+}
+
+impl Settings {
+    fn from_configs(configs: &Configs) -> Self {
+        Settings {
+            url: configs.webdav_url.clone(),
+            username: configs.webdav_username.clone(),
+            password: configs.webdav_password.clone(),
+            interval: configs.webdav_interval,
+        }
+    }
+
+    fn client(&self) -> Result<WebDavClient, KanbanError> {
+        WebDavClient::new(&self.url, &self.username, &self.password)
+    }
+}
+
+/// Syncs the stored projects with the WebDAV collection once. The projects
+/// folder is the local cache, so everything keeps working while the server
+/// is out of reach.
+pub fn sync_with<P: AppPathProvider>(
+    app: &P,
+    client: &WebDavClient,
+    on_change: &dyn Fn(&ProjectChange),
+) -> Result<WebDavReport, KanbanError> {
+    store::sync(app, client, on_change)
+}
+
+fn notify<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> impl Fn(&ProjectChange) + '_ {
+    move |change: &ProjectChange| watcher::notify_synced_change(app, change)
+}
+
+/// The periodic syncs with the server.
+struct WebDavService {
+    settings: Settings,
+    // Dropping the sender stops the periodic syncs
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for WebDavService {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WebDavState {
+    service: Mutex<Option<WebDavService>>,
+}
+
+fn sync_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::SyncError, message)
+}
+
+/// Starts, restarts or stops the WebDAV syncs so they match `configs`.
+pub fn apply_configs<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &WebDavState,
+    configs: &Configs,
+) -> Result<(), KanbanError> {
+    let mut service = state
+        .service
+        .lock()
+        .map_err(|_| sync_error("WebDAV state is poisoned"))?;
+    if !configs.webdav_enabled {
+        *service = None;
+        return Ok(());
+    }
+    let settings = Settings::from_configs(configs);
+    if service.as_ref().is_some_and(|s| s.settings == settings) {
+        return Ok(());
+    }
+    *service = None;
+    let client = settings.client()?;
+    let (stop, thread) = if settings.interval > 0 {
+        let (sender, receiver) = mpsc::channel::<()>();
+        let interval = Duration::from_secs(settings.interval as u64);
+        let handle = app.clone();
+        let thread = thread::spawn(move || loop {
+            if let Err(err) = sync_with(&handle, &client, &notify(&handle)) {
+                error_log::report("Failed to sync with the WebDAV server", err);
+            }
+            if !matches!(
+                receiver.recv_timeout(interval),
+                Err(RecvTimeoutError::Timeout)
+            ) {
+                break;
+            }
+        });
+        (Some(sender), Some(thread))
+    } else {
+        (None, None)
+    };
+    *service = Some(WebDavService {
+        settings,
+        stop,
+        thread,
+    });
+    Ok(())
+}
+
+/// Syncs with the server right away.
+pub fn sync_now<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &WebDavState,
+) -> Result<WebDavReport, KanbanError> {
+    let settings = state
+        .service
+        .lock()
+        .map_err(|_| sync_error("WebDAV state is poisoned"))?
+        .as_ref()
+        .map(|service| service.settings.clone())
+        .ok_or_else(|| sync_error("WebDAV sync is off"))?;
+    sync_with(app, &settings.client()?, &notify(app))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kanban::{
+        project::{self, Project},
+        sync_folder,
+    };
+    use crate::test_support::{
+        mock_projects_app, test_board, test_list, test_project, MockAppPathProvider,
+    };
+    use stand_in::StandInServer;
+    use std::cell::RefCell;
+    use tempdir::TempDir;
+
+    fn synced_project() -> Project {
+        let list = test_list("To Do", &["Item 1"]);
+        test_project([3; 16], vec![test_board("Board 1", vec![list])])
+    }
+
+    fn sync(
+        app: &MockAppPathProvider,
+        client: &WebDavClient,
+    ) -> (WebDavReport, Vec<ProjectChange>) {
+        let changes = RefCell::new(Vec::new());
+        let report = sync_with(app, client, &|change| {
+            changes.borrow_mut().push(change.clone())
+        })
+        .expect("Failed to sync");
+        (report, changes.into_inner())
+    }
+
+    fn rename(app: &MockAppPathProvider, name: &str) -> Project {
+        let mut project = project::read_project(app, &[3; 16]).unwrap();
+        project.name = name.to_string();
+        project::save_project(app, &project).unwrap()
+    }
+
+    #[test]
+    fn test_two_instances_share_projects() {
+        let server = StandInServer::start();
+        let client = WebDavClient::new(&server.url(), "", "").unwrap();
+        let dirs = [
+            TempDir::new("test_webdav_a").unwrap(),
+            TempDir::new("test_webdav_b").unwrap(),
+        ];
+        let (a, b) = (
+            mock_projects_app(dirs[0].path()),
+            mock_projects_app(dirs[1].path()),
+        );
+        let name = "03030303030303030303030303030303";
+        let id = [3; 16];
+        project::save_project(&a, &synced_project()).unwrap();
+        assert_eq!(vec![id], pending_writes(&a).unwrap());
+        let (report, _) = sync(&a, &client);
+        assert_eq!(1, report.sent);
+        assert!(pending_writes(&a).unwrap().is_empty());
+        assert!(server.file(name).is_some());
+        // B takes it, then edits it
+        let (report, changes) = sync(&b, &client);
+        assert_eq!(1, report.received);
+        assert_eq!(vec![ProjectChange::Created(id)], changes);
+        assert_eq!(
+            synced_project().boards,
+            project::read_project(&b, &id).unwrap().boards
+        );
+        rename(&b, "Renamed on B");
+        assert_eq!(1, sync(&b, &client).0.sent);
+        let (report, changes) = sync(&a, &client);
+        assert_eq!(1, report.received);
+        assert_eq!(vec![ProjectChange::Updated(id)], changes);
+        assert_eq!("Renamed on B", project::read_project(&a, &id).unwrap().name);
+        // Nothing left to do
+        assert_eq!(WebDavReport::default(), sync(&a, &client).0);
+        // Archiving is no deletion, the server keeps the project
+        project::archive_project(&a, &id).unwrap();
+        assert!(pending_writes(&a).unwrap().is_empty());
+        assert_eq!(WebDavReport::default(), sync(&a, &client).0);
+        assert!(server.file(name).is_some());
+        project::unarchive_project(&a, &id).unwrap();
+        assert_eq!(WebDavReport::default(), sync(&a, &client).0);
+        // A deletion reaches the other instance
+        project::delete_project(&a, &id).unwrap();
+        assert_eq!(1, sync(&a, &client).0.sent);
+        assert!(server.file(name).is_none());
+        let (_, changes) = sync(&b, &client);
+        assert_eq!(vec![ProjectChange::Deleted(id)], changes);
+        assert!(project::project_stamp(&b, &id).is_err());
+    }
+
+    #[test]
+    fn test_offline_writes_wait() {
+        let server = StandInServer::start();
+        let client = WebDavClient::new(&server.url(), "", "").unwrap();
+        let dir = TempDir::new("test_webdav_offline").unwrap();
+        let app = mock_projects_app(dir.path());
+        project::save_project(&app, &synced_project()).unwrap();
+        sync(&app, &client);
+        server.set_online(false);
+        rename(&app, "Renamed offline");
+        assert!(sync_with(&app, &client, &|_| {}).is_err());
+        // Still readable from the local copy, and still waiting
+        assert_eq!(
+            "Renamed offline",
+            project::read_project(&app, &[3; 16]).unwrap().name
+        );
+        assert_eq!(vec![[3; 16]], pending_writes(&app).unwrap());
+        server.set_online(true);
+        assert_eq!(1, sync(&app, &client).0.sent);
+        assert!(pending_writes(&app).unwrap().is_empty());
+        let stored = server.file("03030303030303030303030303030303").unwrap();
+        let info = project::read_project_info_bytes(&stored).unwrap();
+        assert_eq!("Renamed offline", info.name);
+    }
+
+    #[test]
+    fn test_conflicting_writes_keep_copy() {
+        let server = StandInServer::start();
+        let client = WebDavClient::new(&server.url(), "", "").unwrap();
+        let dirs = [
+            TempDir::new("test_webdav_conflict_a").unwrap(),
+            TempDir::new("test_webdav_conflict_b").unwrap(),
+        ];
+        let (a, b) = (
+            mock_projects_app(dirs[0].path()),
+            mock_projects_app(dirs[1].path()),
+        );
+        let id = [3; 16];
+        project::save_project(&a, &synced_project()).unwrap();
+        sync(&a, &client);
+        sync(&b, &client);
+        rename(&a, "Renamed on A");
+        rename(&b, "Renamed on B");
+        assert_eq!(1, sync(&a, &client).0.sent);
+        // The conditional write fails, so B keeps A's version as a copy
+        let (report, changes) = sync(&b, &client);
+        assert_eq!(1, report.conflicts);
+        assert!(changes.is_empty());
+        assert_eq!("Renamed on B", project::read_project(&b, &id).unwrap().name);
+        let copies = sync_folder::list_conflict_copies(&b).unwrap();
+        assert_eq!(1, copies.len());
+        assert_eq!("Renamed on A", copies[0].name);
+        // Waits while the copy is there
+        let report = sync(&b, &client).0;
+        assert_eq!((0, 1), (report.sent, report.pending));
+        // Keeping B's version writes it over A's
+        sync_folder::discard_conflict_copy(&b, &id, &copies[0].file_name).unwrap();
+        assert_eq!(1, sync(&b, &client).0.sent);
+        sync(&a, &client);
+        assert_eq!("Renamed on B", project::read_project(&a, &id).unwrap().name);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{io::Read, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+// Large enough for any project file, small enough to refuse garbage
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const DAV_NAMESPACE: &str = "DAV:";
+const PROPFIND_BODY: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+<d:propfind xmlns:d=\"DAV:\"><d:prop><d:getetag/></d:prop></d:propfind>";
+
+/// A file in the remote collection and its current ETag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteFile {
+    pub name: String,
+    pub etag: String,
+}
+
+/// The outcome of a conditional write or delete.
+#[derive(Debug, PartialEq)]
+pub enum Written {
+    /// Done, with the new ETag when the server sent one.
+    Done(Option<String>),
+    /// The remote file is not the version the condition named.
+    Conflict,
+}
+
+fn sync_error<M: Into<String>>(message: M) -> KanbanError {
+    KanbanError::new(KanbanErrorKind::SyncError, message)
+}
+
+fn request_error(err: ureq::Error) -> KanbanError {
+    match err {
+        ureq::Error::Status(code, response) => sync_error(format!(
+            "WebDAV server replied {} {}",
+            code,
+            response.status_text()
+        )),
+        ureq::Error::Transport(transport) => {
+            KanbanError::from_source(KanbanErrorKind::SyncError, transport)
+        }
+    }
+}
+
+/// Talks to one WebDAV collection, such as `https://dav.example.com/kanban/`.
+pub struct WebDavClient {
+    agent: ureq::Agent,
+    base: String,
+    authorization: Option<String>,
+}
+
+impl WebDavClient {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self, KanbanError> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(sync_error("WebDAV URL must start with http:// or https://"));
+        }
+        let base = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{}/", url)
+        };
+        let authorization = (!username.is_empty()).then(|| {
+            let credentials = STANDARD.encode(format!("{}:{}", username, password));
+            format!("Basic {}", credentials)
+        });
+        Ok(WebDavClient {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            base,
+            authorization,
+        })
+    }
+
+    fn request(&self, method: &str, name: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}{}", self.base, name));
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// Files in the collection, creating it when it does not exist yet.
+    pub fn list(&self) -> Result<Vec<RemoteFile>, KanbanError> {
+        let result = self
+            .request("PROPFIND", "")
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY);
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => {
+                self.request("MKCOL", "").call().map_err(request_error)?;
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(request_error(err)),
+        };
+        let body = response
+            .into_string()
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+        parse_multistatus(&body)
+    }
+
+    /// The file's content and ETag, none when it does not exist.
+    pub fn get(&self, name: &str) -> Result<Option<(Vec<u8>, String)>, KanbanError> {
+        let response = match self.request("GET", name).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(err) => return Err(request_error(err)),
+        };
+        let etag = response.header("ETag").unwrap_or_default().to_string();
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_FILE_SIZE)
+            .read_to_end(&mut bytes)
+            .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+        Ok(Some((bytes, etag)))
+    }
+
+    /// Writes the file if its ETag is still `etag`, or only if it does not
+    /// exist yet when given none.
+    pub fn put(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        etag: Option<&str>,
+    ) -> Result<Written, KanbanError> {
+        let request = self
+            .request("PUT", name)
+            .set("Content-Type", "application/octet-stream");
+        let request = match etag {
+            Some(etag) => request.set("If-Match", etag),
+            None => request.set("If-None-Match", "*"),
+        };
+        match request.send_bytes(bytes) {
+            Ok(response) => Ok(Written::Done(response.header("ETag").map(str::to_string))),
+            Err(ureq::Error::Status(412, _)) => Ok(Written::Conflict),
+            Err(err) => Err(request_error(err)),
+        }
+    }
+
+    /// Deletes the file if its ETag is still `etag`. A file already gone
+    /// counts as deleted.
+    pub fn delete(&self, name: &str, etag: &str) -> Result<Written, KanbanError> {
+        match self.request("DELETE", name).set("If-Match", etag).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(Written::Done(None)),
+            Err(ureq::Error::Status(412, _)) => Ok(Written::Conflict),
+            Err(err) => Err(request_error(err)),
+        }
+    }
+
+    /// The current ETag of a file, for servers that leave it out of a PUT
+    /// response.
+    pub fn etag(&self, name: &str) -> Result<Option<String>, KanbanError> {
+        let result = self
+            .request("PROPFIND", name)
+            .set("Depth", "0")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY);
+        let body = match result {
+            Ok(response) => response
+                .into_string()
+                .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(err) => return Err(request_error(err)),
+        };
+        Ok(parse_multistatus(&body)?
+            .into_iter()
+            .find(|file| file.name == name)
+            .map(|file| file.etag))
+    }
+}
+
+/// Files with an ETag in a PROPFIND reply. Collections have none, so the
+/// collection itself is left out.
+fn parse_multistatus(body: &str) -> Result<Vec<RemoteFile>, KanbanError> {
+    let document = roxmltree::Document::parse(body)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::SyncError, e))?;
+    let dav_child = |node: roxmltree::Node, name: &str| {
+        node.descendants()
+            .find(|n| n.has_tag_name((DAV_NAMESPACE, name)))
+            .and_then(|n| n.text())
+            .map(|text| text.trim().to_string())
+    };
+    Ok(document
+        .descendants()
+        .filter(|node| node.has_tag_name((DAV_NAMESPACE, "response")))
+        .filter_map(|response| {
+            let href = dav_child(response, "href")?;
+            let etag = dav_child(response, "getetag").filter(|etag| !etag.is_empty())?;
+            let name = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+            Some(RemoteFile { name, etag })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/kanban/</D:href>
+    <D:propstat><D:prop><D:getetag/></D:prop><D:status>HTTP/1.1 404 Not Found</D:status></D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>https://dav.example.com/dav/kanban/000102030405060708090A0B0C0D0E0F</D:href>
+    <D:propstat><D:prop><D:getetag>"abc-1"</D:getetag></D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>
+  </D:response>
+  <response xmlns="DAV:">
+    <href>/dav/kanban/notes.txt</href>
+    <propstat><prop><getetag>W/"x"</getetag></prop></propstat>
+  </response>
+</D:multistatus>"#;
+        let files = parse_multistatus(body).unwrap();
+        assert_eq!(
+            vec![
+                RemoteFile {
+                    name: "000102030405060708090A0B0C0D0E0F".to_string(),
+                    etag: "\"abc-1\"".to_string(),
+                },
+                RemoteFile {
+                    name: "notes.txt".to_string(),
+                    etag: "W/\"x\"".to_string(),
+                },
+            ],
+            files
+        );
+        assert!(parse_multistatus("not xml").is_err());
+    }
+
+    #[test]
+    fn test_new_client() {
+        assert!(WebDavClient::new("ftp://example.com", "", "").is_err());
+        let client = WebDavClient::new("https://dav.example.com/kanban", "user", "pass").unwrap();
+        assert_eq!("https://dav.example.com/kanban/", client.base);
+        assert_eq!(Some("Basic dXNlcjpwYXNz"), client.authorization.as_deref());
+    }
+}
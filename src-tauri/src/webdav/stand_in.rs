@@ -0,0 +1,201 @@
+//! A WebDAV server in memory, just enough of one to test against.
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tiny_http::{Header, Request, Response, Server};
+
+const COLLECTION: &str = "/kanban/";
+
+#[derive(Default)]
+struct Files {
+    collection: bool,
+    files: HashMap<String, (Vec<u8>, u64)>,
+    next_version: u64,
+}
+
+impl Files {
+    fn etag(&self, name: &str) -> Option<String> {
+        self.files
+            .get(name)
+            .map(|(_, version)| format!("\"{}\"", version))
+    }
+}
+
+pub struct StandInServer {
+    files: Arc<Mutex<Files>>,
+    online: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+}
+
+impl StandInServer {
+    pub fn start() -> Self {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let files = Arc::new(Mutex::new(Files::default()));
+        let online = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_files, thread_online, thread_stop) =
+            (files.clone(), online.clone(), stop.clone());
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                let Ok(Some(request)) = server.recv_timeout(Duration::from_millis(50)) else {
+                    continue;
+                };
+                if !thread_online.load(Ordering::Relaxed) {
+                    let _ = request.respond(Response::empty(503));
+                    continue;
+                }
+                handle(&thread_files, request);
+            }
+        });
+        StandInServer {
+            files,
+            online,
+            stop,
+            thread: Some(thread),
+            port,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, COLLECTION)
+    }
+
+    /// While offline every request fails with 503.
+    pub fn set_online(&self, online: bool) {
+        self.online.store(online, Ordering::Relaxed);
+    }
+
+    pub fn file(&self, name: &str) -> Option<Vec<u8>> {
+        let files = self.files.lock().unwrap();
+        files.files.get(name).map(|(bytes, _)| bytes.clone())
+    }
+}
+
+impl Drop for StandInServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+fn with_etag<R: Read>(response: Response<R>, etag: &str) -> Response<R> {
+    response.with_header(Header::from_bytes("ETag", etag).unwrap())
+}
+
+fn multistatus(entries: &[(String, Option<String>)]) -> String {
+    let responses: String = entries
+        .iter()
+        .map(|(href, etag)| {
+            let etag = etag
+                .as_ref()
+                .map(|etag| format!("<D:getetag>{}</D:getetag>", etag.replace('"', "&quot;")))
+                .unwrap_or_default();
+            format!(
+                "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop>\
+                 <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+                href, etag
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><D:multistatus xmlns:D=\"DAV:\">{}</D:multistatus>",
+        responses
+    )
+}
+
+fn handle(files: &Mutex<Files>, mut request: Request) {
+    let mut files = files.lock().unwrap();
+    let Some(name) = request.url().strip_prefix(COLLECTION).map(str::to_string) else {
+        let _ = request.respond(Response::empty(404));
+        return;
+    };
+    let method = request.method().as_str().to_string();
+    let response = match (method.as_str(), name.as_str()) {
+        ("MKCOL", "") => {
+            files.collection = true;
+            Response::empty(201).boxed()
+        }
+        (_, _) if !files.collection => Response::empty(404).boxed(),
+        ("PROPFIND", "") => {
+            let mut entries = vec![(COLLECTION.to_string(), None)];
+            for name in files.files.keys() {
+                entries.push((format!("{}{}", COLLECTION, name), files.etag(name)));
+            }
+            Response::from_string(multistatus(&entries))
+                .with_status_code(207)
+                .boxed()
+        }
+        ("PROPFIND", name) => match files.etag(name) {
+            Some(etag) => {
+                let entries = [(format!("{}{}", COLLECTION, name), Some(etag))];
+                Response::from_string(multistatus(&entries))
+                    .with_status_code(207)
+                    .boxed()
+            }
+            None => Response::empty(404).boxed(),
+        },
+        ("GET", name) => match files.files.get(name) {
+            Some((bytes, _)) => {
+                let etag = files.etag(name).unwrap();
+                with_etag(Response::from_data(bytes.clone()), &etag).boxed()
+            }
+            None => Response::empty(404).boxed(),
+        },
+        ("PUT", name) => {
+            let current = files.etag(name);
+            let matches = match (
+                header(&request, "If-Match"),
+                header(&request, "If-None-Match"),
+            ) {
+                (Some(etag), _) => current.as_deref() == Some(etag),
+                (_, Some("*")) => current.is_none(),
+                _ => true,
+            };
+            if matches {
+                let mut bytes = Vec::new();
+                request.as_reader().read_to_end(&mut bytes).unwrap();
+                files.next_version += 1;
+                let version = files.next_version;
+                files.files.insert(name.to_string(), (bytes, version));
+                let etag = files.etag(name).unwrap();
+                with_etag(Response::empty(201), &etag).boxed()
+            } else {
+                Response::empty(412).boxed()
+            }
+        }
+        ("DELETE", name) => {
+            let current = files.etag(name);
+            match (current, header(&request, "If-Match")) {
+                (None, _) => Response::empty(404).boxed(),
+                (Some(current), Some(etag)) if current != etag => Response::empty(412).boxed(),
+                (Some(_), _) => {
+                    files.files.remove(name);
+                    Response::empty(204).boxed()
+                }
+            }
+        }
+        _ => Response::empty(405).boxed(),
+    };
+    let _ = request.respond(response);
+}
@@ -0,0 +1,338 @@
+use super::client::{RemoteFile, WebDavClient, Written};
+use crate::errors::kanban_error::{KanbanError, KanbanErrorKind};
+use crate::events::ProjectChange;
+use crate::file_system::file_stamp;
+use crate::kanban::{
+    project::{self, AppPathProvider, PathProvider},
+    sync_folder,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+pub(crate) const WEBDAV_PATH: &str = "webdav";
+
+// One sync at a time, whether periodic or asked for
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+/// A project file as last synced with the server.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SyncedFile {
+    etag: String,
+    /// Revision the server has, none while the local one still has to be
+    /// written over it.
+    revision: Option<u64>,
+}
+
+/// Kept in `webdav/state.json`, by file name.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct SyncState {
+    files: BTreeMap<String, SyncedFile>,
+}
+
+/// What a sync with the server did.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
+pub struct WebDavReport {
+    /// Projects created, changed or deleted here.
+    pub received: usize,
+    /// Projects written to or deleted from the server.
+    pub sent: usize,
+    /// Projects changed on both sides, the server's version kept as a
+    /// conflict copy.
+    pub conflicts: usize,
+    /// Local changes still waiting to be written.
+    pub pending: usize,
+}
+
+fn io_error(err: std::io::Error) -> KanbanError {
+    KanbanError::from_source(KanbanErrorKind::IoError, err)
+}
+
+fn data_dir<P: AppPathProvider>(app: &P) -> Result<PathBuf, KanbanError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| KanbanError::from_box_source(KanbanErrorKind::TauriError, e))
+}
+
+fn state_path<P: AppPathProvider>(app: &P) -> Result<PathBuf, KanbanError> {
+    Ok(data_dir(app)?.join(WEBDAV_PATH).join("state.json"))
+}
+
+fn read_state<P: AppPathProvider>(app: &P) -> Result<SyncState, KanbanError> {
+    let path = state_path(app)?;
+    if !fs::exists(&path).map_err(io_error)? {
+        return Ok(SyncState::default());
+    }
+    let json = fs::read_to_string(&path).map_err(io_error)?;
+    serde_json::from_str(&json).map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))
+}
+
+fn write_state<P: AppPathProvider>(app: &P, state: &SyncState) -> Result<(), KanbanError> {
+    let path = state_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_string(state)
+        .map_err(|e| KanbanError::from_source(KanbanErrorKind::TextError, e))?;
+    fs::write(path, json).map_err(io_error)
+}
+
+fn parse_project_id(name: &str) -> Option<[u8; 16]> {
+    if name.len() != 32 {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(name.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(id)
+}
+
+/// Revision of every stored project, by file name.
+fn local_revisions<P: AppPathProvider>(app: &P) -> Result<BTreeMap<String, u64>, KanbanError> {
+    let dir = data_dir(app)?.join(project::PROJECT_PATH);
+    fs::create_dir_all(&dir).map_err(io_error)?;
+    Ok(fs::read_dir(&dir)
+        .map_err(io_error)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| parse_project_id(name).is_some())
+        .filter_map(|name| {
+            let info = project::read_project_info_file(&dir.join(&name)).ok()?;
+            Some((name, info.revision))
+        })
+        .collect())
+}
+
+/// Projects saved or deleted here since they were last written to the
+/// server. They wait, such as while offline, until a sync gets through.
+pub fn pending_writes<P: AppPathProvider>(app: &P) -> Result<Vec<[u8; 16]>, KanbanError> {
+    let state = read_state(app)?;
+    let local = local_revisions(app)?;
+    let names: BTreeSet<&String> = local.keys().chain(state.files.keys()).collect();
+    Ok(names
+        .into_iter()
+        .filter(|name| {
+            let synced = state.files.get(*name).and_then(|file| file.revision);
+            local.get(*name).copied() != synced
+        })
+        .filter_map(|name| parse_project_id(name))
+        .filter(|id| !project::is_archived(app, id).unwrap_or(false))
+        .collect())
+}
+
+struct Sync<'a, P: AppPathProvider> {
+    app: &'a P,
+    client: &'a WebDavClient,
+    on_change: &'a dyn Fn(&ProjectChange),
+    state: SyncState,
+    report: WebDavReport,
+}
+
+impl<P: AppPathProvider> Sync<'_, P> {
+    fn record(&mut self, name: &str, file: Option<SyncedFile>) -> Result<(), KanbanError> {
+        match file {
+            Some(file) => self.state.files.insert(name.to_string(), file),
+            None => self.state.files.remove(name),
+        };
+        write_state(self.app, &self.state)
+    }
+
+    fn project_path(&self, name: &str) -> Result<PathBuf, KanbanError> {
+        Ok(data_dir(self.app)?.join(project::PROJECT_PATH).join(name))
+    }
+
+    /// Replaces the stored project with the server's, unless it was saved
+    /// since its revision was read as `local`.
+    fn download(
+        &mut self,
+        name: &str,
+        id: [u8; 16],
+        local: Option<u64>,
+    ) -> Result<(), KanbanError> {
+        let Some((bytes, etag)) = self.client.get(name)? else {
+            // Deleted since it was listed
+            return self.remove_local(name, id);
+        };
+        let info = project::read_project_info_bytes(&bytes)?;
+        if info.id != id {
+            return Err(KanbanError::new(
+                KanbanErrorKind::SyncError,
+                format!("{} on the WebDAV server is not that project", name),
+            ));
+        }
+        let path = self.project_path(name)?;
+        let temp_path = path.with_extension("webdav");
+        fs::write(&temp_path, &bytes).map_err(io_error)?;
+        {
+            let _guard = project::lock_saves();
+            let current = project::read_project_info_file(&path)
+                .ok()
+                .map(|p| p.revision);
+            if current != local {
+                fs::remove_file(&temp_path).map_err(io_error)?;
+                return Ok(());
+            }
+            fs::rename(&temp_path, &path).map_err(io_error)?;
+        }
+        let file = SyncedFile {
+            etag,
+            revision: Some(info.revision),
+        };
+        self.record(name, Some(file))?;
+        self.report.received += 1;
+        let change = match local {
+            Some(_) => ProjectChange::Updated(id),
+            None => ProjectChange::Created(id),
+        };
+        (self.on_change)(&change);
+        Ok(())
+    }
+
+    fn remove_local(&mut self, name: &str, id: [u8; 16]) -> Result<(), KanbanError> {
+        if fs::exists(self.project_path(name)?).map_err(io_error)? {
+            project::delete_project(self.app, &id)?;
+            self.report.received += 1;
+            (self.on_change)(&ProjectChange::Deleted(id));
+        }
+        self.record(name, None)
+    }
+
+    /// Writes the stored project over the version the server had at the
+    /// last sync, or keeps the server's as a conflict copy when that changed.
+    fn upload(&mut self, name: &str, known: Option<SyncedFile>) -> Result<(), KanbanError> {
+        let bytes = fs::read(self.project_path(name)?).map_err(io_error)?;
+        let revision = project::read_project_info_bytes(&bytes)?.revision;
+        let mut condition = known.map(|file| file.etag);
+        loop {
+            match self.client.put(name, &bytes, condition.as_deref())? {
+                Written::Done(etag) => {
+                    let etag = match etag {
+                        Some(etag) => etag,
+                        None => self.client.etag(name)?.unwrap_or_default(),
+                    };
+                    let file = SyncedFile {
+                        etag,
+                        revision: Some(revision),
+                    };
+                    self.report.sent += 1;
+                    return self.record(name, Some(file));
+                }
+                Written::Conflict => {}
+            }
+            let Some((theirs, etag)) = self.client.get(name)? else {
+                // Deleted on the server meanwhile, so write it anew
+                if condition.take().is_some() {
+                    continue;
+                }
+                return Ok(());
+            };
+            let copy_name = format!("{}.sync-conflict-webdav-{}", name, file_stamp::now_millis());
+            fs::write(self.project_path(&copy_name)?, theirs).map_err(io_error)?;
+            self.report.conflicts += 1;
+            self.report.pending += 1;
+            // Written over the server's version once the copy is dealt with
+            let file = SyncedFile {
+                etag,
+                revision: None,
+            };
+            return self.record(name, Some(file));
+        }
+    }
+
+    /// Deletes the project from the server, or brings the server's version
+    /// back when it changed since the last sync.
+    fn delete_remote(&mut self, name: &str, id: [u8; 16], etag: &str) -> Result<(), KanbanError> {
+        match self.client.delete(name, etag)? {
+            Written::Done(_) => {
+                self.report.sent += 1;
+                self.record(name, None)
+            }
+            Written::Conflict => self.download(name, id, None),
+        }
+    }
+
+    fn sync_file(
+        &mut self,
+        name: &str,
+        local: Option<u64>,
+        remote: Option<&String>,
+        copies: &HashSet<[u8; 16]>,
+    ) -> Result<(), KanbanError> {
+        let Some(id) = parse_project_id(name) else {
+            return Ok(());
+        };
+        // Archived here, the server keeps its copy until it is restored
+        if local.is_none() && project::is_archived(self.app, &id)? {
+            return Ok(());
+        }
+        let known = self.state.files.get(name).cloned();
+        match (local, known, remote) {
+            // Unchanged here
+            (Some(revision), Some(known), remote) if known.revision == Some(revision) => {
+                match remote {
+                    Some(etag) if *etag == known.etag => Ok(()),
+                    Some(_) => self.download(name, id, local),
+                    None => self.remove_local(name, id),
+                }
+            }
+            (Some(_), known, _) => {
+                if copies.contains(&id) {
+                    // Waits until the conflict copy is resolved
+                    self.report.pending += 1;
+                    return Ok(());
+                }
+                self.upload(name, known)
+            }
+            // Deleted here
+            (None, Some(known), Some(etag)) if *etag == known.etag => {
+                self.delete_remote(name, id, etag)
+            }
+            (None, Some(_), Some(_)) | (None, None, Some(_)) => self.download(name, id, None),
+            (None, _, None) => self.record(name, None),
+        }
+    }
+}
+
+/// Brings the stored projects and the server's in line. Projects changed on
+/// one side only are copied to the other. When both changed, the server's
+/// version is kept as a conflict copy and the local one waits until that is
+/// resolved. Stops at the first request that fails, leaving the rest pending.
+pub(crate) fn sync<P: AppPathProvider>(
+    app: &P,
+    client: &WebDavClient,
+    on_change: &dyn Fn(&ProjectChange),
+) -> Result<WebDavReport, KanbanError> {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let local = local_revisions(app)?;
+    let state = read_state(app)?;
+    let remote: BTreeMap<String, String> = client
+        .list()?
+        .into_iter()
+        .map(|RemoteFile { name, etag }| (name, etag))
+        .collect();
+    let copies: HashSet<[u8; 16]> = sync_folder::list_conflict_copies(app)?
+        .into_iter()
+        .map(|copy| copy.project_id)
+        .collect();
+    let names: BTreeSet<String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.files.keys())
+        .cloned()
+        .collect();
+    let mut sync = Sync {
+        app,
+        client,
+        on_change,
+        state,
+        report: WebDavReport::default(),
+    };
+    for name in names {
+        sync.sync_file(&name, local.get(&name).copied(), remote.get(&name), &copies)?;
+    }
+    Ok(sync.report)
+}
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useAtom, useAtomValue } from "jotai";
 import React from "react";
 import { FaCheckCircle, FaTimesCircle } from "react-icons/fa";
@@ -8,6 +9,7 @@ import CreateProjectPopup from "./components/CreateProjectPopup";
 import Sidebar from "./components/Sidebar";
 import { CommandResult } from "./models/commandResult";
 import { ConfigsModel } from "./models/configs";
+import { LoggedError } from "./models/events";
 import { BoardModel } from "./models/project";
 import { configsAtom, openedBoardAtom } from "./stores/projectStore";
 
@@ -36,6 +38,28 @@ function App() {
       setConfigs({
         auto_save_interval: 60,
         new_list_default_color: "#B6DFFF",
+        api_enabled: false,
+        api_port: 7410,
+        api_token: "",
+        compress_projects: false,
+        snapshots_enabled: true,
+        snapshot_hourly_hours: 24,
+        snapshot_daily_days: 30,
+        history_enabled: false,
+        history_path: "",
+        sync_folder: false,
+        instance_id: "",
+        record_operations: false,
+        peer_sync_enabled: false,
+        peer_sync_port: 7411,
+        peer_sync_key: "",
+        peer_sync_peers: [],
+        peer_sync_interval: 60,
+        webdav_enabled: false,
+        webdav_url: "",
+        webdav_username: "",
+        webdav_password: "",
+        webdav_interval: 60,
       });
     } else {
       setConfigs(result.data!);
@@ -50,8 +74,22 @@ function App() {
     window.addEventListener("scroll", lockScroll);
     // Read configs
     getConfigs();
+    const unlistenConfigs = listen("configs-changed", () => getConfigs());
+    const showError = (error: LoggedError): void =>
+      showMessage(false, `${error.context}: ${error.message}`);
+    const unlistenErrors = listen<LoggedError>("error-logged", (event) =>
+      showError(event.payload)
+    );
+    // Listening first, so no error falls between the two
+    unlistenErrors.then(() =>
+      invoke<CommandResult<LoggedError[]>>("take_logged_errors").then(
+        (result) => result.data?.forEach(showError)
+      )
+    );
     return () => {
       window.removeEventListener("scroll", lockScroll);
+      unlistenConfigs.then((fn) => fn());
+      unlistenErrors.then((fn) => fn());
     };
   }, []);
 
@@ -0,0 +1,16 @@
+import { Change, describeChange } from "../models/diff";
+
+interface ChangeListProps {
+  changes: Change[];
+}
+
+export const ChangeList = ({ changes }: ChangeListProps): JSX.Element => {
+  return (
+    <div className="text-lg mb-2 max-h-24 overflow-y-auto">
+      {changes.length === 0 && <p>No changes</p>}
+      {changes.map((change, i) => (
+        <p key={i}>{describeChange(change)}</p>
+      ))}
+    </div>
+  );
+};
@@ -0,0 +1,154 @@
+import { invoke } from "@tauri-apps/api/core";
+import React from "react";
+import { CommandResult } from "../models/commandResult";
+import { Change } from "../models/diff";
+import { Merge, MergeConflict, Side } from "../models/merge";
+import { ProjectModel } from "../models/project";
+import { ConflictCopy } from "../models/syncFolder";
+import { ChangeList } from "./ChangeList";
+import { MergeConflictList } from "./MergeConflictList";
+
+interface ConflictCopyPopupProps {
+  copy: ConflictCopy;
+  showBanner: (success: boolean, message: string) => void;
+  onResolved: () => void;
+}
+
+export const ConflictCopyPopup = ({
+  copy,
+  showBanner,
+  onResolved,
+}: ConflictCopyPopupProps): JSX.Element => {
+  const [stored, setStored] = React.useState<ProjectModel | null>(null);
+  const [copied, setCopied] = React.useState<ProjectModel | null>(null);
+  const [changes, setChanges] = React.useState<Change[] | null>(null);
+  const [conflicts, setConflicts] = React.useState<MergeConflict[] | null>(
+    null
+  );
+  const [choices, setChoices] = React.useState<Side[]>([]);
+
+  // What taking the copy would change in the stored project
+  React.useEffect(() => {
+    const load = async (): Promise<void> => {
+      const storedResult = await invoke<CommandResult<ProjectModel>>(
+        "read_project",
+        { projectId: copy.project_id }
+      );
+      const copyResult = await invoke<CommandResult<ProjectModel>>(
+        "read_conflict_copy",
+        { projectId: copy.project_id, fileName: copy.file_name }
+      );
+      if (!storedResult.data || !copyResult.data) {
+        showBanner(
+          false,
+          storedResult.message ?? copyResult.message ?? "No error message"
+        );
+        return;
+      }
+      setStored(storedResult.data);
+      setCopied(copyResult.data);
+      const diff = await invoke<CommandResult<Change[]>>("diff_projects", {
+        old: storedResult.data,
+        new: copyResult.data,
+      });
+      setChanges(diff.data);
+    };
+    load();
+  }, [copy]);
+
+  const resolve = async (project: ProjectModel): Promise<void> => {
+    const result = await invoke<CommandResult<ProjectModel>>(
+      "resolve_conflict_copy",
+      { projectId: copy.project_id, fileName: copy.file_name, project }
+    );
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    showBanner(true, `Resolved the conflict copy of "${copy.name}"`);
+    onResolved();
+  };
+
+  const merge = async (choices?: Side[]): Promise<void> => {
+    const result = await invoke<CommandResult<Merge>>("merge_conflict_copy", {
+      projectId: copy.project_id,
+      fileName: copy.file_name,
+      choices,
+    });
+    if (!result.success || result.data === null) {
+      showBanner(false, result.message ?? "Failed to merge");
+      return;
+    }
+    // Conflicts are resolved by a second merge with the chosen sides
+    if (choices === undefined && result.data.conflicts.length > 0) {
+      setConflicts(result.data.conflicts);
+      setChoices(result.data.conflicts.map(() => "ours"));
+      return;
+    }
+    await resolve(result.data.project);
+  };
+
+  const takeCopy = async (): Promise<void> => {
+    if (stored === null || copied === null) return;
+    await resolve({ ...copied, revision: stored.revision });
+  };
+
+  const keepStored = async (): Promise<void> => {
+    const result = await invoke<CommandResult<null>>("discard_conflict_copy", {
+      projectId: copy.project_id,
+      fileName: copy.file_name,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    onResolved();
+  };
+
+  const choose = (index: number, side: Side): void => {
+    setChoices((choices) => choices.map((x, i) => (i === index ? side : x)));
+  };
+
+  return (
+    <div className="absolute flex items-center justify-center top-0 left-0 w-screen h-screen bg-black/30 ">
+      <div className="bg-[#EFEFEF] w-[500px] min-h-52 flex flex-col justify-between px-4 py-3 rounded-xl">
+        <h2 className="font-bold text-2xl ">Conflict Copy</h2>
+        <p className="mx-auto text-lg">
+          Your sync folder kept a second version of <strong>{copy.name}</strong>{" "}
+          from {new Date(copy.modified).toLocaleString()}
+        </p>
+        {conflicts === null && changes !== null && (
+          <ChangeList changes={changes} />
+        )}
+        {conflicts !== null && (
+          <MergeConflictList
+            conflicts={conflicts}
+            choices={choices}
+            labels={["Stored", "Copy"]}
+            onChoose={choose}
+          />
+        )}
+        <div className="flex ml-auto">
+          <button
+            className="bg-white px-6 py-1 rounded-xl mr-4"
+            onClick={keepStored}
+          >
+            Keep Stored
+          </button>
+          <button
+            className="bg-white px-6 py-1 rounded-xl mr-4"
+            onClick={takeCopy}
+          >
+            Use Copy
+          </button>
+          <button
+            className="bg-blue-600 text-white px-6 py-1 rounded-xl"
+            onClick={() => merge(conflicts === null ? undefined : choices)}
+          >
+            {conflicts === null ? "Merge" : "Apply Merge"}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
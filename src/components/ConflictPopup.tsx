@@ -0,0 +1,111 @@
+import { invoke } from "@tauri-apps/api/core";
+import React from "react";
+import { CommandResult } from "../models/commandResult";
+import { Change } from "../models/diff";
+import { Merge, MergeConflict, Side } from "../models/merge";
+import { ProjectModel } from "../models/project";
+import { ChangeList } from "./ChangeList";
+import { MergeConflictList } from "./MergeConflictList";
+
+interface ConflictPopupProps {
+  projectName: string;
+  mine: ProjectModel | null;
+  stored: ProjectModel;
+  onKeepMine: () => void;
+  onLoadStored: () => void;
+  onMerged: (merged: ProjectModel) => void;
+  showBanner: (success: boolean, message: string) => void;
+}
+
+export const ConflictPopup = ({
+  projectName,
+  mine,
+  stored,
+  onKeepMine,
+  onLoadStored,
+  onMerged,
+  showBanner,
+}: ConflictPopupProps): JSX.Element => {
+  const [changes, setChanges] = React.useState<Change[] | null>(null);
+  const [conflicts, setConflicts] = React.useState<MergeConflict[] | null>(
+    null
+  );
+  const [choices, setChoices] = React.useState<Side[]>([]);
+
+  const merge = async (choices?: Side[]): Promise<void> => {
+    if (mine === null) return;
+    const result = await invoke<CommandResult<Merge>>("merge_project", {
+      project: mine,
+      choices,
+    });
+    if (!result.success || result.data === null) {
+      showBanner(false, result.message ?? "Failed to merge");
+      return;
+    }
+    // Conflicts are resolved by a second merge with the chosen sides
+    if (choices === undefined && result.data.conflicts.length > 0) {
+      setConflicts(result.data.conflicts);
+      setChoices(result.data.conflicts.map(() => "ours"));
+      return;
+    }
+    onMerged(result.data.project);
+  };
+
+  const choose = (index: number, side: Side): void => {
+    setChoices((choices) => choices.map((x, i) => (i === index ? side : x)));
+  };
+
+  // What loading the stored copy would change in this window
+  React.useEffect(() => {
+    if (mine === null) return;
+    invoke<CommandResult<Change[]>>("diff_projects", {
+      old: mine,
+      new: stored,
+    }).then((result) => setChanges(result.data));
+  }, [mine, stored]);
+
+  return (
+    <div className="absolute flex items-center justify-center top-0 left-0 w-screen h-screen bg-black/30 ">
+      <div className="bg-[#EFEFEF] w-[500px] min-h-52 flex flex-col justify-between px-4 py-3 rounded-xl">
+        <h2 className="font-bold text-2xl ">Project Changed</h2>
+        <p className="mx-auto text-lg">
+          <strong>{projectName}</strong> was changed outside this window while
+          you have unsaved edits
+        </p>
+        {conflicts === null && changes !== null && (
+          <ChangeList changes={changes} />
+        )}
+        {conflicts !== null && (
+          <MergeConflictList
+            conflicts={conflicts}
+            choices={choices}
+            labels={["Mine", "Stored"]}
+            onChoose={choose}
+          />
+        )}
+        <div className="flex ml-auto">
+          <button
+            className="bg-white px-6 py-1 rounded-xl mr-4"
+            onClick={onKeepMine}
+          >
+            Keep Mine
+          </button>
+          {mine !== null && (
+            <button
+              className="bg-white px-6 py-1 rounded-xl mr-4"
+              onClick={() => merge(conflicts === null ? undefined : choices)}
+            >
+              {conflicts === null ? "Merge" : "Apply Merge"}
+            </button>
+          )}
+          <button
+            className="bg-red-500 text-white px-6 py-1 rounded-xl"
+            onClick={onLoadStored}
+          >
+            Load Changes
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
@@ -2,7 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { useAtom } from "jotai";
 import React from "react";
 import { IoClose } from "react-icons/io5";
-import { CommandResult } from "../models/commandResult";
+import { CommandResult, FieldError } from "../models/commandResult";
 import { ProjectModel } from "../models/project";
 import { allProjectsAtom } from "../stores/projectStore";
 
@@ -19,6 +19,10 @@ const CreateProjectPopup = ({
 
   const [name, setName] = React.useState<string>("");
   const [description, setDescription] = React.useState<string>("");
+  const [fieldErrors, setFieldErrors] = React.useState<FieldError[]>([]);
+
+  const fieldError = (field: string): string | undefined =>
+    fieldErrors.find((error) => error.field === field)?.message;
 
   const createProject = async (): Promise<void> => {
     console.log("Creating project");
@@ -27,6 +31,7 @@ const CreateProjectPopup = ({
       { name, description }
     );
     if (!result.success) {
+      setFieldErrors(result.errors ?? []);
       showBanner(false, result.message ?? "No error message");
       return;
     }
@@ -56,6 +61,9 @@ const CreateProjectPopup = ({
             value={name}
             onChange={(e) => setName(e.target.value)}
           />
+          {fieldError("name") && (
+            <p className="text-red-600 mt-1">{fieldError("name")}</p>
+          )}
         </div>
         <div className="mt-4">
           <p className="text-lg">Project Description:</p>
@@ -64,6 +72,9 @@ const CreateProjectPopup = ({
             value={description}
             onChange={(e) => setDescription(e.target.value)}
           />
+          {fieldError("description") && (
+            <p className="text-red-600 mt-1">{fieldError("description")}</p>
+          )}
         </div>
         <div className="flex w-min ml-auto mt-10">
           <button
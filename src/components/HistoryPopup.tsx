@@ -0,0 +1,100 @@
+import { invoke } from "@tauri-apps/api/core";
+import React from "react";
+import { CommandResult } from "../models/commandResult";
+import { Change } from "../models/diff";
+import { HistoryEntry } from "../models/history";
+import { ProjectModel } from "../models/project";
+import { ChangeList } from "./ChangeList";
+
+interface HistoryPopupProps {
+  project: ProjectModel;
+  showBanner: (success: boolean, message: string) => void;
+  onClose: () => void;
+}
+
+export const HistoryPopup = ({
+  project,
+  showBanner,
+  onClose,
+}: HistoryPopupProps): JSX.Element => {
+  const [entries, setEntries] = React.useState<HistoryEntry[]>([]);
+  const [changes, setChanges] = React.useState<Change[] | null>(null);
+
+  React.useEffect(() => {
+    invoke<CommandResult<HistoryEntry[]>>("list_history", {
+      projectId: project.id,
+    }).then((result) => {
+      if (!result.success) {
+        showBanner(false, result.message ?? "No error message");
+        return;
+      }
+      setEntries(result.data ?? []);
+    });
+  }, [project]);
+
+  const diffCommit = async (commit: string): Promise<void> => {
+    const result = await invoke<CommandResult<Change[]>>("diff_history", {
+      projectId: project.id,
+      commit,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setChanges(result.data ?? []);
+  };
+
+  const checkoutCommit = async (commit: string): Promise<void> => {
+    const result = await invoke<CommandResult<ProjectModel>>(
+      "checkout_history",
+      { projectId: project.id, commit, revision: project.revision }
+    );
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    showBanner(true, `Checked out "${project.name}"`);
+    onClose();
+  };
+
+  return (
+    <div className="absolute flex items-center justify-center top-0 left-0 w-screen h-screen bg-black/30 ">
+      <div className="bg-[#EFEFEF] w-[600px] h-96 flex flex-col px-4 py-3 rounded-xl">
+        <h2 className="font-bold text-2xl ">History of {project.name}</h2>
+        <div className="overflow-y-auto my-2 flex-1">
+          {entries.length === 0 && <p className="text-lg">No history</p>}
+          {entries.map((entry) => (
+            <div key={entry.commit} className="flex items-center text-lg py-1">
+              <span className="flex-1" title={entry.message}>
+                {new Date(entry.timestamp).toLocaleString()}{" "}
+                {entry.message.split("\n")[0]}
+              </span>
+              {entry.revision !== null && (
+                <>
+                  <button
+                    className="bg-white px-4 py-0.5 rounded-xl mr-2"
+                    onClick={() => diffCommit(entry.commit)}
+                  >
+                    Diff
+                  </button>
+                  <button
+                    className="bg-blue-600 text-white px-4 py-0.5 rounded-xl"
+                    onClick={() => checkoutCommit(entry.commit)}
+                  >
+                    Check Out
+                  </button>
+                </>
+              )}
+            </div>
+          ))}
+        </div>
+        {changes !== null && <ChangeList changes={changes} />}
+        <div className="flex ml-auto">
+          <button className="bg-white px-6 py-1 rounded-xl" onClick={onClose}>
+            Close
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
@@ -0,0 +1,40 @@
+import { describeSide, MergeConflict, Side } from "../models/merge";
+
+interface MergeConflictListProps {
+  conflicts: MergeConflict[];
+  choices: Side[];
+  // Button labels for our side and theirs
+  labels: [string, string];
+  onChoose: (index: number, side: Side) => void;
+}
+
+export const MergeConflictList = ({
+  conflicts,
+  choices,
+  labels,
+  onChoose,
+}: MergeConflictListProps): JSX.Element => {
+  return (
+    <div className="text-lg mb-2 max-h-48 overflow-y-auto">
+      {conflicts.map((conflict, i) => (
+        <div key={i} className="mb-1">
+          <p className="font-bold">{conflict.location}</p>
+          <div className="flex">
+            {(["ours", "theirs"] as Side[]).map((side) => (
+              <button
+                key={side}
+                className={`px-3 py-1 rounded-xl mr-2 ${
+                  choices[i] === side ? "bg-blue-500 text-white" : "bg-white"
+                }`}
+                onClick={() => onChoose(i, side)}
+              >
+                {side === "ours" ? labels[0] : labels[1]}:{" "}
+                {describeSide(conflict, side)}
+              </button>
+            ))}
+          </div>
+        </div>
+      ))}
+    </div>
+  );
+};
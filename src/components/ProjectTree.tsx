@@ -0,0 +1,154 @@
+import React from "react";
+import { IoChevronDown, IoChevronForward } from "react-icons/io5";
+import { ProjectModel } from "../models/project";
+import {
+  DraggedEntry,
+  GroupEntry,
+  ProjectGroup,
+} from "../models/projectGroup";
+
+const DRAG_TYPE = "application/x-kanban-entry";
+
+interface ProjectTreeProps {
+  entries: GroupEntry[];
+  projects: ProjectModel[];
+  parentId: string | null;
+  depth: number;
+  onOpenProject: (projectId: number[]) => void;
+  onProjectContextMenu: (
+    e: React.MouseEvent<HTMLSpanElement>,
+    idx: number
+  ) => void;
+  onGroupContextMenu: (
+    e: React.MouseEvent<HTMLSpanElement>,
+    group: ProjectGroup
+  ) => void;
+  onMove: (
+    dragged: DraggedEntry,
+    parentId: string | null,
+    index: number
+  ) => void;
+  onItemKeyDown: (e: React.KeyboardEvent<HTMLSpanElement>) => void;
+  onItemBlur: (e: React.FocusEvent<HTMLSpanElement>) => void;
+}
+
+export const ProjectTree = (props: ProjectTreeProps): JSX.Element => {
+  const { entries, projects, parentId, depth } = props;
+  const [collapsed, setCollapsed] = React.useState<string[]>([]);
+
+  const onDragStart = (
+    e: React.DragEvent<HTMLSpanElement>,
+    dragged: DraggedEntry
+  ): void => {
+    e.stopPropagation();
+    e.dataTransfer.setData(DRAG_TYPE, JSON.stringify(dragged));
+  };
+
+  const onDragOver = (e: React.DragEvent<HTMLSpanElement>): void => {
+    if (e.dataTransfer.types.includes(DRAG_TYPE)) e.preventDefault();
+  };
+
+  // Dropped on a project it goes before it, on a group to the end of it
+  const onDrop = (
+    e: React.DragEvent<HTMLSpanElement>,
+    targetParentId: string | null,
+    index: number
+  ): void => {
+    e.preventDefault();
+    e.stopPropagation();
+    const data = e.dataTransfer.getData(DRAG_TYPE);
+    if (data === "") return;
+    props.onMove(JSON.parse(data) as DraggedEntry, targetParentId, index);
+  };
+
+  const toggle = (group: ProjectGroup): void => {
+    setCollapsed((ids) =>
+      ids.includes(group.id)
+        ? ids.filter((id) => id !== group.id)
+        : [...ids, group.id]
+    );
+  };
+
+  return (
+    <>
+      {entries.map((entry, index) => {
+        const indent = { paddingLeft: `${0.75 + depth}rem` };
+        if (entry.type === "group") {
+          const isCollapsed = collapsed.includes(entry.id);
+          return (
+            <React.Fragment key={entry.id}>
+              <span
+                className="w-full text-left pr-3 py-1 text-lg cursor-pointer hover:bg-black/10 flex items-center font-semibold"
+                style={indent}
+                draggable
+                onClick={() => toggle(entry)}
+                onContextMenu={(e) => props.onGroupContextMenu(e, entry)}
+                onDragStart={(e) =>
+                  onDragStart(e, {
+                    type: "group",
+                    id: entry.id,
+                    parentId,
+                    index,
+                  })
+                }
+                onDragOver={onDragOver}
+                onDrop={(e) => onDrop(e, entry.id, entry.entries.length)}
+              >
+                {isCollapsed ? <IoChevronForward /> : <IoChevronDown />}
+                <span className="ml-1">{entry.name}</span>
+              </span>
+              {!isCollapsed && (
+                <ProjectTree
+                  {...props}
+                  entries={entry.entries}
+                  parentId={entry.id}
+                  depth={depth + 1}
+                />
+              )}
+            </React.Fragment>
+          );
+        }
+        const idx = projects.findIndex(
+          (x) => x.id.join(",") === entry.id.join(",")
+        );
+        if (idx === -1) return null;
+        const project = projects[idx];
+        const dimmed = project.locked || project.archived;
+        return (
+          <span
+            key={project.id.join("")}
+            data-item-index={idx}
+            className={`w-full text-left pr-3 py-1 text-lg cursor-pointer hover:bg-black/10 ${
+              dimmed ? "italic text-black/50" : ""
+            }`}
+            style={indent}
+            title={
+              project.archived
+                ? "Archived"
+                : project.locked
+                  ? "Locked"
+                  : undefined
+            }
+            draggable={!project.archived}
+            onClick={() => props.onOpenProject(project.id)}
+            onContextMenu={(e) => props.onProjectContextMenu(e, idx)}
+            onKeyDown={props.onItemKeyDown}
+            onBlur={props.onItemBlur}
+            onDragStart={(e) =>
+              onDragStart(e, {
+                type: "project",
+                id: entry.id,
+                parentId,
+                index,
+              })
+            }
+            onDragOver={onDragOver}
+            onDrop={(e) => onDrop(e, parentId, index)}
+          >
+            {project.name}
+          </span>
+        );
+      })}
+    </>
+  );
+};
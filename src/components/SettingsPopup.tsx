@@ -1,7 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useAtom } from "jotai";
+import React from "react";
 import { CommandResult } from "../models/commandResult";
 import { ConfigsModel } from "../models/configs";
+import { DataLocation } from "../models/dataDirectory";
+import { describeSync, PeerSyncResult } from "../models/peerSync";
+import { describeWebDavSync, WebDavReport } from "../models/webdav";
 import { configsAtom } from "../stores/projectStore";
 
 interface SettingsPopupProps {
@@ -14,6 +18,18 @@ const SettingsPopup = ({
   onClose,
 }: SettingsPopupProps): JSX.Element => {
   const [configs, setConfigs] = useAtom(configsAtom);
+  const [dataLocation, setDataLocation] = React.useState<DataLocation | null>(
+    null
+  );
+  const [newDataPath, setNewDataPath] = React.useState<string>("");
+
+  React.useEffect(() => {
+    invoke<CommandResult<DataLocation>>("get_data_directory").then(
+      (result) => {
+        if (result.success) setDataLocation(result.data ?? null);
+      }
+    );
+  }, []);
 
   if (configs === null) return <></>;
 
@@ -32,6 +48,78 @@ const SettingsPopup = ({
     }
   };
 
+  const recompressProjects = async (): Promise<void> => {
+    const result = await invoke<CommandResult<number>>("recompress_projects");
+    if (!result.success) {
+      showBanner(
+        false,
+        "Failed to recompress projects: " +
+          (result.message ?? "No error message")
+      );
+    } else {
+      showBanner(true, `${result.data ?? 0} projects rewritten`);
+    }
+  };
+
+  const syncPeersNow = async (): Promise<void> => {
+    const result = await invoke<CommandResult<PeerSyncResult[]>>(
+      "sync_peers_now"
+    );
+    if (!result.success) {
+      showBanner(
+        false,
+        "Failed to sync: " + (result.message ?? "No error message")
+      );
+      return;
+    }
+    const failed = (result.data ?? []).filter((x) => x.error !== null);
+    if (failed.length > 0) {
+      showBanner(
+        false,
+        failed.map((x) => `${x.peer}: ${x.error}`).join("; ")
+      );
+    } else {
+      showBanner(true, describeSync(result.data ?? []));
+    }
+  };
+
+  const syncWebDavNow = async (): Promise<void> => {
+    const result = await invoke<CommandResult<WebDavReport>>(
+      "sync_webdav_now"
+    );
+    if (!result.success || !result.data) {
+      showBanner(
+        false,
+        "Failed to sync: " + (result.message ?? "No error message")
+      );
+    } else {
+      showBanner(true, describeWebDavSync(result.data));
+    }
+  };
+
+  const moveDataDirectory = async (): Promise<void> => {
+    const path = newDataPath.trim();
+    if (path === "") return;
+    const confirmed = window.confirm(
+      `Copy all data to ${path} and restart? Unsaved changes are lost and ` +
+        "the current folder is kept as it is."
+    );
+    if (!confirmed) return;
+    const result = await invoke<CommandResult<DataLocation>>(
+      "move_data_directory",
+      { path }
+    );
+    if (!result.success) {
+      showBanner(
+        false,
+        "Failed to move data: " + (result.message ?? "No error message")
+      );
+    } else {
+      setDataLocation(result.data ?? null);
+      showBanner(true, "Data moved, restarting");
+    }
+  };
+
   return (
     <div className="flex absolute left-0 top-0 w-screen h-screen bg-black/50">
       <div className="w-9/12 h-9/12 bg-[#EFEFEF] m-auto px-6 py-4 rounded-2xl flex flex-col">
@@ -59,6 +147,107 @@ const SettingsPopup = ({
               <option value={1800}>30 minutes</option>
               <option value={3600}>1 hour</option>
             </select>
+            <p>Compress Project Files</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.compress_projects}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  compress_projects: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Keep Snapshots</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.snapshots_enabled}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  snapshots_enabled: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Hourly Snapshots For (hours)</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={0}
+              value={configs.snapshot_hourly_hours}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  snapshot_hourly_hours: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Daily Snapshots For (days)</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={0}
+              value={configs.snapshot_daily_days}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  snapshot_daily_days: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Keep Git History</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.history_enabled}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  history_enabled: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>History Folder</p>
+            <input
+              className="w-full h-8 px-2"
+              type="text"
+              placeholder="Data directory"
+              value={configs.history_path}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  history_path: e.target.value,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Sync Folder Mode</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.sync_folder}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  sync_folder: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Apply Saved Compression Setting</p>
+            <button
+              className="bg-blue-600 w-40 py-1 rounded-xl text-white"
+              onClick={recompressProjects}
+            >
+              Recompress All
+            </button>
           </div>
         </div>
         <div className="mt-4">
@@ -79,6 +268,242 @@ const SettingsPopup = ({
             />
           </div>
         </div>
+        <div className="mt-4">
+          <h2 className="text-xl font-bold">Data</h2>
+          <div className="text-lg py-1 grid grid-cols-2 gap-2">
+            <p>Data Directory</p>
+            <p className="break-all">
+              {dataLocation === null
+                ? "Unknown"
+                : dataLocation.path +
+                  (dataLocation.kind === "portable" ? " (portable)" : "")}
+            </p>
+            <p>Move Data To (empty folder)</p>
+            <input
+              className="w-full h-8 px-2"
+              type="text"
+              placeholder="Absolute path"
+              value={newDataPath}
+              disabled={dataLocation?.kind === "portable"}
+              onChange={(e) => setNewDataPath(e.target.value)}
+            />
+            <p>Apply New Data Directory</p>
+            <button
+              className="bg-blue-600 w-40 py-1 rounded-xl text-white disabled:bg-gray-400"
+              disabled={dataLocation?.kind === "portable"}
+              onClick={moveDataDirectory}
+            >
+              Move Data
+            </button>
+          </div>
+        </div>
+        <div className="mt-4">
+          <h2 className="text-xl font-bold">Integrations</h2>
+          <div className="text-lg py-1 grid grid-cols-2 gap-2">
+            <p>Enable Local API</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.api_enabled}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  api_enabled: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Local API Port</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={1}
+              max={65535}
+              value={configs.api_port}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  api_port: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Local API Token</p>
+            <input
+              className="w-full h-8 px-2 font-mono text-sm"
+              type="text"
+              readOnly
+              value={configs.api_token}
+              onFocus={(e) => e.target.select()}
+            />
+            <p>Enable Peer Sync</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.peer_sync_enabled}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  peer_sync_enabled: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Peer Sync Port</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={1}
+              max={65535}
+              value={configs.peer_sync_port}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  peer_sync_port: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Shared Key</p>
+            <input
+              className="w-full h-8 px-2"
+              type="password"
+              value={configs.peer_sync_key}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  peer_sync_key: e.target.value,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Peers</p>
+            <input
+              className="w-full h-8 px-2"
+              type="text"
+              placeholder="192.168.1.20:7411, laptop.local:7411"
+              value={configs.peer_sync_peers.join(", ")}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  peer_sync_peers: e.target.value
+                    .split(",")
+                    .map((x) => x.trim())
+                    .filter((x) => x.length > 0),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Sync Interval (seconds, 0 for manual)</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={0}
+              value={configs.peer_sync_interval}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  peer_sync_interval: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Sync With Peers</p>
+            <button
+              className="bg-blue-600 w-40 py-1 rounded-xl text-white"
+              onClick={syncPeersNow}
+            >
+              Sync Now
+            </button>
+            <p>Record Operations</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.record_operations}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  record_operations: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Enable WebDAV Storage</p>
+            <input
+              className="w-5 h-5 my-auto"
+              type="checkbox"
+              checked={configs.webdav_enabled}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  webdav_enabled: e.target.checked,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>WebDAV Folder URL</p>
+            <input
+              className="w-full h-8 px-2"
+              type="url"
+              placeholder="https://dav.example.com/kanban/"
+              value={configs.webdav_url}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  webdav_url: e.target.value,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>WebDAV Username</p>
+            <input
+              className="w-full h-8 px-2"
+              type="text"
+              value={configs.webdav_username}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  webdav_username: e.target.value,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>WebDAV Password</p>
+            <input
+              className="w-full h-8 px-2"
+              type="password"
+              value={configs.webdav_password}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  webdav_password: e.target.value,
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>WebDAV Sync Interval (seconds, 0 for manual)</p>
+            <input
+              className="w-full h-8 px-2"
+              type="number"
+              min={0}
+              value={configs.webdav_interval}
+              onChange={(e) => {
+                const newConfigs: ConfigsModel = {
+                  ...configs,
+                  webdav_interval: parseInt(e.target.value),
+                };
+                setConfigs(newConfigs);
+              }}
+            />
+            <p>Sync With WebDAV</p>
+            <button
+              className="bg-blue-600 w-40 py-1 rounded-xl text-white"
+              onClick={syncWebDavNow}
+            >
+              Sync Now
+            </button>
+          </div>
+        </div>
         <div className="flex ml-auto mt-auto text-xl text-white">
           <button
             className="bg-blue-600 w-40 py-1 rounded-xl mr-4"
@@ -1,17 +1,42 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useAtom } from "jotai";
 import React from "react";
-import { IoArrowBack, IoSettingsSharp } from "react-icons/io5";
+import {
+  IoArchiveSharp,
+  IoArrowBack,
+  IoFolderSharp,
+  IoLayersSharp,
+  IoSettingsSharp,
+} from "react-icons/io5";
 import { CommandResult } from "../models/commandResult";
+import {
+  ProjectChangedPayload,
+  ProjectConflictPayload,
+} from "../models/events";
 import { BoardModel, ProjectModel } from "../models/project";
+import {
+  DraggedEntry,
+  GroupEntry,
+  ProjectGroup,
+} from "../models/projectGroup";
+import { ConflictCopy } from "../models/syncFolder";
+import { Workspaces } from "../models/workspace";
 import {
   allProjectsAtom,
+  conflictProjectAtom,
   openedBoardAtom,
   openedProjectAtom,
 } from "../stores/projectStore";
+import { ConflictCopyPopup } from "./ConflictCopyPopup";
+import { ConflictPopup } from "./ConflictPopup";
 import { ContextMenu, ContextMenuButton } from "./ContextMenu";
 import { DeletePopup } from "./DeletePopup";
+import { HistoryPopup } from "./HistoryPopup";
+import { ProjectTree } from "./ProjectTree";
 import SettingsPopup from "./SettingsPopup";
+import { SnapshotsPopup } from "./SnapshotsPopup";
+import { WorkspacesPopup } from "./WorkspacesPopup";
 
 interface SidebarProp {
   showBanner: (success: boolean, message: string) => void;
@@ -25,6 +50,7 @@ const Sidebar = ({
   const [projects, setProjects] = useAtom(allProjectsAtom);
   const [openedProject, setOpenedProject] = useAtom(openedProjectAtom);
   const [openedBoard, setOpenedBoard] = useAtom(openedBoardAtom);
+  const [conflictProject, setConflictProject] = useAtom(conflictProjectAtom);
 
   const [showContextMenu, setShowContextMenu] = React.useState<boolean>(false);
   const [contenxtMenuLocation, setContextMenuLocation] = React.useState<{
@@ -37,8 +63,22 @@ const Sidebar = ({
     React.useState<boolean>(false);
   const [isShowingSettings, setIsShowingSettings] =
     React.useState<boolean>(false);
+  const [isShowingWorkspaces, setIsShowingWorkspaces] =
+    React.useState<boolean>(false);
+  const [snapshotsProject, setSnapshotsProject] =
+    React.useState<ProjectModel | null>(null);
+  const [historyProject, setHistoryProject] =
+    React.useState<ProjectModel | null>(null);
+  const [conflictCopies, setConflictCopies] = React.useState<ConflictCopy[]>(
+    []
+  );
+  const [groups, setGroups] = React.useState<GroupEntry[]>([]);
+  const [contextMenuGroup, setContextMenuGroup] =
+    React.useState<ProjectGroup | null>(null);
+  const [showArchived, setShowArchived] = React.useState<boolean>(false);
 
   const addItemRef = React.useRef<HTMLInputElement>(null);
+  const showArchivedRef = React.useRef(showArchived);
   const openedBoardRef = React.useRef(openedBoard);
   const openedProjectRef = React.useRef(openedProject);
 
@@ -50,19 +90,170 @@ const Sidebar = ({
     openedProjectRef.current = openedProject;
   }, [openedProject]);
 
+  const refreshProjects = (): void => {
+    invoke<CommandResult<ProjectModel[]>>("get_all_projects", {
+      includeArchived: showArchivedRef.current,
+    }).then((res: CommandResult<ProjectModel[]>) => {
+      console.log(res);
+      if (!res.success) {
+        showBanner(false, res.message ?? "No error message");
+        return;
+      }
+      setProjects(res.data ?? []);
+    });
+  };
+
+  React.useEffect(() => {
+    if (showArchivedRef.current === showArchived) return;
+    showArchivedRef.current = showArchived;
+    refreshProjects();
+  }, [showArchived]);
+
+  // Reopens the project left open in the active workspace
+  const openLastProject = async (): Promise<void> => {
+    const result = await invoke<CommandResult<Workspaces>>("list_workspaces");
+    const active = result.data?.workspaces.find(
+      (x) => x.id === result.data?.active
+    );
+    if (!active?.last_project) return;
+    const project = await invoke<CommandResult<ProjectModel>>("read_project", {
+      projectId: active.last_project,
+    });
+    // Locked or deleted since, so stay on the project list
+    if (project.success && project.data && !project.data.locked) {
+      setOpenedProject(project.data);
+    }
+  };
+
+  React.useEffect(() => {
+    refreshProjects();
+    openLastProject();
+  }, []);
+
+  const showGroups = (result: CommandResult<GroupEntry[]>): void => {
+    if (!result.success || !result.data) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setGroups(result.data);
+  };
+
+  // The layout picks up projects created or deleted since
+  React.useEffect(() => {
+    invoke<CommandResult<GroupEntry[]>>("get_project_groups").then(showGroups);
+  }, [projects]);
+
+  // Conflict copies left by a sync tool are resolved one at a time
   React.useEffect(() => {
-    invoke<CommandResult<ProjectModel[]>>("get_all_projects").then(
-      (res: CommandResult<ProjectModel[]>) => {
-        console.log(res);
-        if (!res.success) {
-          showBanner(false, res.message ?? "No error message");
+    invoke<CommandResult<ConflictCopy[]>>("list_conflict_copies").then(
+      (result) => setConflictCopies(result.data ?? [])
+    );
+    const unlisten = listen<ConflictCopy>("conflict-copy", (event) => {
+      setConflictCopies((copies) =>
+        copies.some((x) => x.file_name === event.payload.file_name)
+          ? copies
+          : [...copies, event.payload]
+      );
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Keep in sync with changes made by other windows, the CLI or the local API
+  React.useEffect(() => {
+    const isOpened = (id: number[]): boolean =>
+      openedProjectRef.current?.id.join(",") === id.join(",");
+    const unlisteners = [
+      listen<ProjectChangedPayload>("project-created", () => refreshProjects()),
+      listen<ProjectChangedPayload>("project-deleted", (event) => {
+        refreshProjects();
+        if (isOpened(event.payload.id)) {
+          setOpenedProject(null);
+          setOpenedBoard(null);
+        }
+      }),
+      listen<ProjectChangedPayload>("project-updated", async (event) => {
+        refreshProjects();
+        if (!isOpened(event.payload.id)) return;
+        const result = await invoke<CommandResult<ProjectModel>>(
+          "read_project",
+          { projectId: event.payload.id }
+        );
+        if (!result.success || !result.data) return;
+        // Our own saves come back as events too
+        if (
+          JSON.stringify(result.data) ===
+          JSON.stringify(openedProjectRef.current)
+        )
           return;
+        setOpenedProject(result.data);
+        const boardName = openedBoardRef.current?.name;
+        if (boardName !== undefined) {
+          setOpenedBoard(
+            result.data.boards.find((x) => x.name === boardName) ?? null
+          );
         }
-        setProjects(res.data ?? []);
-      }
-    );
+      }),
+      listen<ProjectConflictPayload>("project-conflict", (event) => {
+        refreshProjects();
+        if (isOpened(event.payload.id)) {
+          setConflictProject(event.payload.project);
+        }
+      }),
+    ];
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
   }, []);
 
+  // A rejected save carries the stored copy when the project changed elsewhere
+  const showSaveError = (result: CommandResult<ProjectModel>): void => {
+    if (result.data) {
+      setConflictProject(result.data);
+    } else {
+      showBanner(false, result.message ?? "No error message");
+    }
+  };
+
+  const handleKeepMine = (): void => {
+    if (conflictProject === null) return;
+    // Saving on top of the stored revision overwrites it with our edits
+    if (openedProjectRef.current !== null) {
+      setOpenedProject({
+        ...openedProjectRef.current,
+        revision: conflictProject.revision,
+      });
+    }
+    setConflictProject(null);
+  };
+
+  const handleMergedProject = (merged: ProjectModel): void => {
+    // Still unsaved, so the merge is written by the next save
+    setOpenedProject(merged);
+    const boardName = openedBoardRef.current?.name;
+    if (boardName !== undefined) {
+      setOpenedBoard(merged.boards.find((x) => x.name === boardName) ?? null);
+    }
+    setConflictProject(null);
+  };
+
+  const handleLoadStoredProject = async (): Promise<void> => {
+    if (conflictProject === null) return;
+    await invoke<CommandResult<null>>("set_project_dirty", {
+      projectId: conflictProject.id,
+      dirty: false,
+    });
+    setOpenedProject(conflictProject);
+    const boardName = openedBoardRef.current?.name;
+    if (boardName !== undefined) {
+      setOpenedBoard(
+        conflictProject.boards.find((x) => x.name === boardName) ?? null
+      );
+    }
+    setConflictProject(null);
+  };
+
   const onCreateBtnClick = () => {
     if (openedProject === null) {
       onCreateClick();
@@ -75,6 +266,27 @@ const Sidebar = ({
   };
 
   const openProject = async (projectId: number[]): Promise<void> => {
+    const listed = projects.find((x) => x.id.join(",") === projectId.join(","));
+    if (listed?.archived) {
+      showBanner(false, `Unarchive "${listed.name}" to open it`);
+      return;
+    }
+    if (listed?.locked) {
+      const passphrase = window.prompt(`Passphrase for "${listed.name}"`);
+      if (passphrase === null) return;
+      const result = await invoke<CommandResult<ProjectModel>>(
+        "unlock_project",
+        { projectId, passphrase }
+      );
+      if (!result.success) {
+        showBanner(false, result.message ?? "No error message");
+        return;
+      }
+      refreshProjects();
+      setOpenedProject(result.data!);
+      invoke("set_last_project", { projectId });
+      return;
+    }
     const result = await invoke<CommandResult<ProjectModel>>("read_project", {
       projectId,
     });
@@ -83,6 +295,7 @@ const Sidebar = ({
       return;
     }
     setOpenedProject(result.data!);
+    invoke("set_last_project", { projectId });
   };
 
   const openBoard = async (boardName: string): Promise<void> => {
@@ -137,7 +350,7 @@ const Sidebar = ({
         project: updatedProject,
       });
       if (!result.success || result.data === null) {
-        showBanner(false, result.message ?? "No error message");
+        showSaveError(result);
         return;
       } else {
         setOpenedProject(result.data ?? updatedProject);
@@ -169,6 +382,77 @@ const Sidebar = ({
     setShowContextMenu(false);
   };
 
+  const handleGroupContextMenu = (
+    e: React.MouseEvent<HTMLSpanElement>,
+    group: ProjectGroup
+  ): void => {
+    e.preventDefault();
+    e.stopPropagation();
+    const rect = (e.target as HTMLSpanElement).getBoundingClientRect();
+    setContextMenuLocation({
+      x: rect.right,
+      y: rect.top,
+    });
+    setContextMenuGroup(group);
+  };
+
+  const handleCreateGroup = async (parentId: string | null): Promise<void> => {
+    setContextMenuGroup(null);
+    const name = window.prompt("Group name");
+    if (name === null) return;
+    showGroups(
+      await invoke<CommandResult<GroupEntry[]>>("create_group", {
+        name,
+        parentId,
+      })
+    );
+  };
+
+  const handleRenameGroup = async (group: ProjectGroup): Promise<void> => {
+    setContextMenuGroup(null);
+    const name = window.prompt("Group name", group.name);
+    if (name === null) return;
+    showGroups(
+      await invoke<CommandResult<GroupEntry[]>>("rename_group", {
+        groupId: group.id,
+        name,
+      })
+    );
+  };
+
+  const handleDeleteGroup = async (group: ProjectGroup): Promise<void> => {
+    setContextMenuGroup(null);
+    // The projects stay, moved up to where the group was
+    if (!window.confirm(`Delete the group "${group.name}"?`)) return;
+    showGroups(
+      await invoke<CommandResult<GroupEntry[]>>("delete_group", {
+        groupId: group.id,
+      })
+    );
+  };
+
+  const moveEntry = async (
+    dragged: DraggedEntry,
+    parentId: string | null,
+    index: number
+  ): Promise<void> => {
+    // The backend takes the entry out before inserting it
+    if (dragged.parentId === parentId && dragged.index < index) index -= 1;
+    const result =
+      dragged.type === "project"
+        ? await invoke<CommandResult<GroupEntry[]>>("move_project", {
+            projectId: dragged.id,
+            groupId: parentId,
+            index,
+          })
+        : await invoke<CommandResult<GroupEntry[]>>("move_group", {
+            groupId: dragged.id,
+            parentId,
+            index,
+          });
+    showGroups(result);
+  };
+
   const handleContextMenuOpen = (): void => {
     if (openedProject === null) {
       openProject(projects[contextMenuItem].id);
@@ -179,14 +463,15 @@ const Sidebar = ({
   };
 
   const handleContextMenuRename = (): void => {
-    const items = Array.from(
-      document.querySelectorAll("div#items-container > span")
-    ) as HTMLButtonElement[];
-    items[contextMenuItem].contentEditable = "true";
-    items[contextMenuItem].focus();
+    const item = document.querySelector(
+      `div#items-container [data-item-index="${contextMenuItem}"]`
+    ) as HTMLSpanElement | null;
+    if (item === null) return;
+    item.contentEditable = "true";
+    item.focus();
     const selection = window.getSelection();
     const range = document.createRange();
-    range.selectNodeContents(items[contextMenuItem]);
+    range.selectNodeContents(item);
     selection?.removeAllRanges();
     selection?.addRange(range);
     setShowContextMenu(false);
@@ -217,6 +502,100 @@ const Sidebar = ({
     handleContextMenuClose();
   };
 
+  const handleContextMenuLock = async (): Promise<void> => {
+    const project = projects[contextMenuItem];
+    handleContextMenuClose();
+    const result = await invoke<CommandResult<null>>("lock_project", {
+      projectId: project.id,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+    }
+  };
+
+  const handleContextMenuPassphrase = async (): Promise<void> => {
+    const project = projects[contextMenuItem];
+    handleContextMenuClose();
+    const passphrase = window.prompt(
+      `Current passphrase for "${project.name}", empty if it has none`
+    );
+    if (passphrase === null) return;
+    const newPassphrase = window.prompt(
+      "New passphrase, empty to store the project unencrypted"
+    );
+    if (newPassphrase === null) return;
+    const result = await invoke<CommandResult<null>>(
+      "change_project_passphrase",
+      {
+        projectId: project.id,
+        passphrase: passphrase.length > 0 ? passphrase : null,
+        newPassphrase: newPassphrase.length > 0 ? newPassphrase : null,
+      }
+    );
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+    } else {
+      showBanner(true, `Changed the passphrase of "${project.name}"`);
+    }
+  };
+
+  const handleContextMenuArchive = async (): Promise<void> => {
+    handleContextMenuClose();
+    if (openedProject === null) {
+      const project = projects[contextMenuItem];
+      const result = project.archived
+        ? await invoke<CommandResult<ProjectModel>>("unarchive_project", {
+            projectId: project.id,
+          })
+        : await invoke<CommandResult<null>>("archive_project", {
+            projectId: project.id,
+          });
+      if (!result.success) {
+        showBanner(false, result.message ?? "No error message");
+        return;
+      }
+      refreshProjects();
+      showBanner(
+        true,
+        `${project.archived ? "Unarchived" : "Archived"} "${project.name}"`
+      );
+    } else {
+      const board = openedProject.boards[contextMenuItem];
+      const updatedProject: ProjectModel = {
+        ...openedProject,
+        boards: openedProject.boards.map((x, idx) =>
+          idx === contextMenuItem ? { ...x, archived: !x.archived } : x
+        ),
+      };
+      const result = await invoke<CommandResult<ProjectModel>>("save_project", {
+        project: updatedProject,
+      });
+      if (!result.success || result.data === null) {
+        showSaveError(result);
+        return;
+      }
+      setOpenedProject(result.data ?? updatedProject);
+      // Hidden once archived, so it cannot stay open
+      if (openedBoard?.name === board.name && !showArchived) {
+        setOpenedBoard(null);
+      }
+      showBanner(
+        true,
+        `${board.archived ? "Unarchived" : "Archived"} "${board.name}"`
+      );
+    }
+  };
+
+  const handleContextMenuHistory = (): void => {
+    setHistoryProject(projects[contextMenuItem]);
+    handleContextMenuClose();
+  };
+
+  const handleContextMenuSnapshots = (): void => {
+    setSnapshotsProject(projects[contextMenuItem]);
+    handleContextMenuClose();
+  };
+
   const handleContextMenuDelete = (): void => {
     setShowContextMenu(false);
     setIsShowingDeletePopup(true);
@@ -247,7 +626,7 @@ const Sidebar = ({
         project: updatedProject,
       });
       if (!result.success || result.data === null) {
-        showBanner(false, result.message ?? "No error message");
+        showSaveError(result);
         return;
       } else {
         setOpenedProject(result.data ?? updatedProject);
@@ -286,12 +665,12 @@ const Sidebar = ({
       }
     );
     if (!saveResult.success) {
-      showBanner(false, result.message ?? "No error message");
+      showBanner(false, saveResult.message ?? "No error message");
       return;
     }
     setProjects([
       ...projects.slice(0, projectIndex),
-      project,
+      saveResult.data ?? project,
       ...projects.slice(projectIndex + 1),
     ]);
     showBanner(true, `Renamed ${oldName} to ${newName}`);
@@ -344,7 +723,7 @@ const Sidebar = ({
       project: updatedProject,
     });
     if (!result.success || result.data === null) {
-      showBanner(false, result.message ?? "No error message");
+      showSaveError(result);
       return;
     } else {
       setOpenedProject(result.data ?? updatedProject);
@@ -355,6 +734,7 @@ const Sidebar = ({
     await saveProject();
     setOpenedProject(null);
     setOpenedBoard(null);
+    invoke("set_last_project", { projectId: null });
   };
 
   return (
@@ -384,27 +764,37 @@ const Sidebar = ({
           id="items-container"
           className="overflow-y-auto select-none flex flex-col"
         >
-          {openedProject === null
-            ? projects.map((project, idx) => (
-                <span
-                  key={project.id.join("")}
-                  className=" w-full text-left px-3 py-1 text-lg cursor-pointer hover:bg-black/10"
-                  onClick={() => openProject(project.id)}
-                  onContextMenu={(e) => handleContextMenu(e, idx)}
-                  onKeyDown={onItemKeyDown}
-                  onBlur={onItemBlur}
-                >
-                  {project.name}
-                </span>
-              ))
-            : openedProject!.boards.map((board, idx) => (
+          {openedProject === null ? (
+            <ProjectTree
+              entries={[
+                ...groups,
+                // Archived projects are left out of the groups
+                ...projects
+                  .filter((x) => x.archived)
+                  .map((x): GroupEntry => ({ type: "project", id: x.id })),
+              ]}
+              projects={projects}
+              parentId={null}
+              depth={0}
+              onOpenProject={openProject}
+              onProjectContextMenu={handleContextMenu}
+              onGroupContextMenu={handleGroupContextMenu}
+              onMove={moveEntry}
+              onItemKeyDown={onItemKeyDown}
+              onItemBlur={onItemBlur}
+            />
+          ) : (
+            openedProject!.boards.map((board, idx) =>
+              board.archived && !showArchived ? null : (
                 <span
                   key={board.name}
+                  data-item-index={idx}
                   className={`w-full text-left px-3 py-1 text-lg cursor-pointer ${
                     contextMenuItem === idx
                       ? "bg-black/10"
                       : "hover:bg-black/10"
-                  }`}
+                  } ${board.archived ? "italic text-black/50" : ""}`}
+                  title={board.archived ? "Archived" : undefined}
                   onClick={() => openBoard(board.name)}
                   onContextMenu={(e) => handleContextMenu(e, idx)}
                   onKeyDown={onItemKeyDown}
@@ -412,7 +802,9 @@ const Sidebar = ({
                 >
                   {board.name}
                 </span>
-              ))}
+              )
+            )
+          )}
           <input
             ref={addItemRef}
             className={`w-full text-left px-3 py-1 text-lg ${
@@ -429,8 +821,33 @@ const Sidebar = ({
           >
             + Add {openedProject === null ? "Project" : "Board"}
           </button>
+          <IoArchiveSharp
+            className={`ml-auto my-auto cursor-pointer hover:text-black/5.0 ${
+              showArchived ? "text-blue-600" : ""
+            }`}
+            size={24}
+            title={showArchived ? "Hide Archived" : "Show Archived"}
+            onClick={() => setShowArchived(!showArchived)}
+          />
+          {openedProject === null && (
+            <IoFolderSharp
+              className="ml-3 my-auto cursor-pointer hover:text-black/5.0"
+              size={24}
+              title="New Group"
+              onClick={() => handleCreateGroup(null)}
+            />
+          )}
+          {/* Only from the project list, so no unsaved edits are left behind */}
+          {openedProject === null && (
+            <IoLayersSharp
+              className="ml-3 my-auto cursor-pointer hover:text-black/5.0"
+              size={24}
+              title="Workspaces"
+              onClick={() => setIsShowingWorkspaces(true)}
+            />
+          )}
           <IoSettingsSharp
-            className="ml-auto mr-3 my-auto cursor-pointer hover:text-black/5.0"
+            className="ml-3 mr-3 my-auto cursor-pointer hover:text-black/5.0"
             size={24}
             onClick={() => setIsShowingSettings(true)}
           />
@@ -453,11 +870,62 @@ const Sidebar = ({
               Duplicate
             </ContextMenuButton>
           )}
+          {openedProject === null && (
+            <ContextMenuButton onClick={handleContextMenuPassphrase}>
+              Passphrase
+            </ContextMenuButton>
+          )}
+          {openedProject === null && (
+            <ContextMenuButton onClick={handleContextMenuSnapshots}>
+              Snapshots
+            </ContextMenuButton>
+          )}
+          {openedProject === null && (
+            <ContextMenuButton onClick={handleContextMenuHistory}>
+              History
+            </ContextMenuButton>
+          )}
+          {openedProject === null && (
+            <ContextMenuButton onClick={handleContextMenuLock}>
+              Lock
+            </ContextMenuButton>
+          )}
+          <ContextMenuButton onClick={handleContextMenuArchive}>
+            {(openedProject === null
+              ? projects[contextMenuItem]
+              : openedProject.boards[contextMenuItem]
+            )?.archived
+              ? "Unarchive"
+              : "Archive"}
+          </ContextMenuButton>
           <ContextMenuButton onClick={handleContextMenuDelete}>
             <span className="text-red-500">Delete</span>
           </ContextMenuButton>
         </ContextMenu>
       )}
+      {contextMenuGroup !== null && (
+        <ContextMenu
+          x={contenxtMenuLocation.x}
+          y={contenxtMenuLocation.y}
+          onClose={() => setContextMenuGroup(null)}
+        >
+          <ContextMenuButton
+            onClick={() => handleCreateGroup(contextMenuGroup.id)}
+          >
+            New Group Inside
+          </ContextMenuButton>
+          <ContextMenuButton
+            onClick={() => handleRenameGroup(contextMenuGroup)}
+          >
+            Rename
+          </ContextMenuButton>
+          <ContextMenuButton
+            onClick={() => handleDeleteGroup(contextMenuGroup)}
+          >
+            <span className="text-red-500">Delete</span>
+          </ContextMenuButton>
+        </ContextMenu>
+      )}
       {isShowingDeletePopup && (
         <DeletePopup
           deleteItem={
@@ -469,12 +937,51 @@ const Sidebar = ({
           onConfirm={handleConfirmDelete}
         />
       )}
+      {historyProject !== null && (
+        <HistoryPopup
+          project={historyProject}
+          showBanner={showBanner}
+          onClose={() => setHistoryProject(null)}
+        />
+      )}
+      {snapshotsProject !== null && (
+        <SnapshotsPopup
+          project={snapshotsProject}
+          showBanner={showBanner}
+          onClose={() => setSnapshotsProject(null)}
+        />
+      )}
       {isShowingSettings && (
         <SettingsPopup
           showBanner={showBanner}
           onClose={() => setIsShowingSettings(false)}
         />
       )}
+      {isShowingWorkspaces && (
+        <WorkspacesPopup
+          showBanner={showBanner}
+          onClose={() => setIsShowingWorkspaces(false)}
+        />
+      )}
+      {conflictProject === null && conflictCopies.length > 0 && (
+        <ConflictCopyPopup
+          key={conflictCopies[0].file_name}
+          copy={conflictCopies[0]}
+          showBanner={showBanner}
+          onResolved={() => setConflictCopies((copies) => copies.slice(1))}
+        />
+      )}
+      {conflictProject !== null && (
+        <ConflictPopup
+          projectName={conflictProject.name}
+          mine={openedProject}
+          stored={conflictProject}
+          onKeepMine={handleKeepMine}
+          onLoadStored={handleLoadStoredProject}
+          onMerged={handleMergedProject}
+          showBanner={showBanner}
+        />
+      )}
     </>
   );
 };
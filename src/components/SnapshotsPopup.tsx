@@ -0,0 +1,129 @@
+import { invoke } from "@tauri-apps/api/core";
+import React from "react";
+import { CommandResult } from "../models/commandResult";
+import { Change } from "../models/diff";
+import { ProjectModel } from "../models/project";
+import { SnapshotInfo } from "../models/snapshot";
+import { ChangeList } from "./ChangeList";
+
+interface SnapshotsPopupProps {
+  project: ProjectModel;
+  showBanner: (success: boolean, message: string) => void;
+  onClose: () => void;
+}
+
+export const SnapshotsPopup = ({
+  project,
+  showBanner,
+  onClose,
+}: SnapshotsPopupProps): JSX.Element => {
+  const [snapshots, setSnapshots] = React.useState<SnapshotInfo[]>([]);
+  const [preview, setPreview] = React.useState<ProjectModel | null>(null);
+  const [changes, setChanges] = React.useState<Change[] | null>(null);
+
+  React.useEffect(() => {
+    invoke<CommandResult<SnapshotInfo[]>>("list_snapshots", {
+      projectId: project.id,
+    }).then((result) => {
+      if (!result.success) {
+        showBanner(false, result.message ?? "No error message");
+        return;
+      }
+      setSnapshots(result.data ?? []);
+    });
+  }, [project]);
+
+  const previewSnapshot = async (timestamp: number): Promise<void> => {
+    const result = await invoke<CommandResult<ProjectModel>>(
+      "preview_snapshot",
+      { projectId: project.id, timestamp }
+    );
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setChanges(null);
+    setPreview(result.data);
+  };
+
+  const diffSnapshot = async (timestamp: number): Promise<void> => {
+    const result = await invoke<CommandResult<Change[]>>("diff_snapshot", {
+      projectId: project.id,
+      timestamp,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setPreview(null);
+    setChanges(result.data ?? []);
+  };
+
+  const restoreSnapshot = async (timestamp: number): Promise<void> => {
+    const result = await invoke<CommandResult<ProjectModel>>(
+      "restore_snapshot",
+      { projectId: project.id, timestamp, revision: project.revision }
+    );
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    showBanner(true, `Restored "${project.name}"`);
+    onClose();
+  };
+
+  return (
+    <div className="absolute flex items-center justify-center top-0 left-0 w-screen h-screen bg-black/30 ">
+      <div className="bg-[#EFEFEF] w-[600px] h-96 flex flex-col px-4 py-3 rounded-xl">
+        <h2 className="font-bold text-2xl ">Snapshots of {project.name}</h2>
+        <div className="overflow-y-auto my-2 flex-1">
+          {snapshots.length === 0 && <p className="text-lg">No snapshots</p>}
+          {snapshots.map((snapshot) => (
+            <div
+              key={snapshot.timestamp}
+              className="flex items-center text-lg py-1"
+            >
+              <span className="flex-1">
+                {new Date(snapshot.timestamp).toLocaleString()} (revision{" "}
+                {snapshot.revision})
+              </span>
+              <button
+                className="bg-white px-4 py-0.5 rounded-xl mr-2"
+                onClick={() => previewSnapshot(snapshot.timestamp)}
+              >
+                Preview
+              </button>
+              <button
+                className="bg-white px-4 py-0.5 rounded-xl mr-2"
+                onClick={() => diffSnapshot(snapshot.timestamp)}
+              >
+                Diff
+              </button>
+              <button
+                className="bg-blue-600 text-white px-4 py-0.5 rounded-xl"
+                onClick={() => restoreSnapshot(snapshot.timestamp)}
+              >
+                Restore
+              </button>
+            </div>
+          ))}
+        </div>
+        {changes !== null && <ChangeList changes={changes} />}
+        {preview !== null && (
+          <p className="text-lg mb-2">
+            <strong>{preview.name}</strong>: {preview.boards.length} boards,{" "}
+            {preview.boards
+              .flatMap((board) => board.lists)
+              .reduce((count, list) => count + list.items.length, 0)}{" "}
+            items
+          </p>
+        )}
+        <div className="flex ml-auto">
+          <button className="bg-white px-6 py-1 rounded-xl" onClick={onClose}>
+            Close
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
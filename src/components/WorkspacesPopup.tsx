@@ -0,0 +1,150 @@
+import { invoke } from "@tauri-apps/api/core";
+import React from "react";
+import { CommandResult } from "../models/commandResult";
+import { Workspace, Workspaces } from "../models/workspace";
+
+interface WorkspacesPopupProps {
+  showBanner: (success: boolean, message: string) => void;
+  onClose: () => void;
+}
+
+export const WorkspacesPopup = ({
+  showBanner,
+  onClose,
+}: WorkspacesPopupProps): JSX.Element => {
+  const [workspaces, setWorkspaces] = React.useState<Workspaces | null>(null);
+  const [newName, setNewName] = React.useState<string>("");
+
+  const refreshWorkspaces = async (): Promise<void> => {
+    const result = await invoke<CommandResult<Workspaces>>("list_workspaces");
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setWorkspaces(result.data ?? null);
+  };
+
+  React.useEffect(() => {
+    refreshWorkspaces();
+  }, []);
+
+  const createWorkspace = async (): Promise<void> => {
+    const result = await invoke<CommandResult<Workspace>>("create_workspace", {
+      name: newName,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    setNewName("");
+    refreshWorkspaces();
+  };
+
+  const switchWorkspace = async (workspace: Workspace): Promise<void> => {
+    const result = await invoke<CommandResult<Workspace>>("switch_workspace", {
+      workspaceId: workspace.id,
+    });
+    // Opened even when one of its services failed to start
+    if (!result.data) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    // Start over with the projects and configs of the other workspace
+    window.location.reload();
+  };
+
+  const renameWorkspace = async (workspace: Workspace): Promise<void> => {
+    const name = window.prompt("New workspace name", workspace.name);
+    if (name === null) return;
+    const result = await invoke<CommandResult<Workspace>>("rename_workspace", {
+      workspaceId: workspace.id,
+      name,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    refreshWorkspaces();
+  };
+
+  const deleteWorkspace = async (workspace: Workspace): Promise<void> => {
+    const confirmed = window.confirm(
+      `Delete the workspace "${workspace.name}" and all its projects?`
+    );
+    if (!confirmed) return;
+    const result = await invoke<CommandResult<null>>("delete_workspace", {
+      workspaceId: workspace.id,
+    });
+    if (!result.success) {
+      showBanner(false, result.message ?? "No error message");
+      return;
+    }
+    showBanner(true, `Deleted workspace "${workspace.name}"`);
+    refreshWorkspaces();
+  };
+
+  return (
+    <div className="flex absolute left-0 top-0 w-screen h-screen bg-black/50">
+      <div className="w-[500px] max-h-9/12 bg-[#EFEFEF] m-auto px-6 py-4 rounded-2xl flex flex-col">
+        <h1 className="text-2xl font-bold">Workspaces</h1>
+        <div className="mt-4 overflow-y-auto">
+          {workspaces?.workspaces.map((workspace) => (
+            <div key={workspace.id} className="flex items-center text-lg py-1">
+              <p
+                className={`mr-auto ${
+                  workspace.id === workspaces.active ? "font-bold" : ""
+                }`}
+              >
+                {workspace.name}
+              </p>
+              {workspace.id !== workspaces.active && (
+                <button
+                  className="bg-blue-600 px-4 py-1 rounded-xl text-white ml-2"
+                  onClick={() => switchWorkspace(workspace)}
+                >
+                  Open
+                </button>
+              )}
+              <button
+                className="bg-white px-4 py-1 rounded-xl ml-2"
+                onClick={() => renameWorkspace(workspace)}
+              >
+                Rename
+              </button>
+              {workspace.id !== "default" &&
+                workspace.id !== workspaces.active && (
+                  <button
+                    className="bg-red-500 px-4 py-1 rounded-xl text-white ml-2"
+                    onClick={() => deleteWorkspace(workspace)}
+                  >
+                    Delete
+                  </button>
+                )}
+            </div>
+          ))}
+        </div>
+        <div className="flex mt-4">
+          <input
+            className="w-full h-8 px-2 mr-2"
+            type="text"
+            placeholder="New workspace name"
+            value={newName}
+            onChange={(e) => setNewName(e.target.value)}
+          />
+          <button
+            className="bg-blue-600 w-40 py-1 rounded-xl text-white"
+            onClick={createWorkspace}
+          >
+            Create
+          </button>
+        </div>
+        <button
+          className="bg-white px-6 py-1 rounded-xl mt-4 ml-auto"
+          onClick={onClose}
+        >
+          Close
+        </button>
+      </div>
+    </div>
+  );
+};
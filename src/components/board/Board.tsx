@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import { useAtom, useAtomValue } from "jotai";
+import { useAtom, useAtomValue, useSetAtom } from "jotai";
 import React from "react";
 import { IoAddOutline } from "react-icons/io5";
 import { CommandResult } from "../../models/commandResult";
@@ -10,6 +10,7 @@ import {
 } from "../../stores/dndStore";
 import {
   configsAtom,
+  conflictProjectAtom,
   openedBoardAtom,
   openedProjectAtom,
 } from "../../stores/projectStore";
@@ -26,6 +27,7 @@ const Board = ({ showBanner }: BoardProps): JSX.Element => {
   const draggingListLocation = useAtomValue(draggingListLocationAtom);
   const draggingListIndex = useAtomValue(draggingListIndexAtom);
   const configs = useAtomValue(configsAtom);
+  const setConflictProject = useSetAtom(conflictProjectAtom);
 
   const [isAddingBoard, setIsAddingBoard] = React.useState<boolean>(false);
   const [lastSavedTime, setLastSavedTime] = React.useState<string | null>(null);
@@ -48,6 +50,23 @@ const Board = ({ showBanner }: BoardProps): JSX.Element => {
     openedProjectRef.current = openedProject;
   }, [openedProject]);
 
+  // Let the backend know about unsaved edits, so external changes to the
+  // project are reported as conflicts instead of replacing them
+  const isDirtyRef = React.useRef<boolean>(false);
+  React.useEffect(() => {
+    if (openedProject === null || openedBoard === null) return;
+    const savedBoard = openedProject.boards.find(
+      (x) => x.name === openedBoard.name
+    );
+    const isDirty = JSON.stringify(savedBoard) !== JSON.stringify(openedBoard);
+    if (isDirty === isDirtyRef.current) return;
+    isDirtyRef.current = isDirty;
+    invoke<CommandResult<null>>("set_project_dirty", {
+      projectId: openedProject.id,
+      dirty: isDirty,
+    });
+  }, [openedBoard, openedProject]);
+
   const onAddListClick = (): void => {
     setIsAddingBoard(true);
     setTimeout(() => {
@@ -114,7 +133,11 @@ const Board = ({ showBanner }: BoardProps): JSX.Element => {
     const result = await invoke<CommandResult<ProjectModel>>("save_project", {
       project: updatedProject,
     });
-    if (!result.success || result.data === null) {
+    if (!result.success && result.data) {
+      // Someone else saved first, let the user choose which copy to keep
+      setConflictProject(result.data);
+      return false;
+    } else if (!result.success || result.data === null) {
       showBanner(false, result.message ?? "No error message");
       return false;
     } else {
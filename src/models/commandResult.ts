@@ -1,5 +1,11 @@
+export interface FieldError {
+  field: string;
+  message: string;
+}
+
 export interface CommandResult<T> {
   success: boolean;
   data?: T;
   message?: string;
+  errors?: FieldError[];
 }
@@ -1,4 +1,26 @@
 export interface ConfigsModel {
   auto_save_interval: number;
   new_list_default_color: string;
+  api_enabled: boolean;
+  api_port: number;
+  api_token: string;
+  compress_projects: boolean;
+  snapshots_enabled: boolean;
+  snapshot_hourly_hours: number;
+  snapshot_daily_days: number;
+  history_enabled: boolean;
+  history_path: string;
+  sync_folder: boolean;
+  instance_id: string;
+  record_operations: boolean;
+  peer_sync_enabled: boolean;
+  peer_sync_port: number;
+  peer_sync_key: string;
+  peer_sync_peers: string[];
+  peer_sync_interval: number;
+  webdav_enabled: boolean;
+  webdav_url: string;
+  webdav_username: string;
+  webdav_password: string;
+  webdav_interval: number;
 }
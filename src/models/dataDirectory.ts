@@ -0,0 +1,6 @@
+export type DataLocationKind = "default" | "custom" | "portable";
+
+export interface DataLocation {
+  path: string;
+  kind: DataLocationKind;
+}
@@ -0,0 +1,84 @@
+export type Change =
+  | { kind: "project_renamed"; from: string; to: string }
+  | { kind: "description_edited"; from: string; to: string }
+  | { kind: "board_added"; board: string }
+  | { kind: "board_removed"; board: string }
+  | { kind: "board_renamed"; from: string; to: string }
+  | { kind: "board_moved"; board: string; from: number; to: number }
+  | { kind: "board_archived"; board: string; archived: boolean }
+  | { kind: "list_added"; board: string; list: string }
+  | { kind: "list_removed"; board: string; list: string }
+  | { kind: "list_renamed"; board: string; from: string; to: string }
+  | { kind: "list_moved"; board: string; list: string; from: number; to: number }
+  | {
+      kind: "list_recoloured";
+      board: string;
+      list: string;
+      from: [number, number, number];
+      to: [number, number, number];
+    }
+  | {
+      kind: "item_added";
+      board: string;
+      list: string;
+      item: string;
+      position: number;
+    }
+  | { kind: "item_removed"; board: string; list: string; item: string }
+  | {
+      kind: "item_moved";
+      board: string;
+      from_list: string;
+      to_list: string;
+      item: string;
+      position: number;
+    }
+  | { kind: "item_edited"; board: string; list: string; from: string; to: string };
+
+// Same wording as the CLI
+export const describeChange = (change: Change): string => {
+  switch (change.kind) {
+    case "project_renamed":
+      return `Renamed project "${change.from}" to "${change.to}"`;
+    case "description_edited":
+      return "Edited description";
+    case "board_added":
+      return `Added board "${change.board}"`;
+    case "board_removed":
+      return `Removed board "${change.board}"`;
+    case "board_renamed":
+      return `Renamed board "${change.from}" to "${change.to}"`;
+    case "board_moved":
+      return `Moved board "${change.board}" from ${change.from + 1} to ${
+        change.to + 1
+      }`;
+    case "board_archived":
+      return `${change.archived ? "Archived" : "Unarchived"} board "${
+        change.board
+      }"`;
+    case "list_added":
+      return `${change.board}: Added list "${change.list}"`;
+    case "list_removed":
+      return `${change.board}: Removed list "${change.list}"`;
+    case "list_renamed":
+      return `${change.board}: Renamed list "${change.from}" to "${change.to}"`;
+    case "list_moved":
+      return `${change.board}: Moved list "${change.list}" from ${
+        change.from + 1
+      } to ${change.to + 1}`;
+    case "list_recoloured":
+      return `${change.board}: Recoloured list "${change.list}"`;
+    case "item_added":
+      return `${change.board} / ${change.list}: Added "${change.item}" at ${
+        change.position + 1
+      }`;
+    case "item_removed":
+      return `${change.board} / ${change.list}: Removed "${change.item}"`;
+    case "item_moved":
+      return `${change.board}: Moved "${change.item}" from ${
+        change.from_list
+      } to ${change.to_list} at ${change.position + 1}`;
+    case "item_edited":
+      return `${change.board} / ${change.list}: Edited "${change.from}" to "${change.to}"`;
+  }
+};
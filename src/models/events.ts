@@ -0,0 +1,29 @@
+import { ProjectModel } from "./project";
+
+export interface ProjectChangedPayload {
+  id: number[];
+  version: number;
+}
+
+export interface ConfigsChangedPayload {
+  version: number;
+}
+
+export interface ProjectConflictPayload {
+  id: number[];
+  version: number;
+  project: ProjectModel;
+}
+
+export interface ProgressPayload {
+  task: string;
+  done: number;
+  total: number;
+}
+
+// Errors from background work, such as a snapshot taken after a save
+export interface LoggedError {
+  context: string;
+  message: string;
+  time: number;
+}
@@ -0,0 +1,7 @@
+export interface HistoryEntry {
+  commit: string;
+  timestamp: number;
+  message: string;
+  revision: number | null;
+  name: string | null;
+}
@@ -0,0 +1,45 @@
+import { ProjectModel } from "./project";
+
+export type Side = "ours" | "theirs";
+
+export type MergeConflict =
+  | { kind: "text"; location: string; base: string; ours: string; theirs: string }
+  | {
+      kind: "color";
+      location: string;
+      base: [number, number, number];
+      ours: [number, number, number];
+      theirs: [number, number, number];
+    }
+  | {
+      kind: "items";
+      location: string;
+      base: string[];
+      ours: string[];
+      theirs: string[];
+    }
+  | { kind: "removed"; location: string; removed_by: Side };
+
+export interface Merge {
+  project: ProjectModel;
+  conflicts: MergeConflict[];
+}
+
+const toHex = (color: [number, number, number]): string =>
+  "#" + color.map((x) => x.toString(16).padStart(2, "0")).join("");
+
+// What each side has at the conflict, for the two buttons
+export const describeSide = (conflict: MergeConflict, side: Side): string => {
+  switch (conflict.kind) {
+    case "text":
+      return `"${conflict[side]}"`;
+    case "color":
+      return toHex(conflict[side]);
+    case "items":
+      return conflict[side].length === 0
+        ? "No items"
+        : conflict[side].map((x) => `"${x}"`).join(", ");
+    case "removed":
+      return conflict.removed_by === side ? "Remove" : "Keep";
+  }
+};
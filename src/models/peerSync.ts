@@ -0,0 +1,28 @@
+export interface SyncReport {
+  peer: string;
+  received: number;
+  sent: number;
+  merged: number;
+  conflicts: number;
+  failed: string[];
+}
+
+export interface PeerSyncResult {
+  peer: string;
+  report: SyncReport | null;
+  error: string | null;
+}
+
+export const describeSync = (results: PeerSyncResult[]): string => {
+  const reports = results.flatMap((x) => (x.report === null ? [] : [x.report]));
+  const total = (key: keyof Omit<SyncReport, "peer" | "failed">): number =>
+    reports.reduce((sum, report) => sum + report[key], 0);
+  const conflicts = total("conflicts");
+  const failed = reports.flatMap((report) => report.failed).length;
+  return (
+    `Synced with ${reports.length} peers: ${total("received")} received, ` +
+    `${total("sent")} sent, ${total("merged")} merged` +
+    (conflicts > 0 ? `, ${conflicts} conflicts kept as on this device` : "") +
+    (failed > 0 ? `, ${failed} failed` : "")
+  );
+};
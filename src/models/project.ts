@@ -1,5 +1,8 @@
 export interface ProjectModel {
   id: number[];
+  revision: number;
+  locked?: boolean;
+  archived?: boolean;
   name: string;
   description: string;
   boards: BoardModel[];
@@ -7,6 +10,7 @@ export interface ProjectModel {
 
 export interface BoardModel {
   name: string;
+  archived?: boolean;
   lists: BoardListModel[];
 }
 
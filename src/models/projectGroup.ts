@@ -0,0 +1,21 @@
+export interface ProjectGroup {
+  type: "group";
+  id: string;
+  name: string;
+  entries: GroupEntry[];
+}
+
+export interface GroupedProject {
+  type: "project";
+  id: number[];
+}
+
+export type GroupEntry = ProjectGroup | GroupedProject;
+
+// A group or project being dragged to another place in the sidebar
+export interface DraggedEntry {
+  type: "group" | "project";
+  id: string | number[];
+  parentId: string | null;
+  index: number;
+}
@@ -0,0 +1,5 @@
+export interface SnapshotInfo {
+  timestamp: number;
+  revision: number;
+  name: string;
+}
@@ -0,0 +1,7 @@
+export interface ConflictCopy {
+  project_id: number[];
+  file_name: string;
+  modified: number;
+  revision: number;
+  name: string;
+}
@@ -0,0 +1,13 @@
+export interface WebDavReport {
+  received: number;
+  sent: number;
+  conflicts: number;
+  pending: number;
+}
+
+export const describeWebDavSync = (report: WebDavReport): string =>
+  `Synced with WebDAV: ${report.received} received, ${report.sent} sent` +
+  (report.conflicts > 0
+    ? `, ${report.conflicts} conflicts kept as copies`
+    : "") +
+  (report.pending > 0 ? `, ${report.pending} waiting` : "");
@@ -0,0 +1,10 @@
+export interface Workspace {
+  id: string;
+  name: string;
+  last_project: number[] | null;
+}
+
+export interface Workspaces {
+  active: string;
+  workspaces: Workspace[];
+}
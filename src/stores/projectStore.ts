@@ -5,4 +5,6 @@ import { BoardModel, ProjectModel } from "../models/project";
 export const allProjectsAtom = atom<ProjectModel[]>([]);
 export const openedProjectAtom = atom<ProjectModel | null>(null);
 export const openedBoardAtom = atom<BoardModel | null>(null);
+// Stored copy of the opened project when it changed under unsaved edits
+export const conflictProjectAtom = atom<ProjectModel | null>(null);
 export const configsAtom = atom<ConfigsModel | null>(null);